@@ -0,0 +1,1053 @@
+#![allow(dead_code)]
+
+/// Command Pattern
+///
+/// Encapsulates a request as an object, letting us parameterize clients with
+/// different requests, queue or log requests, and support undoable operations.
+
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// ---------------------------------------------------------------------------
+// Command trait
+// ---------------------------------------------------------------------------
+
+pub trait Command {
+    fn execute(&mut self) -> Result<(), String>;
+    fn undo(&mut self) -> Result<(), String>;
+    fn description(&self) -> String;
+
+    /// Report what `execute` would do without mutating the receiver.
+    fn preview(&self) -> String {
+        format!("would {}", self.description())
+    }
+
+    /// Report what `undo` would do without mutating the receiver.
+    fn preview_undo(&self) -> String {
+        format!("would revert: {}", self.description())
+    }
+
+    /// Approximate bytes of payload this command keeps alive while sitting in
+    /// the undo history. Used to enforce the manager's memory budget.
+    fn payload_size(&self) -> usize {
+        0
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Receiver: TextEditor
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Default, Clone)]
+pub struct TextEditor {
+    content: String,
+}
+
+impl TextEditor {
+    pub fn new() -> Self {
+        TextEditor {
+            content: String::new(),
+        }
+    }
+
+    pub fn insert(&mut self, position: usize, text: &str) -> Result<(), String> {
+        if position > self.content.len() {
+            return Err(format!("position {} out of bounds", position));
+        }
+        self.content.insert_str(position, text);
+        Ok(())
+    }
+
+    pub fn delete(&mut self, position: usize, length: usize) -> Result<String, String> {
+        if position + length > self.content.len() {
+            return Err(format!("range {}..{} out of bounds", position, position + length));
+        }
+        let removed: String = self.content.drain(position..position + length).collect();
+        Ok(removed)
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Concrete commands for the editor
+// ---------------------------------------------------------------------------
+
+pub struct InsertCommand {
+    editor: Rc<RefCell<TextEditor>>,
+    position: usize,
+    text: String,
+}
+
+impl InsertCommand {
+    pub fn new(editor: Rc<RefCell<TextEditor>>, position: usize, text: &str) -> Self {
+        InsertCommand {
+            editor,
+            position,
+            text: text.to_string(),
+        }
+    }
+}
+
+impl Command for InsertCommand {
+    fn execute(&mut self) -> Result<(), String> {
+        self.editor.borrow_mut().insert(self.position, &self.text)
+    }
+
+    fn undo(&mut self) -> Result<(), String> {
+        self.editor
+            .borrow_mut()
+            .delete(self.position, self.text.len())
+            .map(|_| ())
+    }
+
+    fn description(&self) -> String {
+        format!("Insert '{}' at {}", self.text, self.position)
+    }
+
+    fn preview(&self) -> String {
+        format!("would insert '{}' at position {}", self.text, self.position)
+    }
+
+    fn preview_undo(&self) -> String {
+        format!(
+            "would delete '{}' from position {}",
+            self.text, self.position
+        )
+    }
+
+    fn payload_size(&self) -> usize {
+        self.text.len()
+    }
+}
+
+pub struct DeleteCommand {
+    editor: Rc<RefCell<TextEditor>>,
+    position: usize,
+    length: usize,
+    deleted_text: Option<String>,
+}
+
+impl DeleteCommand {
+    pub fn new(editor: Rc<RefCell<TextEditor>>, position: usize, length: usize) -> Self {
+        DeleteCommand {
+            editor,
+            position,
+            length,
+            deleted_text: None,
+        }
+    }
+}
+
+impl Command for DeleteCommand {
+    fn execute(&mut self) -> Result<(), String> {
+        let removed = self.editor.borrow_mut().delete(self.position, self.length)?;
+        self.deleted_text = Some(removed);
+        Ok(())
+    }
+
+    fn undo(&mut self) -> Result<(), String> {
+        match &self.deleted_text {
+            Some(text) => self.editor.borrow_mut().insert(self.position, text),
+            None => Err("nothing to undo".to_string()),
+        }
+    }
+
+    fn description(&self) -> String {
+        format!("Delete {} chars at {}", self.length, self.position)
+    }
+
+    fn preview(&self) -> String {
+        let editor = self.editor.borrow();
+        let end = (self.position + self.length).min(editor.content().len());
+        match editor.content().get(self.position..end) {
+            Some(text) => format!("would delete '{}' at position {}", text, self.position),
+            None => format!("would delete {} chars at {}", self.length, self.position),
+        }
+    }
+
+    fn preview_undo(&self) -> String {
+        match &self.deleted_text {
+            Some(text) => format!("would re-insert '{}' at position {}", text, self.position),
+            None => "nothing to undo yet".to_string(),
+        }
+    }
+
+    fn payload_size(&self) -> usize {
+        self.deleted_text.as_ref().map_or(0, |t| t.len())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Receiver: Light (classic remote-control example)
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Default)]
+pub struct Light {
+    name: String,
+    is_on: bool,
+}
+
+impl Light {
+    pub fn new(name: &str) -> Self {
+        Light {
+            name: name.to_string(),
+            is_on: false,
+        }
+    }
+
+    pub fn turn_on(&mut self) {
+        self.is_on = true;
+        println!("{} light is ON", self.name);
+    }
+
+    pub fn turn_off(&mut self) {
+        self.is_on = false;
+        println!("{} light is OFF", self.name);
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.is_on
+    }
+}
+
+pub struct LightOnCommand {
+    light: Rc<RefCell<Light>>,
+}
+
+impl LightOnCommand {
+    pub fn new(light: Rc<RefCell<Light>>) -> Self {
+        LightOnCommand { light }
+    }
+}
+
+impl Command for LightOnCommand {
+    fn execute(&mut self) -> Result<(), String> {
+        self.light.borrow_mut().turn_on();
+        Ok(())
+    }
+
+    fn undo(&mut self) -> Result<(), String> {
+        self.light.borrow_mut().turn_off();
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Turn on {} light", self.light.borrow().name)
+    }
+}
+
+pub struct LightOffCommand {
+    light: Rc<RefCell<Light>>,
+}
+
+impl LightOffCommand {
+    pub fn new(light: Rc<RefCell<Light>>) -> Self {
+        LightOffCommand { light }
+    }
+}
+
+impl Command for LightOffCommand {
+    fn execute(&mut self) -> Result<(), String> {
+        self.light.borrow_mut().turn_off();
+        Ok(())
+    }
+
+    fn undo(&mut self) -> Result<(), String> {
+        self.light.borrow_mut().turn_on();
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Turn off {} light", self.light.borrow().name)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Memento support for snapshot-based undo
+// ---------------------------------------------------------------------------
+
+/// Snapshot of the editor state taken by the `CommandManager` every N
+/// commands. Undoing restores the nearest snapshot and replays the commands
+/// executed after it, which is cheaper than per-command inverse operations
+/// when individual edits are large.
+#[derive(Debug, Clone)]
+pub struct EditorMemento {
+    content: String,
+    /// Number of commands that had been executed when the snapshot was taken.
+    command_count: usize,
+}
+
+/// How the `CommandManager` performs undo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoStrategy {
+    /// Call `Command::undo` on the last command (the default).
+    InverseOperation,
+    /// Snapshot the editor every N commands; undo restores the nearest
+    /// snapshot and replays the commands executed after it.
+    SnapshotReplay { interval: usize },
+}
+
+// ---------------------------------------------------------------------------
+// Invoker: CommandManager with undo/redo history and a command queue
+// ---------------------------------------------------------------------------
+
+/// Counters for history entries evicted to satisfy the memory budget.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EvictionStats {
+    pub evicted_commands: usize,
+    pub evicted_bytes: usize,
+}
+
+struct QueuedCommand {
+    command: Box<dyn Command>,
+    cancelled: Rc<Cell<bool>>,
+}
+
+/// Handle returned by `enqueue_command`; lets the caller revoke the command
+/// before the queue runs.
+pub struct CommandHandle {
+    cancelled: Rc<Cell<bool>>,
+    description: String,
+}
+
+impl CommandHandle {
+    pub fn cancel(&self) {
+        self.cancelled.set(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.get()
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+/// What kind of operation an audit entry records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Execute,
+    Undo,
+    Redo,
+}
+
+impl AuditAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Execute => "execute",
+            AuditAction::Undo => "undo",
+            AuditAction::Redo => "redo",
+        }
+    }
+}
+
+/// One structured record per command operation, kept for offline analysis.
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub action: AuditAction,
+    pub description: String,
+    /// Seconds since the Unix epoch when the operation started.
+    pub timestamp_secs: u64,
+    pub duration_micros: u128,
+    pub success: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditFormat {
+    Json,
+    Csv,
+}
+
+fn json_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn csv_escape(s: &str) -> String {
+    if s.contains(',') || s.contains('"') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Outcome of `process_queue`: executed commands with their results, plus the
+/// descriptions of entries skipped because they were cancelled.
+#[derive(Default)]
+pub struct QueueReport {
+    pub executed: Vec<(String, Result<(), String>)>,
+    pub cancelled: Vec<String>,
+}
+
+pub struct CommandManager {
+    history: Vec<Box<dyn Command>>,
+    redo_stack: Vec<Box<dyn Command>>,
+    max_history: usize,
+    /// Approximate cap on the summed `payload_size` of history entries.
+    memory_budget: Option<usize>,
+    eviction_stats: EvictionStats,
+    queue: VecDeque<QueuedCommand>,
+    undo_strategy: UndoStrategy,
+    snapshots: Vec<EditorMemento>,
+    editor: Option<Rc<RefCell<TextEditor>>>,
+    audit_log: Vec<AuditEntry>,
+}
+
+impl CommandManager {
+    pub fn new(max_history: usize) -> Self {
+        CommandManager {
+            history: Vec::new(),
+            redo_stack: Vec::new(),
+            max_history,
+            memory_budget: None,
+            eviction_stats: EvictionStats::default(),
+            queue: VecDeque::new(),
+            undo_strategy: UndoStrategy::InverseOperation,
+            snapshots: Vec::new(),
+            editor: None,
+            audit_log: Vec::new(),
+        }
+    }
+
+    /// Switch to snapshot-based undo for the given editor. Snapshots are
+    /// taken every `interval` commands; undo restores the nearest snapshot
+    /// and replays the remaining commands.
+    pub fn with_snapshot_undo(
+        max_history: usize,
+        editor: Rc<RefCell<TextEditor>>,
+        interval: usize,
+    ) -> Self {
+        let mut manager = CommandManager::new(max_history);
+        manager.undo_strategy = UndoStrategy::SnapshotReplay {
+            interval: interval.max(1),
+        };
+        manager.snapshots.push(EditorMemento {
+            content: editor.borrow().content().to_string(),
+            command_count: 0,
+        });
+        manager.editor = Some(editor);
+        manager
+    }
+
+    pub fn execute_command(&mut self, mut command: Box<dyn Command>) -> Result<(), String> {
+        let description = command.description();
+        let start = Instant::now();
+        let result = command.execute();
+        self.record_audit(AuditAction::Execute, description, start, result.is_ok());
+        result?;
+        self.history.push(command);
+        self.redo_stack.clear();
+        if self.history.len() > self.max_history {
+            self.history.remove(0);
+            self.rebase_snapshots();
+        }
+        self.enforce_memory_budget();
+        self.maybe_take_snapshot();
+        Ok(())
+    }
+
+    /// Cap the approximate memory held by the undo history. Oldest entries
+    /// are evicted first once the summed `payload_size` exceeds `bytes`.
+    pub fn set_memory_budget(&mut self, bytes: usize) {
+        self.memory_budget = Some(bytes);
+        self.enforce_memory_budget();
+    }
+
+    pub fn history_bytes(&self) -> usize {
+        self.history.iter().map(|c| c.payload_size()).sum()
+    }
+
+    pub fn eviction_stats(&self) -> EvictionStats {
+        self.eviction_stats
+    }
+
+    fn enforce_memory_budget(&mut self) {
+        let Some(budget) = self.memory_budget else {
+            return;
+        };
+        // Always keep the newest entry so the last action stays undoable.
+        while self.history.len() > 1 && self.history_bytes() > budget {
+            let evicted = self.history.remove(0);
+            self.eviction_stats.evicted_commands += 1;
+            self.eviction_stats.evicted_bytes += evicted.payload_size();
+            self.rebase_snapshots();
+        }
+    }
+
+    pub fn undo(&mut self) -> Result<(), String> {
+        let description = self
+            .history
+            .last()
+            .map_or_else(|| "<empty>".to_string(), |c| c.description());
+        let start = Instant::now();
+        let result = match self.undo_strategy {
+            UndoStrategy::InverseOperation => {
+                let mut command = self.history.pop().ok_or("nothing to undo")?;
+                command.undo()?;
+                self.redo_stack.push(command);
+                Ok(())
+            }
+            UndoStrategy::SnapshotReplay { .. } => self.undo_via_snapshot(),
+        };
+        self.record_audit(AuditAction::Undo, description, start, result.is_ok());
+        result
+    }
+
+    pub fn redo(&mut self) -> Result<(), String> {
+        let mut command = self.redo_stack.pop().ok_or("nothing to redo")?;
+        let description = command.description();
+        let start = Instant::now();
+        let result = command.execute();
+        self.record_audit(AuditAction::Redo, description, start, result.is_ok());
+        result?;
+        self.history.push(command);
+        self.maybe_take_snapshot();
+        Ok(())
+    }
+
+    fn record_audit(
+        &mut self,
+        action: AuditAction,
+        description: String,
+        start: Instant,
+        success: bool,
+    ) {
+        self.audit_log.push(AuditEntry {
+            action,
+            description,
+            timestamp_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            duration_micros: start.elapsed().as_micros(),
+            success,
+        });
+    }
+
+    pub fn audit_log(&self) -> &[AuditEntry] {
+        &self.audit_log
+    }
+
+    /// Serialize the audit log for analysis outside the process.
+    pub fn export_audit(&self, format: AuditFormat) -> String {
+        match format {
+            AuditFormat::Json => {
+                let entries: Vec<String> = self
+                    .audit_log
+                    .iter()
+                    .map(|e| {
+                        format!(
+                            "{{\"action\":\"{}\",\"description\":\"{}\",\"timestamp\":{},\"duration_us\":{},\"success\":{}}}",
+                            e.action.as_str(),
+                            json_escape(&e.description),
+                            e.timestamp_secs,
+                            e.duration_micros,
+                            e.success
+                        )
+                    })
+                    .collect();
+                format!("[{}]", entries.join(","))
+            }
+            AuditFormat::Csv => {
+                let mut out = String::from("action,description,timestamp,duration_us,success\n");
+                for e in &self.audit_log {
+                    out.push_str(&format!(
+                        "{},{},{},{},{}\n",
+                        e.action.as_str(),
+                        csv_escape(&e.description),
+                        e.timestamp_secs,
+                        e.duration_micros,
+                        e.success
+                    ));
+                }
+                out
+            }
+        }
+    }
+
+    pub fn history_descriptions(&self) -> Vec<String> {
+        self.history.iter().map(|c| c.description()).collect()
+    }
+
+    /// What the next `undo` would do, for display in a UI. Does not mutate
+    /// anything.
+    pub fn preview_next_undo(&self) -> Option<String> {
+        self.history.last().map(|c| c.preview_undo())
+    }
+
+    /// Queue a command for later processing. The returned handle can cancel
+    /// the command any time before the queue is processed.
+    pub fn enqueue_command(&mut self, command: Box<dyn Command>) -> CommandHandle {
+        let cancelled = Rc::new(Cell::new(false));
+        let description = command.description();
+        self.queue.push_back(QueuedCommand {
+            command,
+            cancelled: cancelled.clone(),
+        });
+        CommandHandle {
+            cancelled,
+            description,
+        }
+    }
+
+    /// Execute every queued command in FIFO order. Cancelled entries are
+    /// skipped and reported separately from execution results.
+    pub fn process_queue(&mut self) -> QueueReport {
+        let mut report = QueueReport::default();
+        while let Some(entry) = self.queue.pop_front() {
+            if entry.cancelled.get() {
+                report.cancelled.push(entry.command.description());
+            } else {
+                let description = entry.command.description();
+                report
+                    .executed
+                    .push((description, self.execute_command(entry.command)));
+            }
+        }
+        report
+    }
+
+    fn maybe_take_snapshot(&mut self) {
+        if let UndoStrategy::SnapshotReplay { interval } = self.undo_strategy {
+            if self.history.len() % interval == 0 {
+                if let Some(editor) = &self.editor {
+                    self.snapshots.push(EditorMemento {
+                        content: editor.borrow().content().to_string(),
+                        command_count: self.history.len(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// When old history entries are evicted the stored command counts no
+    /// longer line up; drop snapshots that refer to evicted commands.
+    fn rebase_snapshots(&mut self) {
+        for snapshot in &mut self.snapshots {
+            snapshot.command_count = snapshot.command_count.saturating_sub(1);
+        }
+        self.snapshots.retain(|s| s.command_count > 0);
+        if let Some(editor) = &self.editor {
+            if self.snapshots.is_empty() {
+                // Always keep a base snapshot to restore from.
+                self.snapshots.push(EditorMemento {
+                    content: editor.borrow().content().to_string(),
+                    command_count: self.history.len(),
+                });
+            }
+        }
+    }
+
+    fn undo_via_snapshot(&mut self) -> Result<(), String> {
+        if self.history.is_empty() {
+            return Err("nothing to undo".to_string());
+        }
+        let editor = self.editor.clone().ok_or("no editor registered")?;
+        let target = self.history.len() - 1;
+
+        // Nearest snapshot at or before the target state.
+        let snapshot = self
+            .snapshots
+            .iter()
+            .rev()
+            .find(|s| s.command_count <= target)
+            .cloned()
+            .ok_or("no usable snapshot")?;
+
+        editor.borrow_mut().content = snapshot.content.clone();
+
+        // Replay the commands between the snapshot and the target state.
+        for command in &mut self.history[snapshot.command_count..target] {
+            command.execute()?;
+        }
+
+        // Drop snapshots newer than the state we restored to.
+        self.snapshots.retain(|s| s.command_count <= target);
+        let undone = self.history.pop().expect("history checked non-empty");
+        self.redo_stack.push(undone);
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Event sourcing: the command log as the authoritative editor state
+// ---------------------------------------------------------------------------
+
+/// A fact recorded after an edit was applied. Unlike a `Command`, an event is
+/// pure data and can be replayed at any time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandEvent {
+    Inserted { position: usize, text: String },
+    Deleted { position: usize, text: String },
+}
+
+/// Editor content rebuilt from an event log. The log is the source of truth;
+/// the projection is a derived, disposable view.
+#[derive(Debug, Default, Clone)]
+pub struct TextEditorProjection {
+    content: String,
+}
+
+impl TextEditorProjection {
+    /// Rebuild the current content by folding the whole event log.
+    pub fn from_events(events: &[CommandEvent]) -> Self {
+        Self::fold_up_to(events, events.len())
+    }
+
+    /// Reconstruct the historical content after the first `n` events, for
+    /// time-travel debugging.
+    pub fn fold_up_to(events: &[CommandEvent], n: usize) -> Self {
+        let mut projection = TextEditorProjection::default();
+        for event in &events[..n.min(events.len())] {
+            projection.apply(event);
+        }
+        projection
+    }
+
+    fn apply(&mut self, event: &CommandEvent) {
+        match event {
+            CommandEvent::Inserted { position, text } => {
+                self.content.insert_str(*position, text);
+            }
+            CommandEvent::Deleted { position, text } => {
+                self.content.drain(*position..*position + text.len());
+            }
+        }
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+// ---------------------------------------------------------------------------
+// CommandBus: CQRS-style dispatch by command type
+// ---------------------------------------------------------------------------
+
+/// Marker trait for plain-data command messages routed through the bus.
+pub trait BusCommand: Any {
+    fn name(&self) -> &str;
+}
+
+/// Middleware runs before every handler; returning `false` stops dispatch.
+pub type Middleware = Box<dyn Fn(&dyn BusCommand) -> bool>;
+
+/// Routes command messages to the one handler registered for their concrete
+/// type. Senders only know the message type, never the receiver.
+#[derive(Default)]
+pub struct CommandBus {
+    handlers: HashMap<TypeId, Box<dyn Fn(&dyn Any) -> Result<(), String>>>,
+    middleware: Vec<Middleware>,
+}
+
+impl CommandBus {
+    pub fn new() -> Self {
+        CommandBus::default()
+    }
+
+    /// Register the handler for command type `C`. Replaces any previous
+    /// handler for the same type.
+    pub fn register<C: BusCommand + 'static, F>(&mut self, handler: F)
+    where
+        F: Fn(&C) -> Result<(), String> + 'static,
+    {
+        self.handlers.insert(
+            TypeId::of::<C>(),
+            Box::new(move |any| {
+                let command = any
+                    .downcast_ref::<C>()
+                    .expect("dispatch keyed by TypeId, downcast cannot fail");
+                handler(command)
+            }),
+        );
+    }
+
+    /// Add middleware that runs (in registration order) before the handler.
+    pub fn add_middleware<F>(&mut self, middleware: F)
+    where
+        F: Fn(&dyn BusCommand) -> bool + 'static,
+    {
+        self.middleware.push(Box::new(middleware));
+    }
+
+    pub fn dispatch<C: BusCommand + 'static>(&self, command: &C) -> Result<(), String> {
+        for middleware in &self.middleware {
+            if !middleware(command) {
+                return Err(format!("'{}' rejected by middleware", command.name()));
+            }
+        }
+        match self.handlers.get(&TypeId::of::<C>()) {
+            Some(handler) => handler(command),
+            None => Err(format!("no handler registered for '{}'", command.name())),
+        }
+    }
+}
+
+pub struct InsertTextMessage {
+    pub position: usize,
+    pub text: String,
+}
+
+impl BusCommand for InsertTextMessage {
+    fn name(&self) -> &str {
+        "InsertText"
+    }
+}
+
+pub struct SwitchLightMessage {
+    pub on: bool,
+}
+
+impl BusCommand for SwitchLightMessage {
+    fn name(&self) -> &str {
+        "SwitchLight"
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Demo
+// ---------------------------------------------------------------------------
+
+fn demo_editor() {
+    println!("=== Text editor with undo/redo ===");
+    let editor = Rc::new(RefCell::new(TextEditor::new()));
+    let mut manager = CommandManager::new(10);
+
+    manager
+        .execute_command(Box::new(InsertCommand::new(editor.clone(), 0, "Hello")))
+        .unwrap();
+    manager
+        .execute_command(Box::new(InsertCommand::new(editor.clone(), 5, " World")))
+        .unwrap();
+    assert_eq!(editor.borrow().content(), "Hello World");
+    println!("content: {}", editor.borrow().content());
+
+    let delete = DeleteCommand::new(editor.clone(), 0, 5);
+    assert_eq!(delete.preview(), "would delete 'Hello' at position 0");
+    assert_eq!(
+        manager.preview_next_undo().as_deref(),
+        Some("would delete ' World' from position 5")
+    );
+
+    manager.undo().unwrap();
+    assert_eq!(editor.borrow().content(), "Hello");
+    manager.redo().unwrap();
+    assert_eq!(editor.borrow().content(), "Hello World");
+
+    let csv = manager.export_audit(AuditFormat::Csv);
+    // Two executes, one undo, one redo, in that order.
+    assert_eq!(csv.lines().filter(|l| l.starts_with("execute,")).count(), 2);
+    assert_eq!(csv.lines().filter(|l| l.starts_with("undo,")).count(), 1);
+    assert_eq!(csv.lines().filter(|l| l.starts_with("redo,")).count(), 1);
+    println!("audit (CSV):\n{}", csv);
+    println!("audit (JSON): {}", manager.export_audit(AuditFormat::Json));
+}
+
+fn demo_remote_control() {
+    println!("\n=== Remote control ===");
+    let light = Rc::new(RefCell::new(Light::new("Living room")));
+    let mut manager = CommandManager::new(10);
+
+    manager
+        .execute_command(Box::new(LightOnCommand::new(light.clone())))
+        .unwrap();
+    assert!(light.borrow().is_on());
+    manager
+        .execute_command(Box::new(LightOffCommand::new(light.clone())))
+        .unwrap();
+    assert!(!light.borrow().is_on());
+    // Undoing the "off" puts the light back on.
+    manager.undo().unwrap();
+    assert!(light.borrow().is_on());
+}
+
+/// Compare per-command inverse undo against snapshot+replay undo for a batch
+/// of large edits.
+fn benchmark_undo_strategies() {
+    println!("\n=== Undo strategy benchmark ===");
+    let big_chunk = "x".repeat(10_000);
+    let edits = 200;
+    let undos = 50;
+
+    // Inverse-operation undo.
+    let editor = Rc::new(RefCell::new(TextEditor::new()));
+    let mut manager = CommandManager::new(edits + 1);
+    for _ in 0..edits {
+        manager
+            .execute_command(Box::new(InsertCommand::new(editor.clone(), 0, &big_chunk)))
+            .unwrap();
+    }
+    let start = Instant::now();
+    for _ in 0..undos {
+        manager.undo().unwrap();
+    }
+    let inverse_time = start.elapsed();
+    assert_eq!(editor.borrow().content().len(), (edits - undos) * big_chunk.len());
+
+    // Snapshot+replay undo, snapshot every 10 commands.
+    let editor = Rc::new(RefCell::new(TextEditor::new()));
+    let mut manager = CommandManager::with_snapshot_undo(edits + 1, editor.clone(), 10);
+    for _ in 0..edits {
+        manager
+            .execute_command(Box::new(InsertCommand::new(editor.clone(), 0, &big_chunk)))
+            .unwrap();
+    }
+    let start = Instant::now();
+    for _ in 0..undos {
+        manager.undo().unwrap();
+    }
+    let snapshot_time = start.elapsed();
+    // Both strategies must land on the same document.
+    assert_eq!(editor.borrow().content().len(), (edits - undos) * big_chunk.len());
+
+    println!(
+        "{} undos of {}-char inserts: inverse {:?}, snapshot+replay {:?}",
+        undos,
+        big_chunk.len(),
+        inverse_time,
+        snapshot_time
+    );
+}
+
+fn demo_queue_cancellation() {
+    println!("\n=== Queued commands with cancellation ===");
+    let editor = Rc::new(RefCell::new(TextEditor::new()));
+    let mut manager = CommandManager::new(10);
+
+    let _keep = manager.enqueue_command(Box::new(InsertCommand::new(editor.clone(), 0, "kept")));
+    let revoked =
+        manager.enqueue_command(Box::new(InsertCommand::new(editor.clone(), 0, "revoked")));
+    revoked.cancel();
+
+    let report = manager.process_queue();
+    for (description, result) in &report.executed {
+        println!("executed: {} -> {:?}", description, result.is_ok());
+    }
+    for description in &report.cancelled {
+        println!("cancelled: {}", description);
+    }
+    assert_eq!(report.executed.len(), 1);
+    assert!(report.executed[0].1.is_ok());
+    assert_eq!(report.cancelled, vec!["Insert 'revoked' at 0".to_string()]);
+    // Only the kept command touched the editor.
+    assert_eq!(editor.borrow().content(), "kept");
+}
+
+fn demo_event_sourcing() {
+    println!("\n=== Event-sourced editor ===");
+    let events = vec![
+        CommandEvent::Inserted {
+            position: 0,
+            text: "Hello World".to_string(),
+        },
+        CommandEvent::Deleted {
+            position: 5,
+            text: " World".to_string(),
+        },
+        CommandEvent::Inserted {
+            position: 5,
+            text: ", event sourcing".to_string(),
+        },
+    ];
+
+    let current = TextEditorProjection::from_events(&events);
+    assert_eq!(current.content(), "Hello, event sourcing");
+    println!("current: {}", current.content());
+    let expected = ["", "Hello World", "Hello", "Hello, event sourcing"];
+    for n in 0..=events.len() {
+        let at_n = TextEditorProjection::fold_up_to(&events, n);
+        assert_eq!(at_n.content(), expected[n]);
+        println!("after {} events: '{}'", n, at_n.content());
+    }
+}
+
+fn demo_memory_budget() {
+    println!("\n=== Memory-budgeted history ===");
+    let editor = Rc::new(RefCell::new(TextEditor::new()));
+    let mut manager = CommandManager::new(1000);
+    manager.set_memory_budget(25_000);
+
+    for _ in 0..10 {
+        manager
+            .execute_command(Box::new(InsertCommand::new(
+                editor.clone(),
+                0,
+                &"y".repeat(5_000),
+            )))
+            .unwrap();
+    }
+    let stats = manager.eviction_stats();
+    // 10 x 5000-byte inserts against a 25000-byte budget: the oldest five
+    // are evicted, what remains fits the cap.
+    assert_eq!(stats.evicted_commands, 5);
+    assert_eq!(stats.evicted_bytes, 25_000);
+    assert!(manager.history_bytes() <= 25_000);
+    println!(
+        "history holds ~{} bytes after {} evictions ({} bytes dropped)",
+        manager.history_bytes(),
+        stats.evicted_commands,
+        stats.evicted_bytes
+    );
+}
+
+fn demo_command_bus() {
+    println!("\n=== Command bus ===");
+    let editor = Rc::new(RefCell::new(TextEditor::new()));
+    let light = Rc::new(RefCell::new(Light::new("Kitchen")));
+
+    let mut bus = CommandBus::new();
+    bus.add_middleware(|command| {
+        println!("[bus] dispatching {}", command.name());
+        true
+    });
+
+    let editor_handler = editor.clone();
+    bus.register(move |message: &InsertTextMessage| {
+        editor_handler
+            .borrow_mut()
+            .insert(message.position, &message.text)
+    });
+    let light_handler = light.clone();
+    bus.register(move |message: &SwitchLightMessage| {
+        if message.on {
+            light_handler.borrow_mut().turn_on();
+        } else {
+            light_handler.borrow_mut().turn_off();
+        }
+        Ok(())
+    });
+
+    bus.dispatch(&InsertTextMessage {
+        position: 0,
+        text: "routed via bus".to_string(),
+    })
+    .unwrap();
+    bus.dispatch(&SwitchLightMessage { on: true }).unwrap();
+    assert_eq!(editor.borrow().content(), "routed via bus");
+    assert!(light.borrow().is_on());
+
+    // Handler failures surface through dispatch.
+    let err = bus
+        .dispatch(&InsertTextMessage {
+            position: 999,
+            text: "oob".to_string(),
+        })
+        .unwrap_err();
+    assert_eq!(err, "position 999 out of bounds");
+    println!("editor content: {}", editor.borrow().content());
+}
+
+fn main() {
+    demo_editor();
+    demo_remote_control();
+    benchmark_undo_strategies();
+    demo_command_bus();
+    demo_memory_budget();
+    demo_event_sourcing();
+    demo_queue_cancellation();
+}
@@ -0,0 +1,2912 @@
+#![allow(dead_code)]
+
+/// Facade Pattern
+///
+/// Provides a unified, simplified interface to a set of subsystems: a home
+/// theater, a smart home, and a computer boot sequence. Clients talk to the
+/// facade; the facade orchestrates the fiddly subsystem calls.
+
+#[path = "command.rs"]
+mod command;
+#[path = "observer.rs"]
+mod observer;
+
+use command::{Command, CommandManager};
+use observer::{Observer, Subject};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Duration;
+
+// ---------------------------------------------------------------------------
+// Home theater subsystems
+// ---------------------------------------------------------------------------
+
+pub struct Projector {
+    on: bool,
+    input: String,
+}
+
+impl Projector {
+    pub fn new() -> Self {
+        Projector {
+            on: false,
+            input: "hdmi1".to_string(),
+        }
+    }
+
+    pub fn power_on(&mut self) -> String {
+        self.on = true;
+        "Projector: on".to_string()
+    }
+
+    pub fn power_off(&mut self) -> String {
+        self.on = false;
+        "Projector: off".to_string()
+    }
+
+    pub fn set_input(&mut self, input: &str) -> String {
+        self.input = input.to_string();
+        format!("Projector: input {}", input)
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Lamp projectors draw heavily; standby still trickles.
+    pub fn watts(&self) -> f64 {
+        if self.on { 220.0 } else { 2.0 }
+    }
+}
+
+impl Default for Projector {
+    fn default() -> Self {
+        Projector::new()
+    }
+}
+
+pub struct SoundSystem {
+    on: bool,
+    volume: u32,
+}
+
+impl SoundSystem {
+    pub fn new() -> Self {
+        SoundSystem { on: false, volume: 0 }
+    }
+
+    pub fn power_on(&mut self) -> String {
+        self.on = true;
+        "Sound: on".to_string()
+    }
+
+    pub fn power_off(&mut self) -> String {
+        self.on = false;
+        "Sound: off".to_string()
+    }
+
+    pub fn set_volume(&mut self, volume: u32) -> String {
+        self.volume = volume.min(11);
+        format!("Sound: volume {}", self.volume)
+    }
+
+    pub fn volume(&self) -> u32 {
+        self.volume
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Base amplifier draw plus a per-step bump for volume.
+    pub fn watts(&self) -> f64 {
+        if self.on {
+            30.0 + f64::from(self.volume) * 5.0
+        } else {
+            1.0
+        }
+    }
+}
+
+impl Default for SoundSystem {
+    fn default() -> Self {
+        SoundSystem::new()
+    }
+}
+
+pub struct Lights {
+    brightness: u8,
+}
+
+impl Lights {
+    pub fn new() -> Self {
+        Lights { brightness: 100 }
+    }
+
+    /// 0 is off, 100 is full brightness.
+    pub fn dim(&mut self, level: u8) -> String {
+        self.brightness = level.min(100);
+        format!("Lights: {}%", self.brightness)
+    }
+
+    pub fn on(&mut self) -> String {
+        self.dim(100)
+    }
+
+    pub fn off(&mut self) -> String {
+        self.dim(0)
+    }
+
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// Dimming scales draw linearly down from the full-brightness figure.
+    pub fn watts(&self) -> f64 {
+        60.0 * f64::from(self.brightness) / 100.0
+    }
+}
+
+impl Default for Lights {
+    fn default() -> Self {
+        Lights::new()
+    }
+}
+
+pub struct MediaPlayer {
+    on: bool,
+    playing: Option<String>,
+}
+
+impl MediaPlayer {
+    pub fn new() -> Self {
+        MediaPlayer {
+            on: false,
+            playing: None,
+        }
+    }
+
+    pub fn power_on(&mut self) -> String {
+        self.on = true;
+        "Player: on".to_string()
+    }
+
+    pub fn power_off(&mut self) -> String {
+        self.on = false;
+        self.playing = None;
+        "Player: off".to_string()
+    }
+
+    pub fn play(&mut self, title: &str) -> String {
+        self.playing = Some(title.to_string());
+        format!("Player: playing \"{}\"", title)
+    }
+
+    pub fn stop(&mut self) -> String {
+        self.playing = None;
+        "Player: stopped".to_string()
+    }
+
+    pub fn now_playing(&self) -> Option<&str> {
+        self.playing.as_deref()
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    pub fn watts(&self) -> f64 {
+        if self.on { 15.0 } else { 0.5 }
+    }
+}
+
+impl Default for MediaPlayer {
+    fn default() -> Self {
+        MediaPlayer::new()
+    }
+}
+
+pub struct PopcornPopper {
+    on: bool,
+}
+
+impl PopcornPopper {
+    pub fn new() -> Self {
+        PopcornPopper { on: false }
+    }
+
+    pub fn power_on(&mut self) -> String {
+        self.on = true;
+        "Popper: on".to_string()
+    }
+
+    pub fn power_off(&mut self) -> String {
+        self.on = false;
+        "Popper: off".to_string()
+    }
+
+    pub fn pop(&mut self) -> String {
+        "Popper: popping".to_string()
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    pub fn watts(&self) -> f64 {
+        if self.on { 800.0 } else { 0.0 }
+    }
+}
+
+impl Default for PopcornPopper {
+    fn default() -> Self {
+        PopcornPopper::new()
+    }
+}
+
+pub struct StreamingBox {
+    on: bool,
+    apps: Vec<String>,
+    signed_in: HashMap<String, String>,
+    playing: Option<(String, String)>,
+    paused: bool,
+}
+
+impl StreamingBox {
+    pub fn new() -> Self {
+        StreamingBox {
+            on: false,
+            apps: Vec::new(),
+            signed_in: HashMap::new(),
+            playing: None,
+            paused: false,
+        }
+    }
+
+    pub fn power_on(&mut self) -> String {
+        self.on = true;
+        "Streaming: on".to_string()
+    }
+
+    pub fn power_off(&mut self) -> String {
+        self.on = false;
+        self.playing = None;
+        self.paused = false;
+        "Streaming: off".to_string()
+    }
+
+    pub fn install_app(&mut self, service: &str) -> String {
+        if !self.apps.iter().any(|a| a == service) {
+            self.apps.push(service.to_string());
+        }
+        format!("Streaming: installed {}", service)
+    }
+
+    pub fn has_app(&self, service: &str) -> bool {
+        self.apps.iter().any(|a| a == service)
+    }
+
+    pub fn sign_in(&mut self, service: &str, account: &str) -> Result<String, String> {
+        if !self.has_app(service) {
+            return Err(format!("streaming: \"{}\" is not installed", service));
+        }
+        self.signed_in.insert(service.to_string(), account.to_string());
+        Ok(format!("Streaming: {} signed in as {}", service, account))
+    }
+
+    pub fn is_signed_in(&self, service: &str) -> bool {
+        self.signed_in.contains_key(service)
+    }
+
+    pub fn play(&mut self, service: &str, title: &str) -> Result<String, String> {
+        if !self.has_app(service) {
+            return Err(format!("streaming: \"{}\" is not installed", service));
+        }
+        if !self.is_signed_in(service) {
+            return Err(format!("streaming: not signed in to \"{}\"", service));
+        }
+        self.playing = Some((service.to_string(), title.to_string()));
+        self.paused = false;
+        Ok(format!("Streaming: {} playing \"{}\"", service, title))
+    }
+
+    pub fn pause(&mut self) -> String {
+        if self.playing.is_some() {
+            self.paused = true;
+            "Streaming: paused".to_string()
+        } else {
+            "Streaming: nothing playing".to_string()
+        }
+    }
+
+    pub fn resume(&mut self) -> String {
+        if self.playing.is_some() {
+            self.paused = false;
+            "Streaming: resumed".to_string()
+        } else {
+            "Streaming: nothing playing".to_string()
+        }
+    }
+
+    pub fn now_streaming(&self) -> Option<(&str, &str)> {
+        self.playing
+            .as_ref()
+            .map(|(s, t)| (s.as_str(), t.as_str()))
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    pub fn watts(&self) -> f64 {
+        if self.on { 8.0 } else { 0.5 }
+    }
+}
+
+impl Default for StreamingBox {
+    fn default() -> Self {
+        StreamingBox::new()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Scenes: data-driven action sequences
+// ---------------------------------------------------------------------------
+
+/// One subsystem call: which device, what to do, and an optional parameter.
+/// Parameters may contain `{placeholders}` filled in at run time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneAction {
+    pub device: String,
+    pub action: String,
+    pub param: Option<String>,
+}
+
+/// An ordered list of subsystem actions under a name. Scenes are plain data
+/// so users can author their own instead of relying on hardcoded sequences.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub name: String,
+    pub actions: Vec<SceneAction>,
+}
+
+/// Parses scenes from a small TOML subset: `[name]` starts a scene and each
+/// `device.action = value` line appends an action in order. Values may be
+/// bare words, integers, or double-quoted strings; `#` starts a comment.
+///
+/// ```toml
+/// [movie_night]
+/// lights.dim = 10
+/// player.play = "{title}"
+/// ```
+pub fn parse_scenes(text: &str) -> Result<Vec<Scene>, String> {
+    let mut scenes: Vec<Scene> = Vec::new();
+    for (idx, raw) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = match raw.find('#') {
+            Some(pos) => &raw[..pos],
+            None => raw,
+        }
+        .trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name
+                .strip_suffix(']')
+                .ok_or_else(|| format!("line {}: unterminated section header", line_no))?
+                .trim();
+            if name.is_empty() {
+                return Err(format!("line {}: empty scene name", line_no));
+            }
+            scenes.push(Scene {
+                name: name.to_string(),
+                actions: Vec::new(),
+            });
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected `device.action = value`", line_no))?;
+        let (device, action) = key
+            .trim()
+            .split_once('.')
+            .ok_or_else(|| format!("line {}: key must be `device.action`", line_no))?;
+        let value = value.trim();
+        let param = if let Some(quoted) = value.strip_prefix('"') {
+            let inner = quoted
+                .strip_suffix('"')
+                .ok_or_else(|| format!("line {}: unterminated string", line_no))?;
+            Some(inner.to_string())
+        } else if value.is_empty() {
+            None
+        } else {
+            Some(value.to_string())
+        };
+        let scene = scenes
+            .last_mut()
+            .ok_or_else(|| format!("line {}: action before any [scene] header", line_no))?;
+        scene.actions.push(SceneAction {
+            device: device.trim().to_string(),
+            action: action.trim().to_string(),
+            param,
+        });
+    }
+    Ok(scenes)
+}
+
+/// Inverse of [`parse_scenes`]: renders scenes back to the authoring format
+/// so they can be stored and re-parsed. Parameters are always quoted.
+pub fn scenes_to_toml(scenes: &[Scene]) -> String {
+    let mut out = String::new();
+    for scene in scenes {
+        out.push_str(&format!("[{}]\n", scene.name));
+        for action in &scene.actions {
+            match &action.param {
+                Some(param) => out.push_str(&format!(
+                    "{}.{} = \"{}\"\n",
+                    action.device, action.action, param
+                )),
+                None => out.push_str(&format!("{}.{} =\n", action.device, action.action)),
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn substitute(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut out = template.to_string();
+    for (name, value) in vars {
+        out = out.replace(&format!("{{{}}}", name), value);
+    }
+    out
+}
+
+/// The stock scenes, expressed in the same format users author their own in.
+pub const BUILTIN_SCENES: &str = r#"
+[watch_movie]
+popper.power = on
+popper.pop =
+lights.dim = 10
+projector.power = on
+projector.input = hdmi1
+sound.power = on
+sound.volume = 5
+player.power = on
+player.play = "{title}"
+
+[end_movie]
+player.stop =
+player.power = off
+sound.power = off
+projector.power = off
+lights.dim = 100
+popper.power = off
+
+[listen_to_music]
+lights.dim = 40
+sound.power = on
+sound.volume = 7
+player.power = on
+player.play = "{album}"
+
+[end_music]
+player.stop =
+player.power = off
+sound.power = off
+lights.dim = 100
+"#;
+
+// ---------------------------------------------------------------------------
+// Facade events
+// ---------------------------------------------------------------------------
+
+/// What the facades announce while they work, published through the observer
+/// module so monitors can watch activity instead of scraping the returned
+/// step strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FacadeEvent {
+    SceneStarted { scene: String },
+    SubsystemPowered { device: String, on: bool },
+    SceneCompleted { scene: String, steps: usize },
+}
+
+// ---------------------------------------------------------------------------
+// Home theater facade
+// ---------------------------------------------------------------------------
+
+/// One call instead of a dozen: orchestrates the projector, sound, lights,
+/// player and popper. The step sequences themselves are scenes (see
+/// [`BUILTIN_SCENES`]), so they can be replaced or extended with data.
+pub struct HomeTheaterFacade {
+    projector: Projector,
+    sound: SoundSystem,
+    lights: Lights,
+    player: MediaPlayer,
+    popper: PopcornPopper,
+    streaming: StreamingBox,
+    scenes: HashMap<String, Scene>,
+    observers: Vec<Rc<RefCell<dyn Observer<FacadeEvent>>>>,
+}
+
+impl HomeTheaterFacade {
+    pub fn new() -> Self {
+        let mut facade = HomeTheaterFacade {
+            projector: Projector::new(),
+            sound: SoundSystem::new(),
+            lights: Lights::new(),
+            player: MediaPlayer::new(),
+            popper: PopcornPopper::new(),
+            streaming: StreamingBox::new(),
+            scenes: HashMap::new(),
+            observers: Vec::new(),
+        };
+        for scene in parse_scenes(BUILTIN_SCENES).expect("builtin scenes parse") {
+            facade.install_scene(scene);
+        }
+        facade
+    }
+
+    /// Adds or replaces a scene by name.
+    pub fn install_scene(&mut self, scene: Scene) {
+        self.scenes.insert(scene.name.clone(), scene);
+    }
+
+    pub fn scene_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.scenes.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Runs a scene with no placeholder substitution.
+    pub fn run_scene(&mut self, name: &str) -> Result<Vec<String>, String> {
+        self.run_scene_with(name, &[])
+    }
+
+    /// Runs a scene, substituting `{var}` placeholders in parameters.
+    pub fn run_scene_with(
+        &mut self,
+        name: &str,
+        vars: &[(&str, &str)],
+    ) -> Result<Vec<String>, String> {
+        let scene = self
+            .scenes
+            .get(name)
+            .ok_or_else(|| format!("unknown scene \"{}\"", name))?
+            .clone();
+        self.notify_observers(&FacadeEvent::SceneStarted {
+            scene: name.to_string(),
+        });
+        let mut steps = Vec::with_capacity(scene.actions.len());
+        for action in &scene.actions {
+            let param = action.param.as_deref().map(|p| substitute(p, vars));
+            steps.push(self.apply_action(&action.device, &action.action, param.as_deref())?);
+            if action.action == "power" {
+                self.notify_observers(&FacadeEvent::SubsystemPowered {
+                    device: action.device.clone(),
+                    on: param.as_deref() == Some("on"),
+                });
+            }
+        }
+        self.notify_observers(&FacadeEvent::SceneCompleted {
+            scene: name.to_string(),
+            steps: steps.len(),
+        });
+        Ok(steps)
+    }
+
+    fn apply_action(
+        &mut self,
+        device: &str,
+        action: &str,
+        param: Option<&str>,
+    ) -> Result<String, String> {
+        let power = |on: Option<&str>| -> Result<bool, String> {
+            match on {
+                Some("on") => Ok(true),
+                Some("off") => Ok(false),
+                other => Err(format!("power wants on/off, got {:?}", other)),
+            }
+        };
+        match (device, action) {
+            ("lights", "dim") => {
+                let level: u8 = param
+                    .unwrap_or("")
+                    .parse()
+                    .map_err(|_| format!("lights.dim wants a level, got {:?}", param))?;
+                Ok(self.lights.dim(level))
+            }
+            ("projector", "power") => Ok(if power(param)? {
+                self.projector.power_on()
+            } else {
+                self.projector.power_off()
+            }),
+            ("projector", "input") => {
+                let input = param.ok_or("projector.input wants a source")?;
+                Ok(self.projector.set_input(input))
+            }
+            ("sound", "power") => Ok(if power(param)? {
+                self.sound.power_on()
+            } else {
+                self.sound.power_off()
+            }),
+            ("sound", "volume") => {
+                let volume: u32 = param
+                    .unwrap_or("")
+                    .parse()
+                    .map_err(|_| format!("sound.volume wants a number, got {:?}", param))?;
+                Ok(self.sound.set_volume(volume))
+            }
+            ("player", "power") => Ok(if power(param)? {
+                self.player.power_on()
+            } else {
+                self.player.power_off()
+            }),
+            ("player", "play") => {
+                let title = param.ok_or("player.play wants a title")?;
+                Ok(self.player.play(title))
+            }
+            ("player", "stop") => Ok(self.player.stop()),
+            ("popper", "power") => Ok(if power(param)? {
+                self.popper.power_on()
+            } else {
+                self.popper.power_off()
+            }),
+            ("popper", "pop") => Ok(self.popper.pop()),
+            ("streaming", "power") => Ok(if power(param)? {
+                self.streaming.power_on()
+            } else {
+                self.streaming.power_off()
+            }),
+            _ => Err(format!("unknown action {}.{}", device, action)),
+        }
+    }
+
+    pub fn install_streaming_app(&mut self, service: &str) -> String {
+        self.streaming.install_app(service)
+    }
+
+    pub fn sign_in_streaming(&mut self, service: &str, account: &str) -> Result<String, String> {
+        self.streaming.sign_in(service, account)
+    }
+
+    /// Sets the room up for streaming. Validates the service before touching
+    /// any hardware so a bad request leaves everything as it was.
+    pub fn watch_streaming(&mut self, service: &str, title: &str) -> Result<Vec<String>, String> {
+        if !self.streaming.has_app(service) {
+            return Err(format!("streaming: \"{}\" is not installed", service));
+        }
+        if !self.streaming.is_signed_in(service) {
+            return Err(format!("streaming: not signed in to \"{}\"", service));
+        }
+        self.notify_observers(&FacadeEvent::SceneStarted {
+            scene: "watch_streaming".to_string(),
+        });
+        let mut steps = vec![
+            self.lights.dim(15),
+            self.projector.power_on(),
+            self.projector.set_input("hdmi2"),
+            self.sound.power_on(),
+            self.sound.set_volume(6),
+            self.streaming.power_on(),
+        ];
+        for device in ["projector", "sound", "streaming"] {
+            self.notify_observers(&FacadeEvent::SubsystemPowered {
+                device: device.to_string(),
+                on: true,
+            });
+        }
+        steps.push(self.streaming.play(service, title)?);
+        self.notify_observers(&FacadeEvent::SceneCompleted {
+            scene: "watch_streaming".to_string(),
+            steps: steps.len(),
+        });
+        Ok(steps)
+    }
+
+    pub fn pause_streaming(&mut self) -> String {
+        self.streaming.pause()
+    }
+
+    pub fn resume_streaming(&mut self) -> String {
+        self.streaming.resume()
+    }
+
+    pub fn end_streaming(&mut self) -> Vec<String> {
+        vec![
+            self.streaming.power_off(),
+            self.sound.power_off(),
+            self.projector.power_off(),
+            self.lights.dim(100),
+        ]
+    }
+
+    pub fn now_streaming(&self) -> Option<(&str, &str)> {
+        self.streaming.now_streaming()
+    }
+
+    pub fn watch_movie(&mut self, title: &str) -> Vec<String> {
+        self.run_scene_with("watch_movie", &[("title", title)])
+            .expect("builtin scene")
+    }
+
+    pub fn end_movie(&mut self) -> Vec<String> {
+        self.run_scene("end_movie").expect("builtin scene")
+    }
+
+    pub fn listen_to_music(&mut self, album: &str) -> Vec<String> {
+        self.run_scene_with("listen_to_music", &[("album", album)])
+            .expect("builtin scene")
+    }
+
+    pub fn end_music(&mut self) -> Vec<String> {
+        self.run_scene("end_music").expect("builtin scene")
+    }
+
+    pub fn now_playing(&self) -> Option<&str> {
+        self.player.now_playing()
+    }
+
+    /// Every subsystem knob as one flat JSON object, including scenes that
+    /// were added or changed at run time (builtins ride along as TOML text
+    /// only when they differ from stock). Observers are not part of the
+    /// snapshot; monitoring is wiring, not state.
+    pub fn to_snapshot(&self) -> String {
+        let mut fields: Vec<(String, String)> = vec![
+            ("projector_on".into(), self.projector.on.to_string()),
+            ("projector_input".into(), self.projector.input.clone()),
+            ("sound_on".into(), self.sound.on.to_string()),
+            ("sound_volume".into(), self.sound.volume.to_string()),
+            ("lights".into(), self.lights.brightness.to_string()),
+            ("player_on".into(), self.player.on.to_string()),
+            ("popper_on".into(), self.popper.on.to_string()),
+            ("streaming_on".into(), self.streaming.on.to_string()),
+        ];
+        if let Some(title) = self.player.now_playing() {
+            fields.push(("player_playing".into(), title.to_string()));
+        }
+        if !self.streaming.apps.is_empty() {
+            fields.push(("streaming_apps".into(), self.streaming.apps.join(",")));
+        }
+        if !self.streaming.signed_in.is_empty() {
+            let mut accounts: Vec<String> = self
+                .streaming
+                .signed_in
+                .iter()
+                .map(|(service, account)| format!("{}={}", service, account))
+                .collect();
+            accounts.sort_unstable();
+            fields.push(("streaming_accounts".into(), accounts.join(",")));
+        }
+        if let Some((service, title)) = self.streaming.now_streaming() {
+            fields.push(("streaming_service".into(), service.to_string()));
+            fields.push(("streaming_title".into(), title.to_string()));
+        }
+        let stock: HashMap<String, Scene> = parse_scenes(BUILTIN_SCENES)
+            .expect("builtin scenes parse")
+            .into_iter()
+            .map(|scene| (scene.name.clone(), scene))
+            .collect();
+        let mut custom: Vec<Scene> = self
+            .scenes
+            .values()
+            .filter(|scene| stock.get(&scene.name) != Some(scene))
+            .cloned()
+            .collect();
+        custom.sort_by(|a, b| a.name.cmp(&b.name));
+        if !custom.is_empty() {
+            fields.push(("custom_scenes".into(), scenes_to_toml(&custom)));
+        }
+        fields.sort();
+        let body: Vec<String> = fields
+            .iter()
+            .map(|(k, v)| format!("\"{}\":\"{}\"", k, json_escape(v)))
+            .collect();
+        format!("{{{}}}", body.join(","))
+    }
+
+    /// Rebuilds a facade in exactly the saved state.
+    pub fn from_snapshot(text: &str) -> Result<Self, String> {
+        let fields = parse_flat_json(text)?;
+        let get_bool = |key: &str| -> Result<bool, String> {
+            match fields.get(key).map(String::as_str) {
+                None | Some("false") => Ok(false),
+                Some("true") => Ok(true),
+                Some(other) => Err(format!("{}: expected bool, got {:?}", key, other)),
+            }
+        };
+        let mut facade = HomeTheaterFacade::new();
+        facade.projector.on = get_bool("projector_on")?;
+        if let Some(input) = fields.get("projector_input") {
+            facade.projector.input = input.clone();
+        }
+        facade.sound.on = get_bool("sound_on")?;
+        if let Some(volume) = fields.get("sound_volume") {
+            facade.sound.volume = volume
+                .parse()
+                .map_err(|_| format!("sound_volume: bad number {:?}", volume))?;
+        }
+        if let Some(level) = fields.get("lights") {
+            facade.lights.brightness = level
+                .parse()
+                .map_err(|_| format!("lights: bad number {:?}", level))?;
+        }
+        facade.player.on = get_bool("player_on")?;
+        facade.player.playing = fields.get("player_playing").cloned();
+        facade.popper.on = get_bool("popper_on")?;
+        facade.streaming.on = get_bool("streaming_on")?;
+        if let Some(apps) = fields.get("streaming_apps") {
+            facade.streaming.apps = apps.split(',').map(str::to_string).collect();
+        }
+        if let Some(accounts) = fields.get("streaming_accounts") {
+            for entry in accounts.split(',') {
+                let (service, account) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format!("streaming_accounts: bad entry {:?}", entry))?;
+                facade
+                    .streaming
+                    .signed_in
+                    .insert(service.to_string(), account.to_string());
+            }
+        }
+        if let (Some(service), Some(title)) =
+            (fields.get("streaming_service"), fields.get("streaming_title"))
+        {
+            facade.streaming.playing = Some((service.clone(), title.clone()));
+        }
+        if let Some(toml) = fields.get("custom_scenes") {
+            for scene in parse_scenes(toml)? {
+                facade.install_scene(scene);
+            }
+        }
+        Ok(facade)
+    }
+
+    pub fn save_state(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_snapshot()).map_err(|e| e.to_string())
+    }
+
+    pub fn load_state(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        HomeTheaterFacade::from_snapshot(&text)
+    }
+
+    /// Per-device kWh and cost if the room stayed as it is now.
+    pub fn get_energy_report(&self, duration: Duration) -> EnergyReport {
+        EnergyReport::from_draws(
+            vec![
+                ("projector", self.projector.watts()),
+                ("sound", self.sound.watts()),
+                ("lights", self.lights.watts()),
+                ("player", self.player.watts()),
+                ("popper", self.popper.watts()),
+                ("streaming", self.streaming.watts()),
+            ],
+            duration,
+        )
+    }
+
+    /// Applies a snapshot in place, keeping registered observers wired.
+    pub fn restore_snapshot(&mut self, text: &str) -> Result<(), String> {
+        let restored = HomeTheaterFacade::from_snapshot(text)?;
+        self.projector = restored.projector;
+        self.sound = restored.sound;
+        self.lights = restored.lights;
+        self.player = restored.player;
+        self.popper = restored.popper;
+        self.streaming = restored.streaming;
+        self.scenes = restored.scenes;
+        Ok(())
+    }
+
+    /// Parses and runs one phrase; see [`parse_command`] for the grammar.
+    pub fn handle_command(&mut self, input: &str) -> Result<CommandOutcome, String> {
+        let command = parse_command(input)?;
+        let steps = match &command {
+            TheaterCommand::WatchMovie(title) => self.watch_movie(title),
+            TheaterCommand::EndMovie => self.end_movie(),
+            TheaterCommand::ListenToMusic(album) => self.listen_to_music(album),
+            TheaterCommand::EndMusic => self.end_music(),
+            TheaterCommand::VolumeUp => {
+                vec![self.sound.set_volume(self.sound.volume().saturating_add(1))]
+            }
+            TheaterCommand::VolumeDown => {
+                vec![self.sound.set_volume(self.sound.volume().saturating_sub(1))]
+            }
+            TheaterCommand::SetVolume(level) => vec![self.sound.set_volume(*level)],
+            TheaterCommand::RunScene(name) => self.run_scene(name)?,
+        };
+        Ok(CommandOutcome { command, steps })
+    }
+
+    pub fn lights_brightness(&self) -> u8 {
+        self.lights.brightness()
+    }
+
+    pub fn volume(&self) -> u32 {
+        self.sound.volume()
+    }
+}
+
+impl Subject<FacadeEvent> for HomeTheaterFacade {
+    fn register_observer(&mut self, observer: Rc<RefCell<dyn Observer<FacadeEvent>>>) {
+        self.observers.push(observer);
+    }
+
+    fn remove_observer(&mut self, name: &str) {
+        self.observers.retain(|o| o.borrow().name() != name);
+    }
+
+    /// Like the weather station, the facade does not care whether a monitor
+    /// failed to keep up, so delivery errors are discarded.
+    fn notify_observers(&mut self, event: &FacadeEvent) {
+        let observers: Vec<_> = self.observers.to_vec();
+        for observer in observers {
+            let mut observer = observer.borrow_mut();
+            if observer.interested_in(event) {
+                let _ = observer.notify(event);
+            }
+        }
+    }
+}
+
+impl Default for HomeTheaterFacade {
+    fn default() -> Self {
+        HomeTheaterFacade::new()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Energy accounting
+// ---------------------------------------------------------------------------
+
+/// Unheated-room temperature the thermostat model heats against.
+const AMBIENT_C: f64 = 18.0;
+/// Flat tariff used for cost estimates.
+pub const PRICE_PER_KWH: f64 = 0.30;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnergyLine {
+    pub device: String,
+    pub watts: f64,
+    pub kwh: f64,
+    pub cost: f64,
+}
+
+/// Estimated consumption if the current subsystem states were held for some
+/// duration — a way to compare scenes, not a meter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnergyReport {
+    pub lines: Vec<EnergyLine>,
+    pub total_kwh: f64,
+    pub total_cost: f64,
+}
+
+impl EnergyReport {
+    fn from_draws(draws: Vec<(&str, f64)>, duration: Duration) -> Self {
+        let hours = duration.as_secs_f64() / 3600.0;
+        let lines: Vec<EnergyLine> = draws
+            .into_iter()
+            .map(|(device, watts)| {
+                let kwh = watts * hours / 1000.0;
+                EnergyLine {
+                    device: device.to_string(),
+                    watts,
+                    kwh,
+                    cost: kwh * PRICE_PER_KWH,
+                }
+            })
+            .collect();
+        let total_kwh = lines.iter().map(|l| l.kwh).sum();
+        EnergyReport {
+            total_cost: total_kwh * PRICE_PER_KWH,
+            total_kwh,
+            lines,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!("{:.2} kWh, {:.2} cost", self.total_kwh, self.total_cost)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Text commands for the home theater
+// ---------------------------------------------------------------------------
+
+/// A recognised command, the structured half of `handle_command`. Parsing
+/// and execution are separate so callers can inspect or log what a phrase
+/// meant before running it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TheaterCommand {
+    WatchMovie(String),
+    EndMovie,
+    ListenToMusic(String),
+    EndMusic,
+    VolumeUp,
+    VolumeDown,
+    SetVolume(u32),
+    RunScene(String),
+}
+
+/// Parses a small set of natural-ish phrases:
+///
+/// ```text
+/// watch movie <title>      end movie
+/// listen to <album>        end music
+/// volume up | down | <n>   preset <scene>  (or: scene <scene>)
+/// ```
+pub fn parse_command(input: &str) -> Result<TheaterCommand, String> {
+    let input = input.trim();
+    let mut words = input.split_whitespace();
+    let verb = words.next().ok_or("empty command")?;
+    let rest = |skip: usize| -> String {
+        input
+            .split_whitespace()
+            .skip(skip)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    match (verb, words.next()) {
+        ("watch", Some("movie")) => {
+            let title = rest(2);
+            if title.is_empty() {
+                return Err("watch movie needs a title".to_string());
+            }
+            Ok(TheaterCommand::WatchMovie(title))
+        }
+        ("end", Some("movie")) => Ok(TheaterCommand::EndMovie),
+        ("listen", Some("to")) => {
+            let album = rest(2);
+            if album.is_empty() {
+                return Err("listen to needs an album".to_string());
+            }
+            Ok(TheaterCommand::ListenToMusic(album))
+        }
+        ("end", Some("music")) => Ok(TheaterCommand::EndMusic),
+        ("volume", Some("up")) => Ok(TheaterCommand::VolumeUp),
+        ("volume", Some("down")) => Ok(TheaterCommand::VolumeDown),
+        ("volume", Some(level)) => level
+            .parse()
+            .map(TheaterCommand::SetVolume)
+            .map_err(|_| format!("volume wants up, down, or a number, got \"{}\"", level)),
+        ("preset" | "scene", Some(_)) => Ok(TheaterCommand::RunScene(rest(1))),
+        ("volume", None) => Err("volume wants up, down, or a number".to_string()),
+        ("preset" | "scene", None) => Err(format!("{} needs a scene name", verb)),
+        _ => Err(format!("unknown command \"{}\"", input)),
+    }
+}
+
+/// What running a command did: the parsed form plus the subsystem steps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandOutcome {
+    pub command: TheaterCommand,
+    pub steps: Vec<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Undoable scenes via the command module
+// ---------------------------------------------------------------------------
+
+/// Runs one scene, remembering the full subsystem state from just before so
+/// undo restores volume, brightness, and power exactly. State capture rides
+/// on the snapshot format rather than per-subsystem inverse operations.
+struct SceneCommand {
+    theater: Rc<RefCell<HomeTheaterFacade>>,
+    scene: String,
+    vars: Vec<(String, String)>,
+    before: Option<String>,
+}
+
+impl Command for SceneCommand {
+    fn execute(&mut self) -> Result<(), String> {
+        let mut theater = self.theater.borrow_mut();
+        let before = theater.to_snapshot();
+        let vars: Vec<(&str, &str)> = self
+            .vars
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        theater.run_scene_with(&self.scene, &vars)?;
+        self.before = Some(before);
+        Ok(())
+    }
+
+    fn undo(&mut self) -> Result<(), String> {
+        let before = self.before.take().ok_or("scene was never run")?;
+        self.theater.borrow_mut().restore_snapshot(&before)
+    }
+
+    fn description(&self) -> String {
+        format!("scene {}", self.scene)
+    }
+}
+
+/// A theater whose scene runs go through a [`CommandManager`], so the last
+/// scenes can be unwound (and replayed) like editor operations.
+pub struct UndoableTheater {
+    theater: Rc<RefCell<HomeTheaterFacade>>,
+    manager: CommandManager,
+}
+
+impl UndoableTheater {
+    pub fn new() -> Self {
+        UndoableTheater {
+            theater: Rc::new(RefCell::new(HomeTheaterFacade::new())),
+            manager: CommandManager::new(DEFAULT_SCENE_HISTORY),
+        }
+    }
+
+    pub fn theater(&self) -> Rc<RefCell<HomeTheaterFacade>> {
+        self.theater.clone()
+    }
+
+    pub fn run_scene(&mut self, name: &str) -> Result<(), String> {
+        self.run_scene_with(name, &[])
+    }
+
+    pub fn run_scene_with(&mut self, name: &str, vars: &[(&str, &str)]) -> Result<(), String> {
+        self.manager.execute_command(Box::new(SceneCommand {
+            theater: self.theater.clone(),
+            scene: name.to_string(),
+            vars: vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            before: None,
+        }))
+    }
+
+    pub fn watch_movie(&mut self, title: &str) -> Result<(), String> {
+        self.run_scene_with("watch_movie", &[("title", title)])
+    }
+
+    pub fn undo_last_scene(&mut self) -> Result<(), String> {
+        self.manager.undo()
+    }
+
+    pub fn redo_scene(&mut self) -> Result<(), String> {
+        self.manager.redo()
+    }
+}
+
+impl Default for UndoableTheater {
+    fn default() -> Self {
+        UndoableTheater::new()
+    }
+}
+
+/// Scene runs kept undoable; older ones fall off like editor history.
+const DEFAULT_SCENE_HISTORY: usize = 16;
+
+// ---------------------------------------------------------------------------
+// Smart home subsystems and facade
+// ---------------------------------------------------------------------------
+
+pub struct Thermostat {
+    target_c: f64,
+}
+
+impl Thermostat {
+    pub fn new() -> Self {
+        Thermostat { target_c: 21.0 }
+    }
+
+    pub fn set_target(&mut self, target_c: f64) -> String {
+        self.target_c = target_c;
+        format!("Thermostat: {:.1}C", target_c)
+    }
+
+    pub fn target(&self) -> f64 {
+        self.target_c
+    }
+
+    /// Heats whenever the target sits above ambient; otherwise idles.
+    pub fn watts(&self) -> f64 {
+        if self.target_c > AMBIENT_C { 1200.0 } else { 10.0 }
+    }
+}
+
+impl Default for Thermostat {
+    fn default() -> Self {
+        Thermostat::new()
+    }
+}
+
+pub struct SecuritySystem {
+    armed: bool,
+}
+
+impl SecuritySystem {
+    pub fn new() -> Self {
+        SecuritySystem { armed: false }
+    }
+
+    pub fn arm(&mut self) -> String {
+        self.armed = true;
+        "Security: armed".to_string()
+    }
+
+    pub fn disarm(&mut self) -> String {
+        self.armed = false;
+        "Security: disarmed".to_string()
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.armed
+    }
+
+    pub fn watts(&self) -> f64 {
+        if self.armed { 5.0 } else { 2.0 }
+    }
+}
+
+impl Default for SecuritySystem {
+    fn default() -> Self {
+        SecuritySystem::new()
+    }
+}
+
+pub struct MusicSystem {
+    on: bool,
+    playlist: Option<String>,
+}
+
+impl MusicSystem {
+    pub fn new() -> Self {
+        MusicSystem {
+            on: false,
+            playlist: None,
+        }
+    }
+
+    pub fn play(&mut self, playlist: &str) -> String {
+        self.on = true;
+        self.playlist = Some(playlist.to_string());
+        format!("Music: playing \"{}\"", playlist)
+    }
+
+    pub fn stop(&mut self) -> String {
+        self.on = false;
+        self.playlist = None;
+        "Music: off".to_string()
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    pub fn watts(&self) -> f64 {
+        if self.on { 25.0 } else { 1.0 }
+    }
+}
+
+impl Default for MusicSystem {
+    fn default() -> Self {
+        MusicSystem::new()
+    }
+}
+
+/// Canned whole-house routines over lights, thermostat, security and music.
+pub struct SmartHomeFacade {
+    lights: Lights,
+    thermostat: Thermostat,
+    security: SecuritySystem,
+    music: MusicSystem,
+    observers: Vec<Rc<RefCell<dyn Observer<FacadeEvent>>>>,
+    rules: Vec<AutomationRule>,
+    alerts: Vec<String>,
+    vacation: Option<VacationPlan>,
+}
+
+impl SmartHomeFacade {
+    pub fn new() -> Self {
+        SmartHomeFacade {
+            lights: Lights::new(),
+            thermostat: Thermostat::new(),
+            security: SecuritySystem::new(),
+            music: MusicSystem::new(),
+            observers: Vec::new(),
+            rules: Vec::new(),
+            alerts: Vec::new(),
+            vacation: None,
+        }
+    }
+
+    pub fn add_rule(&mut self, rule: AutomationRule) {
+        self.rules.push(rule);
+    }
+
+    /// Raised alert messages, oldest first.
+    pub fn alerts(&self) -> &[String] {
+        &self.alerts
+    }
+
+    /// Feeds one sensor reading through every rule. All matching rules fire
+    /// (automation rules are independent, unlike access policies). Returns a
+    /// description per action taken.
+    pub fn handle_sensor(&mut self, event: &SensorEvent, minute_of_day: u16) -> Vec<String> {
+        let matched: Vec<AutomationRule> = self
+            .rules
+            .iter()
+            .filter(|rule| {
+                rule.pattern.matches(event)
+                    && rule.active_at(minute_of_day)
+                    && (!rule.requires_armed || self.security.is_armed())
+            })
+            .cloned()
+            .collect();
+        let mut actions = Vec::new();
+        for rule in matched {
+            let done = match rule.action {
+                AutomationAction::Alert => {
+                    let message = format!("{}: {}", rule.name, event.describe());
+                    self.alerts.push(message.clone());
+                    format!("alert ({})", message)
+                }
+                AutomationAction::SetLights(level) => self.lights.dim(level),
+                AutomationAction::SetThermostat(target) => self.thermostat.set_target(target),
+                AutomationAction::Run(routine) => {
+                    let steps = routine.run(self);
+                    format!("{} ({} steps)", routine.as_str(), steps.len())
+                }
+            };
+            actions.push(format!("[{}] {}", rule.name, done));
+        }
+        actions
+    }
+
+    /// Routine bodies emit started/completed brackets plus power events for
+    /// the music system, so every facade entry point reports the same way.
+    fn run_routine(
+        &mut self,
+        name: &str,
+        body: impl FnOnce(&mut Self) -> Vec<String>,
+    ) -> Vec<String> {
+        self.notify_observers(&FacadeEvent::SceneStarted {
+            scene: name.to_string(),
+        });
+        let music_was_on = self.music.is_on();
+        let steps = body(self);
+        if self.music.is_on() != music_was_on {
+            self.notify_observers(&FacadeEvent::SubsystemPowered {
+                device: "music".to_string(),
+                on: self.music.is_on(),
+            });
+        }
+        self.notify_observers(&FacadeEvent::SceneCompleted {
+            scene: name.to_string(),
+            steps: steps.len(),
+        });
+        steps
+    }
+
+    pub fn good_morning(&mut self) -> Vec<String> {
+        self.run_routine("good_morning", |home| {
+            vec![
+                home.security.disarm(),
+                home.lights.dim(80),
+                home.thermostat.set_target(21.0),
+                home.music.play("morning mix"),
+            ]
+        })
+    }
+
+    pub fn good_night(&mut self) -> Vec<String> {
+        self.run_routine("good_night", |home| {
+            vec![
+                home.music.stop(),
+                home.lights.off(),
+                home.thermostat.set_target(17.0),
+                home.security.arm(),
+            ]
+        })
+    }
+
+    pub fn leave_home(&mut self) -> Vec<String> {
+        self.run_routine("leave_home", |home| {
+            vec![
+                home.music.stop(),
+                home.lights.off(),
+                home.thermostat.set_target(15.0),
+                home.security.arm(),
+            ]
+        })
+    }
+
+    pub fn arrive_home(&mut self) -> Vec<String> {
+        self.run_routine("arrive_home", |home| {
+            vec![
+                home.security.disarm(),
+                home.lights.dim(70),
+                home.thermostat.set_target(21.0),
+            ]
+        })
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.security.is_armed()
+    }
+
+    pub fn target_temperature(&self) -> f64 {
+        self.thermostat.target()
+    }
+
+    pub fn lights_brightness(&self) -> u8 {
+        self.lights.brightness()
+    }
+
+    /// Per-device kWh and cost if the house stayed as it is now.
+    pub fn get_energy_report(&self, duration: Duration) -> EnergyReport {
+        EnergyReport::from_draws(
+            vec![
+                ("lights", self.lights.watts()),
+                ("thermostat", self.thermostat.watts()),
+                ("security", self.security.watts()),
+                ("music", self.music.watts()),
+            ],
+            duration,
+        )
+    }
+
+    /// Same flat-JSON shape as the theater snapshot, much less state.
+    pub fn to_snapshot(&self) -> String {
+        let mut fields: Vec<(String, String)> = vec![
+            ("armed".into(), self.security.armed.to_string()),
+            ("lights".into(), self.lights.brightness.to_string()),
+            ("target_c".into(), format!("{:.1}", self.thermostat.target_c)),
+        ];
+        if let Some(playlist) = &self.music.playlist {
+            fields.push(("music_playlist".into(), playlist.clone()));
+        }
+        fields.sort();
+        let body: Vec<String> = fields
+            .iter()
+            .map(|(k, v)| format!("\"{}\":\"{}\"", k, json_escape(v)))
+            .collect();
+        format!("{{{}}}", body.join(","))
+    }
+
+    pub fn from_snapshot(text: &str) -> Result<Self, String> {
+        let fields = parse_flat_json(text)?;
+        let mut home = SmartHomeFacade::new();
+        if let Some(armed) = fields.get("armed") {
+            home.security.armed = armed == "true";
+        }
+        if let Some(level) = fields.get("lights") {
+            home.lights.brightness = level
+                .parse()
+                .map_err(|_| format!("lights: bad number {:?}", level))?;
+        }
+        if let Some(target) = fields.get("target_c") {
+            home.thermostat.target_c = target
+                .parse()
+                .map_err(|_| format!("target_c: bad number {:?}", target))?;
+        }
+        if let Some(playlist) = fields.get("music_playlist") {
+            home.music.play(playlist);
+        }
+        Ok(home)
+    }
+
+    pub fn save_state(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_snapshot()).map_err(|e| e.to_string())
+    }
+
+    pub fn load_state(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        SmartHomeFacade::from_snapshot(&text)
+    }
+
+    /// Applies a snapshot in place, keeping observers and rules wired.
+    pub fn restore_snapshot(&mut self, text: &str) -> Result<(), String> {
+        let restored = SmartHomeFacade::from_snapshot(text)?;
+        self.lights = restored.lights;
+        self.thermostat = restored.thermostat;
+        self.security = restored.security;
+        self.music = restored.music;
+        Ok(())
+    }
+
+    /// Arms the house and lays out a randomized evening light/music plan for
+    /// `days` days. The plan is a pure function of the seed, so tests can
+    /// assert on it; the current settings are saved for [`end_vacation`].
+    ///
+    /// [`end_vacation`]: SmartHomeFacade::end_vacation
+    pub fn vacation_mode(&mut self, days: u32, rng_seed: u64) -> Vec<String> {
+        let saved = self.to_snapshot();
+        let mut state = rng_seed.max(1);
+        let mut events = Vec::new();
+        for day in 0..days {
+            // Lights come on somewhere in the early evening and go off
+            // around bedtime, never at the same minute two days running.
+            let on = 18 * 60 + (next_random(&mut state) % 180) as u16;
+            let off = 22 * 60 + (next_random(&mut state) % 120) as u16;
+            let brightness = 40 + (next_random(&mut state) % 61) as u8;
+            events.push(PresenceEvent {
+                day,
+                minute: on,
+                action: PresenceAction::LightsOn(brightness),
+            });
+            // Roughly half the evenings get some music as well.
+            if next_random(&mut state) % 2 == 0 {
+                events.push(PresenceEvent {
+                    day,
+                    minute: on + 10,
+                    action: PresenceAction::MusicOn,
+                });
+                events.push(PresenceEvent {
+                    day,
+                    minute: off.saturating_sub(20),
+                    action: PresenceAction::MusicOff,
+                });
+            }
+            events.push(PresenceEvent {
+                day,
+                minute: off,
+                action: PresenceAction::LightsOff,
+            });
+        }
+        let steps = vec![
+            self.music.stop(),
+            self.lights.off(),
+            self.thermostat.set_target(15.0),
+            self.security.arm(),
+            format!("Vacation: {} presence events over {} days", events.len(), days),
+        ];
+        self.vacation = Some(VacationPlan {
+            events,
+            cursor: 0,
+            saved,
+        });
+        steps
+    }
+
+    /// The remaining planned events, for inspection and tests.
+    pub fn vacation_plan(&self) -> &[PresenceEvent] {
+        self.vacation
+            .as_ref()
+            .map(|plan| &plan.events[plan.cursor..])
+            .unwrap_or(&[])
+    }
+
+    pub fn on_vacation(&self) -> bool {
+        self.vacation.is_some()
+    }
+
+    /// Plays the plan forward to the given day and minute, applying every
+    /// event that has come due. Call from a driver loop or scheduler tick.
+    pub fn run_presence_until(&mut self, day: u32, minute: u16) -> Vec<String> {
+        let Some(plan) = self.vacation.as_mut() else {
+            return Vec::new();
+        };
+        let mut due = Vec::new();
+        while plan.cursor < plan.events.len() {
+            let event = &plan.events[plan.cursor];
+            if (event.day, event.minute) > (day, minute) {
+                break;
+            }
+            due.push(event.clone());
+            plan.cursor += 1;
+        }
+        due.iter()
+            .map(|event| match event.action {
+                PresenceAction::LightsOn(level) => self.lights.dim(level),
+                PresenceAction::LightsOff => self.lights.off(),
+                PresenceAction::MusicOn => self.music.play("presence playlist"),
+                PresenceAction::MusicOff => self.music.stop(),
+            })
+            .collect()
+    }
+
+    /// Drops the plan and puts every subsystem back how it was when
+    /// `vacation_mode` was called.
+    pub fn end_vacation(&mut self) -> Result<Vec<String>, String> {
+        let plan = self
+            .vacation
+            .take()
+            .ok_or("vacation mode is not active")?;
+        self.restore_snapshot(&plan.saved)?;
+        Ok(vec!["Vacation: ended, settings restored".to_string()])
+    }
+}
+
+impl Subject<FacadeEvent> for SmartHomeFacade {
+    fn register_observer(&mut self, observer: Rc<RefCell<dyn Observer<FacadeEvent>>>) {
+        self.observers.push(observer);
+    }
+
+    fn remove_observer(&mut self, name: &str) {
+        self.observers.retain(|o| o.borrow().name() != name);
+    }
+
+    fn notify_observers(&mut self, event: &FacadeEvent) {
+        let observers: Vec<_> = self.observers.to_vec();
+        for observer in observers {
+            let mut observer = observer.borrow_mut();
+            if observer.interested_in(event) {
+                let _ = observer.notify(event);
+            }
+        }
+    }
+}
+
+impl Default for SmartHomeFacade {
+    fn default() -> Self {
+        SmartHomeFacade::new()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Sensors and automation rules
+// ---------------------------------------------------------------------------
+
+/// Inputs from around the house. The facade does not poll hardware; the
+/// demo (or a driver loop) feeds readings in as they happen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SensorEvent {
+    Motion { zone: String },
+    DoorOpened { door: String },
+    DoorClosed { door: String },
+    Temperature { celsius: f64 },
+}
+
+impl SensorEvent {
+    fn describe(&self) -> String {
+        match self {
+            SensorEvent::Motion { zone } => format!("motion in {}", zone),
+            SensorEvent::DoorOpened { door } => format!("{} door opened", door),
+            SensorEvent::DoorClosed { door } => format!("{} door closed", door),
+            SensorEvent::Temperature { celsius } => format!("{:.1}C", celsius),
+        }
+    }
+}
+
+/// What a rule looks for in a reading.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SensorPattern {
+    AnyMotion,
+    MotionIn(String),
+    AnyDoorOpened,
+    TempAbove(f64),
+    TempBelow(f64),
+}
+
+impl SensorPattern {
+    fn matches(&self, event: &SensorEvent) -> bool {
+        match (self, event) {
+            (SensorPattern::AnyMotion, SensorEvent::Motion { .. }) => true,
+            (SensorPattern::MotionIn(want), SensorEvent::Motion { zone }) => want == zone,
+            (SensorPattern::AnyDoorOpened, SensorEvent::DoorOpened { .. }) => true,
+            (SensorPattern::TempAbove(limit), SensorEvent::Temperature { celsius }) => {
+                celsius > limit
+            }
+            (SensorPattern::TempBelow(limit), SensorEvent::Temperature { celsius }) => {
+                celsius < limit
+            }
+            _ => false,
+        }
+    }
+}
+
+/// What a matched rule does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AutomationAction {
+    Alert,
+    SetLights(u8),
+    SetThermostat(f64),
+    Run(Routine),
+}
+
+/// "If `pattern` (within the active window, while armed) then `action`".
+/// Built with the usual chained `with_`-style modifiers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutomationRule {
+    name: String,
+    pattern: SensorPattern,
+    action: AutomationAction,
+    /// Active minutes-of-day window, inclusive start, exclusive end; wraps
+    /// past midnight when start > end. `None` means always active.
+    window: Option<(u16, u16)>,
+    requires_armed: bool,
+}
+
+impl AutomationRule {
+    pub fn new(name: &str, pattern: SensorPattern, action: AutomationAction) -> Self {
+        AutomationRule {
+            name: name.to_string(),
+            pattern,
+            action,
+            window: None,
+            requires_armed: false,
+        }
+    }
+
+    pub fn between(mut self, from: (u8, u8), to: (u8, u8)) -> Self {
+        let minute = |(h, m): (u8, u8)| u16::from(h) * 60 + u16::from(m);
+        self.window = Some((minute(from), minute(to)));
+        self
+    }
+
+    pub fn when_armed(mut self) -> Self {
+        self.requires_armed = true;
+        self
+    }
+
+    fn active_at(&self, minute_of_day: u16) -> bool {
+        match self.window {
+            None => true,
+            Some((from, to)) if from <= to => (from..to).contains(&minute_of_day),
+            // Wrapping window, e.g. 22:00..06:00.
+            Some((from, to)) => minute_of_day >= from || minute_of_day < to,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Vacation mode: randomized presence simulation
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresenceAction {
+    LightsOn(u8),
+    LightsOff,
+    MusicOn,
+    MusicOff,
+}
+
+/// One scheduled fake-presence step, day 0 being the first vacation day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresenceEvent {
+    pub day: u32,
+    pub minute: u16,
+    pub action: PresenceAction,
+}
+
+/// Pending presence events plus the state to restore when the owners return.
+struct VacationPlan {
+    events: Vec<PresenceEvent>,
+    cursor: usize,
+    saved: String,
+}
+
+/// Same xorshift generator the retry proxy uses for jitter: deterministic
+/// under a seed, which is what makes the plan testable.
+fn next_random(state: &mut u64) -> u64 {
+    let mut x = *state;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    *state = x;
+    x
+}
+
+// ---------------------------------------------------------------------------
+// Scheduling engine for the smart home
+// ---------------------------------------------------------------------------
+
+/// The routines a job can trigger; one variant per facade method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Routine {
+    GoodMorning,
+    GoodNight,
+    LeaveHome,
+    ArriveHome,
+}
+
+impl Routine {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Routine::GoodMorning => "good_morning",
+            Routine::GoodNight => "good_night",
+            Routine::LeaveHome => "leave_home",
+            Routine::ArriveHome => "arrive_home",
+        }
+    }
+
+    fn run(&self, home: &mut SmartHomeFacade) -> Vec<String> {
+        match self {
+            Routine::GoodMorning => home.good_morning(),
+            Routine::GoodNight => home.good_night(),
+            Routine::LeaveHome => home.leave_home(),
+            Routine::ArriveHome => home.arrive_home(),
+        }
+    }
+}
+
+/// When a job fires: a wall-clock time, or relative to sunset so routines
+/// track the seasons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleTime {
+    At { hour: u8, minute: u8 },
+    AfterSunset { offset_min: i32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repeat {
+    Once,
+    Daily,
+}
+
+const MINUTES_PER_DAY: u64 = 24 * 60;
+
+struct ScheduledJob {
+    id: u64,
+    routine: Routine,
+    time: ScheduleTime,
+    repeat: Repeat,
+    /// Absolute minute (since the scheduler's day-0 midnight) of the next run.
+    next_fire: u64,
+}
+
+/// Runs smart-home routines at configured times. The scheduler carries its
+/// own simulated clock, advanced explicitly by the caller, so tests and
+/// demos are deterministic without real sleeps.
+pub struct HomeScheduler {
+    jobs: Vec<ScheduledJob>,
+    /// Minutes since day-0 midnight; the clock starts at midnight.
+    now: u64,
+    /// Minute-of-day when the sun sets; fixed unless adjusted.
+    sunset: u16,
+    next_id: u64,
+}
+
+impl HomeScheduler {
+    pub fn new() -> Self {
+        HomeScheduler {
+            jobs: Vec::new(),
+            now: 0,
+            sunset: 20 * 60 + 30,
+            next_id: 1,
+        }
+    }
+
+    pub fn set_sunset(&mut self, hour: u8, minute: u8) {
+        self.sunset = u16::from(hour) * 60 + u16::from(minute);
+    }
+
+    fn minute_of_day(&self, at: u64) -> u64 {
+        at % MINUTES_PER_DAY
+    }
+
+    fn format_clock(&self, at: u64) -> String {
+        let m = self.minute_of_day(at);
+        format!("{:02}:{:02}", m / 60, m % 60)
+    }
+
+    fn target_minute(&self, time: ScheduleTime) -> u64 {
+        match time {
+            ScheduleTime::At { hour, minute } => u64::from(hour) * 60 + u64::from(minute),
+            ScheduleTime::AfterSunset { offset_min } => {
+                let t = i64::from(self.sunset) + i64::from(offset_min);
+                t.clamp(0, MINUTES_PER_DAY as i64 - 1) as u64
+            }
+        }
+    }
+
+    /// First occurrence of `time` strictly after the current clock.
+    fn next_occurrence(&self, time: ScheduleTime) -> u64 {
+        let day_start = self.now - self.minute_of_day(self.now);
+        let candidate = day_start + self.target_minute(time);
+        if candidate > self.now {
+            candidate
+        } else {
+            candidate + MINUTES_PER_DAY
+        }
+    }
+
+    /// Registers a job and returns its id for later cancellation.
+    pub fn schedule(&mut self, routine: Routine, time: ScheduleTime, repeat: Repeat) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        let next_fire = self.next_occurrence(time);
+        self.jobs.push(ScheduledJob {
+            id,
+            routine,
+            time,
+            repeat,
+            next_fire,
+        });
+        id
+    }
+
+    /// Removes the job; false if the id is unknown (already fired or
+    /// cancelled).
+    pub fn cancel(&mut self, id: u64) -> bool {
+        let before = self.jobs.len();
+        self.jobs.retain(|job| job.id != id);
+        self.jobs.len() != before
+    }
+
+    /// Pending jobs in firing order, described for display.
+    pub fn pending(&self) -> Vec<String> {
+        let mut jobs: Vec<&ScheduledJob> = self.jobs.iter().collect();
+        jobs.sort_by_key(|job| (job.next_fire, job.id));
+        jobs.iter()
+            .map(|job| {
+                let when = match job.time {
+                    ScheduleTime::At { .. } => self.format_clock(job.next_fire),
+                    ScheduleTime::AfterSunset { offset_min } => format!(
+                        "{} ({:+} min from sunset)",
+                        self.format_clock(job.next_fire),
+                        offset_min
+                    ),
+                };
+                let repeat = match job.repeat {
+                    Repeat::Once => "once",
+                    Repeat::Daily => "daily",
+                };
+                format!("job {}: {} at {} ({})", job.id, job.routine.as_str(), when, repeat)
+            })
+            .collect()
+    }
+
+    pub fn clock(&self) -> String {
+        self.format_clock(self.now)
+    }
+
+    /// Moves the clock forward, running every job that comes due in order.
+    /// Returns a log line per firing.
+    pub fn advance(&mut self, home: &mut SmartHomeFacade, minutes: u64) -> Vec<String> {
+        let deadline = self.now + minutes;
+        let mut fired = Vec::new();
+        loop {
+            let due = self
+                .jobs
+                .iter()
+                .filter(|job| job.next_fire <= deadline)
+                .min_by_key(|job| (job.next_fire, job.id))
+                .map(|job| job.id);
+            let Some(id) = due else { break };
+            let idx = self.jobs.iter().position(|job| job.id == id).unwrap();
+            self.now = self.jobs[idx].next_fire;
+            let routine = self.jobs[idx].routine;
+            match self.jobs[idx].repeat {
+                Repeat::Once => {
+                    self.jobs.remove(idx);
+                }
+                Repeat::Daily => {
+                    self.jobs[idx].next_fire += MINUTES_PER_DAY;
+                }
+            }
+            let steps = routine.run(home);
+            fired.push(format!(
+                "{} {}: {}",
+                self.clock(),
+                routine.as_str(),
+                steps.join("; ")
+            ));
+        }
+        self.now = deadline;
+        fired
+    }
+}
+
+impl Default for HomeScheduler {
+    fn default() -> Self {
+        HomeScheduler::new()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Computer subsystems and facade
+// ---------------------------------------------------------------------------
+
+pub struct Cpu {
+    freq_ghz: f64,
+    load: f64,
+}
+
+impl Cpu {
+    pub fn new() -> Self {
+        Cpu {
+            freq_ghz: 3.2,
+            load: 0.0,
+        }
+    }
+
+    pub fn freeze(&mut self) -> String {
+        "CPU: freeze".to_string()
+    }
+
+    pub fn jump(&mut self, addr: u64) -> String {
+        format!("CPU: jump to {:#x}", addr)
+    }
+
+    pub fn execute(&mut self) -> String {
+        self.load = KERNEL_LOAD;
+        "CPU: execute".to_string()
+    }
+
+    pub fn load(&self) -> f64 {
+        self.load
+    }
+}
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Cpu::new()
+    }
+}
+
+pub struct Memory {
+    total_gb: f64,
+    used_gb: f64,
+}
+
+impl Memory {
+    pub fn new(total_gb: f64) -> Self {
+        Memory {
+            total_gb,
+            used_gb: 0.0,
+        }
+    }
+
+    pub fn load(&mut self, addr: u64, data: &str) -> String {
+        self.used_gb += KERNEL_MEMORY_GB;
+        format!("Memory: {} bytes at {:#x}", data.len(), addr)
+    }
+
+    pub fn used_gb(&self) -> f64 {
+        self.used_gb
+    }
+
+    pub fn total_gb(&self) -> f64 {
+        self.total_gb
+    }
+}
+
+pub struct HardDrive;
+
+impl HardDrive {
+    pub fn read(&self, sector: u64, size: usize) -> String {
+        format!("boot data ({} bytes from sector {})", size, sector)
+    }
+}
+
+const BOOT_ADDRESS: u64 = 0x7c00;
+const BOOT_SECTOR: u64 = 0;
+const SECTOR_SIZE: usize = 512;
+
+/// What the kernel itself costs once booted; process accounting sits on top.
+const KERNEL_LOAD: f64 = 0.05;
+const KERNEL_MEMORY_GB: f64 = 0.5;
+/// Pids below this are reserved for the system.
+const FIRST_USER_PID: u32 = 100;
+
+/// One entry in the process table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Process {
+    pub pid: u32,
+    pub name: String,
+    /// Fraction of one core, 0.0..=1.0.
+    pub cpu: f64,
+    pub memory_gb: f64,
+}
+
+/// The classic boot-sequence facade: freeze, load the boot sector, jump.
+pub struct ComputerFacade {
+    cpu: Cpu,
+    memory: Memory,
+    drive: HardDrive,
+    running: bool,
+    processes: Vec<Process>,
+    next_pid: u32,
+}
+
+impl ComputerFacade {
+    pub fn new() -> Self {
+        ComputerFacade {
+            cpu: Cpu::new(),
+            memory: Memory::new(16.0),
+            drive: HardDrive,
+            running: false,
+            processes: Vec::new(),
+            next_pid: FIRST_USER_PID,
+        }
+    }
+
+    /// Totals from the process table plus the kernel baseline, folded back
+    /// into the subsystems so `cpu.load` and `memory.used_gb` always agree
+    /// with the table.
+    fn recompute_accounting(&mut self) {
+        let cpu_sum: f64 = self.processes.iter().map(|p| p.cpu).sum();
+        let mem_sum: f64 = self.processes.iter().map(|p| p.memory_gb).sum();
+        self.cpu.load = (KERNEL_LOAD + cpu_sum).min(1.0);
+        self.memory.used_gb = KERNEL_MEMORY_GB + mem_sum;
+    }
+
+    /// Starts a process, or refuses with an OOM error when it would not fit;
+    /// a refused launch changes nothing.
+    pub fn launch_app(&mut self, name: &str, cpu: f64, memory_gb: f64) -> Result<u32, String> {
+        if !self.running {
+            return Err("computer is not running".to_string());
+        }
+        let free = self.memory.total_gb - self.memory.used_gb;
+        if memory_gb > free {
+            return Err(format!(
+                "out of memory: {} needs {:.1} GB, {:.1} GB free",
+                name, memory_gb, free
+            ));
+        }
+        let pid = self.next_pid;
+        self.next_pid += 1;
+        self.processes.push(Process {
+            pid,
+            name: name.to_string(),
+            cpu,
+            memory_gb,
+        });
+        self.recompute_accounting();
+        Ok(pid)
+    }
+
+    pub fn kill(&mut self, pid: u32) -> Result<String, String> {
+        let idx = self
+            .processes
+            .iter()
+            .position(|p| p.pid == pid)
+            .ok_or_else(|| format!("no such process {}", pid))?;
+        let process = self.processes.remove(idx);
+        self.recompute_accounting();
+        Ok(format!("killed {} (pid {})", process.name, pid))
+    }
+
+    pub fn processes(&self) -> &[Process] {
+        &self.processes
+    }
+
+    /// One row per process plus a totals line, like a tiny `top`.
+    pub fn task_list(&self) -> Vec<String> {
+        let mut rows: Vec<String> = self
+            .processes
+            .iter()
+            .map(|p| {
+                format!(
+                    "pid {:>4}  {:<12} cpu {:>3.0}%  mem {:.1} GB",
+                    p.pid,
+                    p.name,
+                    p.cpu * 100.0,
+                    p.memory_gb
+                )
+            })
+            .collect();
+        rows.push(format!(
+            "total: {} processes, cpu {:.0}%, mem {:.1}/{:.1} GB",
+            self.processes.len(),
+            self.cpu.load() * 100.0,
+            self.memory.used_gb(),
+            self.memory.total_gb()
+        ));
+        rows
+    }
+
+    pub fn start(&mut self) -> Vec<String> {
+        let boot = self.drive.read(BOOT_SECTOR, SECTOR_SIZE);
+        let steps = vec![
+            self.cpu.freeze(),
+            self.memory.load(BOOT_ADDRESS, &boot),
+            self.cpu.jump(BOOT_ADDRESS),
+            self.cpu.execute(),
+        ];
+        self.running = true;
+        steps
+    }
+
+    pub fn shutdown(&mut self) -> Vec<String> {
+        let mut steps: Vec<String> = self
+            .processes
+            .drain(..)
+            .map(|p| format!("killed {} (pid {})", p.name, p.pid))
+            .collect();
+        self.running = false;
+        self.cpu.load = 0.0;
+        self.memory.used_gb = 0.0;
+        steps.push("Computer: halted".to_string());
+        steps
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+impl Default for ComputerFacade {
+    fn default() -> Self {
+        ComputerFacade::new()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Flat JSON helpers (shared shape with the proxy/observer modules)
+// ---------------------------------------------------------------------------
+
+fn json_escape(raw: &str) -> String {
+    raw.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Parses a single-level JSON object with string, number, or boolean
+/// values; all values come back as strings.
+fn parse_flat_json(line: &str) -> Result<HashMap<String, String>, String> {
+    let body = line
+        .trim()
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+        .ok_or("expected a JSON object")?;
+    let mut fields = HashMap::new();
+    let mut chars = body.chars().peekable();
+    loop {
+        while matches!(chars.peek(), Some(c) if c.is_whitespace() || *c == ',') {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            return Ok(fields);
+        }
+        let key = parse_json_string(&mut chars)?;
+        match chars.next() {
+            Some(':') => {}
+            other => return Err(format!("expected ':' after key, got {:?}", other)),
+        }
+        let value = match chars.peek() {
+            Some('"') => parse_json_string(&mut chars)?,
+            Some(_) => {
+                let mut raw = String::new();
+                while matches!(chars.peek(), Some(c) if *c != ',') {
+                    raw.push(chars.next().expect("peeked"));
+                }
+                raw.trim().to_string()
+            }
+            None => return Err("missing value".to_string()),
+        };
+        fields.insert(key, value);
+    }
+}
+
+fn parse_json_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    match chars.next() {
+        Some('"') => {}
+        other => return Err(format!("expected '\"', got {:?}", other)),
+    }
+    let mut out = String::new();
+    loop {
+        match chars.next() {
+            None => return Err("unterminated string".to_string()),
+            Some('"') => return Ok(out),
+            Some('\\') => match chars.next() {
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                other => return Err(format!("bad escape {:?}", other)),
+            },
+            Some(other) => out.push(other),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Demos
+// ---------------------------------------------------------------------------
+
+fn demo_home_theater() {
+    println!("=== Home theater ===");
+    let mut theater = HomeTheaterFacade::new();
+
+    let steps = theater.watch_movie("Inception");
+    for step in &steps {
+        println!("  {}", step);
+    }
+    assert_eq!(steps.len(), 9);
+    assert_eq!(theater.now_playing(), Some("Inception"));
+    assert_eq!(theater.lights_brightness(), 10);
+    assert_eq!(theater.volume(), 5);
+
+    let steps = theater.end_movie();
+    assert_eq!(steps.len(), 6);
+    assert_eq!(theater.now_playing(), None);
+    assert_eq!(theater.lights_brightness(), 100);
+
+    let steps = theater.listen_to_music("Kind of Blue");
+    assert_eq!(steps.last().unwrap(), "Player: playing \"Kind of Blue\"");
+    theater.end_music();
+}
+
+fn demo_custom_scene() {
+    println!("\n=== Custom scenes ===");
+    let mut theater = HomeTheaterFacade::new();
+
+    let scenes = parse_scenes(
+        r#"
+# A cosy reading setup: no projector, quiet background music.
+[reading]
+lights.dim = 60
+sound.power = on
+sound.volume = 2
+player.power = on
+player.play = "rainy jazz"
+"#,
+    )
+    .unwrap();
+    assert_eq!(scenes.len(), 1);
+    for scene in scenes {
+        theater.install_scene(scene);
+    }
+    assert!(theater.scene_names().contains(&"reading"));
+
+    let steps = theater.run_scene("reading").unwrap();
+    for step in &steps {
+        println!("  {}", step);
+    }
+    assert_eq!(theater.lights_brightness(), 60);
+    assert_eq!(theater.now_playing(), Some("rainy jazz"));
+
+    // Authoring mistakes come back with line numbers.
+    let err = parse_scenes("[party]\nlights = 50\n").unwrap_err();
+    assert_eq!(err, "line 2: key must be `device.action`");
+    let err = parse_scenes("lights.dim = 50\n").unwrap_err();
+    assert!(err.contains("before any [scene] header"));
+    println!("  rejected: {}", err);
+
+    // Unknown devices fail at run time, not install time.
+    theater.install_scene(Scene {
+        name: "broken".to_string(),
+        actions: vec![SceneAction {
+            device: "toaster".to_string(),
+            action: "power".to_string(),
+            param: Some("on".to_string()),
+        }],
+    });
+    let err = theater.run_scene("broken").unwrap_err();
+    assert_eq!(err, "unknown action toaster.power");
+}
+
+fn demo_streaming() {
+    println!("\n=== Streaming ===");
+    let mut theater = HomeTheaterFacade::new();
+
+    // Nothing installed yet: the facade refuses and the room is untouched.
+    let err = theater.watch_streaming("netflix", "Dark").unwrap_err();
+    assert_eq!(err, "streaming: \"netflix\" is not installed");
+    assert_eq!(theater.lights_brightness(), 100);
+
+    theater.install_streaming_app("netflix");
+    let err = theater.watch_streaming("netflix", "Dark").unwrap_err();
+    assert_eq!(err, "streaming: not signed in to \"netflix\"");
+
+    theater.sign_in_streaming("netflix", "alice").unwrap();
+    let steps = theater.watch_streaming("netflix", "Dark").unwrap();
+    for step in &steps {
+        println!("  {}", step);
+    }
+    assert_eq!(steps.len(), 7);
+    assert_eq!(theater.now_streaming(), Some(("netflix", "Dark")));
+    assert_eq!(theater.lights_brightness(), 15);
+
+    theater.pause_streaming();
+    assert!(theater.streaming.is_paused());
+    theater.resume_streaming();
+    assert!(!theater.streaming.is_paused());
+
+    theater.end_streaming();
+    assert_eq!(theater.now_streaming(), None);
+    assert_eq!(theater.lights_brightness(), 100);
+}
+
+fn demo_commands() {
+    println!("\n=== Text commands ===");
+    let mut theater = HomeTheaterFacade::new();
+
+    let outcome = theater.handle_command("watch movie The Thin Red Line").unwrap();
+    assert_eq!(
+        outcome.command,
+        TheaterCommand::WatchMovie("The Thin Red Line".to_string())
+    );
+    assert_eq!(outcome.steps.len(), 9);
+    assert_eq!(theater.now_playing(), Some("The Thin Red Line"));
+
+    theater.handle_command("volume up").unwrap();
+    theater.handle_command("volume up").unwrap();
+    assert_eq!(theater.volume(), 7);
+    theater.handle_command("volume 3").unwrap();
+    assert_eq!(theater.volume(), 3);
+
+    let outcome = theater.handle_command("end movie").unwrap();
+    assert_eq!(outcome.command, TheaterCommand::EndMovie);
+    println!("  end movie -> {} steps", outcome.steps.len());
+
+    theater.install_scene(
+        parse_scenes("[reading]\nlights.dim = 60\n").unwrap().remove(0),
+    );
+    theater.handle_command("preset reading").unwrap();
+    assert_eq!(theater.lights_brightness(), 60);
+
+    // Bad phrases come back as errors, not panics.
+    let err = theater.handle_command("dance").unwrap_err();
+    assert_eq!(err, "unknown command \"dance\"");
+    let err = theater.handle_command("watch movie").unwrap_err();
+    assert_eq!(err, "watch movie needs a title");
+    let err = theater.handle_command("volume loud").unwrap_err();
+    assert!(err.starts_with("volume wants"));
+    let err = theater.handle_command("preset party").unwrap_err();
+    assert_eq!(err, "unknown scene \"party\"");
+    println!("  rejected: {}", err);
+}
+
+fn demo_undoable_scenes() {
+    println!("\n=== Undoable scenes ===");
+    let mut theater = UndoableTheater::new();
+
+    theater.watch_movie("Stalker").unwrap();
+    {
+        let t = theater.theater();
+        let t = t.borrow();
+        assert_eq!(t.now_playing(), Some("Stalker"));
+        assert_eq!(t.lights_brightness(), 10);
+        assert_eq!(t.volume(), 5);
+    }
+
+    theater.undo_last_scene().unwrap();
+    {
+        let t = theater.theater();
+        let t = t.borrow();
+        assert_eq!(t.now_playing(), None);
+        assert_eq!(t.lights_brightness(), 100);
+        assert_eq!(t.volume(), 0);
+        println!("  undo restored lights to {}%", t.lights_brightness());
+    }
+
+    // Redo replays the scene with the same parameters.
+    theater.redo_scene().unwrap();
+    assert_eq!(theater.theater().borrow().now_playing(), Some("Stalker"));
+
+    // Stacked scenes unwind in reverse order.
+    theater.run_scene_with("listen_to_music", &[("album", "Lateralus")]).unwrap();
+    assert_eq!(theater.theater().borrow().now_playing(), Some("Lateralus"));
+    theater.undo_last_scene().unwrap();
+    assert_eq!(theater.theater().borrow().now_playing(), Some("Stalker"));
+    theater.undo_last_scene().unwrap();
+    assert_eq!(theater.theater().borrow().now_playing(), None);
+    let err = theater.undo_last_scene().unwrap_err();
+    assert_eq!(err, "nothing to undo");
+
+    // A failed scene never reaches the history.
+    let err = theater.run_scene("nonexistent").unwrap_err();
+    assert_eq!(err, "unknown scene \"nonexistent\"");
+    assert_eq!(theater.undo_last_scene().unwrap_err(), "nothing to undo");
+}
+
+fn demo_smart_home() {
+    println!("\n=== Smart home ===");
+    let mut home = SmartHomeFacade::new();
+
+    for step in home.good_night() {
+        println!("  {}", step);
+    }
+    assert!(home.is_armed());
+    assert_eq!(home.lights_brightness(), 0);
+    assert!((home.target_temperature() - 17.0).abs() < f64::EPSILON);
+
+    home.good_morning();
+    assert!(!home.is_armed());
+    assert_eq!(home.lights_brightness(), 80);
+}
+
+fn demo_facade_events() {
+    println!("\n=== Facade events ===");
+
+    /// Records everything it sees; the kind of monitor 3899 is for.
+    struct ActivityMonitor {
+        events: Vec<FacadeEvent>,
+    }
+    impl Observer<FacadeEvent> for ActivityMonitor {
+        fn notify(&mut self, event: &FacadeEvent) -> Result<(), String> {
+            self.events.push(event.clone());
+            Ok(())
+        }
+        fn name(&self) -> &str {
+            "activity-monitor"
+        }
+    }
+    /// Only cares about power transitions.
+    struct PowerMonitor {
+        toggles: Vec<(String, bool)>,
+    }
+    impl Observer<FacadeEvent> for PowerMonitor {
+        fn notify(&mut self, event: &FacadeEvent) -> Result<(), String> {
+            if let FacadeEvent::SubsystemPowered { device, on } = event {
+                self.toggles.push((device.clone(), *on));
+            }
+            Ok(())
+        }
+        fn interested_in(&self, event: &FacadeEvent) -> bool {
+            matches!(event, FacadeEvent::SubsystemPowered { .. })
+        }
+        fn name(&self) -> &str {
+            "power-monitor"
+        }
+    }
+
+    let mut theater = HomeTheaterFacade::new();
+    let activity = Rc::new(RefCell::new(ActivityMonitor { events: Vec::new() }));
+    let power = Rc::new(RefCell::new(PowerMonitor { toggles: Vec::new() }));
+    theater.register_observer(activity.clone());
+    theater.register_observer(power.clone());
+
+    theater.watch_movie("Arrival");
+    {
+        let events = &activity.borrow().events;
+        assert_eq!(
+            events.first().unwrap(),
+            &FacadeEvent::SceneStarted {
+                scene: "watch_movie".to_string()
+            }
+        );
+        assert_eq!(
+            events.last().unwrap(),
+            &FacadeEvent::SceneCompleted {
+                scene: "watch_movie".to_string(),
+                steps: 9
+            }
+        );
+        println!("  activity monitor saw {} events", events.len());
+    }
+    // Four devices power on during the movie scene.
+    assert_eq!(power.borrow().toggles.len(), 4);
+    assert!(power.borrow().toggles.iter().all(|(_, on)| *on));
+
+    theater.end_movie();
+    assert_eq!(power.borrow().toggles.len(), 8);
+    assert!(power.borrow().toggles[4..].iter().all(|(_, on)| !*on));
+
+    // Removal by name stops delivery.
+    theater.remove_observer("activity-monitor");
+    let seen = activity.borrow().events.len();
+    theater.listen_to_music("In Rainbows");
+    assert_eq!(activity.borrow().events.len(), seen);
+
+    // The smart home reports through the same event type.
+    let mut home = SmartHomeFacade::new();
+    let monitor = Rc::new(RefCell::new(ActivityMonitor { events: Vec::new() }));
+    home.register_observer(monitor.clone());
+    home.good_morning();
+    let events = monitor.borrow().events.clone();
+    assert_eq!(events.len(), 3);
+    assert_eq!(
+        events[1],
+        FacadeEvent::SubsystemPowered {
+            device: "music".to_string(),
+            on: true
+        }
+    );
+}
+
+fn demo_state_persistence() {
+    println!("\n=== State persistence ===");
+    let mut theater = HomeTheaterFacade::new();
+    theater.install_scene(
+        parse_scenes("[reading]\nlights.dim = 60\nplayer.play = \"rainy jazz\"\n")
+            .unwrap()
+            .remove(0),
+    );
+    theater.install_streaming_app("netflix");
+    theater.sign_in_streaming("netflix", "alice").unwrap();
+    theater.watch_streaming("netflix", "Dark").unwrap();
+
+    let path = std::env::temp_dir().join("facade-demo-theater.json");
+    theater.save_state(&path).unwrap();
+
+    let restored = HomeTheaterFacade::load_state(&path).unwrap();
+    assert_eq!(restored.now_streaming(), Some(("netflix", "Dark")));
+    assert_eq!(restored.lights_brightness(), 15);
+    assert_eq!(restored.volume(), 6);
+    assert!(restored.streaming.is_signed_in("netflix"));
+    assert!(restored.scene_names().contains(&"reading"));
+    println!("  restored: {} scenes, streaming {:?}",
+        restored.scene_names().len(),
+        restored.now_streaming());
+
+    // The custom preset still runs after the round trip.
+    let mut restored = restored;
+    restored.run_scene("reading").unwrap();
+    assert_eq!(restored.now_playing(), Some("rainy jazz"));
+
+    // Smart home snapshots share the format.
+    let mut home = SmartHomeFacade::new();
+    home.good_night();
+    let copy = SmartHomeFacade::from_snapshot(&home.to_snapshot()).unwrap();
+    assert!(copy.is_armed());
+    assert!((copy.target_temperature() - 17.0).abs() < f64::EPSILON);
+
+    // Corrupt input is rejected with a reason.
+    let err = HomeTheaterFacade::from_snapshot("{\"lights\":\"bright\"}")
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(err, "lights: bad number \"bright\"");
+    let _ = fs::remove_file(&path);
+}
+
+fn demo_scheduler() {
+    println!("\n=== Scheduler ===");
+    let mut home = SmartHomeFacade::new();
+    let mut scheduler = HomeScheduler::new();
+    scheduler.set_sunset(20, 30);
+
+    let night = scheduler.schedule(
+        Routine::GoodNight,
+        ScheduleTime::At { hour: 22, minute: 30 },
+        Repeat::Daily,
+    );
+    let dusk_lights = scheduler.schedule(
+        Routine::ArriveHome,
+        ScheduleTime::AfterSunset { offset_min: -30 },
+        Repeat::Once,
+    );
+    let morning = scheduler.schedule(
+        Routine::GoodMorning,
+        ScheduleTime::At { hour: 7, minute: 0 },
+        Repeat::Daily,
+    );
+
+    let pending = scheduler.pending();
+    for line in &pending {
+        println!("  {}", line);
+    }
+    assert_eq!(pending.len(), 3);
+    // Firing order from midnight: morning, sunset-relative, night.
+    assert!(pending[0].starts_with(&format!("job {}: good_morning at 07:00", morning)));
+    assert!(pending[1].contains("arrive_home at 20:00 (-30 min from sunset)"));
+
+    // Run through one full day.
+    let fired = scheduler.advance(&mut home, MINUTES_PER_DAY);
+    assert_eq!(fired.len(), 3);
+    assert!(fired[0].starts_with("07:00 good_morning"));
+    assert!(fired[1].starts_with("20:00 arrive_home"));
+    assert!(fired[2].starts_with("22:30 good_night"));
+    assert!(home.is_armed());
+    println!("  {}", fired[2]);
+
+    // The one-shot is gone; the daily jobs remain and fire again.
+    assert_eq!(scheduler.pending().len(), 2);
+    let fired = scheduler.advance(&mut home, MINUTES_PER_DAY);
+    assert_eq!(fired.len(), 2);
+
+    // Cancellation stops a routine before its slot comes round.
+    assert!(scheduler.cancel(night));
+    assert!(!scheduler.cancel(dusk_lights));
+    let fired = scheduler.advance(&mut home, MINUTES_PER_DAY);
+    assert_eq!(fired.len(), 1);
+    assert!(fired[0].starts_with("07:00 good_morning"));
+    assert_eq!(scheduler.clock(), "00:00");
+}
+
+fn demo_automation_rules() {
+    println!("\n=== Automation rules ===");
+    let mut home = SmartHomeFacade::new();
+    home.add_rule(
+        AutomationRule::new("night_intruder", SensorPattern::AnyMotion, AutomationAction::Alert)
+            .between((22, 0), (6, 0))
+            .when_armed(),
+    );
+    home.add_rule(
+        AutomationRule::new(
+            "hallway_night_light",
+            SensorPattern::MotionIn("hallway".to_string()),
+            AutomationAction::SetLights(30),
+        )
+        .between((22, 0), (6, 0)),
+    );
+    home.add_rule(AutomationRule::new(
+        "frost_guard",
+        SensorPattern::TempBelow(5.0),
+        AutomationAction::SetThermostat(10.0),
+    ));
+
+    let motion = |zone: &str| SensorEvent::Motion { zone: zone.to_string() };
+    let at = |h: u16, m: u16| h * 60 + m;
+
+    // Daytime motion while disarmed: nothing fires.
+    assert!(home.handle_sensor(&motion("hallway"), at(12, 0)).is_empty());
+
+    // Armed, 23:10: both night rules fire, in declaration order.
+    home.good_night();
+    let actions = home.handle_sensor(&motion("hallway"), at(23, 10));
+    for action in &actions {
+        println!("  {}", action);
+    }
+    assert_eq!(actions.len(), 2);
+    assert_eq!(home.alerts().len(), 1);
+    assert_eq!(home.alerts()[0], "night_intruder: motion in hallway");
+    assert_eq!(home.lights_brightness(), 30);
+
+    // The window wraps past midnight.
+    let actions = home.handle_sensor(&motion("garage"), at(2, 30));
+    assert_eq!(actions.len(), 1);
+    assert_eq!(home.alerts().len(), 2);
+    // ...but not into the morning.
+    assert!(home.handle_sensor(&motion("garage"), at(6, 0)).is_empty());
+
+    // Disarmed motion at night only trips the light, not the alarm.
+    home.good_morning();
+    home.good_night();
+    let before = home.alerts().len();
+    home.arrive_home();
+    let actions = home.handle_sensor(&motion("hallway"), at(23, 0));
+    assert_eq!(actions.len(), 1);
+    assert_eq!(home.alerts().len(), before);
+
+    // Temperature rules ignore the clock and the alarm.
+    home.handle_sensor(&SensorEvent::Temperature { celsius: 2.5 }, at(4, 0));
+    assert!((home.target_temperature() - 10.0).abs() < f64::EPSILON);
+}
+
+fn demo_energy_report() {
+    println!("\n=== Energy report ===");
+    let two_hours = Duration::from_secs(2 * 3600);
+    let mut theater = HomeTheaterFacade::new();
+
+    let idle = theater.get_energy_report(two_hours);
+    theater.listen_to_music("Blue Train");
+    let music = theater.get_energy_report(two_hours);
+    theater.end_music();
+    theater.watch_movie("Ran");
+    let movie = theater.get_energy_report(two_hours);
+
+    for report in [("idle", &idle), ("music", &music), ("movie", &movie)] {
+        println!("  {:<6} {}", report.0, report.1.summary());
+    }
+    for line in &movie.lines {
+        println!("    {:<10} {:>6.1} W  {:.3} kWh", line.device, line.watts, line.kwh);
+    }
+
+    // Scenes rank by draw: a movie lights the projector (and the popper
+    // stays on), music only the amp.
+    assert!(movie.total_kwh > music.total_kwh);
+    assert!(music.total_kwh > idle.total_kwh);
+
+    // Lines add up to the totals.
+    let sum: f64 = movie.lines.iter().map(|l| l.kwh).sum();
+    assert!((movie.total_kwh - sum).abs() < 1e-9);
+    assert!((movie.total_cost - movie.total_kwh * PRICE_PER_KWH).abs() < 1e-9);
+
+    // Spot-check one device: projector at 220 W for two hours.
+    let projector = movie.lines.iter().find(|l| l.device == "projector").unwrap();
+    assert!((projector.kwh - 0.44).abs() < 1e-9);
+
+    // The smart home reports with its own device set.
+    let mut home = SmartHomeFacade::new();
+    home.good_night();
+    let night = home.get_energy_report(two_hours);
+    assert_eq!(night.lines.len(), 4);
+    let thermostat = night.lines.iter().find(|l| l.device == "thermostat").unwrap();
+    // 17C target sits below ambient: the heater idles overnight.
+    assert!((thermostat.watts - 10.0).abs() < 1e-9);
+}
+
+fn demo_vacation_mode() {
+    println!("\n=== Vacation mode ===");
+    let mut home = SmartHomeFacade::new();
+    home.arrive_home();
+    assert_eq!(home.lights_brightness(), 70);
+
+    home.vacation_mode(3, 42);
+    assert!(home.on_vacation());
+    assert!(home.is_armed());
+    let plan: Vec<PresenceEvent> = home.vacation_plan().to_vec();
+    // Two light events per day, music on some evenings.
+    assert!(plan.len() >= 6 && plan.len() <= 12);
+    for event in plan.iter().take(4) {
+        println!("  day {} {:02}:{:02} {:?}", event.day, event.minute / 60, event.minute % 60, event.action);
+    }
+
+    // Same seed, same plan; different seed, different plan.
+    let mut twin = SmartHomeFacade::new();
+    twin.vacation_mode(3, 42);
+    assert_eq!(twin.vacation_plan(), plan.as_slice());
+    let mut other = SmartHomeFacade::new();
+    other.vacation_mode(3, 7);
+    assert_ne!(other.vacation_plan(), plan.as_slice());
+
+    // Day 0, 20:00: the evening lights are on by then (seeded plan), and by
+    // midnight everything is dark again.
+    let steps = home.run_presence_until(0, 20 * 60);
+    assert!(!steps.is_empty());
+    assert!(home.lights_brightness() > 0);
+    home.run_presence_until(0, 23 * 60 + 59);
+    assert_eq!(home.lights_brightness(), 0);
+
+    // Events already played do not repeat.
+    assert!(home.run_presence_until(0, 23 * 60 + 59).is_empty());
+    assert!(home.vacation_plan().iter().all(|e| e.day > 0));
+
+    // Coming home restores the pre-vacation settings exactly.
+    home.end_vacation().unwrap();
+    assert!(!home.on_vacation());
+    assert!(!home.is_armed());
+    assert_eq!(home.lights_brightness(), 70);
+    assert!((home.target_temperature() - 21.0).abs() < f64::EPSILON);
+    let err = home.end_vacation().map(|_| ()).unwrap_err();
+    assert_eq!(err, "vacation mode is not active");
+}
+
+fn demo_computer() {
+    println!("\n=== Computer ===");
+    let mut computer = ComputerFacade::new();
+
+    let steps = computer.start();
+    for step in &steps {
+        println!("  {}", step);
+    }
+    assert!(computer.is_running());
+    assert!(computer.cpu.load() > 0.0);
+    assert!(computer.memory.used_gb() > 0.0);
+
+    computer.shutdown();
+    assert!(!computer.is_running());
+    assert_eq!(computer.memory.used_gb(), 0.0);
+}
+
+fn demo_process_manager() {
+    println!("\n=== Process manager ===");
+    let mut computer = ComputerFacade::new();
+
+    // Nothing launches before boot.
+    let err = computer.launch_app("browser", 0.25, 2.0).unwrap_err();
+    assert_eq!(err, "computer is not running");
+
+    computer.start();
+    let browser = computer.launch_app("browser", 0.25, 2.0).unwrap();
+    let ide = computer.launch_app("ide", 0.40, 4.0).unwrap();
+    assert_eq!((browser, ide), (100, 101));
+    assert!((computer.cpu.load() - 0.70).abs() < 1e-9);
+    assert!((computer.memory.used_gb() - 6.5).abs() < 1e-9);
+
+    for row in computer.task_list() {
+        println!("  {}", row);
+    }
+
+    // 16 GB total, 6.5 used: a 12 GB game does not fit and nothing changes.
+    let err = computer.launch_app("game", 0.5, 12.0).unwrap_err();
+    assert_eq!(err, "out of memory: game needs 12.0 GB, 9.5 GB free");
+    assert_eq!(computer.processes().len(), 2);
+
+    // Killing the browser frees its share; a smaller build fits.
+    let msg = computer.kill(browser).unwrap();
+    assert_eq!(msg, "killed browser (pid 100)");
+    assert!((computer.memory.used_gb() - 4.5).abs() < 1e-9);
+    computer.launch_app("game", 0.5, 11.0).unwrap();
+    assert!((computer.cpu.load() - 0.95).abs() < 1e-9);
+    assert!((computer.memory.used_gb() - 15.5).abs() < 1e-9);
+
+    let err = computer.kill(browser).unwrap_err();
+    assert_eq!(err, "no such process 100");
+
+    // Shutdown reaps the whole table.
+    let steps = computer.shutdown();
+    assert_eq!(steps.len(), 3);
+    assert!(computer.processes().is_empty());
+}
+
+fn main() {
+    demo_home_theater();
+    demo_custom_scene();
+    demo_streaming();
+    demo_commands();
+    demo_undoable_scenes();
+    demo_smart_home();
+    demo_facade_events();
+    demo_state_persistence();
+    demo_scheduler();
+    demo_automation_rules();
+    demo_energy_report();
+    demo_vacation_mode();
+    demo_computer();
+    demo_process_manager();
+
+    println!("\nAll facade demos passed");
+}
@@ -0,0 +1,4439 @@
+#![allow(dead_code)]
+
+/// Interpreter Pattern
+///
+/// Given a language, defines a representation for its grammar along with an
+/// interpreter that uses the representation to interpret sentences in the
+/// language. Three mini-languages live here: arithmetic expressions, boolean
+/// logic, and a SQL-like record query language.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Instant;
+
+// ---------------------------------------------------------------------------
+// Errors
+// ---------------------------------------------------------------------------
+
+/// Structured lexer/parser error carrying the offending byte span so callers
+/// can render a caret diagnostic against the original source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    InvalidToken {
+        text: String,
+        position: usize,
+    },
+    UnexpectedToken {
+        found: String,
+        expected: Vec<String>,
+        span: (usize, usize),
+    },
+    UnexpectedEof {
+        expected: Vec<String>,
+    },
+}
+
+impl ParseError {
+    /// Renders the source line with a caret under the offending span:
+    ///
+    /// ```text
+    /// 2 + * 3
+    ///     ^ expected number, identifier or '('
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let (start, end) = match self {
+            ParseError::InvalidToken { position, .. } => (*position, position + 1),
+            ParseError::UnexpectedToken { span, .. } => *span,
+            ParseError::UnexpectedEof { .. } => (source.len(), source.len() + 1),
+        };
+        let carets = "^".repeat((end - start).max(1));
+        format!("{}\n{}{} {}", source, " ".repeat(start), carets, self)
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |expected: &[String]| -> String {
+            match expected.len() {
+                0 => "end of input".to_string(),
+                1 => expected[0].clone(),
+                n => format!("{} or {}", expected[..n - 1].join(", "), expected[n - 1]),
+            }
+        };
+        match self {
+            ParseError::InvalidToken { text, position } => {
+                write!(f, "invalid token '{}' at byte {}", text, position)
+            }
+            ParseError::UnexpectedToken {
+                found, expected, ..
+            } => write!(f, "expected {}, found '{}'", join(expected), found),
+            ParseError::UnexpectedEof { expected } => {
+                write!(f, "expected {}, found end of input", join(expected))
+            }
+        }
+    }
+}
+
+/// Structured evaluation error.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    UndefinedVariable(String),
+    UnknownFunction(String),
+    DivisionByZero,
+    ModuloByZero,
+    WrongArity {
+        name: String,
+        expected: Arity,
+        got: usize,
+    },
+    /// A builtin or user-registered function rejected its arguments.
+    Function {
+        name: String,
+        message: String,
+    },
+    /// Unit-aware arithmetic combined incompatible dimensions.
+    DimensionMismatch {
+        op: String,
+        left: String,
+        right: String,
+    },
+    /// A query compared a field against a value of a different type.
+    TypeMismatch {
+        field: String,
+        expected: String,
+        found: String,
+    },
+    EmptyProgram,
+}
+
+impl std::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::UndefinedVariable(name) => write!(f, "undefined variable '{}'", name),
+            EvalError::UnknownFunction(name) => write!(f, "unknown function '{}'", name),
+            EvalError::DivisionByZero => write!(f, "division by zero"),
+            EvalError::ModuloByZero => write!(f, "modulo by zero"),
+            EvalError::WrongArity {
+                name,
+                expected,
+                got,
+            } => match expected {
+                Arity::Exact(n) => {
+                    write!(f, "{} expects {} argument(s), got {}", name, n, got)
+                }
+                Arity::AtLeast(n) => {
+                    write!(f, "{} expects at least {} argument(s), got {}", name, n, got)
+                }
+            },
+            EvalError::Function { name, message } => write!(f, "{}: {}", name, message),
+            EvalError::DimensionMismatch { op, left, right } => write!(
+                f,
+                "cannot {} quantities of dimension {} and {}",
+                op, left, right
+            ),
+            EvalError::TypeMismatch {
+                field,
+                expected,
+                found,
+            } => write!(
+                f,
+                "type mismatch on field '{}': expected {}, found {}",
+                field, expected, found
+            ),
+            EvalError::EmptyProgram => write!(f, "empty program"),
+        }
+    }
+}
+
+/// Either phase's failure, for callers that parse and evaluate in one step.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpreterError {
+    Parse(ParseError),
+    Eval(EvalError),
+}
+
+impl From<ParseError> for InterpreterError {
+    fn from(error: ParseError) -> Self {
+        InterpreterError::Parse(error)
+    }
+}
+
+impl From<EvalError> for InterpreterError {
+    fn from(error: EvalError) -> Self {
+        InterpreterError::Eval(error)
+    }
+}
+
+impl std::fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterpreterError::Parse(e) => write!(f, "{}", e),
+            InterpreterError::Eval(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Math expressions
+// ---------------------------------------------------------------------------
+
+/// How many arguments a function accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Exact(usize),
+    /// At least this many, e.g. variadic `min`/`max`.
+    AtLeast(usize),
+}
+
+impl Arity {
+    fn check(&self, name: &str, got: usize) -> Result<(), EvalError> {
+        let ok = match self {
+            Arity::Exact(n) => got == *n,
+            Arity::AtLeast(n) => got >= *n,
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(EvalError::WrongArity {
+                name: name.to_string(),
+                expected: *self,
+                got,
+            })
+        }
+    }
+}
+
+type FunctionBody = Rc<dyn Fn(&[f64]) -> Result<f64, String>>;
+
+#[derive(Clone)]
+struct Function {
+    arity: Arity,
+    body: FunctionBody,
+}
+
+/// Variable environment and function table for expression evaluation.
+/// Variables live in a stack of scopes: lookups walk from the innermost
+/// scope outwards, so inner bindings shadow outer ones.
+#[derive(Clone)]
+pub struct Context {
+    scopes: Vec<HashMap<String, f64>>,
+    functions: HashMap<String, Function>,
+    /// Bumped on every mutation; per-variable versions let caches tell
+    /// exactly which bindings changed since a value was computed.
+    version: u64,
+    variable_versions: HashMap<String, u64>,
+}
+
+impl std::fmt::Debug for Context {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names: Vec<&str> = self.functions.keys().map(|s| s.as_str()).collect();
+        names.sort_unstable();
+        f.debug_struct("Context")
+            .field("scopes", &self.scopes)
+            .field("functions", &names)
+            .finish()
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Context::new()
+    }
+}
+
+impl Context {
+    pub fn new() -> Self {
+        let mut context = Context {
+            scopes: vec![HashMap::new()],
+            functions: HashMap::new(),
+            version: 0,
+            variable_versions: HashMap::new(),
+        };
+        context.install_builtins();
+        context
+    }
+
+    fn install_builtins(&mut self) {
+        fn fold_min(args: &[f64]) -> Result<f64, String> {
+            Ok(args.iter().copied().fold(f64::INFINITY, f64::min))
+        }
+        fn fold_max(args: &[f64]) -> Result<f64, String> {
+            Ok(args.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+        }
+
+        self.register_fn("sin", Arity::Exact(1), |a| Ok(a[0].sin()));
+        self.register_fn("cos", Arity::Exact(1), |a| Ok(a[0].cos()));
+        self.register_fn("tan", Arity::Exact(1), |a| Ok(a[0].tan()));
+        self.register_fn("abs", Arity::Exact(1), |a| Ok(a[0].abs()));
+        self.register_fn("sqrt", Arity::Exact(1), |a| {
+            if a[0] < 0.0 {
+                Err(format!("negative argument {}", a[0]))
+            } else {
+                Ok(a[0].sqrt())
+            }
+        });
+        self.register_fn("ln", Arity::Exact(1), |a| {
+            if a[0] <= 0.0 {
+                Err(format!("non-positive argument {}", a[0]))
+            } else {
+                Ok(a[0].ln())
+            }
+        });
+        self.register_fn("log", Arity::Exact(1), |a| {
+            if a[0] <= 0.0 {
+                Err(format!("non-positive argument {}", a[0]))
+            } else {
+                Ok(a[0].log10())
+            }
+        });
+        self.register_fn("pow", Arity::Exact(2), |a| Ok(a[0].powf(a[1])));
+        self.register_fn("min", Arity::AtLeast(2), fold_min);
+        self.register_fn("max", Arity::AtLeast(2), fold_max);
+        self.register_fn("clamp", Arity::Exact(3), |a| {
+            if a[1] > a[2] {
+                Err(format!("lo {} greater than hi {}", a[1], a[2]))
+            } else {
+                Ok(a[0].clamp(a[1], a[2]))
+            }
+        });
+    }
+
+    pub fn register_fn<F>(&mut self, name: &str, arity: Arity, body: F)
+    where
+        F: Fn(&[f64]) -> Result<f64, String> + 'static,
+    {
+        self.functions.insert(
+            name.to_string(),
+            Function {
+                arity,
+                body: Rc::new(body),
+            },
+        );
+    }
+
+    pub fn call(&self, name: &str, args: &[f64]) -> Result<f64, EvalError> {
+        let function = self
+            .functions
+            .get(name)
+            .ok_or_else(|| EvalError::UnknownFunction(name.to_string()))?;
+        function.arity.check(name, args.len())?;
+        (function.body)(args).map_err(|message| EvalError::Function {
+            name: name.to_string(),
+            message,
+        })
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    /// The outermost scope is never popped. Names bound in the popped
+    /// scope revert to their outer meaning, so their versions are bumped.
+    pub fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            let popped = self.scopes.pop().expect("checked above");
+            for name in popped.keys() {
+                self.bump(name);
+            }
+        }
+    }
+
+    fn bump(&mut self, name: &str) {
+        self.version += 1;
+        self.variable_versions
+            .insert(name.to_string(), self.version);
+    }
+
+    /// The version recorded at the variable's last mutation; `0` means the
+    /// name has never been written.
+    pub fn variable_version(&self, name: &str) -> u64 {
+        self.variable_versions.get(name).copied().unwrap_or(0)
+    }
+
+    /// Updates the innermost scope that already binds `name`, or defines it
+    /// in the current scope.
+    pub fn set(&mut self, name: &str, value: f64) {
+        self.bump(name);
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(slot) = scope.get_mut(name) {
+                *slot = value;
+                return;
+            }
+        }
+        self.scopes
+            .last_mut()
+            .expect("at least one scope")
+            .insert(name.to_string(), value);
+    }
+
+    /// Defines `name` in the current scope, shadowing any outer binding.
+    pub fn define(&mut self, name: &str, value: f64) {
+        self.bump(name);
+        self.scopes
+            .last_mut()
+            .expect("at least one scope")
+            .insert(name.to_string(), value);
+    }
+
+    /// Flattened view of all visible bindings, inner scopes shadowing outer.
+    pub fn variables(&self) -> HashMap<String, f64> {
+        let mut flat = HashMap::new();
+        for scope in &self.scopes {
+            for (name, value) in scope {
+                flat.insert(name.clone(), *value);
+            }
+        }
+        flat
+    }
+
+    pub fn get(&self, name: &str) -> Result<f64, EvalError> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+            .ok_or_else(|| EvalError::UndefinedVariable(name.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    /// `%`, remainder.
+    Mod,
+    /// `^`, right-associative power.
+    Pow,
+}
+
+/// Comparison operators; they evaluate numeric operands and yield a boolean
+/// encoded as `1.0` / `0.0` so comparisons compose with arithmetic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum CmpOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl CmpOp {
+    fn from_symbol(symbol: &str) -> Result<CmpOp, String> {
+        match symbol {
+            "<" => Ok(CmpOp::Lt),
+            "<=" => Ok(CmpOp::Le),
+            ">" => Ok(CmpOp::Gt),
+            ">=" => Ok(CmpOp::Ge),
+            "==" => Ok(CmpOp::Eq),
+            "!=" => Ok(CmpOp::Ne),
+            other => Err(format!("unknown comparison operator '{}'", other)),
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            CmpOp::Lt => "<",
+            CmpOp::Le => "<=",
+            CmpOp::Gt => ">",
+            CmpOp::Ge => ">=",
+            CmpOp::Eq => "==",
+            CmpOp::Ne => "!=",
+        }
+    }
+
+    fn apply_ord(&self, ord: Ordering) -> bool {
+        match self {
+            CmpOp::Lt => ord == Ordering::Less,
+            CmpOp::Le => ord != Ordering::Greater,
+            CmpOp::Gt => ord == Ordering::Greater,
+            CmpOp::Ge => ord != Ordering::Less,
+            CmpOp::Eq => ord == Ordering::Equal,
+            CmpOp::Ne => ord != Ordering::Equal,
+        }
+    }
+
+    fn apply(&self, l: f64, r: f64) -> bool {
+        match self {
+            CmpOp::Lt => l < r,
+            CmpOp::Le => l <= r,
+            CmpOp::Gt => l > r,
+            CmpOp::Ge => l >= r,
+            CmpOp::Eq => l == r,
+            CmpOp::Ne => l != r,
+        }
+    }
+}
+
+impl BinOp {
+    fn from_symbol(symbol: &str) -> Result<BinOp, String> {
+        match symbol {
+            "+" => Ok(BinOp::Add),
+            "-" => Ok(BinOp::Sub),
+            "*" => Ok(BinOp::Mul),
+            "/" => Ok(BinOp::Div),
+            "%" => Ok(BinOp::Mod),
+            "^" => Ok(BinOp::Pow),
+            other => Err(format!("unknown binary operator '{}'", other)),
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Mod => "%",
+            BinOp::Pow => "^",
+        }
+    }
+
+    fn apply(&self, l: f64, r: f64) -> Result<f64, EvalError> {
+        match self {
+            BinOp::Add => Ok(l + r),
+            BinOp::Sub => Ok(l - r),
+            BinOp::Mul => Ok(l * r),
+            BinOp::Div => {
+                if r == 0.0 {
+                    Err(EvalError::DivisionByZero)
+                } else {
+                    Ok(l / r)
+                }
+            }
+            BinOp::Mod => {
+                if r == 0.0 {
+                    Err(EvalError::ModuloByZero)
+                } else {
+                    Ok(l % r)
+                }
+            }
+            BinOp::Pow => Ok(l.powf(r)),
+        }
+    }
+}
+
+/// Abstract syntax tree of the arithmetic language.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum Expr {
+    Number(f64),
+    Variable(String),
+    Binary {
+        op: BinOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Compare {
+        op: CmpOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    /// Unary negation: `-x`.
+    Negate(Box<Expr>),
+    Call {
+        name: String,
+        args: Vec<Expr>,
+    },
+    /// `name = value`; evaluates to the assigned value.
+    Assign {
+        name: String,
+        value: Box<Expr>,
+    },
+    /// `let name = value in body`: binds `name` in a fresh scope for `body`.
+    Let {
+        name: String,
+        value: Box<Expr>,
+        body: Box<Expr>,
+    },
+    /// `;`-separated statements; evaluates to the last statement's value.
+    Sequence(Vec<Expr>),
+}
+
+impl Expr {
+    pub fn interpret(&self, context: &mut Context) -> Result<f64, EvalError> {
+        self.accept(&mut Evaluator { context })
+    }
+
+    /// Sorted list of the distinct variable names the expression reads or
+    /// assigns.
+    pub fn variables(&self) -> Vec<String> {
+        let mut collector = VariableCollector::default();
+        self.accept(&mut collector);
+        collector.names.into_iter().collect()
+    }
+
+    /// Height of the tree: 1 for a leaf.
+    pub fn depth(&self) -> usize {
+        self.accept(&mut DepthVisitor)
+    }
+
+    /// Returns a simplified tree: constant subtrees are folded
+    /// (`2 * 3 + x` → `6 + x`), identity operations are removed (`x * 1`,
+    /// `x + 0`), and division or modulo by a constant zero is reported as an
+    /// error without evaluating anything else.
+    pub fn optimize(&self) -> Result<Expr, EvalError> {
+        Ok(match self {
+            Expr::Binary { op, left, right } => {
+                let left = left.optimize()?;
+                let right = right.optimize()?;
+                if let (Expr::Number(l), Expr::Number(r)) = (&left, &right) {
+                    return match op {
+                        BinOp::Add => Ok(Expr::Number(l + r)),
+                        BinOp::Sub => Ok(Expr::Number(l - r)),
+                        BinOp::Mul => Ok(Expr::Number(l * r)),
+                        BinOp::Div if *r == 0.0 => Err(EvalError::DivisionByZero),
+                        BinOp::Div => Ok(Expr::Number(l / r)),
+                        BinOp::Mod if *r == 0.0 => Err(EvalError::ModuloByZero),
+                        BinOp::Mod => Ok(Expr::Number(l % r)),
+                        BinOp::Pow => Ok(Expr::Number(l.powf(*r))),
+                    };
+                }
+                match (op, &left, &right) {
+                    (BinOp::Div | BinOp::Mod, _, Expr::Number(r)) if *r == 0.0 => {
+                        return Err(if *op == BinOp::Div {
+                            EvalError::DivisionByZero
+                        } else {
+                            EvalError::ModuloByZero
+                        });
+                    }
+                    (BinOp::Add, Expr::Number(l), _) if *l == 0.0 => right,
+                    (BinOp::Add | BinOp::Sub, _, Expr::Number(r)) if *r == 0.0 => left,
+                    (BinOp::Mul, Expr::Number(l), _) if *l == 1.0 => right,
+                    (BinOp::Mul | BinOp::Div | BinOp::Pow, _, Expr::Number(r)) if *r == 1.0 => {
+                        left
+                    }
+                    (BinOp::Mul, Expr::Number(l), _) if *l == 0.0 => Expr::Number(0.0),
+                    (BinOp::Mul, _, Expr::Number(r)) if *r == 0.0 => Expr::Number(0.0),
+                    _ => Expr::Binary {
+                        op: *op,
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    },
+                }
+            }
+            Expr::Compare { op, left, right } => Expr::Compare {
+                op: *op,
+                left: Box::new(left.optimize()?),
+                right: Box::new(right.optimize()?),
+            },
+            Expr::Negate(inner) => match inner.optimize()? {
+                Expr::Number(value) => Expr::Number(-value),
+                optimized => Expr::Negate(Box::new(optimized)),
+            },
+            Expr::Call { name, args } => Expr::Call {
+                name: name.clone(),
+                args: args.iter().map(|a| a.optimize()).collect::<Result<_, _>>()?,
+            },
+            Expr::Assign { name, value } => Expr::Assign {
+                name: name.clone(),
+                value: Box::new(value.optimize()?),
+            },
+            Expr::Let { name, value, body } => Expr::Let {
+                name: name.clone(),
+                value: Box::new(value.optimize()?),
+                body: Box::new(body.optimize()?),
+            },
+            Expr::Sequence(statements) => Expr::Sequence(
+                statements
+                    .iter()
+                    .map(|s| s.optimize())
+                    .collect::<Result<_, _>>()?,
+            ),
+            Expr::Number(_) | Expr::Variable(_) => self.clone(),
+        })
+    }
+
+    pub fn to_string(&self) -> String {
+        self.accept(&mut Printer)
+    }
+
+    /// Double dispatch entry point of the visitor pattern: each node kind
+    /// calls back into the matching `visit_*` method.
+    pub fn accept<V: ExprVisitor>(&self, visitor: &mut V) -> V::Output {
+        match self {
+            Expr::Number(value) => visitor.visit_number(*value),
+            Expr::Variable(name) => visitor.visit_variable(name),
+            Expr::Binary { op, left, right } => visitor.visit_binary(*op, left, right),
+            Expr::Compare { op, left, right } => visitor.visit_compare(*op, left, right),
+            Expr::Negate(inner) => visitor.visit_negate(inner),
+            Expr::Call { name, args } => visitor.visit_call(name, args),
+            Expr::Assign { name, value } => visitor.visit_assign(name, value),
+            Expr::Let { name, value, body } => visitor.visit_let(name, value, body),
+            Expr::Sequence(statements) => visitor.visit_sequence(statements),
+        }
+    }
+}
+
+/// Visitor over `Expr` trees; evaluation, printing and analyses implement
+/// this instead of matching on the enum directly.
+pub trait ExprVisitor {
+    type Output;
+
+    fn visit_number(&mut self, value: f64) -> Self::Output;
+    fn visit_variable(&mut self, name: &str) -> Self::Output;
+    fn visit_binary(&mut self, op: BinOp, left: &Expr, right: &Expr) -> Self::Output;
+    fn visit_compare(&mut self, op: CmpOp, left: &Expr, right: &Expr) -> Self::Output;
+    fn visit_negate(&mut self, inner: &Expr) -> Self::Output;
+    fn visit_call(&mut self, name: &str, args: &[Expr]) -> Self::Output;
+    fn visit_assign(&mut self, name: &str, value: &Expr) -> Self::Output;
+    fn visit_let(&mut self, name: &str, value: &Expr, body: &Expr) -> Self::Output;
+    fn visit_sequence(&mut self, statements: &[Expr]) -> Self::Output;
+}
+
+/// Evaluates the tree against a mutable context.
+struct Evaluator<'a> {
+    context: &'a mut Context,
+}
+
+impl ExprVisitor for Evaluator<'_> {
+    type Output = Result<f64, EvalError>;
+
+    fn visit_number(&mut self, value: f64) -> Self::Output {
+        Ok(value)
+    }
+
+    fn visit_variable(&mut self, name: &str) -> Self::Output {
+        self.context.get(name)
+    }
+
+    fn visit_binary(&mut self, op: BinOp, left: &Expr, right: &Expr) -> Self::Output {
+        let l = left.accept(self)?;
+        let r = right.accept(self)?;
+        op.apply(l, r)
+    }
+
+    fn visit_compare(&mut self, op: CmpOp, left: &Expr, right: &Expr) -> Self::Output {
+        let l = left.accept(self)?;
+        let r = right.accept(self)?;
+        Ok(if op.apply(l, r) { 1.0 } else { 0.0 })
+    }
+
+    fn visit_negate(&mut self, inner: &Expr) -> Self::Output {
+        Ok(-inner.accept(self)?)
+    }
+
+    fn visit_call(&mut self, name: &str, args: &[Expr]) -> Self::Output {
+        // `if` is lazy: only the selected branch is evaluated.
+        if name == "if" {
+            if args.len() != 3 {
+                return Err(EvalError::WrongArity {
+                    name: "if".to_string(),
+                    expected: Arity::Exact(3),
+                    got: args.len(),
+                });
+            }
+            let cond = args[0].accept(self)?;
+            return if cond != 0.0 {
+                args[1].accept(self)
+            } else {
+                args[2].accept(self)
+            };
+        }
+        let values: Vec<f64> = args
+            .iter()
+            .map(|a| a.accept(self))
+            .collect::<Result<_, _>>()?;
+        self.context.call(name, &values)
+    }
+
+    fn visit_assign(&mut self, name: &str, value: &Expr) -> Self::Output {
+        let result = value.accept(self)?;
+        self.context.set(name, result);
+        Ok(result)
+    }
+
+    fn visit_let(&mut self, name: &str, value: &Expr, body: &Expr) -> Self::Output {
+        let bound = value.accept(self)?;
+        self.context.push_scope();
+        self.context.define(name, bound);
+        let result = body.accept(self);
+        self.context.pop_scope();
+        result
+    }
+
+    fn visit_sequence(&mut self, statements: &[Expr]) -> Self::Output {
+        let mut last = None;
+        for statement in statements {
+            last = Some(statement.accept(self)?);
+        }
+        last.ok_or(EvalError::EmptyProgram)
+    }
+}
+
+/// Pretty-prints the tree with explicit parentheses.
+struct Printer;
+
+impl ExprVisitor for Printer {
+    type Output = String;
+
+    fn visit_number(&mut self, value: f64) -> Self::Output {
+        if value.fract() == 0.0 && value.abs() < 1e15 {
+            format!("{}", value as i64)
+        } else {
+            format!("{}", value)
+        }
+    }
+
+    fn visit_variable(&mut self, name: &str) -> Self::Output {
+        name.to_string()
+    }
+
+    fn visit_binary(&mut self, op: BinOp, left: &Expr, right: &Expr) -> Self::Output {
+        format!("({} {} {})", left.accept(self), op.symbol(), right.accept(self))
+    }
+
+    fn visit_compare(&mut self, op: CmpOp, left: &Expr, right: &Expr) -> Self::Output {
+        format!("({} {} {})", left.accept(self), op.symbol(), right.accept(self))
+    }
+
+    fn visit_negate(&mut self, inner: &Expr) -> Self::Output {
+        format!("(-{})", inner.accept(self))
+    }
+
+    fn visit_call(&mut self, name: &str, args: &[Expr]) -> Self::Output {
+        let rendered: Vec<String> = args.iter().map(|a| a.accept(self)).collect();
+        format!("{}({})", name, rendered.join(", "))
+    }
+
+    fn visit_assign(&mut self, name: &str, value: &Expr) -> Self::Output {
+        format!("{} = {}", name, value.accept(self))
+    }
+
+    fn visit_let(&mut self, name: &str, value: &Expr, body: &Expr) -> Self::Output {
+        format!("let {} = {} in {}", name, value.accept(self), body.accept(self))
+    }
+
+    fn visit_sequence(&mut self, statements: &[Expr]) -> Self::Output {
+        let rendered: Vec<String> = statements.iter().map(|s| s.accept(self)).collect();
+        rendered.join("; ")
+    }
+}
+
+/// Collects the distinct variable names a tree mentions.
+#[derive(Default)]
+struct VariableCollector {
+    names: std::collections::BTreeSet<String>,
+}
+
+impl ExprVisitor for VariableCollector {
+    type Output = ();
+
+    fn visit_number(&mut self, _value: f64) {}
+
+    fn visit_variable(&mut self, name: &str) {
+        self.names.insert(name.to_string());
+    }
+
+    fn visit_binary(&mut self, _op: BinOp, left: &Expr, right: &Expr) {
+        left.accept(self);
+        right.accept(self);
+    }
+
+    fn visit_compare(&mut self, _op: CmpOp, left: &Expr, right: &Expr) {
+        left.accept(self);
+        right.accept(self);
+    }
+
+    fn visit_negate(&mut self, inner: &Expr) {
+        inner.accept(self);
+    }
+
+    fn visit_call(&mut self, _name: &str, args: &[Expr]) {
+        for arg in args {
+            arg.accept(self);
+        }
+    }
+
+    fn visit_assign(&mut self, name: &str, value: &Expr) {
+        self.names.insert(name.to_string());
+        value.accept(self);
+    }
+
+    fn visit_let(&mut self, name: &str, value: &Expr, body: &Expr) {
+        self.names.insert(name.to_string());
+        value.accept(self);
+        body.accept(self);
+    }
+
+    fn visit_sequence(&mut self, statements: &[Expr]) {
+        for statement in statements {
+            statement.accept(self);
+        }
+    }
+}
+
+/// Computes the height of the tree.
+struct DepthVisitor;
+
+impl ExprVisitor for DepthVisitor {
+    type Output = usize;
+
+    fn visit_number(&mut self, _value: f64) -> usize {
+        1
+    }
+
+    fn visit_variable(&mut self, _name: &str) -> usize {
+        1
+    }
+
+    fn visit_binary(&mut self, _op: BinOp, left: &Expr, right: &Expr) -> usize {
+        1 + left.accept(self).max(right.accept(self))
+    }
+
+    fn visit_compare(&mut self, _op: CmpOp, left: &Expr, right: &Expr) -> usize {
+        1 + left.accept(self).max(right.accept(self))
+    }
+
+    fn visit_negate(&mut self, inner: &Expr) -> usize {
+        1 + inner.accept(self)
+    }
+
+    fn visit_call(&mut self, _name: &str, args: &[Expr]) -> usize {
+        1 + args.iter().map(|a| a.accept(self)).max().unwrap_or(0)
+    }
+
+    fn visit_assign(&mut self, _name: &str, value: &Expr) -> usize {
+        1 + value.accept(self)
+    }
+
+    fn visit_let(&mut self, _name: &str, value: &Expr, body: &Expr) -> usize {
+        1 + value.accept(self).max(body.accept(self))
+    }
+
+    fn visit_sequence(&mut self, statements: &[Expr]) -> usize {
+        1 + statements.iter().map(|s| s.accept(self)).max().unwrap_or(0)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// AST serialization
+// ---------------------------------------------------------------------------
+
+/// Minimal JSON value for (de)serializing expression trees without external
+/// dependencies; only the shapes the AST writer produces are supported.
+#[derive(Debug, Clone, PartialEq)]
+enum JsonValue {
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    fn get<'a>(&'a self, key: &str) -> Option<&'a JsonValue> {
+        match self {
+            JsonValue::Object(entries) => entries
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn string(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+}
+
+fn json_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            other => escaped.push(other),
+        }
+    }
+    escaped
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl JsonParser {
+    fn parse(input: &str) -> Result<JsonValue, String> {
+        let mut parser = JsonParser {
+            chars: input.chars().collect(),
+            position: 0,
+        };
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.position < parser.chars.len() {
+            return Err(format!("trailing JSON at position {}", parser.position));
+        }
+        Ok(value)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|c| c.is_whitespace())
+        {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn eat(&mut self, expected: char) -> Result<(), String> {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(format!(
+                "expected '{}' at position {}",
+                expected, self.position
+            ))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => Ok(JsonValue::String(self.parse_string()?)),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            other => Err(format!("unexpected JSON input {:?}", other)),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, String> {
+        self.eat('{')?;
+        let mut entries = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(JsonValue::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.eat(':')?;
+            let value = self.parse_value()?;
+            entries.push((key, value));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some('}') => {
+                    self.position += 1;
+                    return Ok(JsonValue::Object(entries));
+                }
+                other => return Err(format!("expected ',' or '}}', found {:?}", other)),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, String> {
+        self.eat('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(JsonValue::Array(items));
+                }
+                other => return Err(format!("expected ',' or ']', found {:?}", other)),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.eat('"')?;
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.position += 1;
+                    return Ok(text);
+                }
+                Some('\\') => {
+                    self.position += 1;
+                    match self.peek() {
+                        Some('"') => text.push('"'),
+                        Some('\\') => text.push('\\'),
+                        Some('n') => text.push('\n'),
+                        Some('t') => text.push('\t'),
+                        other => return Err(format!("bad escape {:?}", other)),
+                    }
+                    self.position += 1;
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.position += 1;
+                }
+                None => return Err("unterminated JSON string".to_string()),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, String> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| {
+            c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')
+        }) {
+            self.position += 1;
+        }
+        let text: String = self.chars[start..self.position].iter().collect();
+        text.parse::<f64>()
+            .map(JsonValue::Number)
+            .map_err(|_| format!("bad JSON number '{}'", text))
+    }
+}
+
+/// Serializes the tree as a tagged JSON object, one object per node.
+struct JsonWriter;
+
+impl ExprVisitor for JsonWriter {
+    type Output = String;
+
+    fn visit_number(&mut self, value: f64) -> Self::Output {
+        format!("{{\"type\":\"number\",\"value\":{}}}", value)
+    }
+
+    fn visit_variable(&mut self, name: &str) -> Self::Output {
+        format!("{{\"type\":\"variable\",\"name\":\"{}\"}}", json_escape(name))
+    }
+
+    fn visit_binary(&mut self, op: BinOp, left: &Expr, right: &Expr) -> Self::Output {
+        format!(
+            "{{\"type\":\"binary\",\"op\":\"{}\",\"left\":{},\"right\":{}}}",
+            op.symbol(),
+            left.accept(self),
+            right.accept(self)
+        )
+    }
+
+    fn visit_compare(&mut self, op: CmpOp, left: &Expr, right: &Expr) -> Self::Output {
+        format!(
+            "{{\"type\":\"compare\",\"op\":\"{}\",\"left\":{},\"right\":{}}}",
+            op.symbol(),
+            left.accept(self),
+            right.accept(self)
+        )
+    }
+
+    fn visit_negate(&mut self, inner: &Expr) -> Self::Output {
+        format!("{{\"type\":\"negate\",\"inner\":{}}}", inner.accept(self))
+    }
+
+    fn visit_call(&mut self, name: &str, args: &[Expr]) -> Self::Output {
+        let rendered: Vec<String> = args.iter().map(|a| a.accept(self)).collect();
+        format!(
+            "{{\"type\":\"call\",\"name\":\"{}\",\"args\":[{}]}}",
+            json_escape(name),
+            rendered.join(",")
+        )
+    }
+
+    fn visit_assign(&mut self, name: &str, value: &Expr) -> Self::Output {
+        format!(
+            "{{\"type\":\"assign\",\"name\":\"{}\",\"value\":{}}}",
+            json_escape(name),
+            value.accept(self)
+        )
+    }
+
+    fn visit_let(&mut self, name: &str, value: &Expr, body: &Expr) -> Self::Output {
+        format!(
+            "{{\"type\":\"let\",\"name\":\"{}\",\"value\":{},\"body\":{}}}",
+            json_escape(name),
+            value.accept(self),
+            body.accept(self)
+        )
+    }
+
+    fn visit_sequence(&mut self, statements: &[Expr]) -> Self::Output {
+        let rendered: Vec<String> = statements.iter().map(|s| s.accept(self)).collect();
+        format!("{{\"type\":\"sequence\",\"statements\":[{}]}}", rendered.join(","))
+    }
+}
+
+impl Expr {
+    /// Serializes the tree to a tagged JSON representation.
+    pub fn to_json(&self) -> String {
+        self.accept(&mut JsonWriter)
+    }
+
+    /// Rebuilds a tree from `to_json` output, so ASTs can be stored and
+    /// reloaded without re-parsing the source.
+    pub fn from_json(input: &str) -> Result<Expr, String> {
+        Expr::from_json_value(&JsonParser::parse(input)?)
+    }
+
+    fn from_json_value(value: &JsonValue) -> Result<Expr, String> {
+        let tag = value
+            .get("type")
+            .and_then(|t| t.string())
+            .ok_or("node is missing a \"type\" tag")?;
+        let field = |key: &str| -> Result<&JsonValue, String> {
+            value.get(key).ok_or(format!("{} node missing \"{}\"", tag, key))
+        };
+        let name = |key: &str| -> Result<String, String> {
+            field(key)?
+                .string()
+                .map(|s| s.to_string())
+                .ok_or(format!("{} node: \"{}\" must be a string", tag, key))
+        };
+        let child = |key: &str| -> Result<Box<Expr>, String> {
+            Ok(Box::new(Expr::from_json_value(field(key)?)?))
+        };
+        let children = |key: &str| -> Result<Vec<Expr>, String> {
+            match field(key)? {
+                JsonValue::Array(items) => items.iter().map(Expr::from_json_value).collect(),
+                _ => Err(format!("{} node: \"{}\" must be an array", tag, key)),
+            }
+        };
+        match tag {
+            "number" => match field("value")? {
+                JsonValue::Number(n) => Ok(Expr::Number(*n)),
+                _ => Err("number node: \"value\" must be a number".to_string()),
+            },
+            "variable" => Ok(Expr::Variable(name("name")?)),
+            "binary" => Ok(Expr::Binary {
+                op: BinOp::from_symbol(&name("op")?)?,
+                left: child("left")?,
+                right: child("right")?,
+            }),
+            "compare" => Ok(Expr::Compare {
+                op: CmpOp::from_symbol(&name("op")?)?,
+                left: child("left")?,
+                right: child("right")?,
+            }),
+            "negate" => Ok(Expr::Negate(child("inner")?)),
+            "call" => Ok(Expr::Call {
+                name: name("name")?,
+                args: children("args")?,
+            }),
+            "assign" => Ok(Expr::Assign {
+                name: name("name")?,
+                value: child("value")?,
+            }),
+            "let" => Ok(Expr::Let {
+                name: name("name")?,
+                value: child("value")?,
+                body: child("body")?,
+            }),
+            "sequence" => Ok(Expr::Sequence(children("statements")?)),
+            other => Err(format!("unknown node type '{}'", other)),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tokenizer and parser
+// ---------------------------------------------------------------------------
+
+/// One lexed token with its half-open byte span in the source string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Lexer for the arithmetic language. Numbers support decimals, underscores
+/// as digit separators (`1_000_000`) and scientific notation (`1.5e-3`);
+/// identifiers may be any unicode alphabetic sequence.
+pub fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let bytes = input.as_bytes();
+    let mut chars = input.char_indices().peekable();
+
+    let is_digit = |b: Option<&u8>| b.is_some_and(|b| b.is_ascii_digit());
+    let mut push = |text: &str, start: usize, end: usize| {
+        tokens.push(Token {
+            text: text.to_string(),
+            start,
+            end,
+        });
+    };
+
+    while let Some(&(start, c)) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '0'..='9' | '.' => {
+                let mut end = start;
+                while let Some(&(i, d)) = chars.peek() {
+                    if d.is_ascii_digit() || d == '.' || d == '_' {
+                        end = i + d.len_utf8();
+                        chars.next();
+                    } else if (d == 'e' || d == 'E')
+                        && (is_digit(bytes.get(i + 1))
+                            || (matches!(bytes.get(i + 1), Some(b'+') | Some(b'-'))
+                                && is_digit(bytes.get(i + 2))))
+                    {
+                        chars.next(); // e
+                        let (j, sign) = *chars.peek().unwrap();
+                        if sign == '+' || sign == '-' {
+                            end = j + 1;
+                            chars.next();
+                        }
+                        while let Some(&(k, d)) = chars.peek() {
+                            if d.is_ascii_digit() || d == '_' {
+                                end = k + 1;
+                                chars.next();
+                            } else {
+                                break;
+                            }
+                        }
+                        break;
+                    } else {
+                        break;
+                    }
+                }
+                push(&input[start..end], start, end);
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let mut end = start;
+                while let Some(&(i, d)) = chars.peek() {
+                    if d.is_alphanumeric() || d == '_' {
+                        end = i + d.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                push(&input[start..end], start, end);
+            }
+            '+' | '-' | '*' | '/' | '%' | '^' | '(' | ')' | ',' | ';' => {
+                push(&input[start..start + 1], start, start + 1);
+                chars.next();
+            }
+            '<' | '>' | '=' | '!' => {
+                chars.next();
+                if chars.peek().is_some_and(|&(_, d)| d == '=') {
+                    chars.next();
+                    push(&input[start..start + 2], start, start + 2);
+                } else if c == '!' {
+                    return Err(ParseError::InvalidToken {
+                        text: "!".to_string(),
+                        position: start,
+                    });
+                } else {
+                    push(&input[start..start + 1], start, start + 1);
+                }
+            }
+            other => {
+                return Err(ParseError::InvalidToken {
+                    text: other.to_string(),
+                    position: start,
+                })
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive-descent parser with standard precedence:
+/// `+ -` < `* / %` < unary `-` < `^` (right-associative) < atoms.
+pub struct ExpressionParser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl ExpressionParser {
+    pub fn parse(input: &str) -> Result<Expr, ParseError> {
+        let mut parser = ExpressionParser {
+            tokens: tokenize(input)?,
+            position: 0,
+        };
+        let expr = parser.parse_statement()?;
+        if parser.position < parser.tokens.len() {
+            let token = &parser.tokens[parser.position];
+            return Err(ParseError::UnexpectedToken {
+                found: token.text.clone(),
+                expected: Vec::new(),
+                span: (token.start, token.end),
+            });
+        }
+        Ok(expr)
+    }
+
+    /// Parses `;`-separated statements into an `Expr::Sequence`.
+    /// A single statement parses to itself, not a one-element sequence.
+    pub fn parse_program(input: &str) -> Result<Expr, ParseError> {
+        let mut parser = ExpressionParser {
+            tokens: tokenize(input)?,
+            position: 0,
+        };
+        let mut statements = vec![parser.parse_statement()?];
+        while parser.peek() == Some(";") {
+            parser.advance();
+            if parser.peek().is_none() {
+                break; // trailing semicolon
+            }
+            statements.push(parser.parse_statement()?);
+        }
+        if parser.position < parser.tokens.len() {
+            let token = &parser.tokens[parser.position];
+            return Err(ParseError::UnexpectedToken {
+                found: token.text.clone(),
+                expected: Vec::new(),
+                span: (token.start, token.end),
+            });
+        }
+        if statements.len() == 1 {
+            Ok(statements.pop().unwrap())
+        } else {
+            Ok(Expr::Sequence(statements))
+        }
+    }
+
+    fn parse_statement(&mut self) -> Result<Expr, ParseError> {
+        // `let name = value in body`
+        if self.peek() == Some("let") {
+            self.advance();
+            let name = match self.advance() {
+                Some(t) if t.text.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_') => {
+                    t.text
+                }
+                Some(t) => {
+                    return Err(ParseError::UnexpectedToken {
+                        found: t.text,
+                        expected: vec!["identifier".to_string()],
+                        span: (t.start, t.end),
+                    })
+                }
+                None => {
+                    return Err(ParseError::UnexpectedEof {
+                        expected: vec!["identifier".to_string()],
+                    })
+                }
+            };
+            self.expect("=")?;
+            let value = self.parse_statement()?;
+            self.expect("in")?;
+            let body = self.parse_statement()?;
+            return Ok(Expr::Let {
+                name,
+                value: Box::new(value),
+                body: Box::new(body),
+            });
+        }
+        // `name = expr` (a single `=`; `==` lexes as its own token).
+        if let (Some(first), Some("=")) = (
+            self.tokens.get(self.position).map(|t| t.text.clone()),
+            self.tokens.get(self.position + 1).map(|t| t.text.as_str()),
+        ) {
+            if first.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_') {
+                self.position += 2;
+                let value = self.parse_statement()?;
+                return Ok(Expr::Assign {
+                    name: first,
+                    value: Box::new(value),
+                });
+            }
+        }
+        self.parse_comparison()
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(|t| t.text.as_str())
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        if token.is_some() {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseError> {
+        match self.advance() {
+            Some(t) if t.text == token => Ok(()),
+            Some(t) => Err(ParseError::UnexpectedToken {
+                found: t.text,
+                expected: vec![format!("'{}'", token)],
+                span: (t.start, t.end),
+            }),
+            None => Err(ParseError::UnexpectedEof {
+                expected: vec![format!("'{}'", token)],
+            }),
+        }
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, ParseError> {
+        let left = self.parse_additive()?;
+        let op = match self.peek() {
+            Some("<") => CmpOp::Lt,
+            Some("<=") => CmpOp::Le,
+            Some(">") => CmpOp::Gt,
+            Some(">=") => CmpOp::Ge,
+            Some("==") => CmpOp::Eq,
+            Some("!=") => CmpOp::Ne,
+            _ => return Ok(left),
+        };
+        self.advance();
+        let right = self.parse_additive()?;
+        Ok(Expr::Compare {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        })
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_multiplicative()?;
+        while let Some(op) = match self.peek() {
+            Some("+") => Some(BinOp::Add),
+            Some("-") => Some(BinOp::Sub),
+            _ => None,
+        } {
+            self.advance();
+            let right = self.parse_multiplicative()?;
+            left = Expr::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_unary()?;
+        while let Some(op) = match self.peek() {
+            Some("*") => Some(BinOp::Mul),
+            Some("/") => Some(BinOp::Div),
+            Some("%") => Some(BinOp::Mod),
+            _ => None,
+        } {
+            self.advance();
+            let right = self.parse_unary()?;
+            left = Expr::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        if self.peek() == Some("-") {
+            self.advance();
+            return Ok(Expr::Negate(Box::new(self.parse_unary()?)));
+        }
+        self.parse_power()
+    }
+
+    fn parse_power(&mut self) -> Result<Expr, ParseError> {
+        let base = self.parse_primary()?;
+        if self.peek() == Some("^") {
+            self.advance();
+            // Right-associative: 2 ^ 3 ^ 2 == 2 ^ (3 ^ 2).
+            let exponent = self.parse_unary()?;
+            return Ok(Expr::Binary {
+                op: BinOp::Pow,
+                left: Box::new(base),
+                right: Box::new(exponent),
+            });
+        }
+        Ok(base)
+    }
+
+    fn primary_expectations() -> Vec<String> {
+        vec!["number".to_string(), "identifier".to_string(), "'('".to_string()]
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+        let token = self.advance().ok_or_else(|| ParseError::UnexpectedEof {
+            expected: ExpressionParser::primary_expectations(),
+        })?;
+        let span = (token.start, token.end);
+        let token = token.text;
+        if token == "(" {
+            // Full statement grammar inside parentheses, so `(let x = 1 in x)`
+            // can appear as an operand.
+            let expr = self.parse_statement()?;
+            self.expect(")")?;
+            return Ok(expr);
+        }
+        if let Ok(value) = token.replace('_', "").parse::<f64>() {
+            return Ok(Expr::Number(value));
+        }
+        if token.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_') {
+            if self.peek() == Some("(") {
+                self.advance();
+                let mut args = Vec::new();
+                if self.peek() != Some(")") {
+                    loop {
+                        args.push(self.parse_comparison()?);
+                        if self.peek() == Some(",") {
+                            self.advance();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                self.expect(")")?;
+                return Ok(Expr::Call { name: token, args });
+            }
+            return Ok(Expr::Variable(token));
+        }
+        Err(ParseError::UnexpectedToken {
+            found: token,
+            expected: ExpressionParser::primary_expectations(),
+            span,
+        })
+    }
+}
+
+/// How a `Calculator` evaluates: IEEE f64, or exact rationals so decimal
+/// arithmetic like `0.1 + 0.2 == 0.3` holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvalMode {
+    #[default]
+    Float,
+    Exact,
+}
+
+// ---------------------------------------------------------------------------
+// Memoized evaluation
+// ---------------------------------------------------------------------------
+
+struct MemoEntry {
+    value: f64,
+    /// Referenced variables and the context version at which each was last
+    /// written when the value was computed.
+    deps: Vec<(String, u64)>,
+}
+
+/// Per-node analysis computed in a single pass before evaluation:
+/// structural hash (the cache key), purity, and referenced variables.
+/// Each node combines its children's results, so the pass is linear in
+/// the tree size rather than hashing every subtree from scratch.
+struct NodeInfo {
+    hash: u64,
+    pure: bool,
+    vars: Vec<String>,
+    children: Vec<NodeInfo>,
+}
+
+fn analyze(expr: &Expr) -> NodeInfo {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn merge(mut acc: Vec<String>, extra: &[String]) -> Vec<String> {
+        for name in extra {
+            if !acc.iter().any(|n| n == name) {
+                acc.push(name.clone());
+            }
+        }
+        acc
+    }
+
+    fn fold(hasher: &mut DefaultHasher, children: Vec<NodeInfo>) -> (bool, Vec<String>, Vec<NodeInfo>) {
+        let mut pure = true;
+        let mut vars = Vec::new();
+        for child in &children {
+            child.hash.hash(hasher);
+            pure = pure && child.pure;
+            vars = merge(vars, &child.vars);
+        }
+        (pure, vars, children)
+    }
+
+    let mut hasher = DefaultHasher::new();
+    let (pure, vars, children) = match expr {
+        Expr::Number(value) => {
+            0u8.hash(&mut hasher);
+            value.to_bits().hash(&mut hasher);
+            (true, Vec::new(), Vec::new())
+        }
+        Expr::Variable(name) => {
+            1u8.hash(&mut hasher);
+            name.hash(&mut hasher);
+            (true, vec![name.clone()], Vec::new())
+        }
+        Expr::Binary { op, left, right } => {
+            2u8.hash(&mut hasher);
+            op.symbol().hash(&mut hasher);
+            fold(&mut hasher, vec![analyze(left), analyze(right)])
+        }
+        Expr::Compare { op, left, right } => {
+            3u8.hash(&mut hasher);
+            op.symbol().hash(&mut hasher);
+            fold(&mut hasher, vec![analyze(left), analyze(right)])
+        }
+        Expr::Negate(inner) => {
+            4u8.hash(&mut hasher);
+            fold(&mut hasher, vec![analyze(inner)])
+        }
+        Expr::Call { name, args } => {
+            5u8.hash(&mut hasher);
+            name.hash(&mut hasher);
+            fold(&mut hasher, args.iter().map(analyze).collect())
+        }
+        Expr::Assign { name, value } => {
+            6u8.hash(&mut hasher);
+            name.hash(&mut hasher);
+            let (_, vars, children) = fold(&mut hasher, vec![analyze(value)]);
+            (false, vars, children)
+        }
+        Expr::Let { name, value, body } => {
+            7u8.hash(&mut hasher);
+            name.hash(&mut hasher);
+            let (_, vars, children) = fold(&mut hasher, vec![analyze(value), analyze(body)]);
+            (false, vars, children)
+        }
+        Expr::Sequence(statements) => {
+            8u8.hash(&mut hasher);
+            let (_, vars, children) = fold(&mut hasher, statements.iter().map(analyze).collect());
+            (false, vars, children)
+        }
+    };
+    NodeInfo {
+        hash: hasher.finish(),
+        pure,
+        vars,
+        children,
+    }
+}
+
+/// Sub-expression result cache keyed by the structural hash of the subtree.
+/// Entries are invalidated lazily: a lookup revalidates every dependency
+/// against the context's per-variable versions.
+#[derive(Default)]
+pub struct MemoCache {
+    entries: HashMap<u64, MemoEntry>,
+    hits: u64,
+    misses: u64,
+}
+
+impl MemoCache {
+    pub fn new() -> Self {
+        MemoCache::default()
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Evaluator that consults a `MemoCache` for pure subtrees. Registered
+/// functions are assumed deterministic; a stateful closure would defeat
+/// the cache.
+struct MemoEvaluator<'a> {
+    context: &'a mut Context,
+    cache: &'a mut MemoCache,
+}
+
+impl MemoEvaluator<'_> {
+    fn evaluate(&mut self, expr: &Expr) -> Result<f64, EvalError> {
+        let info = analyze(expr);
+        self.eval(expr, &info)
+    }
+
+    fn eval(&mut self, expr: &Expr, info: &NodeInfo) -> Result<f64, EvalError> {
+        // Leaves are cheaper to evaluate than to look up.
+        let cacheable = info.pure && !matches!(expr, Expr::Number(_) | Expr::Variable(_));
+        if cacheable {
+            if let Some(entry) = self.cache.entries.get(&info.hash) {
+                let fresh = entry
+                    .deps
+                    .iter()
+                    .all(|(name, version)| self.context.variable_version(name) == *version);
+                if fresh {
+                    self.cache.hits += 1;
+                    return Ok(entry.value);
+                }
+            }
+        }
+        let value = self.step(expr, info)?;
+        if cacheable {
+            self.cache.misses += 1;
+            let deps = info
+                .vars
+                .iter()
+                .map(|name| (name.clone(), self.context.variable_version(name)))
+                .collect();
+            self.cache.entries.insert(info.hash, MemoEntry { value, deps });
+        }
+        Ok(value)
+    }
+
+    fn step(&mut self, expr: &Expr, info: &NodeInfo) -> Result<f64, EvalError> {
+        match expr {
+            Expr::Number(value) => Ok(*value),
+            Expr::Variable(name) => self.context.get(name),
+            Expr::Binary { op, left, right } => {
+                let l = self.eval(left, &info.children[0])?;
+                let r = self.eval(right, &info.children[1])?;
+                op.apply(l, r)
+            }
+            Expr::Compare { op, left, right } => {
+                let l = self.eval(left, &info.children[0])?;
+                let r = self.eval(right, &info.children[1])?;
+                Ok(if op.apply(l, r) { 1.0 } else { 0.0 })
+            }
+            Expr::Negate(inner) => Ok(-self.eval(inner, &info.children[0])?),
+            Expr::Call { name, args } => {
+                if name == "if" {
+                    if args.len() != 3 {
+                        return Err(EvalError::WrongArity {
+                            name: "if".to_string(),
+                            expected: Arity::Exact(3),
+                            got: args.len(),
+                        });
+                    }
+                    let cond = self.eval(&args[0], &info.children[0])?;
+                    return if cond != 0.0 {
+                        self.eval(&args[1], &info.children[1])
+                    } else {
+                        self.eval(&args[2], &info.children[2])
+                    };
+                }
+                let values: Vec<f64> = args
+                    .iter()
+                    .zip(&info.children)
+                    .map(|(a, i)| self.eval(a, i))
+                    .collect::<Result<_, _>>()?;
+                self.context.call(name, &values)
+            }
+            Expr::Assign { name, value } => {
+                let result = self.eval(value, &info.children[0])?;
+                self.context.set(name, result);
+                Ok(result)
+            }
+            Expr::Let { name, value, body } => {
+                let bound = self.eval(value, &info.children[0])?;
+                self.context.push_scope();
+                self.context.define(name, bound);
+                let result = self.eval(body, &info.children[1]);
+                self.context.pop_scope();
+                result
+            }
+            Expr::Sequence(statements) => {
+                let mut last = None;
+                for (statement, child) in statements.iter().zip(&info.children) {
+                    last = Some(self.eval(statement, child)?);
+                }
+                last.ok_or(EvalError::EmptyProgram)
+            }
+        }
+    }
+}
+
+/// Facade over parser + context for one-line evaluation.
+#[derive(Default)]
+pub struct Calculator {
+    context: Context,
+    mode: EvalMode,
+    memo: Option<MemoCache>,
+}
+
+impl Calculator {
+    pub fn new() -> Self {
+        Calculator {
+            context: Context::new(),
+            mode: EvalMode::Float,
+            memo: None,
+        }
+    }
+
+    pub fn exact() -> Self {
+        Calculator {
+            context: Context::new(),
+            mode: EvalMode::Exact,
+            memo: None,
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: EvalMode) {
+        self.mode = mode;
+    }
+
+    /// Turns on sub-expression memoization for float-mode evaluation.
+    pub fn enable_memoization(&mut self) {
+        self.memo = Some(MemoCache::new());
+    }
+
+    /// `(hits, misses)` of the memo cache; zeros when memoization is off.
+    pub fn memo_stats(&self) -> (u64, u64) {
+        match &self.memo {
+            Some(cache) => (cache.hits(), cache.misses()),
+            None => (0, 0),
+        }
+    }
+
+    /// Registers a custom function on the underlying context, extending the
+    /// expression language at runtime without new AST node types. The arity
+    /// is checked before the closure runs; `Err` strings from the closure
+    /// surface as `EvalError::Function`.
+    pub fn register_fn<F>(&mut self, name: &str, arity: Arity, body: F)
+    where
+        F: Fn(&[f64]) -> Result<f64, String> + 'static,
+    {
+        self.context.register_fn(name, arity, body);
+    }
+
+    pub fn set_variable(&mut self, name: &str, value: f64) {
+        self.context.set(name, value);
+    }
+
+    pub fn evaluate(&mut self, input: &str) -> Result<f64, InterpreterError> {
+        let expr = ExpressionParser::parse(input)?;
+        self.run(&expr)
+    }
+
+    /// Runs a `;`-separated program, returning the last statement's value.
+    /// Assignments persist in the calculator's context afterwards.
+    pub fn run_program(&mut self, input: &str) -> Result<f64, InterpreterError> {
+        let expr = ExpressionParser::parse_program(input)?;
+        self.run(&expr)
+    }
+
+    /// Evaluates with exact rational arithmetic regardless of the mode.
+    pub fn evaluate_exact(&mut self, input: &str) -> Result<Rational, InterpreterError> {
+        let expr = ExpressionParser::parse(input)?;
+        Ok(expr.accept(&mut RationalEvaluator {
+            context: &mut self.context,
+        })?)
+    }
+
+    fn run(&mut self, expr: &Expr) -> Result<f64, InterpreterError> {
+        match self.mode {
+            EvalMode::Float => match &mut self.memo {
+                Some(cache) => Ok(MemoEvaluator {
+                    context: &mut self.context,
+                    cache,
+                }
+                .evaluate(expr)?),
+                None => Ok(expr.interpret(&mut self.context)?),
+            },
+            EvalMode::Exact => Ok(expr
+                .accept(&mut RationalEvaluator {
+                    context: &mut self.context,
+                })?
+                .to_f64()),
+        }
+    }
+
+    pub fn variables(&self) -> HashMap<String, f64> {
+        self.context.variables()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Boolean expressions
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Default, Clone)]
+pub struct BoolContext {
+    variables: HashMap<String, bool>,
+}
+
+impl BoolContext {
+    pub fn new() -> Self {
+        BoolContext::default()
+    }
+
+    pub fn set(&mut self, name: &str, value: bool) {
+        self.variables.insert(name.to_string(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Result<bool, EvalError> {
+        self.variables
+            .get(name)
+            .copied()
+            .ok_or_else(|| EvalError::UndefinedVariable(name.to_string()))
+    }
+}
+
+pub trait BooleanExpression {
+    fn evaluate(&self, context: &BoolContext) -> Result<bool, EvalError>;
+    fn to_string(&self) -> String;
+}
+
+pub struct BoolVariable {
+    pub name: String,
+}
+
+impl BooleanExpression for BoolVariable {
+    fn evaluate(&self, context: &BoolContext) -> Result<bool, EvalError> {
+        context.get(&self.name)
+    }
+
+    fn to_string(&self) -> String {
+        self.name.clone()
+    }
+}
+
+pub struct BoolLiteral {
+    pub value: bool,
+}
+
+impl BooleanExpression for BoolLiteral {
+    fn evaluate(&self, _context: &BoolContext) -> Result<bool, EvalError> {
+        Ok(self.value)
+    }
+
+    fn to_string(&self) -> String {
+        if self.value { "TRUE" } else { "FALSE" }.to_string()
+    }
+}
+
+pub struct AndExpression {
+    pub left: Box<dyn BooleanExpression>,
+    pub right: Box<dyn BooleanExpression>,
+}
+
+impl BooleanExpression for AndExpression {
+    /// Short-circuits: a false left operand decides the result, so errors
+    /// in the right operand are never surfaced.
+    fn evaluate(&self, context: &BoolContext) -> Result<bool, EvalError> {
+        if !self.left.evaluate(context)? {
+            return Ok(false);
+        }
+        self.right.evaluate(context)
+    }
+
+    fn to_string(&self) -> String {
+        format!("({} AND {})", self.left.to_string(), self.right.to_string())
+    }
+}
+
+pub struct OrExpression {
+    pub left: Box<dyn BooleanExpression>,
+    pub right: Box<dyn BooleanExpression>,
+}
+
+impl BooleanExpression for OrExpression {
+    /// Short-circuits: a true left operand decides the result without
+    /// evaluating the right.
+    fn evaluate(&self, context: &BoolContext) -> Result<bool, EvalError> {
+        if self.left.evaluate(context)? {
+            return Ok(true);
+        }
+        self.right.evaluate(context)
+    }
+
+    fn to_string(&self) -> String {
+        format!("({} OR {})", self.left.to_string(), self.right.to_string())
+    }
+}
+
+/// Exclusive or; both operands always matter, so nothing short-circuits.
+pub struct XorExpression {
+    pub left: Box<dyn BooleanExpression>,
+    pub right: Box<dyn BooleanExpression>,
+}
+
+impl BooleanExpression for XorExpression {
+    fn evaluate(&self, context: &BoolContext) -> Result<bool, EvalError> {
+        let left = self.left.evaluate(context)?;
+        let right = self.right.evaluate(context)?;
+        Ok(left != right)
+    }
+
+    fn to_string(&self) -> String {
+        format!("({} XOR {})", self.left.to_string(), self.right.to_string())
+    }
+}
+
+/// Material implication: `a IMPLIES b` is `NOT a OR b`. A false premise
+/// makes the implication vacuously true, so the conclusion is skipped.
+pub struct ImpliesExpression {
+    pub left: Box<dyn BooleanExpression>,
+    pub right: Box<dyn BooleanExpression>,
+}
+
+impl BooleanExpression for ImpliesExpression {
+    fn evaluate(&self, context: &BoolContext) -> Result<bool, EvalError> {
+        if !self.left.evaluate(context)? {
+            return Ok(true);
+        }
+        self.right.evaluate(context)
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "({} IMPLIES {})",
+            self.left.to_string(),
+            self.right.to_string()
+        )
+    }
+}
+
+pub struct NotExpression {
+    pub inner: Box<dyn BooleanExpression>,
+}
+
+impl BooleanExpression for NotExpression {
+    fn evaluate(&self, context: &BoolContext) -> Result<bool, EvalError> {
+        Ok(!self.inner.evaluate(context)?)
+    }
+
+    fn to_string(&self) -> String {
+        format!("(NOT {})", self.inner.to_string())
+    }
+}
+
+/// Recursive-descent parser for the boolean language, mirroring
+/// `ExpressionParser`: `IMPLIES` < `OR` < `XOR` < `AND` < `NOT` < atoms,
+/// with `IMPLIES` right-associative. Keywords are case-insensitive;
+/// `to_string()` output re-parses to an equal tree.
+pub struct BooleanParser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl BooleanParser {
+    pub fn parse(input: &str) -> Result<Box<dyn BooleanExpression>, ParseError> {
+        let mut tokens = Vec::new();
+        let mut chars = input.char_indices().peekable();
+        while let Some(&(start, c)) = chars.peek() {
+            match c {
+                _ if c.is_whitespace() => {
+                    chars.next();
+                }
+                '(' | ')' => {
+                    tokens.push(Token {
+                        text: c.to_string(),
+                        start,
+                        end: start + 1,
+                    });
+                    chars.next();
+                }
+                _ if c.is_alphanumeric() || c == '_' => {
+                    let mut end = start;
+                    while let Some(&(i, d)) = chars.peek() {
+                        if d.is_alphanumeric() || d == '_' {
+                            end = i + d.len_utf8();
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    tokens.push(Token {
+                        text: input[start..end].to_string(),
+                        start,
+                        end,
+                    });
+                }
+                other => {
+                    return Err(ParseError::InvalidToken {
+                        text: other.to_string(),
+                        position: start,
+                    })
+                }
+            }
+        }
+        let mut parser = BooleanParser {
+            tokens,
+            position: 0,
+        };
+        let expr = parser.parse_implies()?;
+        if parser.position < parser.tokens.len() {
+            let token = &parser.tokens[parser.position];
+            return Err(ParseError::UnexpectedToken {
+                found: token.text.clone(),
+                expected: Vec::new(),
+                span: (token.start, token.end),
+            });
+        }
+        Ok(expr)
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        self.tokens
+            .get(self.position)
+            .is_some_and(|t| t.text.eq_ignore_ascii_case(keyword))
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        if token.is_some() {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn parse_implies(&mut self) -> Result<Box<dyn BooleanExpression>, ParseError> {
+        let left = self.parse_or()?;
+        if self.peek_keyword("IMPLIES") {
+            self.advance();
+            let right = self.parse_implies()?;
+            return Ok(Box::new(ImpliesExpression { left, right }));
+        }
+        Ok(left)
+    }
+
+    fn parse_or(&mut self) -> Result<Box<dyn BooleanExpression>, ParseError> {
+        let mut left = self.parse_xor()?;
+        while self.peek_keyword("OR") {
+            self.advance();
+            let right = self.parse_xor()?;
+            left = Box::new(OrExpression { left, right });
+        }
+        Ok(left)
+    }
+
+    fn parse_xor(&mut self) -> Result<Box<dyn BooleanExpression>, ParseError> {
+        let mut left = self.parse_and()?;
+        while self.peek_keyword("XOR") {
+            self.advance();
+            let right = self.parse_and()?;
+            left = Box::new(XorExpression { left, right });
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Box<dyn BooleanExpression>, ParseError> {
+        let mut left = self.parse_not()?;
+        while self.peek_keyword("AND") {
+            self.advance();
+            let right = self.parse_not()?;
+            left = Box::new(AndExpression { left, right });
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Box<dyn BooleanExpression>, ParseError> {
+        if self.peek_keyword("NOT") {
+            self.advance();
+            let inner = self.parse_not()?;
+            return Ok(Box::new(NotExpression { inner }));
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<Box<dyn BooleanExpression>, ParseError> {
+        let token = self.advance().ok_or_else(|| ParseError::UnexpectedEof {
+            expected: vec!["identifier".to_string(), "'('".to_string()],
+        })?;
+        if token.text == "(" {
+            let expr = self.parse_implies()?;
+            match self.advance() {
+                Some(t) if t.text == ")" => Ok(expr),
+                Some(t) => Err(ParseError::UnexpectedToken {
+                    found: t.text,
+                    expected: vec!["')'".to_string()],
+                    span: (t.start, t.end),
+                }),
+                None => Err(ParseError::UnexpectedEof {
+                    expected: vec!["')'".to_string()],
+                }),
+            }
+        } else if token.text.eq_ignore_ascii_case("TRUE") {
+            Ok(Box::new(BoolLiteral { value: true }))
+        } else if token.text.eq_ignore_ascii_case("FALSE") {
+            Ok(Box::new(BoolLiteral { value: false }))
+        } else {
+            Ok(Box::new(BoolVariable { name: token.text }))
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Glob pattern matching
+// ---------------------------------------------------------------------------
+
+/// One element of a compiled glob pattern. A pattern like
+/// `user_*[0-9].{log,txt}` compiles to a sequence of these nodes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GlobNode {
+    /// A run of characters matched verbatim.
+    Literal(String),
+    /// `?`: exactly one arbitrary character.
+    AnyChar,
+    /// `*`: zero or more arbitrary characters.
+    Star,
+    /// `[0-9a-f]` or negated `[!abc]`; single characters are stored as
+    /// degenerate ranges.
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    /// `{jpg,png}`: any of the branch patterns.
+    Alternation(Vec<GlobPattern>),
+}
+
+impl GlobNode {
+    fn class_matches(negated: bool, ranges: &[(char, char)], c: char) -> bool {
+        let inside = ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi);
+        inside != negated
+    }
+
+    fn to_string(&self) -> String {
+        match self {
+            GlobNode::Literal(text) => text.clone(),
+            GlobNode::AnyChar => "?".to_string(),
+            GlobNode::Star => "*".to_string(),
+            GlobNode::Class { negated, ranges } => {
+                let mut out = String::from("[");
+                if *negated {
+                    out.push('!');
+                }
+                for &(lo, hi) in ranges {
+                    if lo == hi {
+                        out.push(lo);
+                    } else {
+                        out.push(lo);
+                        out.push('-');
+                        out.push(hi);
+                    }
+                }
+                out.push(']');
+                out
+            }
+            GlobNode::Alternation(branches) => {
+                let inner: Vec<String> = branches.iter().map(GlobPattern::to_string).collect();
+                format!("{{{}}}", inner.join(","))
+            }
+        }
+    }
+}
+
+/// A compiled glob pattern: a sequence of nodes matched against the whole
+/// input, with backtracking for `*` and alternation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobPattern {
+    nodes: Vec<GlobNode>,
+}
+
+impl GlobPattern {
+    pub fn compile(pattern: &str) -> Result<GlobPattern, ParseError> {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut position = 0;
+        let pattern = GlobPattern::compile_sequence(&chars, &mut position, false)?;
+        debug_assert_eq!(position, chars.len(), "sequence parser consumes all input");
+        Ok(pattern)
+    }
+
+    /// Parses until end of input, or until `,` / `}` when inside an
+    /// alternation branch.
+    fn compile_sequence(
+        chars: &[char],
+        position: &mut usize,
+        in_alternation: bool,
+    ) -> Result<GlobPattern, ParseError> {
+        let mut nodes: Vec<GlobNode> = Vec::new();
+        while let Some(&c) = chars.get(*position) {
+            match c {
+                ',' | '}' if in_alternation => break,
+                '*' => {
+                    *position += 1;
+                    // Consecutive stars are equivalent to one.
+                    if nodes.last() != Some(&GlobNode::Star) {
+                        nodes.push(GlobNode::Star);
+                    }
+                }
+                '?' => {
+                    *position += 1;
+                    nodes.push(GlobNode::AnyChar);
+                }
+                '[' => {
+                    *position += 1;
+                    nodes.push(GlobPattern::compile_class(chars, position)?);
+                }
+                '{' => {
+                    *position += 1;
+                    let mut branches = Vec::new();
+                    loop {
+                        branches.push(GlobPattern::compile_sequence(chars, position, true)?);
+                        match chars.get(*position) {
+                            Some(',') => *position += 1,
+                            Some('}') => {
+                                *position += 1;
+                                break;
+                            }
+                            _ => {
+                                return Err(ParseError::UnexpectedEof {
+                                    expected: vec!["','".to_string(), "'}'".to_string()],
+                                })
+                            }
+                        }
+                    }
+                    nodes.push(GlobNode::Alternation(branches));
+                }
+                _ => {
+                    // Fold plain characters into the preceding literal.
+                    if let Some(GlobNode::Literal(text)) = nodes.last_mut() {
+                        text.push(c);
+                    } else {
+                        nodes.push(GlobNode::Literal(c.to_string()));
+                    }
+                    *position += 1;
+                }
+            }
+        }
+        Ok(GlobPattern { nodes })
+    }
+
+    fn compile_class(chars: &[char], position: &mut usize) -> Result<GlobNode, ParseError> {
+        let negated = chars.get(*position) == Some(&'!');
+        if negated {
+            *position += 1;
+        }
+        let mut ranges = Vec::new();
+        loop {
+            match chars.get(*position) {
+                None => {
+                    return Err(ParseError::UnexpectedEof {
+                        expected: vec!["']'".to_string()],
+                    })
+                }
+                Some(']') if !ranges.is_empty() => {
+                    *position += 1;
+                    return Ok(GlobNode::Class { negated, ranges });
+                }
+                Some(&lo) => {
+                    *position += 1;
+                    // `a-z` forms a range unless the `-` is the last
+                    // character before `]`.
+                    if chars.get(*position) == Some(&'-') && chars.get(*position + 1) != Some(&']')
+                    {
+                        *position += 1;
+                        let hi = *chars.get(*position).ok_or_else(|| ParseError::UnexpectedEof {
+                            expected: vec!["']'".to_string()],
+                        })?;
+                        *position += 1;
+                        if lo > hi {
+                            return Err(ParseError::InvalidToken {
+                                text: format!("{}-{}", lo, hi),
+                                position: *position - 3,
+                            });
+                        }
+                        ranges.push((lo, hi));
+                    } else {
+                        ranges.push((lo, lo));
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn is_match(&self, input: &str) -> bool {
+        let chars: Vec<char> = input.chars().collect();
+        GlobPattern::match_from(&self.nodes, &chars)
+    }
+
+    fn match_from(nodes: &[GlobNode], input: &[char]) -> bool {
+        let Some(node) = nodes.first() else {
+            return input.is_empty();
+        };
+        let rest = &nodes[1..];
+        match node {
+            GlobNode::Literal(text) => {
+                let literal: Vec<char> = text.chars().collect();
+                input.starts_with(&literal) && GlobPattern::match_from(rest, &input[literal.len()..])
+            }
+            GlobNode::AnyChar => {
+                !input.is_empty() && GlobPattern::match_from(rest, &input[1..])
+            }
+            GlobNode::Star => {
+                // Try consuming 0..=n characters, shortest first.
+                (0..=input.len()).any(|taken| GlobPattern::match_from(rest, &input[taken..]))
+            }
+            GlobNode::Class { negated, ranges } => match input.first() {
+                Some(&c) if GlobNode::class_matches(*negated, ranges, c) => {
+                    GlobPattern::match_from(rest, &input[1..])
+                }
+                _ => false,
+            },
+            GlobNode::Alternation(branches) => branches.iter().any(|branch| {
+                // A branch may end in `*`, so try every split point.
+                (0..=input.len()).any(|taken| {
+                    GlobPattern::match_from(&branch.nodes, &input[..taken])
+                        && GlobPattern::match_from(rest, &input[taken..])
+                })
+            }),
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        self.nodes.iter().map(GlobNode::to_string).collect()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// SQL-like record queries
+// ---------------------------------------------------------------------------
+
+/// A typed field value: string, number, boolean or ISO date (`YYYY-MM-DD`).
+/// Dates compare chronologically, which for the ISO form is plain string
+/// ordering.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Str(String),
+    Number(f64),
+    Bool(bool),
+    Date(String),
+}
+
+impl FieldValue {
+    /// Infers a type from a raw string: numbers, `true`/`false` and
+    /// `YYYY-MM-DD` dates are recognized, everything else stays a string.
+    pub fn infer(text: &str) -> FieldValue {
+        if let Ok(value) = text.parse::<f64>() {
+            return FieldValue::Number(value);
+        }
+        if text.eq_ignore_ascii_case("true") {
+            return FieldValue::Bool(true);
+        }
+        if text.eq_ignore_ascii_case("false") {
+            return FieldValue::Bool(false);
+        }
+        if FieldValue::looks_like_date(text) {
+            return FieldValue::Date(text.to_string());
+        }
+        FieldValue::Str(text.to_string())
+    }
+
+    fn looks_like_date(text: &str) -> bool {
+        let bytes = text.as_bytes();
+        bytes.len() == 10
+            && bytes[4] == b'-'
+            && bytes[7] == b'-'
+            && text
+                .char_indices()
+                .all(|(i, c)| i == 4 || i == 7 || c.is_ascii_digit())
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            FieldValue::Str(_) => "string",
+            FieldValue::Number(_) => "number",
+            FieldValue::Bool(_) => "bool",
+            FieldValue::Date(_) => "date",
+        }
+    }
+
+    fn render(&self) -> String {
+        match self {
+            FieldValue::Str(s) => format!("'{}'", s),
+            FieldValue::Number(n) => format!("{}", n),
+            FieldValue::Bool(b) => format!("{}", b),
+            FieldValue::Date(d) => format!("'{}'", d),
+        }
+    }
+
+    /// Orders two values of the same type; `None` for mismatched types.
+    fn compare(&self, other: &FieldValue) -> Option<Ordering> {
+        match (self, other) {
+            (FieldValue::Str(a), FieldValue::Str(b)) => Some(a.cmp(b)),
+            (FieldValue::Number(a), FieldValue::Number(b)) => a.partial_cmp(b),
+            (FieldValue::Bool(a), FieldValue::Bool(b)) => Some(a.cmp(b)),
+            (FieldValue::Date(a), FieldValue::Date(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+fn type_mismatch(field: &str, expected: &FieldValue, found: &FieldValue) -> EvalError {
+    EvalError::TypeMismatch {
+        field: field.to_string(),
+        expected: expected.type_name().to_string(),
+        found: format!("{} {}", found.type_name(), found.render()),
+    }
+}
+
+/// One row of an in-memory table with typed fields.
+#[derive(Debug, Default, Clone)]
+pub struct Record {
+    fields: HashMap<String, FieldValue>,
+}
+
+impl Record {
+    /// Builds a record from raw strings, inferring each field's type.
+    pub fn new(fields: &[(&str, &str)]) -> Self {
+        Record {
+            fields: fields
+                .iter()
+                .map(|(k, v)| (k.to_string(), FieldValue::infer(v)))
+                .collect(),
+        }
+    }
+
+    /// Builds a record with explicitly typed values.
+    pub fn with_values(fields: Vec<(&str, FieldValue)>) -> Self {
+        Record {
+            fields: fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        }
+    }
+
+    pub fn value(&self, field: &str) -> Option<&FieldValue> {
+        self.fields.get(field)
+    }
+
+    /// String view of a field; `None` for missing or non-string fields.
+    pub fn get(&self, field: &str) -> Option<&str> {
+        match self.fields.get(field) {
+            Some(FieldValue::Str(s)) | Some(FieldValue::Date(s)) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+}
+
+pub trait QueryExpression {
+    fn matches(&self, record: &Record) -> Result<bool, EvalError>;
+    fn to_string(&self) -> String;
+}
+
+pub struct FieldEqualsExpression {
+    pub field: String,
+    pub value: FieldValue,
+}
+
+impl QueryExpression for FieldEqualsExpression {
+    fn matches(&self, record: &Record) -> Result<bool, EvalError> {
+        match record.value(&self.field) {
+            None => Ok(false),
+            Some(actual) => match actual.compare(&self.value) {
+                Some(ord) => Ok(ord == Ordering::Equal),
+                None => Err(type_mismatch(&self.field, actual, &self.value)),
+            },
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!("{} = {}", self.field, self.value.render())
+    }
+}
+
+pub struct FieldContainsExpression {
+    pub field: String,
+    pub needle: String,
+}
+
+impl QueryExpression for FieldContainsExpression {
+    fn matches(&self, record: &Record) -> Result<bool, EvalError> {
+        match record.value(&self.field) {
+            None => Ok(false),
+            Some(FieldValue::Str(v)) => Ok(v.contains(&self.needle)),
+            Some(actual) => Err(type_mismatch(
+                &self.field,
+                actual,
+                &FieldValue::Str(self.needle.clone()),
+            )),
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!("{} CONTAINS '{}'", self.field, self.needle)
+    }
+}
+
+pub struct QueryAndExpression {
+    pub left: Box<dyn QueryExpression>,
+    pub right: Box<dyn QueryExpression>,
+}
+
+impl QueryExpression for QueryAndExpression {
+    fn matches(&self, record: &Record) -> Result<bool, EvalError> {
+        Ok(self.left.matches(record)? && self.right.matches(record)?)
+    }
+
+    fn to_string(&self) -> String {
+        format!("({} AND {})", self.left.to_string(), self.right.to_string())
+    }
+}
+
+pub struct QueryOrExpression {
+    pub left: Box<dyn QueryExpression>,
+    pub right: Box<dyn QueryExpression>,
+}
+
+impl QueryExpression for QueryOrExpression {
+    fn matches(&self, record: &Record) -> Result<bool, EvalError> {
+        Ok(self.left.matches(record)? || self.right.matches(record)?)
+    }
+
+    fn to_string(&self) -> String {
+        format!("({} OR {})", self.left.to_string(), self.right.to_string())
+    }
+}
+
+pub struct QueryNotExpression {
+    pub inner: Box<dyn QueryExpression>,
+}
+
+impl QueryExpression for QueryNotExpression {
+    fn matches(&self, record: &Record) -> Result<bool, EvalError> {
+        Ok(!self.inner.matches(record)?)
+    }
+
+    fn to_string(&self) -> String {
+        format!("(NOT {})", self.inner.to_string())
+    }
+}
+
+/// Ordered comparison against a field, e.g. `salary > 70000` or
+/// `hired >= '2020-01-01'`. Both sides must have the same type; missing
+/// fields simply don't match.
+pub struct FieldCompareExpression {
+    pub field: String,
+    pub op: CmpOp,
+    pub value: FieldValue,
+}
+
+impl QueryExpression for FieldCompareExpression {
+    fn matches(&self, record: &Record) -> Result<bool, EvalError> {
+        match record.value(&self.field) {
+            None => Ok(false),
+            Some(actual) => match actual.compare(&self.value) {
+                Some(ord) => Ok(self.op.apply_ord(ord)),
+                None => Err(type_mismatch(&self.field, actual, &self.value)),
+            },
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!("{} {} {}", self.field, self.op.symbol(), self.value.render())
+    }
+}
+
+/// `field BETWEEN lo AND hi`, bounds inclusive.
+pub struct FieldBetweenExpression {
+    pub field: String,
+    pub lo: FieldValue,
+    pub hi: FieldValue,
+}
+
+impl QueryExpression for FieldBetweenExpression {
+    fn matches(&self, record: &Record) -> Result<bool, EvalError> {
+        match record.value(&self.field) {
+            None => Ok(false),
+            Some(actual) => {
+                let lo = actual
+                    .compare(&self.lo)
+                    .ok_or_else(|| type_mismatch(&self.field, actual, &self.lo))?;
+                let hi = actual
+                    .compare(&self.hi)
+                    .ok_or_else(|| type_mismatch(&self.field, actual, &self.hi))?;
+                Ok(lo != Ordering::Less && hi != Ordering::Greater)
+            }
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "{} BETWEEN {} AND {}",
+            self.field,
+            self.lo.render(),
+            self.hi.render()
+        )
+    }
+}
+
+/// `field IN (a, b, c)`.
+pub struct FieldInExpression {
+    pub field: String,
+    pub options: Vec<FieldValue>,
+}
+
+impl QueryExpression for FieldInExpression {
+    fn matches(&self, record: &Record) -> Result<bool, EvalError> {
+        match record.value(&self.field) {
+            None => Ok(false),
+            Some(actual) => {
+                for option in &self.options {
+                    match actual.compare(option) {
+                        Some(Ordering::Equal) => return Ok(true),
+                        Some(_) => {}
+                        None => return Err(type_mismatch(&self.field, actual, option)),
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    fn to_string(&self) -> String {
+        let rendered: Vec<String> = self.options.iter().map(|o| o.render()).collect();
+        format!("{} IN ({})", self.field, rendered.join(", "))
+    }
+}
+
+/// SQL `LIKE` with `%` (any run) and `_` (any single character).
+pub struct FieldLikeExpression {
+    pub field: String,
+    pub pattern: String,
+}
+
+fn like_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('%', rest)) => (0..=text.len()).any(|i| like_match(rest, &text[i..])),
+        Some(('_', rest)) => !text.is_empty() && like_match(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && like_match(rest, &text[1..]),
+    }
+}
+
+impl QueryExpression for FieldLikeExpression {
+    fn matches(&self, record: &Record) -> Result<bool, EvalError> {
+        match record.value(&self.field) {
+            None => Ok(false),
+            Some(FieldValue::Str(v)) => {
+                let pattern: Vec<char> = self.pattern.chars().collect();
+                let text: Vec<char> = v.chars().collect();
+                Ok(like_match(&pattern, &text))
+            }
+            Some(actual) => Err(type_mismatch(
+                &self.field,
+                actual,
+                &FieldValue::Str(self.pattern.clone()),
+            )),
+        }
+    }
+
+    fn to_string(&self) -> String {
+        format!("{} LIKE '{}'", self.field, self.pattern)
+    }
+}
+
+/// Parser for WHERE-clause strings like
+/// `department = 'Engineering' AND (salary > 70000 OR name CONTAINS 'e')`.
+///
+/// String literals are single-quoted; a quote inside a literal is escaped by
+/// doubling it (`'O''Brien'`), as in SQL.
+pub struct QueryParser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl QueryParser {
+    pub fn parse(input: &str) -> Result<Box<dyn QueryExpression>, ParseError> {
+        let mut parser = QueryParser {
+            tokens: QueryParser::lex(input)?,
+            position: 0,
+        };
+        let expr = parser.parse_or()?;
+        if parser.position < parser.tokens.len() {
+            let token = &parser.tokens[parser.position];
+            return Err(ParseError::UnexpectedToken {
+                found: token.text.clone(),
+                expected: Vec::new(),
+                span: (token.start, token.end),
+            });
+        }
+        Ok(expr)
+    }
+
+    fn lex(input: &str) -> Result<Vec<Token>, ParseError> {
+        let mut tokens = Vec::new();
+        let mut chars = input.char_indices().peekable();
+        while let Some(&(start, c)) = chars.peek() {
+            match c {
+                _ if c.is_whitespace() => {
+                    chars.next();
+                }
+                '(' | ')' | '=' | ',' => {
+                    tokens.push(Token {
+                        text: c.to_string(),
+                        start,
+                        end: start + 1,
+                    });
+                    chars.next();
+                }
+                '<' | '>' | '!' => {
+                    chars.next();
+                    if chars.peek().is_some_and(|&(_, d)| d == '=') {
+                        chars.next();
+                        tokens.push(Token {
+                            text: format!("{}=", c),
+                            start,
+                            end: start + 2,
+                        });
+                    } else if c == '!' {
+                        return Err(ParseError::InvalidToken {
+                            text: "!".to_string(),
+                            position: start,
+                        });
+                    } else {
+                        tokens.push(Token {
+                            text: c.to_string(),
+                            start,
+                            end: start + 1,
+                        });
+                    }
+                }
+                '\'' => {
+                    chars.next();
+                    let mut literal = String::from("'");
+                    let end;
+                    loop {
+                        match chars.next() {
+                            Some((i, '\'')) => {
+                                // `''` is an escaped quote inside the literal.
+                                if chars.peek().is_some_and(|&(_, d)| d == '\'') {
+                                    chars.next();
+                                    literal.push('\'');
+                                } else {
+                                    end = i + 1;
+                                    break;
+                                }
+                            }
+                            Some((_, d)) => {
+                                literal.push(d);
+                            }
+                            None => {
+                                return Err(ParseError::UnexpectedEof {
+                                    expected: vec!["closing quote".to_string()],
+                                })
+                            }
+                        }
+                    }
+                    tokens.push(Token {
+                        text: literal,
+                        start,
+                        end,
+                    });
+                }
+                _ if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                    let mut end = start;
+                    while let Some(&(i, d)) = chars.peek() {
+                        if d.is_alphanumeric() || d == '_' || d == '.' || d == '-' {
+                            end = i + d.len_utf8();
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    tokens.push(Token {
+                        text: input[start..end].to_string(),
+                        start,
+                        end,
+                    });
+                }
+                other => {
+                    return Err(ParseError::InvalidToken {
+                        text: other.to_string(),
+                        position: start,
+                    })
+                }
+            }
+        }
+        Ok(tokens)
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        self.tokens
+            .get(self.position)
+            .is_some_and(|t| t.text.eq_ignore_ascii_case(keyword))
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        if token.is_some() {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Box<dyn QueryExpression>, ParseError> {
+        let mut left = self.parse_and()?;
+        while self.peek_keyword("OR") {
+            self.advance();
+            let right = self.parse_and()?;
+            left = Box::new(QueryOrExpression { left, right });
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Box<dyn QueryExpression>, ParseError> {
+        let mut left = self.parse_primary()?;
+        while self.peek_keyword("AND") {
+            self.advance();
+            let right = self.parse_primary()?;
+            left = Box::new(QueryAndExpression { left, right });
+        }
+        Ok(left)
+    }
+
+    fn parse_primary(&mut self) -> Result<Box<dyn QueryExpression>, ParseError> {
+        if self.peek_keyword("NOT") {
+            self.advance();
+            let inner = self.parse_primary()?;
+            return Ok(Box::new(QueryNotExpression { inner }));
+        }
+        if self.peek_keyword("(") {
+            self.advance();
+            let expr = self.parse_or()?;
+            match self.advance() {
+                Some(t) if t.text == ")" => return Ok(expr),
+                Some(t) => {
+                    return Err(ParseError::UnexpectedToken {
+                        found: t.text,
+                        expected: vec!["')'".to_string()],
+                        span: (t.start, t.end),
+                    })
+                }
+                None => {
+                    return Err(ParseError::UnexpectedEof {
+                        expected: vec!["')'".to_string()],
+                    })
+                }
+            }
+        }
+        self.parse_condition()
+    }
+
+    fn parse_condition(&mut self) -> Result<Box<dyn QueryExpression>, ParseError> {
+        let field = self.advance().ok_or_else(|| ParseError::UnexpectedEof {
+            expected: vec!["field name".to_string()],
+        })?;
+        let op = self.advance().ok_or_else(|| ParseError::UnexpectedEof {
+            expected: vec!["operator".to_string()],
+        })?;
+        if op.text.eq_ignore_ascii_case("BETWEEN") {
+            let lo = self.parse_value()?;
+            self.expect_keyword("AND")?;
+            let hi = self.parse_value()?;
+            return Ok(Box::new(FieldBetweenExpression {
+                field: field.text,
+                lo,
+                hi,
+            }));
+        }
+        if op.text.eq_ignore_ascii_case("IN") {
+            self.expect_keyword("(")?;
+            let mut options = vec![self.parse_value()?];
+            while self.peek_keyword(",") {
+                self.advance();
+                options.push(self.parse_value()?);
+            }
+            self.expect_keyword(")")?;
+            return Ok(Box::new(FieldInExpression {
+                field: field.text,
+                options,
+            }));
+        }
+        if op.text.eq_ignore_ascii_case("LIKE") {
+            let pattern = match self.parse_value()? {
+                FieldValue::Str(s) | FieldValue::Date(s) => s,
+                other => {
+                    return Err(ParseError::UnexpectedToken {
+                        found: other.render(),
+                        expected: vec!["quoted pattern".to_string()],
+                        span: (field.start, field.end),
+                    })
+                }
+            };
+            return Ok(Box::new(FieldLikeExpression {
+                field: field.text,
+                pattern,
+            }));
+        }
+        if op.text.eq_ignore_ascii_case("CONTAINS") {
+            let needle = match self.parse_value()? {
+                FieldValue::Str(s) | FieldValue::Date(s) => s,
+                other => other.render(),
+            };
+            return Ok(Box::new(FieldContainsExpression {
+                field: field.text,
+                needle,
+            }));
+        }
+        let value = self.parse_value()?;
+        if op.text == "=" {
+            return Ok(Box::new(FieldEqualsExpression {
+                field: field.text,
+                value,
+            }));
+        }
+        let cmp = match op.text.as_str() {
+            "<" => CmpOp::Lt,
+            "<=" => CmpOp::Le,
+            ">" => CmpOp::Gt,
+            ">=" => CmpOp::Ge,
+            "!=" => CmpOp::Ne,
+            _ => {
+                return Err(ParseError::UnexpectedToken {
+                    found: op.text,
+                    expected: vec!["comparison operator".to_string()],
+                    span: (op.start, op.end),
+                })
+            }
+        };
+        Ok(Box::new(FieldCompareExpression {
+            field: field.text,
+            op: cmp,
+            value,
+        }))
+    }
+
+    /// Reads one literal. Quoted tokens become strings (or dates when they
+    /// look like `YYYY-MM-DD`); bare tokens get their type inferred.
+    fn parse_value(&mut self) -> Result<FieldValue, ParseError> {
+        let token = self.advance().ok_or_else(|| ParseError::UnexpectedEof {
+            expected: vec!["value".to_string()],
+        })?;
+        Ok(match token.text.strip_prefix('\'') {
+            Some(inner) => {
+                if FieldValue::looks_like_date(inner) {
+                    FieldValue::Date(inner.to_string())
+                } else {
+                    FieldValue::Str(inner.to_string())
+                }
+            }
+            None => FieldValue::infer(&token.text),
+        })
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        match self.advance() {
+            Some(t) if t.text.eq_ignore_ascii_case(keyword) => Ok(()),
+            Some(t) => Err(ParseError::UnexpectedToken {
+                found: t.text,
+                expected: vec![format!("'{}'", keyword)],
+                span: (t.start, t.end),
+            }),
+            None => Err(ParseError::UnexpectedEof {
+                expected: vec![format!("'{}'", keyword)],
+            }),
+        }
+    }
+}
+
+/// An in-memory table the query language can run against end-to-end.
+#[derive(Debug, Default, Clone)]
+pub struct RecordSet {
+    records: Vec<Record>,
+}
+
+impl RecordSet {
+    pub fn new(records: Vec<Record>) -> Self {
+        RecordSet { records }
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Keeps the records matching `query`; the first match error aborts.
+    pub fn filter(&self, query: &dyn QueryExpression) -> Result<RecordSet, EvalError> {
+        let mut kept = Vec::new();
+        for record in &self.records {
+            if query.matches(record)? {
+                kept.push(record.clone());
+            }
+        }
+        Ok(RecordSet::new(kept))
+    }
+
+    /// Parses and runs a WHERE clause in one step.
+    pub fn query(&self, clause: &str) -> Result<RecordSet, InterpreterError> {
+        let parsed = QueryParser::parse(clause)?;
+        Ok(self.filter(parsed.as_ref())?)
+    }
+
+    /// Sorts ascending by `field`; records without the field sort last, and
+    /// cross-type comparisons keep their relative order.
+    pub fn order_by(&self, field: &str) -> RecordSet {
+        let mut sorted = self.records.clone();
+        sorted.sort_by(|a, b| match (a.value(field), b.value(field)) {
+            (Some(x), Some(y)) => x.compare(y).unwrap_or(Ordering::Equal),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+        RecordSet::new(sorted)
+    }
+
+    pub fn limit(&self, n: usize) -> RecordSet {
+        RecordSet::new(self.records.iter().take(n).cloned().collect())
+    }
+
+    /// Keeps only the listed fields in each record.
+    pub fn project(&self, fields: &[&str]) -> RecordSet {
+        let kept = self
+            .records
+            .iter()
+            .map(|record| Record {
+                fields: record
+                    .fields
+                    .iter()
+                    .filter(|(name, _)| fields.contains(&name.as_str()))
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect(),
+            })
+            .collect();
+        RecordSet::new(kept)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Exact (rational) evaluation mode
+// ---------------------------------------------------------------------------
+
+fn exact_error(message: impl Into<String>) -> EvalError {
+    EvalError::Function {
+        name: "exact".to_string(),
+        message: message.into(),
+    }
+}
+
+/// An exact rational number, always reduced with a positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    num: i64,
+    den: i64,
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational { num: 0, den: 1 };
+
+    pub fn new(num: i64, den: i64) -> Result<Rational, EvalError> {
+        Rational::reduce(num as i128, den as i128)
+    }
+
+    fn reduce(num: i128, den: i128) -> Result<Rational, EvalError> {
+        if den == 0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        fn gcd(a: i128, b: i128) -> i128 {
+            if b == 0 { a.abs() } else { gcd(b, a % b) }
+        }
+        let sign = if den < 0 { -1 } else { 1 };
+        let g = gcd(num, den).max(1);
+        let num = sign * num / g;
+        let den = sign * den / g;
+        if num > i64::MAX as i128
+            || num < i64::MIN as i128
+            || den > i64::MAX as i128
+        {
+            return Err(exact_error("rational overflow"));
+        }
+        Ok(Rational {
+            num: num as i64,
+            den: den as i64,
+        })
+    }
+
+    /// Converts via the shortest decimal representation, so literals that
+    /// were written in decimal (`0.1`) become exact tenths rather than the
+    /// nearest binary fraction.
+    pub fn from_f64(value: f64) -> Result<Rational, EvalError> {
+        if !value.is_finite() {
+            return Err(exact_error(format!("{} is not finite", value)));
+        }
+        let text = format!("{}", value);
+        let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+            Some((m, e)) => (
+                m.to_string(),
+                e.parse::<i32>().map_err(|_| exact_error("bad exponent"))?,
+            ),
+            None => (text, 0),
+        };
+        let (int_part, frac_part) = match mantissa.split_once('.') {
+            Some((i, f)) => (i.to_string(), f.to_string()),
+            None => (mantissa, String::new()),
+        };
+        let digits: i128 = format!("{}{}", int_part, frac_part)
+            .parse()
+            .map_err(|_| exact_error("mantissa overflow"))?;
+        let scale = frac_part.len() as i32 - exponent;
+        if scale >= 0 {
+            Rational::reduce(digits, 10i128.checked_pow(scale as u32).ok_or_else(|| exact_error("rational overflow"))?)
+        } else {
+            Rational::reduce(
+                digits
+                    .checked_mul(10i128.checked_pow((-scale) as u32).ok_or_else(|| exact_error("rational overflow"))?)
+                    .ok_or_else(|| exact_error("rational overflow"))?,
+                1,
+            )
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    fn add(&self, other: &Rational) -> Result<Rational, EvalError> {
+        Rational::reduce(
+            self.num as i128 * other.den as i128 + other.num as i128 * self.den as i128,
+            self.den as i128 * other.den as i128,
+        )
+    }
+
+    fn sub(&self, other: &Rational) -> Result<Rational, EvalError> {
+        self.add(&Rational {
+            num: -other.num,
+            den: other.den,
+        })
+    }
+
+    fn mul(&self, other: &Rational) -> Result<Rational, EvalError> {
+        Rational::reduce(
+            self.num as i128 * other.num as i128,
+            self.den as i128 * other.den as i128,
+        )
+    }
+
+    fn div(&self, other: &Rational) -> Result<Rational, EvalError> {
+        if other.num == 0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        Rational::reduce(
+            self.num as i128 * other.den as i128,
+            self.den as i128 * other.num as i128,
+        )
+    }
+
+    fn rem(&self, other: &Rational) -> Result<Rational, EvalError> {
+        if other.num == 0 {
+            return Err(EvalError::ModuloByZero);
+        }
+        let quotient = self.div(other)?;
+        let floor = quotient.num.div_euclid(quotient.den);
+        let trunc = if quotient.num < 0 && quotient.num % quotient.den != 0 {
+            floor + 1
+        } else {
+            floor
+        };
+        self.sub(&other.mul(&Rational { num: trunc, den: 1 })?)
+    }
+
+    /// Integer exponents only; negative exponents invert.
+    fn pow(&self, exponent: &Rational) -> Result<Rational, EvalError> {
+        if exponent.den != 1 {
+            return Err(exact_error("fractional exponents are not exact"));
+        }
+        let mut result = Rational { num: 1, den: 1 };
+        let base = if exponent.num >= 0 {
+            *self
+        } else {
+            Rational { num: 1, den: 1 }.div(self)?
+        };
+        for _ in 0..exponent.num.unsigned_abs() {
+            result = result.mul(&base)?;
+        }
+        Ok(result)
+    }
+
+    fn cmp_exact(&self, other: &Rational) -> Ordering {
+        (self.num as i128 * other.den as i128).cmp(&(other.num as i128 * self.den as i128))
+    }
+
+    pub fn to_string(&self) -> String {
+        if self.den == 1 {
+            format!("{}", self.num)
+        } else {
+            format!("{}/{}", self.num, self.den)
+        }
+    }
+}
+
+/// Evaluates the tree with exact rational arithmetic instead of f64.
+struct RationalEvaluator<'a> {
+    context: &'a mut Context,
+}
+
+impl ExprVisitor for RationalEvaluator<'_> {
+    type Output = Result<Rational, EvalError>;
+
+    fn visit_number(&mut self, value: f64) -> Self::Output {
+        Rational::from_f64(value)
+    }
+
+    fn visit_variable(&mut self, name: &str) -> Self::Output {
+        Rational::from_f64(self.context.get(name)?)
+    }
+
+    fn visit_binary(&mut self, op: BinOp, left: &Expr, right: &Expr) -> Self::Output {
+        let l = left.accept(self)?;
+        let r = right.accept(self)?;
+        match op {
+            BinOp::Add => l.add(&r),
+            BinOp::Sub => l.sub(&r),
+            BinOp::Mul => l.mul(&r),
+            BinOp::Div => l.div(&r),
+            BinOp::Mod => l.rem(&r),
+            BinOp::Pow => l.pow(&r),
+        }
+    }
+
+    fn visit_compare(&mut self, op: CmpOp, left: &Expr, right: &Expr) -> Self::Output {
+        let l = left.accept(self)?;
+        let r = right.accept(self)?;
+        let matched = op.apply_ord(l.cmp_exact(&r));
+        Ok(if matched {
+            Rational { num: 1, den: 1 }
+        } else {
+            Rational::ZERO
+        })
+    }
+
+    fn visit_negate(&mut self, inner: &Expr) -> Self::Output {
+        let value = inner.accept(self)?;
+        Ok(Rational {
+            num: -value.num,
+            den: value.den,
+        })
+    }
+
+    fn visit_call(&mut self, name: &str, args: &[Expr]) -> Self::Output {
+        if name == "if" {
+            if args.len() != 3 {
+                return Err(EvalError::WrongArity {
+                    name: "if".to_string(),
+                    expected: Arity::Exact(3),
+                    got: args.len(),
+                });
+            }
+            let cond = args[0].accept(self)?;
+            return if cond.num != 0 {
+                args[1].accept(self)
+            } else {
+                args[2].accept(self)
+            };
+        }
+        // Library functions are defined over f64; their results are
+        // re-rationalized, so only the call itself is approximate.
+        let values: Vec<f64> = args
+            .iter()
+            .map(|a| a.accept(self).map(|r| r.to_f64()))
+            .collect::<Result<_, _>>()?;
+        Rational::from_f64(self.context.call(name, &values)?)
+    }
+
+    fn visit_assign(&mut self, name: &str, value: &Expr) -> Self::Output {
+        let result = value.accept(self)?;
+        self.context.set(name, result.to_f64());
+        Ok(result)
+    }
+
+    fn visit_let(&mut self, name: &str, value: &Expr, body: &Expr) -> Self::Output {
+        let bound = value.accept(self)?;
+        self.context.push_scope();
+        self.context.define(name, bound.to_f64());
+        let result = body.accept(self);
+        self.context.pop_scope();
+        result
+    }
+
+    fn visit_sequence(&mut self, statements: &[Expr]) -> Self::Output {
+        let mut last = None;
+        for statement in statements {
+            last = Some(statement.accept(self)?);
+        }
+        last.ok_or(EvalError::EmptyProgram)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Unit-aware arithmetic
+// ---------------------------------------------------------------------------
+
+/// Physical dimension as exponents over the base units metre, kilogram and
+/// second. `m/s` is `length 1, time -1`; a bare number is all zeros.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dimension {
+    pub length: i8,
+    pub mass: i8,
+    pub time: i8,
+}
+
+impl Dimension {
+    pub const NONE: Dimension = Dimension {
+        length: 0,
+        mass: 0,
+        time: 0,
+    };
+
+    fn mul(&self, other: &Dimension) -> Dimension {
+        Dimension {
+            length: self.length + other.length,
+            mass: self.mass + other.mass,
+            time: self.time + other.time,
+        }
+    }
+
+    fn div(&self, other: &Dimension) -> Dimension {
+        Dimension {
+            length: self.length - other.length,
+            mass: self.mass - other.mass,
+            time: self.time - other.time,
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        if *self == Dimension::NONE {
+            return "1".to_string();
+        }
+        let mut numerator = Vec::new();
+        let mut denominator = Vec::new();
+        for (unit, exp) in [("m", self.length), ("kg", self.mass), ("s", self.time)] {
+            let target = if exp > 0 {
+                &mut numerator
+            } else if exp < 0 {
+                &mut denominator
+            } else {
+                continue;
+            };
+            if exp.abs() == 1 {
+                target.push(unit.to_string());
+            } else {
+                target.push(format!("{}^{}", unit, exp.abs()));
+            }
+        }
+        let top = if numerator.is_empty() {
+            "1".to_string()
+        } else {
+            numerator.join("*")
+        };
+        if denominator.is_empty() {
+            top
+        } else {
+            format!("{}/{}", top, denominator.join("*"))
+        }
+    }
+}
+
+/// A value normalized to base units, tagged with its dimension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub dimension: Dimension,
+}
+
+impl Quantity {
+    fn dimensionless(value: f64) -> Quantity {
+        Quantity {
+            value,
+            dimension: Dimension::NONE,
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        if self.dimension == Dimension::NONE {
+            Expr::Number(self.value).to_string()
+        } else {
+            format!(
+                "{} {}",
+                Expr::Number(self.value).to_string(),
+                self.dimension.to_string()
+            )
+        }
+    }
+}
+
+/// Unit table: suffix, factor into the base unit, dimension.
+fn unit_table(symbol: &str) -> Option<(f64, Dimension)> {
+    let length = Dimension {
+        length: 1,
+        mass: 0,
+        time: 0,
+    };
+    let mass = Dimension {
+        length: 0,
+        mass: 1,
+        time: 0,
+    };
+    let time = Dimension {
+        length: 0,
+        mass: 0,
+        time: 1,
+    };
+    match symbol {
+        "mm" => Some((0.001, length)),
+        "cm" => Some((0.01, length)),
+        "m" => Some((1.0, length)),
+        "km" => Some((1000.0, length)),
+        "g" => Some((0.001, mass)),
+        "kg" => Some((1.0, mass)),
+        "t" => Some((1000.0, mass)),
+        "ms" => Some((0.001, time)),
+        "s" => Some((1.0, time)),
+        "min" => Some((60.0, time)),
+        "h" => Some((3600.0, time)),
+        _ => None,
+    }
+}
+
+/// Evaluates expressions over unit-tagged quantities, e.g. `3 m / 2 s` or
+/// `5 kg + 200 g`. Addition and subtraction require matching dimensions;
+/// multiplication and division combine them.
+pub struct UnitCalculator {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl UnitCalculator {
+    pub fn evaluate(input: &str) -> Result<Quantity, InterpreterError> {
+        let mut parser = UnitCalculator {
+            tokens: tokenize(input)?,
+            position: 0,
+        };
+        let quantity = parser.parse_additive()?;
+        if parser.position < parser.tokens.len() {
+            let token = &parser.tokens[parser.position];
+            return Err(ParseError::UnexpectedToken {
+                found: token.text.clone(),
+                expected: Vec::new(),
+                span: (token.start, token.end),
+            }
+            .into());
+        }
+        Ok(quantity)
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(|t| t.text.as_str())
+    }
+
+    fn parse_additive(&mut self) -> Result<Quantity, InterpreterError> {
+        let mut left = self.parse_multiplicative()?;
+        while let Some(op) = match self.peek() {
+            Some("+") => Some("+"),
+            Some("-") => Some("-"),
+            _ => None,
+        } {
+            self.position += 1;
+            let right = self.parse_multiplicative()?;
+            if left.dimension != right.dimension {
+                return Err(EvalError::DimensionMismatch {
+                    op: if op == "+" { "add" } else { "subtract" }.to_string(),
+                    left: left.dimension.to_string(),
+                    right: right.dimension.to_string(),
+                }
+                .into());
+            }
+            left.value = if op == "+" {
+                left.value + right.value
+            } else {
+                left.value - right.value
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Quantity, InterpreterError> {
+        let mut left = self.parse_primary()?;
+        while let Some(op) = match self.peek() {
+            Some("*") => Some("*"),
+            Some("/") => Some("/"),
+            _ => None,
+        } {
+            self.position += 1;
+            let right = self.parse_primary()?;
+            if op == "*" {
+                left = Quantity {
+                    value: left.value * right.value,
+                    dimension: left.dimension.mul(&right.dimension),
+                };
+            } else {
+                if right.value == 0.0 {
+                    return Err(EvalError::DivisionByZero.into());
+                }
+                left = Quantity {
+                    value: left.value / right.value,
+                    dimension: left.dimension.div(&right.dimension),
+                };
+            }
+        }
+        Ok(left)
+    }
+
+    fn parse_primary(&mut self) -> Result<Quantity, InterpreterError> {
+        if self.peek() == Some("-") {
+            self.position += 1;
+            let mut inner = self.parse_primary()?;
+            inner.value = -inner.value;
+            return Ok(inner);
+        }
+        if self.peek() == Some("(") {
+            self.position += 1;
+            let inner = self.parse_additive()?;
+            match self.peek() {
+                Some(")") => self.position += 1,
+                _ => {
+                    return Err(ParseError::UnexpectedEof {
+                        expected: vec!["')'".to_string()],
+                    }
+                    .into())
+                }
+            }
+            return Ok(self.apply_unit(inner)?);
+        }
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or(ParseError::UnexpectedEof {
+                expected: vec!["number".to_string()],
+            })?;
+        let value = token
+            .text
+            .replace('_', "")
+            .parse::<f64>()
+            .map_err(|_| ParseError::UnexpectedToken {
+                found: token.text.clone(),
+                expected: vec!["number".to_string()],
+                span: (token.start, token.end),
+            })?;
+        self.position += 1;
+        self.apply_unit(Quantity::dimensionless(value))
+    }
+
+    /// Consumes a unit suffix if one follows, scaling into base units.
+    fn apply_unit(&mut self, quantity: Quantity) -> Result<Quantity, InterpreterError> {
+        if let Some(symbol) = self.peek() {
+            if let Some((factor, dimension)) = unit_table(symbol) {
+                self.position += 1;
+                return Ok(Quantity {
+                    value: quantity.value * factor,
+                    dimension: quantity.dimension.mul(&dimension),
+                });
+            }
+        }
+        Ok(quantity)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Template interpolation
+// ---------------------------------------------------------------------------
+
+/// Renders a template like `"Hello {user.name}, you owe {total * 1.1:.2}"`
+/// against a record. Placeholders are either a field path (dotted names look
+/// up record fields directly) or an arithmetic expression over the record's
+/// numeric and boolean fields; an optional `:.N` spec fixes the number of
+/// decimals. `{{` and `}}` escape literal braces.
+pub fn render(template: &str, record: &Record) -> Result<String, InterpreterError> {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(d) => placeholder.push(d),
+                        None => {
+                            return Err(ParseError::UnexpectedEof {
+                                expected: vec!["'}'".to_string()],
+                            }
+                            .into())
+                        }
+                    }
+                }
+                out.push_str(&render_placeholder(&placeholder, record)?);
+            }
+            other => out.push(other),
+        }
+    }
+    Ok(out)
+}
+
+fn render_placeholder(placeholder: &str, record: &Record) -> Result<String, InterpreterError> {
+    let (expr_text, precision) = match placeholder.split_once(':') {
+        Some((expr_text, spec)) => (expr_text, Some(parse_format_spec(spec)?)),
+        None => (placeholder, None),
+    };
+    let expr_text = expr_text.trim();
+
+    // A bare field path renders the field directly, covering non-numeric
+    // fields like strings and dates.
+    if is_field_path(expr_text) {
+        if let Some(value) = record.value(expr_text) {
+            return match (value, precision) {
+                (FieldValue::Number(n), Some(p)) => Ok(format!("{:.*}", p, n)),
+                (FieldValue::Number(n), None) => Ok(Expr::Number(*n).to_string()),
+                (FieldValue::Str(s), None) | (FieldValue::Date(s), None) => Ok(s.clone()),
+                (FieldValue::Bool(b), None) => Ok(format!("{}", b)),
+                (other, Some(_)) => Err(EvalError::Function {
+                    name: "format".to_string(),
+                    message: format!("precision spec applied to {} value", other.type_name()),
+                }
+                .into()),
+            };
+        }
+        // Dotted paths can only be field lookups; report the miss directly
+        // instead of letting the expression lexer choke on the dot.
+        if expr_text.contains('.') {
+            return Err(EvalError::UndefinedVariable(expr_text.to_string()).into());
+        }
+    }
+
+    // Everything else is an expression over the record's numeric fields.
+    let mut context = Context::new();
+    for (name, value) in &record.fields {
+        if !is_identifier(name) {
+            continue;
+        }
+        match value {
+            FieldValue::Number(n) => context.set(name, *n),
+            FieldValue::Bool(b) => context.set(name, if *b { 1.0 } else { 0.0 }),
+            _ => {}
+        }
+    }
+    let result = ExpressionParser::parse(expr_text)?.interpret(&mut context)?;
+    Ok(match precision {
+        Some(p) => format!("{:.*}", p, result),
+        None => Expr::Number(result).to_string(),
+    })
+}
+
+/// Accepts `.N` format specs, e.g. `.2` for two decimal places.
+fn parse_format_spec(spec: &str) -> Result<usize, InterpreterError> {
+    spec.strip_prefix('.')
+        .and_then(|digits| digits.parse::<usize>().ok())
+        .ok_or_else(|| {
+            InterpreterError::Parse(ParseError::UnexpectedToken {
+                found: spec.to_string(),
+                expected: vec!["format spec like '.2'".to_string()],
+                span: (0, spec.len()),
+            })
+        })
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn is_field_path(text: &str) -> bool {
+    !text.is_empty() && text.split('.').all(is_identifier)
+}
+
+// ---------------------------------------------------------------------------
+// Demo
+// ---------------------------------------------------------------------------
+
+fn demo_math() {
+    println!("=== Math interpreter ===");
+    let mut calculator = Calculator::new();
+    calculator.set_variable("x", 10.0);
+
+    let cases = [
+        ("3 + 4 * 2", 11.0),
+        ("(3 + 4) * 2", 14.0),
+        ("-5 + 3", -2.0),
+        ("3 * -2", -6.0),
+        ("2 ^ 3 ^ 2", 512.0), // right-associative
+        ("-2 ^ 2", -4.0),     // unary minus binds looser than ^
+        ("10 % 3", 1.0),
+        ("x % 4 + x / 2", 7.0),
+        ("x > 5", 1.0),
+        ("3 + 1 <= 2 * 2", 1.0),
+        ("x != 10", 0.0),
+        ("if(x >= 10, x * 2, 0)", 20.0),
+        ("if(0, 1 / 0, 42)", 42.0), // untaken branch is not evaluated
+    ];
+    for (input, expected) in cases {
+        let result = calculator.evaluate(input).unwrap();
+        assert_eq!(result, expected, "{}", input);
+        println!("{:<14} = {}", input, result);
+    }
+    println!("parse tree: {}", ExpressionParser::parse("2 ^ 3 ^ 2").unwrap().to_string());
+
+    // Function library with arity checking.
+    assert_eq!(calculator.evaluate("min(3, 1, 2)").unwrap(), 1.0);
+    assert_eq!(calculator.evaluate("clamp(x, 0, 5)").unwrap(), 5.0);
+    assert_eq!(calculator.evaluate("pow(2, 10)").unwrap(), 1024.0);
+    assert_eq!(calculator.evaluate("abs(-3) + sqrt(16)").unwrap(), 7.0);
+    println!("bad arity : {}", calculator.evaluate("sqrt(1, 2)").unwrap_err());
+    println!("bad domain: {}", calculator.evaluate("ln(-1)").unwrap_err());
+}
+
+fn demo_lexer() {
+    println!("\n=== Lexer ===");
+    let mut calculator = Calculator::new();
+    calculator.set_variable("τ", std::f64::consts::TAU);
+    assert_eq!(calculator.evaluate("1.5e-3 * 2_000").unwrap(), 3.0);
+    assert_eq!(calculator.evaluate("2.5E2 + 1e1").unwrap(), 260.0);
+    assert!((calculator.evaluate("cos(τ)").unwrap() - 1.0).abs() < 1e-12);
+    println!("1.5e-3 * 2_000 = 3, cos(τ) = 1");
+
+    for token in tokenize("1_000 + τ * 1e-2").unwrap() {
+        println!("  {:>2}..{:<2} {:?}", token.start, token.end, token.text);
+    }
+    println!("lex error: {}", tokenize("2 + @").unwrap_err());
+}
+
+fn demo_diagnostics() {
+    println!("\n=== Diagnostics ===");
+    for input in ["2 + * 3", "2 + (3 * 4", "1 $ 2"] {
+        let error = ExpressionParser::parse(input).unwrap_err();
+        println!("{}", error.render(input));
+    }
+    let mut calculator = Calculator::new();
+    assert_eq!(
+        calculator.evaluate("nope + 1").unwrap_err(),
+        InterpreterError::Eval(EvalError::UndefinedVariable("nope".to_string()))
+    );
+    assert_eq!(
+        calculator.evaluate("1 / 0").unwrap_err(),
+        InterpreterError::Eval(EvalError::DivisionByZero)
+    );
+    println!("eval errors are structured enums");
+}
+
+fn demo_optimizer() {
+    println!("\n=== Optimizer ===");
+    let cases = [
+        ("2 * 3 + x", "(6 + x)"),
+        ("x * 1 + 0", "x"),
+        ("1 * (x + 0) - 0", "x"),
+        ("x * 0 + y", "y"),
+        ("2 ^ 10 / 4", "256"),
+        ("sin(2 - 2) + x", "(sin(0) + x)"),
+    ];
+    for (input, expected) in cases {
+        let optimized = ExpressionParser::parse(input).unwrap().optimize().unwrap();
+        assert_eq!(optimized.to_string(), expected, "{}", input);
+        println!("{:<16} => {}", input, optimized.to_string());
+    }
+    let error = ExpressionParser::parse("x / (3 - 3)").unwrap().optimize().unwrap_err();
+    println!("x / (3 - 3)      => error: {}", error);
+}
+
+fn demo_visitors() {
+    println!("\n=== Visitors ===");
+    let expr = ExpressionParser::parse_program("y = x * 2 + sin(x); y - z").unwrap();
+    assert_eq!(expr.variables(), ["x", "y", "z"]);
+    assert_eq!(expr.depth(), 5);
+    println!("tree      : {}", expr.to_string());
+    println!("variables : {:?}", expr.variables());
+    println!("depth     : {}", expr.depth());
+}
+
+fn demo_units() {
+    println!("\n=== Units ===");
+    let cases = [
+        ("3 m / 2 s", "1.5 m/s"),
+        ("5 kg + 200 g", "5.2 kg"),
+        ("2 km / 10 min", "3.3333333333333335 m/s"),
+        ("(1 m + 50 cm) * 2", "3 m"),
+        ("10 m / 2 m", "5"),
+        ("3 kg * 4 m / (2 s * 2 s)", "3 m*kg/s^2"),
+    ];
+    for (input, expected) in cases {
+        let quantity = UnitCalculator::evaluate(input).unwrap();
+        assert_eq!(quantity.to_string(), expected, "{}", input);
+        println!("{:<24} = {}", input, quantity.to_string());
+    }
+    println!(
+        "dimension error: {}",
+        UnitCalculator::evaluate("1 m + 2 s").unwrap_err()
+    );
+}
+
+fn demo_templates() {
+    println!("\n=== Templates ===");
+    let order = Record::new(&[
+        ("user.name", "Alice"),
+        ("total", "120"),
+        ("items", "3"),
+        ("due", "2026-09-15"),
+    ]);
+    let rendered = render(
+        "Hello {user.name}, you owe {total * 1.1:.2} for {items} items by {due}. {{braces}}",
+        &order,
+    )
+    .unwrap();
+    assert_eq!(
+        rendered,
+        "Hello Alice, you owe 132.00 for 3 items by 2026-09-15. {braces}"
+    );
+    println!("{}", rendered);
+
+    println!(
+        "missing field: {}",
+        render("{user.email}", &order).unwrap_err()
+    );
+    println!(
+        "bad spec     : {}",
+        render("{total:x}", &order).unwrap_err()
+    );
+}
+
+fn demo_serialization() {
+    println!("\n=== AST serialization ===");
+    let source = "let r = sin(x) in r ^ 2 + -r * 0.5";
+    let parsed = ExpressionParser::parse(source).unwrap();
+    let json = parsed.to_json();
+    let reloaded = Expr::from_json(&json).unwrap();
+    assert_eq!(parsed, reloaded);
+
+    let mut context = Context::new();
+    context.set("x", 1.2);
+    assert_eq!(
+        parsed.interpret(&mut context).unwrap(),
+        reloaded.interpret(&mut context).unwrap()
+    );
+    println!("json: {}", json);
+    println!("round trip parse -> serialize -> deserialize -> evaluate holds");
+    println!("bad json: {}", Expr::from_json("{\"type\":\"loop\"}").unwrap_err());
+}
+
+fn demo_scopes() {
+    println!("\n=== Scopes ===");
+    let mut calculator = Calculator::new();
+    calculator.set_variable("x", 1.0);
+
+    // `let` binds in a fresh scope; the outer x is shadowed, not overwritten.
+    assert_eq!(calculator.evaluate("let x = 10 in x + 1").unwrap(), 11.0);
+    assert_eq!(calculator.evaluate("x").unwrap(), 1.0);
+
+    // Nested lets and lookup through parent scopes.
+    let nested = "let a = 2 in let b = a * 3 in a + b";
+    assert_eq!(calculator.evaluate(nested).unwrap(), 8.0);
+    println!("{} => 8", nested);
+
+    // The binding disappears once the body is done.
+    assert_eq!(
+        calculator.evaluate("(let y = 5 in y) + y").unwrap_err(),
+        InterpreterError::Eval(EvalError::UndefinedVariable("y".to_string()))
+    );
+    println!("let-bound variables do not leak");
+    println!("tree: {}", ExpressionParser::parse("let x = 10 in x + 1").unwrap().to_string());
+}
+
+fn demo_exact() {
+    println!("\n=== Exact mode ===");
+    // The classic float pitfall: binary fractions cannot represent 0.1.
+    let mut float_calc = Calculator::new();
+    assert_eq!(float_calc.evaluate("0.1 + 0.2 == 0.3").unwrap(), 0.0);
+
+    // In exact mode decimal literals become rationals, so it holds.
+    let mut exact_calc = Calculator::exact();
+    assert_eq!(exact_calc.evaluate("0.1 + 0.2 == 0.3").unwrap(), 1.0);
+    println!("0.1 + 0.2 == 0.3 is {} in float mode, {} in exact mode", false, true);
+
+    // Financial arithmetic stays precise through division and scaling.
+    let total = exact_calc.evaluate_exact("(19.99 + 5.01) * 3 / 10").unwrap();
+    assert_eq!(total, Rational::new(15, 2).unwrap());
+    println!("(19.99 + 5.01) * 3 / 10 = {} exactly", total.to_string());
+
+    let third = exact_calc.evaluate_exact("1 / 3 + 1 / 6").unwrap();
+    assert_eq!(third, Rational::new(1, 2).unwrap());
+    println!("1/3 + 1/6 = {}", third.to_string());
+
+    // Variables round-trip through the shared context.
+    exact_calc.set_variable("price", 0.1);
+    assert_eq!(exact_calc.evaluate("price * 3 == 0.3").unwrap(), 1.0);
+
+    // Fractional exponents have no exact answer and say so.
+    let err = exact_calc.evaluate_exact("2 ^ 0.5").unwrap_err();
+    println!("2 ^ 0.5 in exact mode: {}", err);
+}
+
+fn demo_memo() {
+    println!("\n=== Memoized evaluation ===");
+    // One expensive term repeated many times: a worst case for naive
+    // re-evaluation and a best case for the memo cache.
+    let term = "(sin(x) + cos(x) + sqrt(abs(x)) + ln(x + 10))";
+    let source = vec![term; 500].join(" + ");
+
+    let mut plain = Calculator::new();
+    plain.set_variable("x", 0.5);
+    let started = Instant::now();
+    let expected = plain.evaluate(&source).unwrap();
+    let plain_time = started.elapsed();
+
+    let mut memo = Calculator::new();
+    memo.enable_memoization();
+    memo.set_variable("x", 0.5);
+    let started = Instant::now();
+    let result = memo.evaluate(&source).unwrap();
+    let cold_time = started.elapsed();
+
+    assert_eq!(result, expected);
+    let (hits, misses) = memo.memo_stats();
+    assert!(hits >= 499, "every repeated term after the first should hit");
+
+    // A second evaluation is answered from the top-level cache entry.
+    let started = Instant::now();
+    assert_eq!(memo.evaluate(&source).unwrap(), expected);
+    let warm_time = started.elapsed();
+    let (hits_after, misses_after) = memo.memo_stats();
+    assert!(hits_after > hits);
+    assert_eq!(misses_after, misses);
+    println!(
+        "500 shared terms: plain {:?}, memoized cold {:?} / warm {:?} ({} hits, {} misses)",
+        plain_time, cold_time, warm_time, hits, misses
+    );
+
+    // Writing a referenced variable bumps its version and invalidates
+    // every entry that depends on it.
+    memo.set_variable("x", 2.0);
+    let changed = memo.evaluate(&source).unwrap();
+    assert_ne!(changed, expected);
+    let (_, misses_changed) = memo.memo_stats();
+    assert!(misses_changed > misses_after);
+    println!("after x changes the cache recomputes: {:.4} -> {:.4}", expected, changed);
+
+    // Entries keyed on untouched variables survive.
+    memo.set_variable("y", 1.0);
+    memo.evaluate("y * 10").unwrap();
+    memo.set_variable("x", 3.0);
+    let (hits_before_y, _) = memo.memo_stats();
+    memo.evaluate("y * 10").unwrap();
+    let (hits_y, _) = memo.memo_stats();
+    assert_eq!(hits_y, hits_before_y + 1);
+    println!("entries for unrelated variables stay valid");
+}
+
+fn demo_programs() {
+    println!("\n=== Programs ===");
+    let mut calculator = Calculator::new();
+    let result = calculator.run_program("x = 3 + 4; y = x * 2; y - 1").unwrap();
+    assert_eq!(result, 13.0);
+    assert_eq!(calculator.variables().get("x"), Some(&7.0));
+    assert_eq!(calculator.variables().get("y"), Some(&14.0));
+    println!("program result {}, x = {}, y = {}", result, 7, 14);
+
+    // Chained assignment and trailing semicolon.
+    assert_eq!(calculator.run_program("a = b = 2 ^ 3;").unwrap(), 8.0);
+    assert_eq!(calculator.variables().get("b"), Some(&8.0));
+    println!("program: {}", ExpressionParser::parse_program("x = 1; x + 1").unwrap().to_string());
+}
+
+fn demo_functions() {
+    println!("\n=== Custom functions ===");
+    let mut calculator = Calculator::new();
+    calculator.register_fn("tax", Arity::Exact(2), |args| {
+        let (amount, rate) = (args[0], args[1]);
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(format!("rate {} outside 0..=1", rate));
+        }
+        Ok(amount * rate)
+    });
+    calculator.register_fn("avg", Arity::AtLeast(1), |args| {
+        Ok(args.iter().sum::<f64>() / args.len() as f64)
+    });
+
+    assert_eq!(calculator.evaluate("tax(200, 0.25)").unwrap(), 50.0);
+    assert_eq!(calculator.evaluate("avg(1, 2, 3, 4)").unwrap(), 2.5);
+    println!("tax(200, 0.25) = 50, avg(1, 2, 3, 4) = 2.5");
+
+    // Custom functions compose with the rest of the language.
+    calculator.set_variable("price", 80.0);
+    assert_eq!(calculator.evaluate("price + tax(price, 0.1)").unwrap(), 88.0);
+
+    // Arity is validated before the closure runs.
+    let err = calculator.evaluate("tax(200)").unwrap_err();
+    assert_eq!(
+        err,
+        InterpreterError::Eval(EvalError::WrongArity {
+            name: "tax".to_string(),
+            expected: Arity::Exact(2),
+            got: 1,
+        })
+    );
+    println!("tax(200): {}", err);
+
+    // Errors returned by the closure propagate with the function's name.
+    let err = calculator.evaluate("tax(200, 1.5)").unwrap_err();
+    assert_eq!(
+        err,
+        InterpreterError::Eval(EvalError::Function {
+            name: "tax".to_string(),
+            message: "rate 1.5 outside 0..=1".to_string(),
+        })
+    );
+    println!("tax(200, 1.5): {}", err);
+
+    // Re-registering a name replaces the previous definition, so builtins
+    // can be overridden too.
+    calculator.register_fn("tax", Arity::Exact(1), |args| Ok(args[0] * 0.2));
+    assert_eq!(calculator.evaluate("tax(100)").unwrap(), 20.0);
+    println!("re-registration replaces the old definition");
+}
+
+fn demo_boolean() {
+    println!("\n=== Boolean interpreter ===");
+    let mut context = BoolContext::new();
+    context.set("sunny", true);
+    context.set("weekend", false);
+
+    // sunny AND (NOT weekend OR TRUE)
+    let expr = AndExpression {
+        left: Box::new(BoolVariable {
+            name: "sunny".to_string(),
+        }),
+        right: Box::new(OrExpression {
+            left: Box::new(NotExpression {
+                inner: Box::new(BoolVariable {
+                    name: "weekend".to_string(),
+                }),
+            }),
+            right: Box::new(BoolLiteral { value: true }),
+        }),
+    };
+    println!("{} => {}", expr.to_string(), expr.evaluate(&context).unwrap());
+
+    // Parsed tree, precedence: NOT > AND > OR.
+    let parsed = BooleanParser::parse("sunny AND (weekend OR NOT sunny) OR TRUE").unwrap();
+    println!("{} => {}", parsed.to_string(), parsed.evaluate(&context).unwrap());
+
+    // Round trip: pretty-printed output parses back to the same tree.
+    for input in [
+        "a AND (b OR NOT c)",
+        "NOT a OR b AND c",
+        "TRUE OR FALSE",
+        "a XOR b IMPLIES c",
+    ] {
+        let once = BooleanParser::parse(input).unwrap();
+        let again = BooleanParser::parse(&once.to_string()).unwrap();
+        assert_eq!(once.to_string(), again.to_string(), "{}", input);
+    }
+    println!("round-trip parse/print stable");
+
+    // Short-circuiting: `missing` is unbound, but the left operand already
+    // decides the result, so the lookup error never happens.
+    let eval = |input: &str| BooleanParser::parse(input).unwrap().evaluate(&context);
+    assert_eq!(eval("FALSE AND missing").unwrap(), false);
+    assert_eq!(eval("TRUE OR missing").unwrap(), true);
+    assert_eq!(eval("FALSE IMPLIES missing").unwrap(), true);
+    println!("short-circuit suppresses errors in undecided operands");
+
+    // ...but the right operand is still evaluated when it matters.
+    assert_eq!(
+        eval("TRUE AND missing").unwrap_err(),
+        EvalError::UndefinedVariable("missing".to_string())
+    );
+    assert_eq!(
+        eval("FALSE OR missing").unwrap_err(),
+        EvalError::UndefinedVariable("missing".to_string())
+    );
+
+    // XOR always needs both sides.
+    assert_eq!(eval("TRUE XOR TRUE").unwrap(), false);
+    assert_eq!(eval("TRUE XOR FALSE").unwrap(), true);
+    assert!(eval("FALSE XOR missing").is_err());
+
+    // IMPLIES is right-associative and binds loosest.
+    assert_eq!(eval("TRUE IMPLIES FALSE").unwrap(), false);
+    assert_eq!(
+        BooleanParser::parse("a IMPLIES b IMPLIES c").unwrap().to_string(),
+        "(a IMPLIES (b IMPLIES c))"
+    );
+    println!("XOR and IMPLIES round out the operator set");
+}
+
+fn demo_glob() {
+    println!("\n=== Glob interpreter ===");
+    let pattern = GlobPattern::compile("user_*[0-9].log").unwrap();
+    println!("pattern: {}", pattern.to_string());
+    assert!(pattern.is_match("user_alice3.log"));
+    assert!(pattern.is_match("user_7.log"));
+    assert!(!pattern.is_match("user_alice.log"), "needs a trailing digit");
+    assert!(!pattern.is_match("user_alice3.txt"));
+    assert!(!pattern.is_match("admin_alice3.log"));
+
+    // Each node type in isolation.
+    assert!(GlobPattern::compile("exact").unwrap().is_match("exact"));
+    assert!(!GlobPattern::compile("exact").unwrap().is_match("exactly"));
+    assert!(GlobPattern::compile("?at").unwrap().is_match("cat"));
+    assert!(!GlobPattern::compile("?at").unwrap().is_match("at"));
+    assert!(GlobPattern::compile("*").unwrap().is_match(""));
+    assert!(GlobPattern::compile("a*b").unwrap().is_match("ab"));
+    assert!(GlobPattern::compile("a*b").unwrap().is_match("a-anything-b"));
+    assert!(!GlobPattern::compile("a*b").unwrap().is_match("a-anything-c"));
+    assert!(GlobPattern::compile("[a-f0-9]").unwrap().is_match("c"));
+    assert!(!GlobPattern::compile("[a-f0-9]").unwrap().is_match("z"));
+    assert!(GlobPattern::compile("[!aeiou]").unwrap().is_match("x"));
+    assert!(!GlobPattern::compile("[!aeiou]").unwrap().is_match("e"));
+
+    // Alternation, including a starred branch and nesting.
+    let images = GlobPattern::compile("*.{jpg,png,svg}").unwrap();
+    assert!(images.is_match("photo.jpg"));
+    assert!(images.is_match("diagram.svg"));
+    assert!(!images.is_match("notes.txt"));
+    let backups = GlobPattern::compile("{draft*,final}.doc").unwrap();
+    assert!(backups.is_match("draft-v2.doc"));
+    assert!(backups.is_match("final.doc"));
+    assert!(!backups.is_match("finalized.doc"));
+    let nested = GlobPattern::compile("{a,b{1,2}}x").unwrap();
+    assert!(nested.is_match("ax"));
+    assert!(nested.is_match("b2x"));
+    assert!(!nested.is_match("b3x"));
+
+    // Stars backtrack past greedy prefixes.
+    assert!(GlobPattern::compile("*.log").unwrap().is_match("a.log.old.log"));
+    assert!(GlobPattern::compile("**x**").unwrap().is_match("x"));
+
+    // Compiled form prints back to the original pattern.
+    for input in ["user_*[0-9].log", "*.{jpg,png}", "[!a-z]??", "{a,b{1,2}}x"] {
+        assert_eq!(GlobPattern::compile(input).unwrap().to_string(), input);
+    }
+    println!("literal / ? / * / class / alternation all covered");
+
+    // Malformed patterns are parse errors, not panics.
+    assert!(GlobPattern::compile("data[0-9.log").is_err());
+    assert!(GlobPattern::compile("{a,b").is_err());
+    assert!(GlobPattern::compile("[z-a]").is_err());
+    // A `}` or `,` outside an alternation is just a literal character.
+    assert!(GlobPattern::compile("a}b").unwrap().is_match("a}b"));
+    println!(
+        "unclosed class: {}",
+        GlobPattern::compile("data[0-9.log").unwrap_err()
+    );
+}
+
+fn demo_query() {
+    println!("\n=== Query interpreter ===");
+    let records = vec![
+        Record::new(&[("name", "Alice"), ("department", "Engineering")]),
+        Record::new(&[("name", "Bob"), ("department", "Sales")]),
+        Record::new(&[("name", "Eve"), ("department", "Engineering")]),
+    ];
+    let query = QueryAndExpression {
+        left: Box::new(FieldEqualsExpression {
+            field: "department".to_string(),
+            value: FieldValue::Str("Engineering".to_string()),
+        }),
+        right: Box::new(FieldContainsExpression {
+            field: "name".to_string(),
+            needle: "e".to_string(),
+        }),
+    };
+    println!("query: {}", query.to_string());
+    for record in &records {
+        if query.matches(record).unwrap() {
+            println!("match: {:?}", record.get("name").unwrap());
+        }
+    }
+
+    // Same query via the WHERE-clause parser, plus a numeric comparison.
+    let parsed =
+        QueryParser::parse("department = 'Engineering' AND (salary > 70000 OR name CONTAINS 'e')")
+            .unwrap();
+    println!("parsed: {}", parsed.to_string());
+    let salaried = [
+        Record::new(&[("name", "Alice"), ("department", "Engineering"), ("salary", "90000")]),
+        Record::new(&[("name", "Ben"), ("department", "Engineering"), ("salary", "60000")]),
+        Record::new(&[("name", "Carol"), ("department", "Sales"), ("salary", "80000")]),
+    ];
+    let names: Vec<&str> = salaried
+        .iter()
+        .filter(|r| parsed.matches(r).unwrap())
+        .map(|r| r.get("name").unwrap())
+        .collect();
+    assert_eq!(names, ["Alice", "Ben"]);
+    println!("matched: {:?}", names);
+
+    // Typed operators: BETWEEN, IN, LIKE, and date comparison.
+    let people = [
+        Record::new(&[("name", "Alice"), ("age", "34"), ("hired", "2019-05-01"), ("remote", "true")]),
+        Record::new(&[("name", "Ben"), ("age", "45"), ("hired", "2021-11-15"), ("remote", "false")]),
+        Record::new(&[("name", "Carol"), ("age", "29"), ("hired", "2023-02-01"), ("remote", "true")]),
+    ];
+    let run = |query: &str| -> Vec<&str> {
+        let parsed = QueryParser::parse(query).unwrap();
+        people
+            .iter()
+            .filter(|r| parsed.matches(r).unwrap())
+            .map(|r| r.get("name").unwrap())
+            .collect()
+    };
+    assert_eq!(run("age BETWEEN 30 AND 50"), ["Alice", "Ben"]);
+    assert_eq!(run("name IN ('Alice', 'Carol')"), ["Alice", "Carol"]);
+    assert_eq!(run("name LIKE '_e%'"), ["Ben"]);
+    assert_eq!(run("hired >= '2021-01-01'"), ["Ben", "Carol"]);
+    assert_eq!(run("remote = true AND age < 30"), ["Carol"]);
+    println!("typed operators: BETWEEN, IN, LIKE, dates, bools");
+
+    // Type mismatches are reported, not silently false.
+    let mismatch = QueryParser::parse("age LIKE '3%'").unwrap();
+    println!("mismatch: {}", mismatch.matches(&people[0]).unwrap_err());
+
+    // End-to-end query over a RecordSet: filter, order, limit, project.
+    let table = RecordSet::new(people.to_vec());
+    let seniors = table
+        .query("NOT remote = true OR age >= 30")
+        .unwrap()
+        .order_by("age")
+        .limit(2)
+        .project(&["name", "age"]);
+    let listed: Vec<(&str, &FieldValue)> = seniors
+        .records()
+        .iter()
+        .map(|r| (r.get("name").unwrap(), r.value("age").unwrap()))
+        .collect();
+    assert_eq!(seniors.len(), 2);
+    assert!(seniors.records()[0].value("hired").is_none(), "projected out");
+    println!("record set: {:?}", listed);
+
+    // Escaped quote inside a literal.
+    let escaped = QueryParser::parse("name = 'O''Brien'").unwrap();
+    assert!(escaped
+        .matches(&Record::new(&[("name", "O'Brien")]))
+        .unwrap());
+    println!("escaped: {}", escaped.to_string());
+}
+
+fn main() {
+    demo_math();
+    demo_lexer();
+    demo_diagnostics();
+    demo_optimizer();
+    demo_visitors();
+    demo_units();
+    demo_templates();
+    demo_serialization();
+    demo_scopes();
+    demo_exact();
+    demo_memo();
+    demo_programs();
+    demo_functions();
+    demo_boolean();
+    demo_glob();
+    demo_query();
+}
@@ -0,0 +1,3151 @@
+#![allow(dead_code)]
+
+/// Observer Pattern
+///
+/// Defines a one-to-many dependency between objects so that when one object
+/// changes state, all its dependents are notified and updated automatically.
+/// Two subjects live here: a weather station pushing measurements to display
+/// observers, and an event manager fanning out system events to interested
+/// subscribers.
+
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::rc::{Rc, Weak};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// ---------------------------------------------------------------------------
+// Bounded history
+// ---------------------------------------------------------------------------
+
+/// Rough in-memory footprint, used for the optional byte cap on histories.
+pub trait ByteSized {
+    fn approx_bytes(&self) -> usize;
+}
+
+/// Bounded FIFO history: at most `capacity` items and, if configured, at
+/// most `byte_cap` approximate bytes. The oldest entries are evicted first
+/// and evictions are counted rather than silent.
+pub struct RingBuffer<T: ByteSized> {
+    items: VecDeque<T>,
+    capacity: usize,
+    byte_cap: Option<usize>,
+    bytes: usize,
+    evicted: u64,
+}
+
+impl<T: ByteSized> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "ring buffer needs room for at least one item");
+        RingBuffer {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+            byte_cap: None,
+            bytes: 0,
+            evicted: 0,
+        }
+    }
+
+    pub fn with_byte_cap(capacity: usize, byte_cap: usize) -> Self {
+        let mut buffer = RingBuffer::new(capacity);
+        buffer.byte_cap = Some(byte_cap);
+        buffer
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.bytes += item.approx_bytes();
+        self.items.push_back(item);
+        while self.items.len() > self.capacity || self.over_byte_cap() {
+            if self.items.len() == 1 {
+                // A single oversized item is retained rather than leaving
+                // the history empty.
+                break;
+            }
+            let evicted = self.items.pop_front().expect("loop guard");
+            self.bytes -= evicted.approx_bytes();
+            self.evicted += 1;
+        }
+    }
+
+    fn over_byte_cap(&self) -> bool {
+        self.byte_cap.is_some_and(|cap| self.bytes > cap)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn evicted(&self) -> u64 {
+        self.evicted
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter()
+    }
+
+    /// One page of the retained window, newest first. `offset` is in items
+    /// counted back from the newest entry.
+    pub fn page(&self, offset: usize, count: usize) -> Vec<&T> {
+        self.items.iter().rev().skip(offset).take(count).collect()
+    }
+}
+
+impl ByteSized for WeatherData {
+    fn approx_bytes(&self) -> usize {
+        std::mem::size_of::<WeatherData>()
+    }
+}
+
+impl ByteSized for SystemEvent {
+    fn approx_bytes(&self) -> usize {
+        let payload = match self {
+            SystemEvent::UserLoggedIn { user } | SystemEvent::LoginFailed { user } => user.len(),
+            SystemEvent::FileUploaded { path, .. } => path.len(),
+            SystemEvent::HttpRequest { path, .. } => path.len(),
+            SystemEvent::Error { message } => message.len(),
+        };
+        std::mem::size_of::<SystemEvent>() + payload
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Weather station (push model)
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeatherData {
+    /// Degrees Celsius.
+    pub temperature: f64,
+    /// Relative humidity, percent.
+    pub humidity: f64,
+    /// Hectopascal.
+    pub pressure: f64,
+}
+
+impl WeatherData {
+    /// Dew point via the Magnus formula, in the preferred temperature unit.
+    pub fn dew_point(&self) -> f64 {
+        const A: f64 = 17.62;
+        const B: f64 = 243.12;
+        let gamma = (self.humidity / 100.0).ln() + A * self.temperature / (B + self.temperature);
+        B * gamma / (A - gamma)
+    }
+
+    /// Rothfusz heat index. Only meaningful for warm, humid conditions;
+    /// below ~27°C it just returns the air temperature.
+    pub fn heat_index(&self) -> f64 {
+        if self.temperature < 27.0 {
+            return self.temperature;
+        }
+        let t = TemperatureUnit::Fahrenheit.from_celsius(self.temperature);
+        let rh = self.humidity;
+        let hi = -42.379 + 2.049_015_23 * t + 10.143_331_27 * rh
+            - 0.224_755_41 * t * rh
+            - 6.837_83e-3 * t * t
+            - 5.481_717e-2 * rh * rh
+            + 1.228_74e-3 * t * t * rh
+            + 8.528_2e-4 * t * rh * rh
+            - 1.99e-6 * t * t * rh * rh;
+        (hi - 32.0) * 5.0 / 9.0
+    }
+
+    /// Canadian wind chill index; meaningful at or below 10°C with wind
+    /// above 4.8 km/h, otherwise the air temperature is returned.
+    pub fn wind_chill(&self, wind_kmh: f64) -> f64 {
+        if self.temperature > 10.0 || wind_kmh <= 4.8 {
+            return self.temperature;
+        }
+        let v = wind_kmh.powf(0.16);
+        13.12 + 0.6215 * self.temperature - 11.37 * v + 0.3965 * self.temperature * v
+    }
+
+    pub fn temperature_in(&self, unit: TemperatureUnit) -> f64 {
+        unit.from_celsius(self.temperature)
+    }
+
+    pub fn pressure_in(&self, unit: PressureUnit) -> f64 {
+        unit.from_hectopascal(self.pressure)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    pub fn from_celsius(&self, celsius: f64) -> f64 {
+        match self {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PressureUnit {
+    #[default]
+    Hectopascal,
+    InchesOfMercury,
+}
+
+impl PressureUnit {
+    pub fn from_hectopascal(&self, hpa: f64) -> f64 {
+        match self {
+            PressureUnit::Hectopascal => hpa,
+            PressureUnit::InchesOfMercury => hpa * 0.029_53,
+        }
+    }
+
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            PressureUnit::Hectopascal => "hPa",
+            PressureUnit::InchesOfMercury => "inHg",
+        }
+    }
+}
+
+/// Per-observer display units, so the station keeps publishing SI data and
+/// each display converts on its own instead of duplicating math upstream.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnitPreferences {
+    pub temperature: TemperatureUnit,
+    pub pressure: PressureUnit,
+}
+
+/// Generic observer half: receives events of one type. Delivery is
+/// fallible so subjects that care (the event manager) can collect errors
+/// and contain bad observers; subjects that do not (the weather station)
+/// discard the result.
+pub trait Observer<Event> {
+    fn notify(&mut self, event: &Event) -> Result<(), String>;
+
+    /// Batched delivery; by default each event is applied in turn, so
+    /// existing observers work unchanged under batching.
+    fn notify_batch(&mut self, batch: &[Event]) -> Result<(), String> {
+        for event in batch {
+            self.notify(event)?;
+        }
+        Ok(())
+    }
+
+    /// Observers that only care about some events override this and the
+    /// subject skips them for the rest.
+    fn interested_in(&self, _event: &Event) -> bool {
+        true
+    }
+
+    fn name(&self) -> &str;
+}
+
+/// Pull-model observer: it is only told *that* something changed and then
+/// queries the station for exactly the fields it cares about.
+pub trait PullObserver {
+    fn on_change(&mut self, station: &WeatherStation);
+    fn name(&self) -> &str;
+}
+
+/// Generic subject half, parameterised over the event type rather than
+/// hard-wired to weather observers.
+pub trait Subject<Event> {
+    fn register_observer(&mut self, observer: Rc<RefCell<dyn Observer<Event>>>);
+    fn remove_observer(&mut self, name: &str);
+    fn notify_observers(&mut self, event: &Event);
+}
+
+/// Holds observers through `Weak` references: registering does not keep a
+/// display alive, and observers whose last strong reference is gone are
+/// pruned on the next notification instead of leaking.
+pub struct WeatherStation {
+    observers: RefCell<Vec<Weak<RefCell<dyn Observer<WeatherData>>>>>,
+    current: Option<WeatherData>,
+    data_history: RingBuffer<WeatherData>,
+    pruned: Cell<u64>,
+    batch: Option<BatchBuffer>,
+    pull_observers: RefCell<Vec<Weak<RefCell<dyn PullObserver>>>>,
+    /// Field values handed out through the pull getters, for comparing
+    /// bandwidth against full-state push.
+    fields_pulled: Cell<u64>,
+}
+
+/// Readings retained by default; old ones age out of the ring buffer.
+const DEFAULT_HISTORY_CAPACITY: usize = 256;
+
+/// Batching behaviour for high-frequency sensors. Readings accumulate per
+/// `queue_measurement` call and observers get one `update_batch` per flush
+/// instead of one `update` per reading. In a deployment the flush would be
+/// timer-driven; here it triggers after `flush_after` queued readings or an
+/// explicit `flush_batch`.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchConfig {
+    pub flush_after: usize,
+    /// Keep only the latest reading per sensor key within a batch.
+    pub coalesce: bool,
+}
+
+struct BatchBuffer {
+    config: BatchConfig,
+    pending: Vec<(String, WeatherData)>,
+    coalesced_dropped: u64,
+}
+
+impl WeatherStation {
+    pub fn new() -> Self {
+        WeatherStation::with_history_capacity(DEFAULT_HISTORY_CAPACITY)
+    }
+
+    pub fn with_history_capacity(capacity: usize) -> Self {
+        WeatherStation {
+            observers: RefCell::new(Vec::new()),
+            current: None,
+            data_history: RingBuffer::new(capacity),
+            pruned: Cell::new(0),
+            batch: None,
+            pull_observers: RefCell::new(Vec::new()),
+            fields_pulled: Cell::new(0),
+        }
+    }
+
+    pub fn register_pull_observer(&mut self, observer: Rc<RefCell<dyn PullObserver>>) {
+        self.pull_observers.borrow_mut().push(Rc::downgrade(&observer));
+    }
+
+    /// Single-field getters for pull observers; each read is counted.
+    pub fn temperature(&self) -> Option<f64> {
+        self.fields_pulled.set(self.fields_pulled.get() + 1);
+        self.current.map(|d| d.temperature)
+    }
+
+    pub fn humidity(&self) -> Option<f64> {
+        self.fields_pulled.set(self.fields_pulled.get() + 1);
+        self.current.map(|d| d.humidity)
+    }
+
+    pub fn pressure(&self) -> Option<f64> {
+        self.fields_pulled.set(self.fields_pulled.get() + 1);
+        self.current.map(|d| d.pressure)
+    }
+
+    pub fn fields_pulled(&self) -> u64 {
+        self.fields_pulled.get()
+    }
+
+    pub fn enable_batching(&mut self, config: BatchConfig) {
+        assert!(config.flush_after > 0, "flush_after must be positive");
+        self.batch = Some(BatchBuffer {
+            config,
+            pending: Vec::new(),
+            coalesced_dropped: 0,
+        });
+    }
+
+    /// Buffers a keyed reading when batching is on; otherwise it behaves
+    /// like `set_measurements`.
+    pub fn queue_measurement(&mut self, sensor: &str, data: WeatherData) {
+        let Some(buffer) = &mut self.batch else {
+            self.set_measurements(data);
+            return;
+        };
+        if buffer.config.coalesce {
+            if let Some(slot) = buffer.pending.iter_mut().find(|(key, _)| key == sensor) {
+                slot.1 = data;
+                buffer.coalesced_dropped += 1;
+                return;
+            }
+        }
+        buffer.pending.push((sensor.to_string(), data));
+        if buffer.pending.len() >= buffer.config.flush_after {
+            self.flush_batch();
+        }
+    }
+
+    /// Delivers everything queued since the last flush as one batch.
+    pub fn flush_batch(&mut self) {
+        let Some(buffer) = &mut self.batch else {
+            return;
+        };
+        if buffer.pending.is_empty() {
+            return;
+        }
+        let batch: Vec<WeatherData> = buffer.pending.drain(..).map(|(_, data)| data).collect();
+        for data in &batch {
+            self.data_history.push(*data);
+        }
+        self.current = Some(*batch.last().expect("non-empty batch"));
+        self.for_each_observer(|observer| {
+            let _ = observer.notify_batch(&batch);
+        });
+    }
+
+    /// Readings replaced by a newer one for the same sensor before the
+    /// batch was flushed.
+    pub fn coalesced_dropped(&self) -> u64 {
+        self.batch.as_ref().map_or(0, |b| b.coalesced_dropped)
+    }
+
+    /// Runs `action` on every live observer, pruning the dead ones.
+    fn for_each_observer(&self, mut action: impl FnMut(&mut dyn Observer<WeatherData>)) {
+        let observers = self.observers.borrow().clone();
+        let mut alive = Vec::with_capacity(observers.len());
+        for weak in observers {
+            match weak.upgrade() {
+                Some(observer) => {
+                    action(&mut *observer.borrow_mut());
+                    alive.push(weak);
+                }
+                None => self.pruned.set(self.pruned.get() + 1),
+            }
+        }
+        *self.observers.borrow_mut() = alive;
+    }
+
+    pub fn observer_count(&self) -> usize {
+        self.observers.borrow().len()
+    }
+
+    /// Observers dropped elsewhere and removed during `notify_observers`.
+    pub fn pruned_count(&self) -> u64 {
+        self.pruned.get()
+    }
+
+    pub fn set_measurements(&mut self, data: WeatherData) {
+        self.current = Some(data);
+        self.data_history.push(data);
+        self.notify_observers(&data);
+    }
+
+    pub fn current(&self) -> Option<WeatherData> {
+        self.current
+    }
+
+    pub fn history(&self) -> &RingBuffer<WeatherData> {
+        &self.data_history
+    }
+}
+
+/// How fast `ingest_csv` replays a historical feed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// Honor the recorded gaps between readings.
+    Realtime,
+    /// Recorded gaps divided by this factor, e.g. `Accelerated(60.0)`
+    /// replays an hour of data in a minute.
+    Accelerated(f64),
+    /// No delays at all; useful for tests and backfills.
+    Instant,
+}
+
+impl WeatherStation {
+    /// Replays a CSV feed of timestamped readings through
+    /// `set_measurements`, so observers see realistic historical data
+    /// instead of a hardcoded array. Expected columns:
+    /// `timestamp_s,temperature,humidity,pressure`; a header line is
+    /// skipped. Returns how many readings were ingested.
+    pub fn ingest_csv<R: BufRead>(
+        &mut self,
+        reader: R,
+        speed: ReplaySpeed,
+    ) -> Result<usize, String> {
+        let mut ingested = 0;
+        let mut last_timestamp: Option<f64> = None;
+        for (number, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| e.to_string())?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if number == 0 && line.starts_with("timestamp") {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [timestamp, temperature, humidity, pressure] = fields[..] else {
+                return Err(format!("line {}: expected 4 columns, got {}", number + 1, fields.len()));
+            };
+            let parse = |name: &str, field: &str| {
+                field
+                    .parse::<f64>()
+                    .map_err(|_| format!("line {}: bad {} {:?}", number + 1, name, field))
+            };
+            let timestamp = parse("timestamp", timestamp)?;
+            let data = WeatherData {
+                temperature: parse("temperature", temperature)?,
+                humidity: parse("humidity", humidity)?,
+                pressure: parse("pressure", pressure)?,
+            };
+            if let Some(previous) = last_timestamp {
+                let gap = (timestamp - previous).max(0.0);
+                let delay = match speed {
+                    ReplaySpeed::Realtime => gap,
+                    ReplaySpeed::Accelerated(factor) => gap / factor.max(f64::MIN_POSITIVE),
+                    ReplaySpeed::Instant => 0.0,
+                };
+                if delay > 0.0 {
+                    thread::sleep(Duration::from_secs_f64(delay));
+                }
+            }
+            last_timestamp = Some(timestamp);
+            self.set_measurements(data);
+            ingested += 1;
+        }
+        Ok(ingested)
+    }
+}
+
+impl Default for WeatherStation {
+    fn default() -> Self {
+        WeatherStation::new()
+    }
+}
+
+impl Subject<WeatherData> for WeatherStation {
+    fn register_observer(&mut self, observer: Rc<RefCell<dyn Observer<WeatherData>>>) {
+        self.observers.borrow_mut().push(Rc::downgrade(&observer));
+    }
+
+    fn remove_observer(&mut self, name: &str) {
+        self.observers
+            .borrow_mut()
+            .retain(|o| o.upgrade().is_some_and(|o| o.borrow().name() != name));
+    }
+
+    fn notify_observers(&mut self, event: &WeatherData) {
+        self.for_each_observer(|observer| {
+            if observer.interested_in(event) {
+                let _ = observer.notify(event);
+            }
+        });
+        // Pull observers get a bare change signal and come back for the
+        // fields themselves.
+        let pull = self.pull_observers.borrow().clone();
+        let mut alive = Vec::with_capacity(pull.len());
+        for weak in pull {
+            match weak.upgrade() {
+                Some(observer) => {
+                    observer.borrow_mut().on_change(self);
+                    alive.push(weak);
+                }
+                None => self.pruned.set(self.pruned.get() + 1),
+            }
+        }
+        *self.pull_observers.borrow_mut() = alive;
+    }
+}
+
+/// Shows the most recent reading, converted to its unit preferences.
+pub struct CurrentConditionsDisplay {
+    name: String,
+    units: UnitPreferences,
+    last: Option<WeatherData>,
+}
+
+impl CurrentConditionsDisplay {
+    pub fn new(name: &str) -> Self {
+        CurrentConditionsDisplay::with_units(name, UnitPreferences::default())
+    }
+
+    pub fn with_units(name: &str, units: UnitPreferences) -> Self {
+        CurrentConditionsDisplay {
+            name: name.to_string(),
+            units,
+            last: None,
+        }
+    }
+
+    pub fn last(&self) -> Option<WeatherData> {
+        self.last
+    }
+
+    /// The current reading formatted in this display's units.
+    pub fn render(&self) -> Option<String> {
+        let data = self.last?;
+        Some(format!(
+            "{:.1}{}, {:.0}% humidity, {:.2}{}",
+            data.temperature_in(self.units.temperature),
+            self.units.temperature.suffix(),
+            data.humidity,
+            data.pressure_in(self.units.pressure),
+            self.units.pressure.suffix(),
+        ))
+    }
+}
+
+impl Observer<WeatherData> for CurrentConditionsDisplay {
+    fn notify(&mut self, data: &WeatherData) -> Result<(), String> {
+        self.last = Some(*data);
+        println!("[{}] {}", self.name, self.render().expect("just set"));
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Tracks min/avg/max temperature over all readings it has seen.
+pub struct StatisticsDisplay {
+    name: String,
+    temperatures: Vec<f64>,
+}
+
+impl StatisticsDisplay {
+    pub fn new(name: &str) -> Self {
+        StatisticsDisplay {
+            name: name.to_string(),
+            temperatures: Vec::new(),
+        }
+    }
+
+    pub fn stats(&self) -> Option<(f64, f64, f64)> {
+        if self.temperatures.is_empty() {
+            return None;
+        }
+        let min = self.temperatures.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = self
+            .temperatures
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let avg = self.temperatures.iter().sum::<f64>() / self.temperatures.len() as f64;
+        Some((min, avg, max))
+    }
+}
+
+impl Observer<WeatherData> for StatisticsDisplay {
+    fn notify(&mut self, data: &WeatherData) -> Result<(), String> {
+        self.temperatures.push(data.temperature);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Naive pressure-trend forecast.
+pub struct ForecastDisplay {
+    name: String,
+    last_pressure: Option<f64>,
+    forecast: String,
+}
+
+impl ForecastDisplay {
+    pub fn new(name: &str) -> Self {
+        ForecastDisplay {
+            name: name.to_string(),
+            last_pressure: None,
+            forecast: "unknown".to_string(),
+        }
+    }
+
+    pub fn forecast(&self) -> &str {
+        &self.forecast
+    }
+}
+
+impl Observer<WeatherData> for ForecastDisplay {
+    fn notify(&mut self, data: &WeatherData) -> Result<(), String> {
+        self.forecast = match self.last_pressure {
+            Some(previous) if data.pressure > previous => "improving".to_string(),
+            Some(previous) if data.pressure < previous => "cooler, rainy".to_string(),
+            Some(_) => "more of the same".to_string(),
+            None => "unknown".to_string(),
+        };
+        self.last_pressure = Some(data.pressure);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Alert transitions emitted by `ThresholdAlertDisplay`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertEvent {
+    Raised { value: f64 },
+    Cleared { value: f64 },
+}
+
+/// Stateful observer with hysteresis: the alert raises above one
+/// threshold and only clears below a lower one, so a reading hovering
+/// around a single cutoff cannot flap the alert on and off.
+pub struct ThresholdAlertDisplay {
+    name: String,
+    raise_above: f64,
+    clear_below: f64,
+    active: bool,
+    events: Vec<AlertEvent>,
+}
+
+impl ThresholdAlertDisplay {
+    pub fn new(name: &str, raise_above: f64, clear_below: f64) -> Self {
+        assert!(
+            clear_below < raise_above,
+            "hysteresis band requires clear_below < raise_above"
+        );
+        ThresholdAlertDisplay {
+            name: name.to_string(),
+            raise_above,
+            clear_below,
+            active: false,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    /// Every raise/clear transition, in order. Readings inside the band
+    /// leave the alert state unchanged and generate nothing.
+    pub fn events(&self) -> &[AlertEvent] {
+        &self.events
+    }
+}
+
+impl Observer<WeatherData> for ThresholdAlertDisplay {
+    fn notify(&mut self, data: &WeatherData) -> Result<(), String> {
+        let value = data.temperature;
+        if !self.active && value > self.raise_above {
+            self.active = true;
+            self.events.push(AlertEvent::Raised { value });
+            println!("[{}] ALERT raised at {:.1}°C", self.name, value);
+        } else if self.active && value < self.clear_below {
+            self.active = false;
+            self.events.push(AlertEvent::Cleared { value });
+            println!("[{}] alert cleared at {:.1}°C", self.name, value);
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Event manager (interest-filtered fan-out)
+// ---------------------------------------------------------------------------
+
+/// Version written by `to_json`; bump when the payload layout changes and
+/// teach `from_json` to migrate the previous one.
+pub const EVENT_SCHEMA_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SystemEvent {
+    UserLoggedIn { user: String },
+    LoginFailed { user: String },
+    FileUploaded { path: String, size_bytes: u64 },
+    HttpRequest { path: String, status: u16 },
+    Error { message: String },
+}
+
+impl SystemEvent {
+    /// Stable string tag used by observers to declare interest.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SystemEvent::UserLoggedIn { .. } => "user_logged_in",
+            SystemEvent::LoginFailed { .. } => "login_failed",
+            SystemEvent::FileUploaded { .. } => "file_uploaded",
+            SystemEvent::HttpRequest { .. } => "http_request",
+            SystemEvent::Error { .. } => "error",
+        }
+    }
+
+    /// One flat JSON object per event, suitable for JSON Lines files.
+    /// Serializes with an explicit schema version tag, so payloads can be
+    /// migrated when the layout changes between releases.
+    pub fn to_json(&self) -> String {
+        let v = EVENT_SCHEMA_VERSION;
+        match self {
+            SystemEvent::UserLoggedIn { user } => format!(
+                "{{\"v\":{},\"kind\":\"user_logged_in\",\"user\":\"{}\"}}",
+                v,
+                json_escape(user)
+            ),
+            SystemEvent::LoginFailed { user } => format!(
+                "{{\"v\":{},\"kind\":\"login_failed\",\"user\":\"{}\"}}",
+                v,
+                json_escape(user)
+            ),
+            SystemEvent::FileUploaded { path, size_bytes } => format!(
+                "{{\"v\":{},\"kind\":\"file_uploaded\",\"path\":\"{}\",\"size_bytes\":{}}}",
+                v,
+                json_escape(path),
+                size_bytes
+            ),
+            SystemEvent::HttpRequest { path, status } => format!(
+                "{{\"v\":{},\"kind\":\"http_request\",\"path\":\"{}\",\"status\":{}}}",
+                v,
+                json_escape(path),
+                status
+            ),
+            SystemEvent::Error { message } => format!(
+                "{{\"v\":{},\"kind\":\"error\",\"message\":\"{}\"}}",
+                v,
+                json_escape(message)
+            ),
+        }
+    }
+
+    /// Accepts the current schema plus every older one: payloads without a
+    /// `v` tag predate versioning and are read as version 1, where the
+    /// upload size field was still called `size`. Payloads from a newer
+    /// release are rejected rather than silently misread.
+    pub fn from_json(line: &str) -> Result<SystemEvent, String> {
+        let fields = parse_flat_json(line)?;
+        let take = |key: &str| -> Result<String, String> {
+            fields
+                .get(key)
+                .cloned()
+                .ok_or_else(|| format!("missing field '{}'", key))
+        };
+        let version = match fields.get("v") {
+            None => 1,
+            Some(raw) => raw.parse::<u32>().map_err(|e| format!("bad version: {}", e))?,
+        };
+        if version > EVENT_SCHEMA_VERSION {
+            return Err(format!(
+                "event schema v{} is newer than supported v{}",
+                version, EVENT_SCHEMA_VERSION
+            ));
+        }
+        match take("kind")?.as_str() {
+            "user_logged_in" => Ok(SystemEvent::UserLoggedIn { user: take("user")? }),
+            "login_failed" => Ok(SystemEvent::LoginFailed { user: take("user")? }),
+            "file_uploaded" => Ok(SystemEvent::FileUploaded {
+                path: take("path")?,
+                // v2 renamed `size` to `size_bytes`; migrate old payloads.
+                size_bytes: if version >= 2 { take("size_bytes") } else { take("size") }?
+                    .parse()
+                    .map_err(|e| format!("bad size: {}", e))?,
+            }),
+            "http_request" => Ok(SystemEvent::HttpRequest {
+                path: take("path")?,
+                status: take("status")?
+                    .parse()
+                    .map_err(|e| format!("bad status: {}", e))?,
+            }),
+            "error" => Ok(SystemEvent::Error {
+                message: take("message")?,
+            }),
+            other => Err(format!("unknown event kind '{}'", other)),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            SystemEvent::UserLoggedIn { user } => format!("user {} logged in", user),
+            SystemEvent::LoginFailed { user } => format!("login failed for {}", user),
+            SystemEvent::FileUploaded { path, size_bytes } => {
+                format!("uploaded {} ({} bytes)", path, size_bytes)
+            }
+            SystemEvent::HttpRequest { path, status } => format!("{} -> {}", path, status),
+            SystemEvent::Error { message } => format!("error: {}", message),
+        }
+    }
+}
+
+/// Middleware runs ahead of the observers, chain-of-responsibility style:
+/// each stage may pass the event through (possibly rewritten) or swallow it
+/// by returning `None`, which stops the chain and skips notification.
+pub trait EventMiddleware {
+    fn process(&mut self, event: SystemEvent) -> Option<SystemEvent>;
+    fn name(&self) -> &str;
+}
+
+/// Tags error messages with a monotonically increasing correlation id so
+/// alerts and log lines about the same failure can be matched up.
+pub struct CorrelationEnricher {
+    next_id: u64,
+}
+
+impl CorrelationEnricher {
+    pub fn new() -> Self {
+        CorrelationEnricher { next_id: 1 }
+    }
+}
+
+impl Default for CorrelationEnricher {
+    fn default() -> Self {
+        CorrelationEnricher::new()
+    }
+}
+
+impl EventMiddleware for CorrelationEnricher {
+    fn process(&mut self, event: SystemEvent) -> Option<SystemEvent> {
+        match event {
+            SystemEvent::Error { message } => {
+                let tagged = format!("[cid {}] {}", self.next_id, message);
+                self.next_id += 1;
+                Some(SystemEvent::Error { message: tagged })
+            }
+            other => Some(other),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "correlation-enricher"
+    }
+}
+
+/// Drops events identical to one of the last `window` events that passed.
+pub struct Deduplicator {
+    window: usize,
+    recent: VecDeque<SystemEvent>,
+}
+
+impl Deduplicator {
+    pub fn new(window: usize) -> Self {
+        Deduplicator {
+            window,
+            recent: VecDeque::new(),
+        }
+    }
+}
+
+impl EventMiddleware for Deduplicator {
+    fn process(&mut self, event: SystemEvent) -> Option<SystemEvent> {
+        if self.recent.contains(&event) {
+            return None;
+        }
+        if self.recent.len() == self.window {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(event.clone());
+        Some(event)
+    }
+
+    fn name(&self) -> &str {
+        "deduplicator"
+    }
+}
+
+/// Drops whole event kinds, e.g. successful requests during an incident.
+pub struct NoiseFilter {
+    drop_kinds: Vec<String>,
+}
+
+impl NoiseFilter {
+    pub fn new(drop_kinds: &[&str]) -> Self {
+        NoiseFilter {
+            drop_kinds: drop_kinds.iter().map(|k| k.to_string()).collect(),
+        }
+    }
+}
+
+impl EventMiddleware for NoiseFilter {
+    fn process(&mut self, event: SystemEvent) -> Option<SystemEvent> {
+        if self.drop_kinds.iter().any(|k| k == event.kind()) {
+            None
+        } else {
+            Some(event)
+        }
+    }
+
+    fn name(&self) -> &str {
+        "noise-filter"
+    }
+}
+
+fn json_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Minimal parser for the flat JSON objects `to_json` emits: string and
+/// unsigned-number values only, no nesting.
+fn parse_flat_json(line: &str) -> Result<HashMap<String, String>, String> {
+    let line = line.trim();
+    let inner = line
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+        .ok_or_else(|| format!("not a JSON object: {}", line))?;
+    let mut fields = HashMap::new();
+    let mut chars = inner.chars().peekable();
+    loop {
+        while matches!(chars.peek(), Some(' ') | Some(',')) {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            return Ok(fields);
+        }
+        let key = parse_json_string(&mut chars)?;
+        while matches!(chars.peek(), Some(' ')) {
+            chars.next();
+        }
+        if chars.next() != Some(':') {
+            return Err(format!("expected ':' after key {}", key));
+        }
+        while matches!(chars.peek(), Some(' ')) {
+            chars.next();
+        }
+        let value = if chars.peek() == Some(&'"') {
+            parse_json_string(&mut chars)?
+        } else {
+            let mut number = String::new();
+            while chars.peek().is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+                number.push(chars.next().expect("peeked"));
+            }
+            if number.is_empty() {
+                return Err(format!("expected value for key {}", key));
+            }
+            number
+        };
+        fields.insert(key, value);
+    }
+}
+
+fn parse_json_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    if chars.next() != Some('"') {
+        return Err("expected '\"'".to_string());
+    }
+    let mut out = String::new();
+    loop {
+        match chars.next() {
+            None => return Err("unterminated string".to_string()),
+            Some('"') => return Ok(out),
+            Some('\\') => match chars.next() {
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                other => return Err(format!("bad escape {:?}", other)),
+            },
+            Some(other) => out.push(other),
+        }
+    }
+}
+
+/// What happens to events that exceed an observer's rate limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Excess events are counted and discarded.
+    Drop,
+    /// Excess events wait in a per-observer queue; see `pump_queued`.
+    Queue,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub max_per_second: u32,
+    pub policy: OverflowPolicy,
+}
+
+/// Token bucket: capacity `max_per_second`, refilled continuously.
+struct Limiter {
+    config: RateLimit,
+    tokens: f64,
+    last_refill: Instant,
+    dropped: u64,
+    queued: VecDeque<SystemEvent>,
+}
+
+impl Limiter {
+    fn new(config: RateLimit) -> Self {
+        Limiter {
+            config,
+            tokens: config.max_per_second as f64,
+            last_refill: Instant::now(),
+            dropped: 0,
+            queued: VecDeque::new(),
+        }
+    }
+
+    fn allow(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.last_refill = now;
+        let cap = self.config.max_per_second as f64;
+        self.tokens = (self.tokens + elapsed * cap).min(cap);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Rate-limit counters for one observer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitStats {
+    pub dropped: u64,
+    pub queued: usize,
+}
+
+/// How long a subscription stays registered.
+enum Lifetime {
+    Persistent,
+    /// Auto-unsubscribes after the first event the observer is
+    /// interested in.
+    Once,
+    /// Expires at the deadline; checked on each publish.
+    Until(Instant),
+}
+
+struct Subscription {
+    observer: Rc<RefCell<dyn Observer<SystemEvent>>>,
+    priority: i32,
+    /// Subscription order, the tie-breaker among equal priorities.
+    sequence: u64,
+    /// Consecutive failures; reset by the next successful delivery.
+    failures: u32,
+    limiter: Option<Limiter>,
+    lifetime: Lifetime,
+    /// Set once a one-shot has fired or a deadline has passed; the
+    /// subscription is removed at the end of the publish.
+    spent: bool,
+    /// Handling-time samples for every invocation, ordered by arrival.
+    latencies: Vec<Duration>,
+}
+
+/// Per-observer handling statistics from `get_metrics`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObserverMetrics {
+    pub name: String,
+    pub invocations: u64,
+    pub total_time: Duration,
+    pub p95_latency: Duration,
+}
+
+/// Per-publish outcome: how many observers succeeded, which ones failed
+/// (with their error or a panic marker), and which were auto-unsubscribed
+/// for failing too often.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NotifyReport {
+    pub delivered: usize,
+    pub errors: Vec<(String, String)>,
+    pub dropped: Vec<String>,
+}
+
+/// Who was notified by the last `publish_event`, in order. Ordering is
+/// deterministic: priority descending, then subscription order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NotificationOrder {
+    entries: Vec<String>,
+}
+
+impl NotificationOrder {
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+}
+
+pub struct EventManager {
+    observers: Vec<Subscription>,
+    event_history: RingBuffer<SystemEvent>,
+    next_sequence: u64,
+    last_order: NotificationOrder,
+    /// Auto-unsubscribe after this many consecutive failures, if set.
+    failure_limit: Option<u32>,
+    middleware: Vec<Box<dyn EventMiddleware>>,
+    /// Events swallowed by a middleware stage, per stage name.
+    middleware_dropped: HashMap<String, u64>,
+}
+
+impl EventManager {
+    pub fn new() -> Self {
+        EventManager::with_history(RingBuffer::new(DEFAULT_HISTORY_CAPACITY))
+    }
+
+    /// Caller-configured retention, e.g. `RingBuffer::with_byte_cap(..)`.
+    pub fn with_history(history: RingBuffer<SystemEvent>) -> Self {
+        EventManager {
+            observers: Vec::new(),
+            event_history: history,
+            next_sequence: 0,
+            last_order: NotificationOrder::default(),
+            failure_limit: None,
+            middleware: Vec::new(),
+            middleware_dropped: HashMap::new(),
+        }
+    }
+
+    /// Appends a middleware stage; stages run in the order they were added.
+    pub fn add_middleware(&mut self, middleware: Box<dyn EventMiddleware>) {
+        self.middleware.push(middleware);
+    }
+
+    pub fn middleware_dropped(&self) -> &HashMap<String, u64> {
+        &self.middleware_dropped
+    }
+
+    pub fn set_failure_limit(&mut self, limit: Option<u32>) {
+        self.failure_limit = limit;
+    }
+
+    /// Subscribes at the default priority `0`.
+    pub fn subscribe(&mut self, observer: Rc<RefCell<dyn Observer<SystemEvent>>>) {
+        self.subscribe_with_priority(observer, 0);
+    }
+
+    /// Subscribes for exactly one matching event; the subscription is
+    /// removed after the first event the observer is interested in.
+    pub fn subscribe_once(&mut self, observer: Rc<RefCell<dyn Observer<SystemEvent>>>) {
+        self.subscribe_with_priority(observer, 0);
+        self.newest_subscription().lifetime = Lifetime::Once;
+    }
+
+    /// Subscribes until `ttl` has elapsed; expired subscriptions are
+    /// dropped on the next publish without being notified.
+    pub fn subscribe_for(
+        &mut self,
+        observer: Rc<RefCell<dyn Observer<SystemEvent>>>,
+        ttl: Duration,
+    ) {
+        self.subscribe_with_priority(observer, 0);
+        self.newest_subscription().lifetime = Lifetime::Until(Instant::now() + ttl);
+    }
+
+    fn newest_subscription(&mut self) -> &mut Subscription {
+        self.observers
+            .iter_mut()
+            .max_by_key(|s| s.sequence)
+            .expect("just subscribed")
+    }
+
+    /// Caps how many events per second the observer receives, so a chatty
+    /// publisher cannot overwhelm an expensive observer.
+    pub fn subscribe_with_rate_limit(
+        &mut self,
+        observer: Rc<RefCell<dyn Observer<SystemEvent>>>,
+        limit: RateLimit,
+    ) {
+        self.subscribe_with_priority(observer, 0);
+        self.newest_subscription().limiter = Some(Limiter::new(limit));
+    }
+
+    /// Higher priorities are notified first; among equal priorities the
+    /// earlier subscriber wins, so the order never depends on hash state
+    /// or iteration luck.
+    pub fn subscribe_with_priority(
+        &mut self,
+        observer: Rc<RefCell<dyn Observer<SystemEvent>>>,
+        priority: i32,
+    ) {
+        let subscription = Subscription {
+            observer,
+            priority,
+            sequence: self.next_sequence,
+            failures: 0,
+            limiter: None,
+            lifetime: Lifetime::Persistent,
+            spent: false,
+            latencies: Vec::new(),
+        };
+        self.next_sequence += 1;
+        let at = self
+            .observers
+            .partition_point(|s| (-s.priority, s.sequence) <= (-priority, subscription.sequence));
+        self.observers.insert(at, subscription);
+    }
+
+    pub fn unsubscribe(&mut self, name: &str) {
+        self.observers.retain(|s| s.observer.borrow().name() != name);
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.observers.len()
+    }
+
+    pub fn publish_event(&mut self, event: SystemEvent) -> NotifyReport {
+        // Run the middleware chain first; a swallowed event reaches
+        // neither the observers nor the history.
+        let mut event = event;
+        for stage in &mut self.middleware {
+            match stage.process(event) {
+                Some(passed) => event = passed,
+                None => {
+                    *self
+                        .middleware_dropped
+                        .entry(stage.name().to_string())
+                        .or_insert(0) += 1;
+                    return NotifyReport::default();
+                }
+            }
+        }
+        let now = Instant::now();
+        let mut order = NotificationOrder::default();
+        let mut report = NotifyReport::default();
+        for subscription in &mut self.observers {
+            if let Lifetime::Until(deadline) = subscription.lifetime {
+                if now >= deadline {
+                    subscription.spent = true;
+                    continue;
+                }
+            }
+            let mut observer = subscription.observer.borrow_mut();
+            if !observer.interested_in(&event) {
+                continue;
+            }
+            if let Some(limiter) = &mut subscription.limiter {
+                if !limiter.allow() {
+                    match limiter.config.policy {
+                        OverflowPolicy::Drop => limiter.dropped += 1,
+                        OverflowPolicy::Queue => limiter.queued.push_back(event.clone()),
+                    }
+                    continue;
+                }
+            }
+            if matches!(subscription.lifetime, Lifetime::Once) {
+                subscription.spent = true;
+            }
+            let name = observer.name().to_string();
+            // A panicking observer is contained the same way as an Err.
+            let started = Instant::now();
+            let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                observer.notify(&event)
+            }));
+            subscription.latencies.push(started.elapsed());
+            match outcome {
+                Ok(Ok(())) => {
+                    subscription.failures = 0;
+                    report.delivered += 1;
+                    order.entries.push(name);
+                }
+                Ok(Err(message)) => {
+                    subscription.failures += 1;
+                    report.errors.push((name, message));
+                }
+                Err(_) => {
+                    subscription.failures += 1;
+                    report.errors.push((name, "panicked".to_string()));
+                }
+            }
+        }
+        self.observers.retain(|subscription| !subscription.spent);
+        if let Some(limit) = self.failure_limit {
+            self.observers.retain(|subscription| {
+                if subscription.failures >= limit {
+                    report
+                        .dropped
+                        .push(subscription.observer.borrow().name().to_string());
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        self.last_order = order;
+        self.event_history.push(event);
+        report
+    }
+
+    /// Notification order of the most recent publish.
+    pub fn last_notification_order(&self) -> &NotificationOrder {
+        &self.last_order
+    }
+
+    /// Delivers queued events to rate-limited observers as their budgets
+    /// allow. Returns how many were delivered.
+    pub fn pump_queued(&mut self) -> usize {
+        let mut delivered = 0;
+        for subscription in &mut self.observers {
+            let Some(limiter) = &mut subscription.limiter else {
+                continue;
+            };
+            while !limiter.queued.is_empty() && limiter.allow() {
+                let event = limiter.queued.pop_front().expect("checked non-empty");
+                // Queued delivery bypasses the failure bookkeeping; the
+                // event was already accepted at publish time.
+                let _ = subscription.observer.borrow_mut().notify(&event);
+                delivered += 1;
+            }
+        }
+        delivered
+    }
+
+    /// Handling metrics per observer, in notification order. The p95 is
+    /// computed over all recorded invocations.
+    pub fn get_metrics(&self) -> Vec<ObserverMetrics> {
+        self.observers
+            .iter()
+            .map(|subscription| {
+                let mut sorted = subscription.latencies.clone();
+                sorted.sort_unstable();
+                let p95 = if sorted.is_empty() {
+                    Duration::ZERO
+                } else {
+                    let rank = (sorted.len() as f64 * 0.95).ceil() as usize;
+                    sorted[rank.saturating_sub(1)]
+                };
+                ObserverMetrics {
+                    name: subscription.observer.borrow().name().to_string(),
+                    invocations: subscription.latencies.len() as u64,
+                    total_time: subscription.latencies.iter().sum(),
+                    p95_latency: p95,
+                }
+            })
+            .collect()
+    }
+
+    pub fn rate_limit_stats(&self, name: &str) -> Option<RateLimitStats> {
+        self.observers
+            .iter()
+            .find(|s| s.observer.borrow().name() == name)
+            .and_then(|s| s.limiter.as_ref())
+            .map(|l| RateLimitStats {
+                dropped: l.dropped,
+                queued: l.queued.len(),
+            })
+    }
+
+    /// Newest `count` retained events; see `get_recent_events_page` to
+    /// walk further back through the window.
+    pub fn get_recent_events(&self, count: usize) -> Vec<&SystemEvent> {
+        self.event_history.page(0, count)
+    }
+
+    pub fn get_recent_events_page(&self, offset: usize, count: usize) -> Vec<&SystemEvent> {
+        self.event_history.page(offset, count)
+    }
+
+    pub fn history(&self) -> &RingBuffer<SystemEvent> {
+        &self.event_history
+    }
+}
+
+impl Default for EventManager {
+    fn default() -> Self {
+        EventManager::new()
+    }
+}
+
+/// Destination for formatted log lines. Sinks buffer internally; callers
+/// that need durability call `flush`.
+pub trait LogSink {
+    fn write_line(&mut self, line: &str) -> Result<(), String>;
+    fn flush(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+pub struct StdoutSink;
+
+impl LogSink for StdoutSink {
+    fn write_line(&mut self, line: &str) -> Result<(), String> {
+        println!("{}", line);
+        Ok(())
+    }
+}
+
+/// Buffered append-only file sink.
+pub struct FileSink {
+    writer: BufWriter<File>,
+}
+
+impl FileSink {
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("open {}: {}", path.display(), e))?;
+        Ok(FileSink {
+            writer: BufWriter::new(file),
+        })
+    }
+}
+
+impl LogSink for FileSink {
+    fn write_line(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.writer, "{}", line).map_err(|e| e.to_string())
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        self.writer.flush().map_err(|e| e.to_string())
+    }
+}
+
+/// Size-based rotating file sink: when the active file would exceed
+/// `max_bytes` it is renamed to `<path>.<n>` and a fresh file is started.
+pub struct RotatingFileSink {
+    path: std::path::PathBuf,
+    max_bytes: u64,
+    current_bytes: u64,
+    rotations: u32,
+    writer: BufWriter<File>,
+}
+
+impl RotatingFileSink {
+    pub fn create(path: &Path, max_bytes: u64) -> Result<Self, String> {
+        Ok(RotatingFileSink {
+            path: path.to_path_buf(),
+            max_bytes,
+            current_bytes: 0,
+            rotations: 0,
+            writer: Self::open(path)?,
+        })
+    }
+
+    fn open(path: &Path) -> Result<BufWriter<File>, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("open {}: {}", path.display(), e))?;
+        Ok(BufWriter::new(file))
+    }
+
+    pub fn rotations(&self) -> u32 {
+        self.rotations
+    }
+
+    fn rotate(&mut self) -> Result<(), String> {
+        self.writer.flush().map_err(|e| e.to_string())?;
+        self.rotations += 1;
+        let rotated = self.path.with_extension(format!("log.{}", self.rotations));
+        std::fs::rename(&self.path, &rotated).map_err(|e| e.to_string())?;
+        self.writer = Self::open(&self.path)?;
+        self.current_bytes = 0;
+        Ok(())
+    }
+}
+
+impl LogSink for RotatingFileSink {
+    fn write_line(&mut self, line: &str) -> Result<(), String> {
+        let bytes = line.len() as u64 + 1;
+        if self.current_bytes > 0 && self.current_bytes + bytes > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.writer, "{}", line).map_err(|e| e.to_string())?;
+        self.current_bytes += bytes;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        self.writer.flush().map_err(|e| e.to_string())
+    }
+}
+
+/// Subscribes to everything; keeps formatted lines in memory and forwards
+/// them to any configured sinks.
+pub struct EventLogger {
+    name: String,
+    entries: Vec<String>,
+    sinks: Vec<Box<dyn LogSink>>,
+}
+
+impl EventLogger {
+    pub fn new(name: &str) -> Self {
+        EventLogger {
+            name: name.to_string(),
+            entries: Vec::new(),
+            sinks: Vec::new(),
+        }
+    }
+
+    pub fn add_sink(&mut self, sink: Box<dyn LogSink>) {
+        self.sinks.push(sink);
+    }
+
+    pub fn flush_sinks(&mut self) -> Result<(), String> {
+        for sink in &mut self.sinks {
+            sink.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+}
+
+impl Observer<SystemEvent> for EventLogger {
+    fn notify(&mut self, event: &SystemEvent) -> Result<(), String> {
+        let line = format!("[{}] {}", event.kind(), event.describe());
+        for sink in &mut self.sinks {
+            sink.write_line(&line)?;
+        }
+        self.entries.push(line);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// What a tripped security rule does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityAction {
+    Alert,
+    /// Alert and remember the matched subject as locked out.
+    Lockout,
+}
+
+/// Which events a rule counts. A match yields the subject the rule groups
+/// by: the user for login failures, the path for HTTP statuses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventPattern {
+    LoginFailure,
+    HttpStatus(u16),
+    Kind(String),
+}
+
+impl EventPattern {
+    fn matches(&self, event: &SystemEvent) -> Option<String> {
+        match (self, event) {
+            (EventPattern::LoginFailure, SystemEvent::LoginFailed { user }) => Some(user.clone()),
+            (EventPattern::HttpStatus(expected), SystemEvent::HttpRequest { path, status })
+                if status == expected =>
+            {
+                Some(path.clone())
+            }
+            (EventPattern::Kind(kind), event) if event.kind() == kind => {
+                Some(kind.clone())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Declarative monitor rule: `threshold` matches within `window` trigger
+/// the action. Thresholds are counted per matched subject.
+#[derive(Debug, Clone)]
+pub struct SecurityRule {
+    pub name: String,
+    pub pattern: EventPattern,
+    pub threshold: u32,
+    pub window: Duration,
+    pub action: SecurityAction,
+}
+
+/// Evaluates a configured rule set over the event stream instead of
+/// hard-coding the checks.
+pub struct SecurityMonitor {
+    name: String,
+    rules: Vec<SecurityRule>,
+    /// Timestamps of recent matches per (rule index, subject).
+    hits: HashMap<(usize, String), VecDeque<Duration>>,
+    started: Instant,
+    /// Test hook: simulated extra elapsed time, so window expiry can be
+    /// exercised without sleeping.
+    skew: Duration,
+    alerts: Vec<String>,
+    lockouts: Vec<String>,
+}
+
+impl SecurityMonitor {
+    /// The default rule set mirrors the classic checks: three failed
+    /// logins per user inside a minute, and any 401 response.
+    pub fn new(name: &str) -> Self {
+        SecurityMonitor::with_rules(
+            name,
+            vec![
+                SecurityRule {
+                    name: "brute-force".to_string(),
+                    pattern: EventPattern::LoginFailure,
+                    threshold: 3,
+                    window: Duration::from_secs(60),
+                    action: SecurityAction::Alert,
+                },
+                SecurityRule {
+                    name: "unauthorized".to_string(),
+                    pattern: EventPattern::HttpStatus(401),
+                    threshold: 1,
+                    window: Duration::from_secs(1),
+                    action: SecurityAction::Alert,
+                },
+            ],
+        )
+    }
+
+    pub fn with_rules(name: &str, rules: Vec<SecurityRule>) -> Self {
+        SecurityMonitor {
+            name: name.to_string(),
+            rules,
+            hits: HashMap::new(),
+            started: Instant::now(),
+            skew: Duration::ZERO,
+            alerts: Vec::new(),
+            lockouts: Vec::new(),
+        }
+    }
+
+    fn now(&self) -> Duration {
+        self.started.elapsed() + self.skew
+    }
+
+    /// Moves the monitor's clock forward, e.g. to expire rule windows.
+    pub fn advance_clock(&mut self, by: Duration) {
+        self.skew += by;
+    }
+
+    pub fn alerts(&self) -> &[String] {
+        &self.alerts
+    }
+
+    pub fn lockouts(&self) -> &[String] {
+        &self.lockouts
+    }
+
+    pub fn is_locked_out(&self, subject: &str) -> bool {
+        self.lockouts.iter().any(|s| s == subject)
+    }
+}
+
+impl Observer<SystemEvent> for SecurityMonitor {
+    fn notify(&mut self, event: &SystemEvent) -> Result<(), String> {
+        let now = self.now();
+        for (index, rule) in self.rules.iter().enumerate() {
+            let Some(subject) = rule.pattern.matches(event) else {
+                continue;
+            };
+            let bucket = self.hits.entry((index, subject.clone())).or_default();
+            bucket.push_back(now);
+            while bucket
+                .front()
+                .is_some_and(|&first| now.saturating_sub(first) > rule.window)
+            {
+                bucket.pop_front();
+            }
+            if bucket.len() as u32 >= rule.threshold {
+                bucket.clear();
+                self.alerts.push(format!("{}: {}", rule.name, subject));
+                if rule.action == SecurityAction::Lockout {
+                    self.lockouts.push(subject);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+// ---------------------------------------------------------------------------
+// JSON Lines persistence
+// ---------------------------------------------------------------------------
+
+/// Observer that appends every event it sees to a JSON Lines file, so the
+/// event store survives restarts and can be analysed offline.
+pub struct JsonlSink {
+    name: String,
+    writer: BufWriter<File>,
+    written: u64,
+}
+
+impl JsonlSink {
+    pub fn create(name: &str, path: &Path) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("open {}: {}", path.display(), e))?;
+        Ok(JsonlSink {
+            name: name.to_string(),
+            writer: BufWriter::new(file),
+            written: 0,
+        })
+    }
+
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.writer.flush().map_err(|e| e.to_string())
+    }
+}
+
+impl Observer<SystemEvent> for JsonlSink {
+    fn notify(&mut self, event: &SystemEvent) -> Result<(), String> {
+        writeln!(self.writer, "{}", event.to_json()).map_err(|e| e.to_string())?;
+        self.written += 1;
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Replays a JSON Lines file through a manager, as if the events were being
+/// published live. Returns how many events were replayed.
+pub fn replay_jsonl(path: &Path, manager: &mut EventManager) -> Result<usize, String> {
+    let file = File::open(path).map_err(|e| format!("open {}: {}", path.display(), e))?;
+    let mut replayed = 0;
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = SystemEvent::from_json(&line)
+            .map_err(|e| format!("{}:{}: {}", path.display(), number + 1, e))?;
+        manager.publish_event(event);
+        replayed += 1;
+    }
+    Ok(replayed)
+}
+
+// ---------------------------------------------------------------------------
+// Typed event bus
+// ---------------------------------------------------------------------------
+
+/// General-purpose bus where the event type itself is the topic: handlers
+/// subscribe per payload type and publishers post plain structs. No shared
+/// event enum, no string interest-matching — the type system routes events,
+/// and adding a new event type touches no existing code.
+pub struct EventBus {
+    handlers: HashMap<TypeId, Vec<Box<dyn Fn(&dyn Any)>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        EventBus {
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Registers `handler` for payloads of type `E`.
+    pub fn subscribe<E, F>(&mut self, handler: F)
+    where
+        E: 'static,
+        F: Fn(&E) + 'static,
+    {
+        self.handlers
+            .entry(TypeId::of::<E>())
+            .or_default()
+            .push(Box::new(move |any| {
+                if let Some(event) = any.downcast_ref::<E>() {
+                    handler(event);
+                }
+            }));
+    }
+
+    /// Delivers to every handler registered for `E`; returns how many ran.
+    pub fn publish<E: 'static>(&self, event: &E) -> usize {
+        match self.handlers.get(&TypeId::of::<E>()) {
+            Some(handlers) => {
+                for handler in handlers {
+                    handler(event);
+                }
+                handlers.len()
+            }
+            None => 0,
+        }
+    }
+
+    pub fn subscriber_count<E: 'static>(&self) -> usize {
+        self.handlers
+            .get(&TypeId::of::<E>())
+            .map_or(0, |handlers| handlers.len())
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        EventBus::new()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Thread-safe variants
+// ---------------------------------------------------------------------------
+
+/// Observer for the shared manager. Handlers take `&self` and must manage
+/// their own interior mutability so notification needs no outer lock around
+/// observer state.
+pub trait SharedEventObserver: Send + Sync {
+    fn on_event(&self, event: &SystemEvent);
+    fn is_interested_in(&self, _kind: &str) -> bool {
+        true
+    }
+    fn name(&self) -> &str;
+}
+
+/// `Arc`/`RwLock`-based counterpart to `EventManager`: subscriptions and
+/// publishing may happen from any thread. The observer list takes a read
+/// lock during notification, so publishing from several threads proceeds
+/// in parallel.
+pub struct SharedEventManager {
+    observers: RwLock<Vec<Arc<dyn SharedEventObserver>>>,
+    event_history: Mutex<Vec<SystemEvent>>,
+}
+
+impl SharedEventManager {
+    pub fn new() -> Self {
+        SharedEventManager {
+            observers: RwLock::new(Vec::new()),
+            event_history: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn subscribe(&self, observer: Arc<dyn SharedEventObserver>) {
+        self.observers.write().unwrap().push(observer);
+    }
+
+    pub fn unsubscribe(&self, name: &str) {
+        self.observers.write().unwrap().retain(|o| o.name() != name);
+    }
+
+    pub fn publish_event(&self, event: SystemEvent) {
+        let kind = event.kind();
+        for observer in self.observers.read().unwrap().iter() {
+            if observer.is_interested_in(kind) {
+                observer.on_event(&event);
+            }
+        }
+        self.event_history.lock().unwrap().push(event);
+    }
+
+    pub fn event_count(&self) -> usize {
+        self.event_history.lock().unwrap().len()
+    }
+}
+
+impl Default for SharedEventManager {
+    fn default() -> Self {
+        SharedEventManager::new()
+    }
+}
+
+/// Thread-safe logger: a `Mutex` guards the entries, nothing else is shared.
+pub struct SharedEventLogger {
+    name: String,
+    entries: Mutex<Vec<String>>,
+}
+
+impl SharedEventLogger {
+    pub fn new(name: &str) -> Self {
+        SharedEventLogger {
+            name: name.to_string(),
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+}
+
+impl SharedEventObserver for SharedEventLogger {
+    fn on_event(&self, event: &SystemEvent) {
+        self.entries
+            .lock()
+            .unwrap()
+            .push(format!("[{}] {}", event.kind(), event.describe()));
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Channel-based producer/consumer broadcasting
+// ---------------------------------------------------------------------------
+
+enum WeatherMessage {
+    Reading(WeatherData),
+    Shutdown,
+}
+
+/// Fans readings out over std mpsc channels, one per observer thread: the
+/// producer never touches observer state and a slow consumer only backs up
+/// its own queue. `shutdown` delivers a poison message so every consumer
+/// exits after draining its backlog.
+pub struct ChannelBroadcaster {
+    senders: Vec<mpsc::Sender<WeatherMessage>>,
+}
+
+impl ChannelBroadcaster {
+    pub fn new() -> Self {
+        ChannelBroadcaster {
+            senders: Vec::new(),
+        }
+    }
+
+    /// Spawns a consumer thread owning `observer`; the join handle yields
+    /// the observer back after shutdown so its final state can be read.
+    pub fn attach<O>(&mut self, mut observer: O) -> thread::JoinHandle<O>
+    where
+        O: Observer<WeatherData> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        self.senders.push(sender);
+        thread::spawn(move || {
+            while let Ok(message) = receiver.recv() {
+                match message {
+                    WeatherMessage::Reading(data) => {
+                        let _ = observer.notify(&data);
+                    }
+                    WeatherMessage::Shutdown => break,
+                }
+            }
+            observer
+        })
+    }
+
+    /// Returns how many consumers the reading reached.
+    pub fn broadcast(&self, data: WeatherData) -> usize {
+        self.senders
+            .iter()
+            .filter(|s| s.send(WeatherMessage::Reading(data)).is_ok())
+            .count()
+    }
+
+    /// Consumes the broadcaster: after the queued readings, every consumer
+    /// thread sees the shutdown message and returns.
+    pub fn shutdown(self) {
+        for sender in &self.senders {
+            let _ = sender.send(WeatherMessage::Shutdown);
+        }
+    }
+}
+
+impl Default for ChannelBroadcaster {
+    fn default() -> Self {
+        ChannelBroadcaster::new()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Async observers (tokio, behind the `async` feature)
+// ---------------------------------------------------------------------------
+
+#[cfg(feature = "async")]
+pub mod async_events {
+    use super::SystemEvent;
+    use std::future::Future;
+    use std::time::Duration;
+    use tokio::sync::broadcast;
+    use tokio::task::JoinHandle;
+
+    /// Async observer. Implementations use `async fn handle_event`; the
+    /// `Send` bound on the returned future lets the manager run each
+    /// observer in its own spawned task.
+    pub trait AsyncEventObserver {
+        fn handle_event(&mut self, event: SystemEvent) -> impl Future<Output = ()> + Send;
+
+        /// Called when the observer fell so far behind that the broadcast
+        /// channel dropped `skipped` events for it.
+        fn handle_lag(&mut self, skipped: u64) -> impl Future<Output = ()> + Send {
+            let _ = skipped;
+            async {}
+        }
+    }
+
+    /// Fan-out over a tokio broadcast channel. `publish` only copies the
+    /// event into the channel and returns immediately; every observer
+    /// drains its own receiver in a dedicated task, so a slow observer
+    /// lags (and eventually drops) its own queue without ever blocking
+    /// the publisher or its peers.
+    pub struct AsyncEventManager {
+        sender: broadcast::Sender<SystemEvent>,
+    }
+
+    impl AsyncEventManager {
+        /// `capacity` is the per-observer backlog before lagging starts.
+        pub fn new(capacity: usize) -> Self {
+            let (sender, _) = broadcast::channel(capacity);
+            AsyncEventManager { sender }
+        }
+
+        /// Returns the number of observers that will see the event.
+        pub fn publish(&self, event: SystemEvent) -> usize {
+            self.sender.send(event).unwrap_or(0)
+        }
+
+        /// Spawns a task that feeds the observer until every manager
+        /// handle is dropped, then hands the observer back through the
+        /// join handle so callers can inspect its final state.
+        pub fn attach<O>(&self, mut observer: O) -> JoinHandle<O>
+        where
+            O: AsyncEventObserver + Send + 'static,
+        {
+            let mut receiver = self.sender.subscribe();
+            tokio::spawn(async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(event) => observer.handle_event(event).await,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            observer.handle_lag(skipped).await
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+                observer
+            })
+        }
+    }
+
+    /// Simulated HTTP-posting observer: every event becomes a slow POST.
+    pub struct HttpPostingObserver {
+        endpoint: String,
+        posted: u64,
+        lag_dropped: u64,
+    }
+
+    impl HttpPostingObserver {
+        pub fn new(endpoint: &str) -> Self {
+            HttpPostingObserver {
+                endpoint: endpoint.to_string(),
+                posted: 0,
+                lag_dropped: 0,
+            }
+        }
+
+        pub fn posted(&self) -> u64 {
+            self.posted
+        }
+
+        pub fn lag_dropped(&self) -> u64 {
+            self.lag_dropped
+        }
+    }
+
+    impl AsyncEventObserver for HttpPostingObserver {
+        async fn handle_event(&mut self, event: SystemEvent) {
+            // Stand-in for `client.post(&self.endpoint).json(&event)`.
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let _ = (&self.endpoint, event);
+            self.posted += 1;
+        }
+
+        async fn handle_lag(&mut self, skipped: u64) {
+            self.lag_dropped += skipped;
+        }
+    }
+
+    pub async fn demo() {
+        println!("\n=== Async event manager ===");
+        let manager = AsyncEventManager::new(64);
+        let poster = manager.attach(HttpPostingObserver::new("https://hooks.example/events"));
+
+        // Publishing 20 events takes microseconds even though the
+        // observer needs 20ms per event.
+        let started = std::time::Instant::now();
+        for i in 0..20 {
+            manager.publish(SystemEvent::HttpRequest {
+                path: format!("/api/{}", i),
+                status: 200,
+            });
+        }
+        let publish_time = started.elapsed();
+        assert!(publish_time < Duration::from_millis(20));
+        println!("published 20 events in {:?}", publish_time);
+
+        // Dropping the manager closes the channel; the observer drains
+        // its backlog and hands itself back.
+        drop(manager);
+        let poster = poster.await.unwrap();
+        assert_eq!(poster.posted(), 20);
+        println!("observer drained its queue: {} posts", poster.posted());
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Demo
+// ---------------------------------------------------------------------------
+
+fn demo_weather_station() {
+    println!("=== Weather station ===");
+    let mut station = WeatherStation::new();
+    let current = Rc::new(RefCell::new(CurrentConditionsDisplay::new("current")));
+    let stats = Rc::new(RefCell::new(StatisticsDisplay::new("stats")));
+    let forecast = Rc::new(RefCell::new(ForecastDisplay::new("forecast")));
+    station.register_observer(current.clone());
+    station.register_observer(stats.clone());
+    station.register_observer(forecast.clone());
+
+    for (t, h, p) in [(21.5, 65.0, 1013.0), (23.1, 70.0, 1009.0), (19.8, 90.0, 1002.0)] {
+        station.set_measurements(WeatherData {
+            temperature: t,
+            humidity: h,
+            pressure: p,
+        });
+    }
+
+    let (min, avg, max) = stats.borrow().stats().unwrap();
+    println!("temperature min {:.1} avg {:.1} max {:.1}", min, avg, max);
+    assert_eq!(min, 19.8);
+    assert_eq!(max, 23.1);
+    assert_eq!(forecast.borrow().forecast(), "cooler, rainy");
+
+    // Removed observers stop receiving updates.
+    station.remove_observer("current");
+    station.set_measurements(WeatherData {
+        temperature: 25.0,
+        humidity: 50.0,
+        pressure: 1015.0,
+    });
+    assert_eq!(current.borrow().last().unwrap().temperature, 19.8);
+    assert_eq!(station.history().len(), 4);
+
+    // The station only holds weak references: dropping the last strong Rc
+    // is enough to unsubscribe, and notify prunes the dead entry.
+    assert_eq!(station.observer_count(), 2);
+    drop(forecast);
+    station.set_measurements(WeatherData {
+        temperature: 24.0,
+        humidity: 55.0,
+        pressure: 1012.0,
+    });
+    assert_eq!(station.observer_count(), 1);
+    assert_eq!(station.pruned_count(), 1);
+    println!(
+        "dropped display pruned automatically ({} pruned so far)",
+        station.pruned_count()
+    );
+}
+
+fn demo_derived_metrics() {
+    println!("\n=== Derived metrics and units ===");
+    let muggy = WeatherData {
+        temperature: 32.0,
+        humidity: 70.0,
+        pressure: 1013.25,
+    };
+    let dew = muggy.dew_point();
+    let heat = muggy.heat_index();
+    assert!((25.0..27.5).contains(&dew), "dew point {}", dew);
+    assert!(heat > muggy.temperature, "heat index should exceed air temp");
+    println!("32°C / 70%: dew point {:.1}°C, feels like {:.1}°C", dew, heat);
+
+    let frosty = WeatherData {
+        temperature: -5.0,
+        humidity: 80.0,
+        pressure: 1020.0,
+    };
+    let chill = frosty.wind_chill(30.0);
+    assert!((-14.0..-10.0).contains(&chill), "wind chill {}", chill);
+    // Calm or warm conditions fall back to the air temperature.
+    assert_eq!(frosty.wind_chill(2.0), -5.0);
+    assert_eq!(muggy.wind_chill(30.0), 32.0);
+    println!("-5°C at 30 km/h wind: feels like {:.1}°C", chill);
+
+    // Unit conversions used by display preferences.
+    assert_eq!(TemperatureUnit::Fahrenheit.from_celsius(25.0), 77.0);
+    let inhg = PressureUnit::InchesOfMercury.from_hectopascal(1013.25);
+    assert!((inhg - 29.92).abs() < 0.01);
+
+    // The same station update renders differently per observer.
+    let mut station = WeatherStation::new();
+    let metric = Rc::new(RefCell::new(CurrentConditionsDisplay::new("metric")));
+    let imperial = Rc::new(RefCell::new(CurrentConditionsDisplay::with_units(
+        "imperial",
+        UnitPreferences {
+            temperature: TemperatureUnit::Fahrenheit,
+            pressure: PressureUnit::InchesOfMercury,
+        },
+    )));
+    station.register_observer(metric.clone());
+    station.register_observer(imperial.clone());
+    station.set_measurements(muggy);
+    assert!(metric.borrow().render().unwrap().starts_with("32.0°C"));
+    assert!(imperial.borrow().render().unwrap().starts_with("89.6°F"));
+}
+
+fn demo_pull_model() {
+    println!("\n=== Pull-model observation ===");
+    /// Only cares about temperature; never touches the other fields.
+    struct TemperatureTicker {
+        name: String,
+        readings: Vec<f64>,
+    }
+    impl PullObserver for TemperatureTicker {
+        fn on_change(&mut self, station: &WeatherStation) {
+            if let Some(t) = station.temperature() {
+                self.readings.push(t);
+            }
+        }
+        fn name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    /// Only cares about pressure.
+    struct PressureTicker {
+        name: String,
+        readings: Vec<f64>,
+    }
+    impl PullObserver for PressureTicker {
+        fn on_change(&mut self, station: &WeatherStation) {
+            if let Some(p) = station.pressure() {
+                self.readings.push(p);
+            }
+        }
+        fn name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    let mut station = WeatherStation::new();
+    let temp = Rc::new(RefCell::new(TemperatureTicker {
+        name: "temp-ticker".to_string(),
+        readings: Vec::new(),
+    }));
+    let pressure = Rc::new(RefCell::new(PressureTicker {
+        name: "pressure-ticker".to_string(),
+        readings: Vec::new(),
+    }));
+    // One classic push display alongside the two pull observers.
+    let push = Rc::new(RefCell::new(StatisticsDisplay::new("push-stats")));
+    station.register_pull_observer(temp.clone());
+    station.register_pull_observer(pressure.clone());
+    station.register_observer(push.clone());
+
+    let updates = 3_u64;
+    for i in 0..updates {
+        station.set_measurements(WeatherData {
+            temperature: 20.0 + i as f64,
+            humidity: 60.0,
+            pressure: 1010.0 + i as f64,
+        });
+    }
+
+    assert_eq!(temp.borrow().readings, [20.0, 21.0, 22.0]);
+    assert_eq!(pressure.borrow().readings, [1010.0, 1011.0, 1012.0]);
+
+    // Bandwidth: push ships every field to every observer on every update;
+    // pull shipped exactly one field per observer per update.
+    let pushed_fields = updates * 3;
+    assert_eq!(station.fields_pulled(), updates * 2);
+    println!(
+        "push sent {} field values to 1 observer; pull served {} to 2 observers",
+        pushed_fields,
+        station.fields_pulled()
+    );
+}
+
+fn demo_batching() {
+    println!("\n=== Batched notifications ===");
+    /// Records one entry per delivered batch rather than per reading.
+    struct BatchSizeDisplay {
+        name: String,
+        batch_sizes: Vec<usize>,
+    }
+    impl Observer<WeatherData> for BatchSizeDisplay {
+        fn notify(&mut self, _data: &WeatherData) -> Result<(), String> {
+            self.batch_sizes.push(1);
+            Ok(())
+        }
+        fn notify_batch(&mut self, batch: &[WeatherData]) -> Result<(), String> {
+            self.batch_sizes.push(batch.len());
+            Ok(())
+        }
+        fn name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    let mut station = WeatherStation::new();
+    let display = Rc::new(RefCell::new(BatchSizeDisplay {
+        name: "batch-sizes".to_string(),
+        batch_sizes: Vec::new(),
+    }));
+    station.register_observer(display.clone());
+    station.enable_batching(BatchConfig {
+        flush_after: 3,
+        coalesce: true,
+    });
+
+    let reading = |t: f64| WeatherData {
+        temperature: t,
+        humidity: 60.0,
+        pressure: 1010.0,
+    };
+
+    // Two rapid-fire readings from the roof sensor coalesce into one.
+    station.queue_measurement("roof", reading(20.0));
+    station.queue_measurement("roof", reading(20.5));
+    station.queue_measurement("garden", reading(19.0));
+    assert!(display.borrow().batch_sizes.is_empty(), "nothing flushed yet");
+
+    // The third distinct key reaches flush_after and triggers delivery.
+    station.queue_measurement("cellar", reading(12.0));
+    assert_eq!(display.borrow().batch_sizes.as_slice(), [3]);
+    assert_eq!(station.coalesced_dropped(), 1);
+    assert_eq!(station.current().unwrap().temperature, 12.0);
+
+    // A partial batch can be flushed explicitly.
+    station.queue_measurement("roof", reading(21.0));
+    station.flush_batch();
+    assert_eq!(display.borrow().batch_sizes.as_slice(), [3, 1]);
+    println!(
+        "batches of {:?}, {} reading(s) coalesced away",
+        display.borrow().batch_sizes,
+        station.coalesced_dropped()
+    );
+}
+
+fn demo_csv_ingestion() {
+    println!("\n=== CSV feed replay ===");
+    let feed = "\
+timestamp_s,temperature,humidity,pressure
+0,18.2,71,1011.5
+600,19.0,69,1011.1
+1200,20.4,66,1010.2
+1800,21.9,63,1009.4
+";
+    let mut station = WeatherStation::new();
+    let stats = Rc::new(RefCell::new(StatisticsDisplay::new("stats")));
+    let forecast = Rc::new(RefCell::new(ForecastDisplay::new("forecast")));
+    station.register_observer(stats.clone());
+    station.register_observer(forecast.clone());
+
+    // Half an hour of readings, replayed at 36000x (~50ms wall time).
+    let started = Instant::now();
+    let ingested = station
+        .ingest_csv(
+            std::io::Cursor::new(feed),
+            ReplaySpeed::Accelerated(36_000.0),
+        )
+        .unwrap();
+    assert_eq!(ingested, 4);
+    assert_eq!(station.history().len(), 4);
+    assert!(started.elapsed() >= Duration::from_millis(45));
+    let (min, _, max) = stats.borrow().stats().unwrap();
+    assert_eq!((min, max), (18.2, 21.9));
+    assert_eq!(forecast.borrow().forecast(), "cooler, rainy");
+    println!(
+        "replayed {} readings in {:?}; forecast {:?}",
+        ingested,
+        started.elapsed(),
+        forecast.borrow().forecast()
+    );
+
+    // Malformed rows are reported with their line number.
+    let mut scratch = WeatherStation::new();
+    let err = scratch
+        .ingest_csv(std::io::Cursor::new("0,18.0,70\n"), ReplaySpeed::Instant)
+        .unwrap_err();
+    assert!(err.contains("expected 4 columns"), "{}", err);
+}
+
+fn demo_threshold_alerts() {
+    println!("\n=== Threshold alerts with hysteresis ===");
+    let mut station = WeatherStation::new();
+    let heat = Rc::new(RefCell::new(ThresholdAlertDisplay::new(
+        "heat-warning",
+        35.0,
+        33.0,
+    )));
+    station.register_observer(heat.clone());
+
+    let reading = |t: f64| WeatherData {
+        temperature: t,
+        humidity: 40.0,
+        pressure: 1008.0,
+    };
+
+    // A reading oscillating through the 33-35°C band would flap a
+    // single-threshold alert on every sample; here it transitions twice.
+    for t in [30.0, 36.2, 34.0, 35.8, 33.5, 34.9, 32.4, 34.0] {
+        station.set_measurements(reading(t));
+    }
+    let heat = heat.borrow();
+    assert_eq!(
+        heat.events(),
+        [
+            AlertEvent::Raised { value: 36.2 },
+            AlertEvent::Cleared { value: 32.4 },
+        ]
+    );
+    assert!(!heat.active(), "cleared and not re-raised at 34.0");
+    println!("transitions: {:?}", heat.events());
+}
+
+fn demo_event_manager() {
+    println!("\n=== Event manager ===");
+    let mut manager = EventManager::new();
+    let logger = Rc::new(RefCell::new(EventLogger::new("logger")));
+    let monitor = Rc::new(RefCell::new(SecurityMonitor::new("security")));
+    manager.subscribe(logger.clone());
+    manager.subscribe(monitor.clone());
+
+    manager.publish_event(SystemEvent::UserLoggedIn {
+        user: "alice".to_string(),
+    });
+    for _ in 0..3 {
+        manager.publish_event(SystemEvent::LoginFailed {
+            user: "mallory".to_string(),
+        });
+    }
+    manager.publish_event(SystemEvent::HttpRequest {
+        path: "/admin".to_string(),
+        status: 401,
+    });
+    manager.publish_event(SystemEvent::FileUploaded {
+        path: "report.pdf".to_string(),
+        size_bytes: 52_288,
+    });
+
+    // The logger sees everything; the monitor only what it asked for.
+    assert_eq!(logger.borrow().entries().len(), 6);
+    let alerts = monitor.borrow().alerts().to_vec();
+    assert_eq!(alerts.len(), 2);
+    for alert in &alerts {
+        println!("alert: {}", alert);
+    }
+    assert_eq!(manager.get_recent_events(2).len(), 2);
+
+    // Priorities: security must react before anything is logged, even
+    // though the logger subscribed first.
+    let mut prioritized = EventManager::new();
+    prioritized.subscribe(Rc::new(RefCell::new(EventLogger::new("logger"))));
+    prioritized.subscribe_with_priority(Rc::new(RefCell::new(SecurityMonitor::new("security"))), 10);
+    prioritized.subscribe(Rc::new(RefCell::new(EventLogger::new("audit"))));
+
+    prioritized.publish_event(SystemEvent::LoginFailed {
+        user: "eve".to_string(),
+    });
+    let first = prioritized.last_notification_order().clone();
+    assert_eq!(first.entries(), ["security", "logger", "audit"]);
+
+    // The order is a property of the subscriptions, not of the run.
+    prioritized.publish_event(SystemEvent::LoginFailed {
+        user: "eve".to_string(),
+    });
+    assert_eq!(prioritized.last_notification_order(), &first);
+    println!("notification order: {:?}", first.entries());
+
+    // Bounded history: only the newest five events survive, and the
+    // pages walk backwards through the retained window.
+    let mut bounded = EventManager::with_history(RingBuffer::new(5));
+    for i in 0..8 {
+        bounded.publish_event(SystemEvent::HttpRequest {
+            path: format!("/req/{}", i),
+            status: 200,
+        });
+    }
+    assert_eq!(bounded.history().len(), 5);
+    assert_eq!(bounded.history().evicted(), 3);
+    let newest = bounded.get_recent_events(2);
+    assert_eq!(newest[0].describe(), "/req/7 -> 200");
+    let older = bounded.get_recent_events_page(2, 2);
+    assert_eq!(older[0].describe(), "/req/5 -> 200");
+    println!(
+        "history window: {} retained, {} evicted, {} bytes",
+        bounded.history().len(),
+        bounded.history().evicted(),
+        bounded.history().bytes()
+    );
+
+    // The byte cap evicts even before the item capacity is reached.
+    let mut tight = RingBuffer::with_byte_cap(100, 200);
+    for i in 0..4 {
+        tight.push(SystemEvent::Error {
+            message: format!("padding padding padding {}", i),
+        });
+    }
+    assert!(tight.bytes() <= 200);
+    assert!(tight.evicted() > 0);
+}
+
+fn demo_subscription_lifetimes() {
+    println!("\n=== One-shot and expiring subscriptions ===");
+    let mut manager = EventManager::new();
+    manager.subscribe(Rc::new(RefCell::new(EventLogger::new("audit"))));
+    let once = Rc::new(RefCell::new(EventLogger::new("first-login-hook")));
+    manager.subscribe_once(once.clone());
+    let ttl = Rc::new(RefCell::new(EventLogger::new("during-rollout")));
+    manager.subscribe_for(ttl.clone(), Duration::from_millis(40));
+    assert_eq!(manager.subscriber_count(), 3);
+
+    // All three see the first event; the one-shot is then gone.
+    let report = manager.publish_event(SystemEvent::UserLoggedIn {
+        user: "alice".to_string(),
+    });
+    assert_eq!(report.delivered, 3);
+    assert_eq!(manager.subscriber_count(), 2);
+
+    let report = manager.publish_event(SystemEvent::UserLoggedIn {
+        user: "bob".to_string(),
+    });
+    assert_eq!(report.delivered, 2);
+    assert_eq!(once.borrow().entries().len(), 1, "one-shot fired once");
+
+    // After the TTL the expiring subscription is dropped unnotified.
+    thread::sleep(Duration::from_millis(50));
+    let report = manager.publish_event(SystemEvent::UserLoggedIn {
+        user: "carol".to_string(),
+    });
+    assert_eq!(report.delivered, 1);
+    assert_eq!(manager.subscriber_count(), 1);
+    assert_eq!(ttl.borrow().entries().len(), 2, "expired before the third");
+    println!(
+        "subscribers left: {}, one-shot saw {}, expiring saw {}",
+        manager.subscriber_count(),
+        once.borrow().entries().len(),
+        ttl.borrow().entries().len()
+    );
+}
+
+fn demo_failure_isolation() {
+    println!("\n=== Failure isolation ===");
+    /// Fails every delivery; panics on the marker event.
+    struct FlakyObserver {
+        name: String,
+    }
+    impl Observer<SystemEvent> for FlakyObserver {
+        fn notify(&mut self, event: &SystemEvent) -> Result<(), String> {
+            if matches!(event, SystemEvent::Error { .. }) {
+                panic!("flaky observer blew up");
+            }
+            Err("connection refused".to_string())
+        }
+        fn name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    let mut manager = EventManager::new();
+    manager.set_failure_limit(Some(2));
+    manager.subscribe(Rc::new(RefCell::new(FlakyObserver {
+        name: "flaky".to_string(),
+    })));
+    let logger = Rc::new(RefCell::new(EventLogger::new("logger")));
+    manager.subscribe(logger.clone());
+
+    // Suppress the default panic printout while the contained panic fires.
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let report = manager.publish_event(SystemEvent::Error {
+        message: "boom".to_string(),
+    });
+    std::panic::set_hook(hook);
+
+    // The panic was contained and the logger still got the event.
+    assert_eq!(report.delivered, 1);
+    assert_eq!(report.errors, [("flaky".to_string(), "panicked".to_string())]);
+    assert!(report.dropped.is_empty());
+    assert_eq!(logger.borrow().entries().len(), 1);
+    println!("panic contained: {:?}", report.errors);
+
+    // The second consecutive failure trips the limit and unsubscribes.
+    let report = manager.publish_event(SystemEvent::UserLoggedIn {
+        user: "alice".to_string(),
+    });
+    assert_eq!(
+        report.errors,
+        [("flaky".to_string(), "connection refused".to_string())]
+    );
+    assert_eq!(report.dropped, ["flaky".to_string()]);
+
+    // From now on delivery is clean.
+    let report = manager.publish_event(SystemEvent::UserLoggedIn {
+        user: "alice".to_string(),
+    });
+    assert!(report.errors.is_empty());
+    assert_eq!(report.delivered, 1);
+    println!("flaky observer dropped after 2 consecutive failures");
+}
+
+fn demo_middleware() {
+    println!("\n=== Event middleware ===");
+    let mut manager = EventManager::new();
+    manager.add_middleware(Box::new(NoiseFilter::new(&["http_request"])));
+    manager.add_middleware(Box::new(Deduplicator::new(8)));
+    manager.add_middleware(Box::new(CorrelationEnricher::new()));
+    let logger = Rc::new(RefCell::new(EventLogger::new("logger")));
+    manager.subscribe(logger.clone());
+
+    // Noisy request traffic never reaches the observers.
+    for i in 0..5 {
+        manager.publish_event(SystemEvent::HttpRequest {
+            path: format!("/health/{}", i),
+            status: 200,
+        });
+    }
+    // The same error published three times is delivered once.
+    for _ in 0..3 {
+        manager.publish_event(SystemEvent::Error {
+            message: "disk full".to_string(),
+        });
+    }
+    // A distinct error passes and gets its own correlation id.
+    manager.publish_event(SystemEvent::Error {
+        message: "net split".to_string(),
+    });
+
+    let entries = logger.borrow().entries().to_vec();
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].contains("[cid 1] disk full"), "{}", entries[0]);
+    assert!(entries[1].contains("[cid 2] net split"), "{}", entries[1]);
+    assert_eq!(manager.middleware_dropped()["noise-filter"], 5);
+    assert_eq!(manager.middleware_dropped()["deduplicator"], 2);
+    assert_eq!(manager.history().len(), 2, "dropped events stay out of history");
+    for entry in &entries {
+        println!("delivered: {}", entry);
+    }
+    println!("dropped: {:?}", manager.middleware_dropped());
+}
+
+fn demo_jsonl_persistence() {
+    println!("\n=== JSON Lines persistence ===");
+    let path = std::env::temp_dir().join("observer_events.jsonl");
+    let _ = std::fs::remove_file(&path);
+
+    let mut manager = EventManager::new();
+    let sink = Rc::new(RefCell::new(JsonlSink::create("jsonl", &path).unwrap()));
+    manager.subscribe(sink.clone());
+    manager.publish_event(SystemEvent::UserLoggedIn {
+        user: "alice".to_string(),
+    });
+    manager.publish_event(SystemEvent::FileUploaded {
+        path: "backup \"q3\".tar".to_string(),
+        size_bytes: 1_048_576,
+    });
+    manager.publish_event(SystemEvent::HttpRequest {
+        path: "/admin".to_string(),
+        status: 401,
+    });
+    sink.borrow_mut().flush().unwrap();
+    assert_eq!(sink.borrow().written(), 3);
+
+    // Round trip a single event through its JSON form.
+    let event = SystemEvent::Error {
+        message: "line1\nline2".to_string(),
+    };
+    assert_eq!(SystemEvent::from_json(&event.to_json()).unwrap(), event);
+
+    // Replaying the file drives a fresh manager as if the events were live.
+    let mut replayer = EventManager::new();
+    let logger = Rc::new(RefCell::new(EventLogger::new("logger")));
+    let monitor = Rc::new(RefCell::new(SecurityMonitor::new("security")));
+    replayer.subscribe(logger.clone());
+    replayer.subscribe(monitor.clone());
+    let replayed = replay_jsonl(&path, &mut replayer).unwrap();
+    assert_eq!(replayed, 3);
+    assert_eq!(logger.borrow().entries().len(), 3);
+    assert_eq!(monitor.borrow().alerts().len(), 1, "401 alert fires on replay");
+    println!("replayed {} events; security re-raised {:?}", replayed, monitor.borrow().alerts());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+fn demo_schema_versioning() {
+    println!("\n=== Event schema versioning ===");
+    let event = SystemEvent::FileUploaded {
+        path: "report.pdf".to_string(),
+        size_bytes: 4096,
+    };
+    let line = event.to_json();
+    assert!(line.starts_with("{\"v\":2,"), "{}", line);
+    assert_eq!(SystemEvent::from_json(&line).unwrap(), event);
+
+    // An untagged payload from before versioning still deserializes,
+    // including the old `size` field name.
+    let legacy = "{\"kind\":\"file_uploaded\",\"path\":\"report.pdf\",\"size\":4096}";
+    assert_eq!(SystemEvent::from_json(legacy).unwrap(), event);
+
+    // A payload from a future release is rejected, not misread.
+    let future = "{\"v\":3,\"kind\":\"file_uploaded\",\"path\":\"x\",\"size_bytes\":1}";
+    let err = SystemEvent::from_json(future).unwrap_err();
+    assert!(err.contains("newer than supported"), "{}", err);
+
+    // Replay works across a mixed-version store.
+    let path = std::env::temp_dir().join("observer-demo-mixed.jsonl");
+    std::fs::write(
+        &path,
+        format!("{}\n{}\n", legacy, line),
+    )
+    .unwrap();
+    let mut manager = EventManager::new();
+    let replayed = replay_jsonl(&path, &mut manager).unwrap();
+    assert_eq!(replayed, 2);
+    let _ = std::fs::remove_file(&path);
+    println!("current: {}", line);
+    println!("legacy payload migrated; v3 rejected: {}", err);
+}
+
+fn demo_rate_limiting() {
+    println!("\n=== Per-observer rate limiting ===");
+    let mut manager = EventManager::new();
+    let cheap = Rc::new(RefCell::new(EventLogger::new("cheap")));
+    let dropping = Rc::new(RefCell::new(EventLogger::new("dropping")));
+    let queueing = Rc::new(RefCell::new(EventLogger::new("queueing")));
+    manager.subscribe(cheap.clone());
+    manager.subscribe_with_rate_limit(
+        dropping.clone(),
+        RateLimit {
+            max_per_second: 10,
+            policy: OverflowPolicy::Drop,
+        },
+    );
+    manager.subscribe_with_rate_limit(
+        queueing.clone(),
+        RateLimit {
+            max_per_second: 10,
+            policy: OverflowPolicy::Queue,
+        },
+    );
+
+    // A burst of 13 events: the unlimited observer sees all of them, the
+    // limited ones only their 10-token burst.
+    for i in 0..13 {
+        manager.publish_event(SystemEvent::HttpRequest {
+            path: format!("/burst/{}", i),
+            status: 200,
+        });
+    }
+    assert_eq!(cheap.borrow().entries().len(), 13);
+    assert_eq!(dropping.borrow().entries().len(), 10);
+    assert_eq!(queueing.borrow().entries().len(), 10);
+    assert_eq!(
+        manager.rate_limit_stats("dropping").unwrap(),
+        RateLimitStats {
+            dropped: 3,
+            queued: 0
+        }
+    );
+    assert_eq!(manager.rate_limit_stats("queueing").unwrap().queued, 3);
+
+    // Once the bucket refills, pumping drains the queue.
+    thread::sleep(std::time::Duration::from_millis(350));
+    let pumped = manager.pump_queued();
+    assert_eq!(pumped, 3);
+    assert_eq!(queueing.borrow().entries().len(), 13);
+    assert_eq!(manager.rate_limit_stats("queueing").unwrap().queued, 0);
+    println!(
+        "burst of 13: dropped {}, queued then delivered {}",
+        manager.rate_limit_stats("dropping").unwrap().dropped,
+        pumped
+    );
+}
+
+fn demo_metrics() {
+    println!("\n=== Notification metrics ===");
+    /// Deliberately slow handler, the kind the metrics should expose.
+    struct SlowObserver {
+        name: String,
+    }
+    impl Observer<SystemEvent> for SlowObserver {
+        fn notify(&mut self, _event: &SystemEvent) -> Result<(), String> {
+            thread::sleep(Duration::from_millis(2));
+            Ok(())
+        }
+        fn name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    let mut manager = EventManager::new();
+    manager.subscribe(Rc::new(RefCell::new(EventLogger::new("fast-logger"))));
+    manager.subscribe(Rc::new(RefCell::new(SlowObserver {
+        name: "slow-poster".to_string(),
+    })));
+
+    for i in 0..30 {
+        manager.publish_event(SystemEvent::HttpRequest {
+            path: format!("/metrics/{}", i),
+            status: 200,
+        });
+    }
+
+    let metrics = manager.get_metrics();
+    for m in &metrics {
+        println!(
+            "{:<12} {:>3} calls, total {:?}, p95 {:?}",
+            m.name, m.invocations, m.total_time, m.p95_latency
+        );
+    }
+    let fast = metrics.iter().find(|m| m.name == "fast-logger").unwrap();
+    let slow = metrics.iter().find(|m| m.name == "slow-poster").unwrap();
+    assert_eq!(fast.invocations, 30);
+    assert_eq!(slow.invocations, 30);
+    assert!(slow.total_time > fast.total_time);
+    assert!(slow.p95_latency >= Duration::from_millis(2));
+}
+
+fn demo_log_sinks() {
+    println!("\n=== Log sinks ===");
+    let dir = std::env::temp_dir();
+    let plain_path = dir.join("observer_plain.log");
+    let rotating_path = dir.join("observer_rotating.log");
+    let _ = std::fs::remove_file(&plain_path);
+    for i in 0..6 {
+        let _ = std::fs::remove_file(rotating_path.with_extension(format!("log.{}", i)));
+    }
+    let _ = std::fs::remove_file(&rotating_path);
+
+    let mut logger = EventLogger::new("sink-logger");
+    logger.add_sink(Box::new(FileSink::create(&plain_path).unwrap()));
+    logger.add_sink(Box::new(RotatingFileSink::create(&rotating_path, 120).unwrap()));
+
+    let mut manager = EventManager::new();
+    let logger = Rc::new(RefCell::new(logger));
+    manager.subscribe(logger.clone());
+    for i in 0..10 {
+        manager.publish_event(SystemEvent::FileUploaded {
+            path: format!("archive-{:02}.tar", i),
+            size_bytes: 1_000 + i,
+        });
+    }
+    logger.borrow_mut().flush_sinks().unwrap();
+
+    // The plain file holds every line; the rotating sink split them over
+    // several generations without losing any.
+    let plain = std::fs::read_to_string(&plain_path).unwrap();
+    assert_eq!(plain.lines().count(), 10);
+    let mut total = std::fs::read_to_string(&rotating_path).unwrap().lines().count();
+    let mut generations = 0;
+    for i in 1..=10 {
+        let rotated = rotating_path.with_extension(format!("log.{}", i));
+        if let Ok(content) = std::fs::read_to_string(&rotated) {
+            total += content.lines().count();
+            generations += 1;
+            let _ = std::fs::remove_file(&rotated);
+        }
+    }
+    assert_eq!(total, 10);
+    assert!(generations >= 2, "120-byte cap should force rotations");
+    println!("10 lines, {} rotated generations, none lost", generations);
+
+    let _ = std::fs::remove_file(&plain_path);
+    let _ = std::fs::remove_file(&rotating_path);
+}
+
+fn demo_security_rules() {
+    println!("\n=== Rule-based security ===");
+    let mut monitor = SecurityMonitor::with_rules(
+        "rules",
+        vec![
+            SecurityRule {
+                name: "login-lockout".to_string(),
+                pattern: EventPattern::LoginFailure,
+                threshold: 2,
+                window: Duration::from_secs(5),
+                action: SecurityAction::Lockout,
+            },
+            SecurityRule {
+                name: "upload-flood".to_string(),
+                pattern: EventPattern::Kind("file_uploaded".to_string()),
+                threshold: 3,
+                window: Duration::from_secs(10),
+                action: SecurityAction::Alert,
+            },
+        ],
+    );
+
+    let failed = |user: &str| SystemEvent::LoginFailed {
+        user: user.to_string(),
+    };
+
+    // Two quick failures trip the lockout rule.
+    monitor.notify(&failed("eve")).unwrap();
+    monitor.notify(&failed("eve")).unwrap();
+    assert!(monitor.is_locked_out("eve"));
+    assert_eq!(monitor.alerts(), ["login-lockout: eve"]);
+
+    // Window expiry: a failure older than the window no longer counts.
+    monitor.notify(&failed("bob")).unwrap();
+    monitor.advance_clock(Duration::from_secs(6));
+    monitor.notify(&failed("bob")).unwrap();
+    assert!(!monitor.is_locked_out("bob"), "first failure expired");
+
+    // ...but a second failure inside the window still trips it.
+    monitor.notify(&failed("bob")).unwrap();
+    assert!(monitor.is_locked_out("bob"));
+
+    // Subjects are counted independently per rule.
+    for i in 0..3 {
+        monitor
+            .notify(&SystemEvent::FileUploaded {
+                path: format!("dump-{}.bin", i),
+                size_bytes: 1 << 30,
+            })
+            .unwrap();
+    }
+    assert_eq!(monitor.alerts().last().unwrap(), "upload-flood: file_uploaded");
+    println!("alerts: {:?}", monitor.alerts());
+    println!("lockouts: {:?}", monitor.lockouts());
+}
+
+fn demo_event_bus() {
+    println!("\n=== Typed event bus ===");
+    struct UserLoggedIn {
+        user: String,
+    }
+    struct TemperatureChanged {
+        celsius: f64,
+    }
+
+    let mut bus = EventBus::new();
+    let greetings = Rc::new(RefCell::new(Vec::new()));
+    let audit = Rc::new(RefCell::new(Vec::new()));
+    let readings = Rc::new(RefCell::new(Vec::new()));
+
+    {
+        let greetings = greetings.clone();
+        bus.subscribe::<UserLoggedIn, _>(move |event| {
+            greetings.borrow_mut().push(format!("welcome, {}", event.user));
+        });
+    }
+    {
+        let audit = audit.clone();
+        bus.subscribe::<UserLoggedIn, _>(move |event| {
+            audit.borrow_mut().push(event.user.clone());
+        });
+    }
+    {
+        let readings = readings.clone();
+        bus.subscribe::<TemperatureChanged, _>(move |event| {
+            readings.borrow_mut().push(event.celsius);
+        });
+    }
+
+    // Each payload type reaches exactly its own subscribers.
+    assert_eq!(
+        bus.publish(&UserLoggedIn {
+            user: "alice".to_string()
+        }),
+        2
+    );
+    assert_eq!(bus.publish(&TemperatureChanged { celsius: 21.5 }), 1);
+    assert_eq!(greetings.borrow().as_slice(), ["welcome, alice"]);
+    assert_eq!(audit.borrow().as_slice(), ["alice"]);
+    assert_eq!(readings.borrow().as_slice(), [21.5]);
+
+    // Publishing a type nobody subscribed to is a quiet no-op.
+    struct Unwatched;
+    assert_eq!(bus.publish(&Unwatched), 0);
+    assert_eq!(bus.subscriber_count::<UserLoggedIn>(), 2);
+    println!("typed routing: 2 login handlers, 1 temperature handler, 0 unwatched");
+}
+
+fn demo_thread_safe() {
+    println!("\n=== Thread-safe event manager ===");
+    let manager = Arc::new(SharedEventManager::new());
+
+    // Observers registered from other threads.
+    let mut registrations = Vec::new();
+    for i in 0..2 {
+        let manager = Arc::clone(&manager);
+        registrations.push(thread::spawn(move || {
+            manager.subscribe(Arc::new(SharedEventLogger::new(&format!("logger-{}", i))));
+        }));
+    }
+    for handle in registrations {
+        handle.join().unwrap();
+    }
+
+    // A producer thread publishes while the main thread also publishes.
+    let producer = {
+        let manager = Arc::clone(&manager);
+        thread::spawn(move || {
+            for i in 0..50 {
+                manager.publish_event(SystemEvent::HttpRequest {
+                    path: format!("/api/{}", i),
+                    status: 200,
+                });
+            }
+        })
+    };
+    for _ in 0..50 {
+        manager.publish_event(SystemEvent::UserLoggedIn {
+            user: "bob".to_string(),
+        });
+    }
+    producer.join().unwrap();
+
+    assert_eq!(manager.event_count(), 100);
+    println!("100 events fanned out across threads");
+
+    // Each registered logger saw every event.
+    let checker = Arc::new(SharedEventLogger::new("checker"));
+    manager.subscribe(checker.clone());
+    manager.publish_event(SystemEvent::Error {
+        message: "disk full".to_string(),
+    });
+    assert_eq!(checker.entry_count(), 1);
+}
+
+fn demo_channel_broadcast() {
+    println!("\n=== Channel producer/consumer ===");
+    let mut broadcaster = ChannelBroadcaster::new();
+    let stats_handle = broadcaster.attach(StatisticsDisplay::new("threaded-stats"));
+    let forecast_handle = broadcaster.attach(ForecastDisplay::new("threaded-forecast"));
+
+    // The producer owns the broadcaster and runs on its own thread.
+    let producer = thread::spawn(move || {
+        for i in 0..100 {
+            let reached = broadcaster.broadcast(WeatherData {
+                temperature: 15.0 + (i % 10) as f64,
+                humidity: 60.0,
+                pressure: 1000.0 + i as f64,
+            });
+            assert_eq!(reached, 2);
+        }
+        broadcaster.shutdown();
+    });
+    producer.join().unwrap();
+
+    // Consumers drain their queues, see the shutdown marker and return
+    // their observers.
+    let stats = stats_handle.join().unwrap();
+    let forecast = forecast_handle.join().unwrap();
+    let (min, _, max) = stats.stats().unwrap();
+    assert_eq!((min, max), (15.0, 24.0));
+    assert_eq!(forecast.forecast(), "improving");
+    println!(
+        "100 readings consumed on 2 threads: min {} max {} forecast '{}'",
+        min,
+        max,
+        forecast.forecast()
+    );
+}
+
+fn main() {
+    demo_weather_station();
+    demo_derived_metrics();
+    demo_pull_model();
+    demo_batching();
+    demo_csv_ingestion();
+    demo_threshold_alerts();
+    demo_event_manager();
+    demo_subscription_lifetimes();
+    demo_failure_isolation();
+    demo_middleware();
+    demo_jsonl_persistence();
+    demo_schema_versioning();
+    demo_rate_limiting();
+    demo_metrics();
+    demo_log_sinks();
+    demo_security_rules();
+    demo_event_bus();
+    demo_thread_safe();
+    demo_channel_broadcast();
+    #[cfg(feature = "async")]
+    tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(async_events::demo());
+}
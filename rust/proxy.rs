@@ -0,0 +1,3112 @@
+#![allow(dead_code)]
+
+/// Proxy Pattern
+///
+/// Provides a surrogate or placeholder for another object to control access
+/// to it: caching, protection, lazy loading, remote access.
+
+#[path = "strategy.rs"]
+mod strategy;
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use strategy::{
+    Cache, CompressionStrategy, EvictionStrategy, FifoEviction, LfuEviction, LruEviction,
+    RleCompression,
+};
+
+// ---------------------------------------------------------------------------
+// Subject: a slow data service
+// ---------------------------------------------------------------------------
+
+pub trait DataService {
+    fn fetch(&self, key: &str) -> String;
+}
+
+/// The real subject: every fetch is expensive.
+pub struct RemoteDataService {
+    fetch_count: Cell<u64>,
+}
+
+impl RemoteDataService {
+    pub fn new() -> Self {
+        RemoteDataService {
+            fetch_count: Cell::new(0),
+        }
+    }
+
+    pub fn fetch_count(&self) -> u64 {
+        self.fetch_count.get()
+    }
+}
+
+impl Default for RemoteDataService {
+    fn default() -> Self {
+        RemoteDataService::new()
+    }
+}
+
+impl DataService for RemoteDataService {
+    fn fetch(&self, key: &str) -> String {
+        self.fetch_count.set(self.fetch_count.get() + 1);
+        // Stand-in for a network round trip.
+        format!("payload for {}", key)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Subject: a web service
+// ---------------------------------------------------------------------------
+
+/// Errors surfaced by web-service proxies.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServiceError {
+    /// Transient backend failure; safe to retry.
+    Unavailable(String),
+    /// The resource does not exist; retrying cannot help.
+    NotFound(String),
+    /// The caller exceeded its request budget; try again after the delay.
+    RateLimited { retry_after: Duration },
+    /// The caller lacks the role the resource requires.
+    Forbidden(String),
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceError::Unavailable(reason) => write!(f, "service unavailable: {}", reason),
+            ServiceError::NotFound(path) => write!(f, "not found: {}", path),
+            ServiceError::RateLimited { retry_after } => {
+                write!(f, "rate limited, retry after {:?}", retry_after)
+            }
+            ServiceError::Forbidden(reason) => write!(f, "forbidden: {}", reason),
+        }
+    }
+}
+
+/// Request/response subject for the richer proxies; unlike `DataService`,
+/// calls can fail.
+pub trait WebService {
+    fn get(&self, path: &str) -> Result<String, ServiceError>;
+}
+
+// ---------------------------------------------------------------------------
+// Remote proxy over HTTP (std-only, behind the `net` feature)
+// ---------------------------------------------------------------------------
+
+/// A remote proxy: the local object stands in for a service on the other
+/// side of a socket and hides the wire protocol from its callers.
+#[cfg(feature = "net")]
+pub mod remote {
+    use super::{ServiceError, WebService};
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    /// Implements `WebService` by issuing an HTTP/1.1 GET per call; the
+    /// caller cannot tell it from an in-process implementation.
+    pub struct RemoteWebServiceProxy {
+        authority: String,
+    }
+
+    impl RemoteWebServiceProxy {
+        pub fn new(authority: &str) -> Self {
+            RemoteWebServiceProxy {
+                authority: authority.to_string(),
+            }
+        }
+    }
+
+    impl WebService for RemoteWebServiceProxy {
+        fn get(&self, path: &str) -> Result<String, ServiceError> {
+            let mut stream = TcpStream::connect(&self.authority)
+                .map_err(|e| ServiceError::Unavailable(e.to_string()))?;
+            write!(
+                stream,
+                "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+                path, self.authority
+            )
+            .map_err(|e| ServiceError::Unavailable(e.to_string()))?;
+            let mut response = String::new();
+            stream
+                .read_to_string(&mut response)
+                .map_err(|e| ServiceError::Unavailable(e.to_string()))?;
+            let status = response
+                .split_whitespace()
+                .nth(1)
+                .and_then(|s| s.parse::<u16>().ok())
+                .ok_or_else(|| ServiceError::Unavailable("malformed response".to_string()))?;
+            let body = response
+                .split_once("\r\n\r\n")
+                .map(|(_, body)| body.to_string())
+                .unwrap_or_default();
+            match status {
+                200 => Ok(body),
+                404 => Err(ServiceError::NotFound(path.to_string())),
+                other => Err(ServiceError::Unavailable(format!("status {}", other))),
+            }
+        }
+    }
+
+    /// Minimal single-threaded HTTP server for exercising the remote
+    /// proxy in-process. It serves the given routes until it receives a
+    /// GET for `/shutdown`.
+    pub fn spawn_test_server(routes: HashMap<String, String>) -> (String, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind loopback");
+        let authority = listener.local_addr().expect("local addr").to_string();
+        let handle = thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                if serve_one(stream, &routes) {
+                    break;
+                }
+            }
+        });
+        (authority, handle)
+    }
+
+    /// Handles one connection; returns true on the shutdown request.
+    fn serve_one(mut stream: TcpStream, routes: &HashMap<String, String>) -> bool {
+        let mut request_line = String::new();
+        let mut reader = BufReader::new(stream.try_clone().expect("clone stream"));
+        if reader.read_line(&mut request_line).is_err() {
+            return false;
+        }
+        // Drain the headers so the client never sees the connection close
+        // mid-write.
+        loop {
+            let mut header = String::new();
+            match reader.read_line(&mut header) {
+                Ok(_) if header.trim().is_empty() => break,
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+        let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+        let (status, body) = match routes.get(path) {
+            Some(body) => ("200 OK", body.as_str()),
+            None if path == "/shutdown" => ("200 OK", "bye"),
+            None => ("404 Not Found", ""),
+        };
+        let _ = write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        path == "/shutdown"
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Rate-limiting proxy
+// ---------------------------------------------------------------------------
+
+/// Continuously refilled token bucket with capacity `max_per_second`.
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u32) -> Self {
+        TokenBucket {
+            tokens: capacity as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Takes one token, or reports how long until one is available.
+    fn take(&mut self, rate: u32) -> Result<(), Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.last_refill = now;
+        let cap = rate as f64;
+        self.tokens = (self.tokens + elapsed * cap).min(cap);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / cap))
+        }
+    }
+}
+
+/// Enforces a per-user request budget in front of the service; calls over
+/// budget fail fast with `RateLimited` instead of reaching the backend.
+pub struct RateLimitingProxy<S: WebService> {
+    service: S,
+    max_per_second: u32,
+    buckets: RefCell<HashMap<String, TokenBucket>>,
+    allowed: Cell<u64>,
+    denied: Cell<u64>,
+}
+
+impl<S: WebService> RateLimitingProxy<S> {
+    pub fn new(service: S, max_per_second: u32) -> Self {
+        assert!(max_per_second > 0, "rate must be positive");
+        RateLimitingProxy {
+            service,
+            max_per_second,
+            buckets: RefCell::new(HashMap::new()),
+            allowed: Cell::new(0),
+            denied: Cell::new(0),
+        }
+    }
+
+    /// The rate limit applies per user; each gets its own bucket.
+    pub fn get_as(&self, user: &str, path: &str) -> Result<String, ServiceError> {
+        let mut buckets = self.buckets.borrow_mut();
+        let bucket = buckets
+            .entry(user.to_string())
+            .or_insert_with(|| TokenBucket::new(self.max_per_second));
+        match bucket.take(self.max_per_second) {
+            Ok(()) => {
+                self.allowed.set(self.allowed.get() + 1);
+                drop(buckets);
+                self.service.get(path)
+            }
+            Err(retry_after) => {
+                self.denied.set(self.denied.get() + 1);
+                Err(ServiceError::RateLimited { retry_after })
+            }
+        }
+    }
+
+    /// `(allowed, denied)` counts across all users.
+    pub fn counters(&self) -> (u64, u64) {
+        (self.allowed.get(), self.denied.get())
+    }
+}
+
+/// Calls without an explicit user share the anonymous bucket.
+impl<S: WebService> WebService for RateLimitingProxy<S> {
+    fn get(&self, path: &str) -> Result<String, ServiceError> {
+        self.get_as("anonymous", path)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Circuit breaker proxy
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls flow through; consecutive failures are counted.
+    Closed,
+    /// Calls fail fast until the cool-down elapses.
+    Open,
+    /// One probe call is allowed; its outcome decides the next state.
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive transient failures that trip the breaker.
+    pub failure_threshold: u32,
+    /// How long the breaker stays open before probing again.
+    pub cool_down: Duration,
+}
+
+/// Stops hammering a failing backend: after `failure_threshold` transient
+/// errors in a row the breaker opens and calls fail fast, then a single
+/// probe after the cool-down decides whether to close it again. Permanent
+/// errors like `NotFound` pass through without counting against the
+/// breaker.
+pub struct CircuitBreakerProxy<S: WebService> {
+    service: S,
+    config: CircuitBreakerConfig,
+    state: Cell<CircuitState>,
+    consecutive_failures: Cell<u32>,
+    opened_at: Cell<Option<Instant>>,
+    transitions: RefCell<Vec<(CircuitState, CircuitState)>>,
+    listeners: RefCell<Vec<Box<dyn Fn(CircuitState, CircuitState)>>>,
+}
+
+impl<S: WebService> CircuitBreakerProxy<S> {
+    pub fn new(service: S, config: CircuitBreakerConfig) -> Self {
+        assert!(config.failure_threshold > 0, "threshold must be positive");
+        CircuitBreakerProxy {
+            service,
+            config,
+            state: Cell::new(CircuitState::Closed),
+            consecutive_failures: Cell::new(0),
+            opened_at: Cell::new(None),
+            transitions: RefCell::new(Vec::new()),
+            listeners: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.state.get()
+    }
+
+    /// Runs `listener` on every state change, after the change is logged.
+    pub fn on_transition(&self, listener: impl Fn(CircuitState, CircuitState) + 'static) {
+        self.listeners.borrow_mut().push(Box::new(listener));
+    }
+
+    /// Every `(from, to)` state change, in order.
+    pub fn transitions(&self) -> Vec<(CircuitState, CircuitState)> {
+        self.transitions.borrow().clone()
+    }
+
+    fn transition(&self, to: CircuitState) {
+        let from = self.state.replace(to);
+        if from == to {
+            return;
+        }
+        self.transitions.borrow_mut().push((from, to));
+        for listener in self.listeners.borrow().iter() {
+            listener(from, to);
+        }
+    }
+
+    fn record_success(&self) {
+        self.consecutive_failures.set(0);
+        self.transition(CircuitState::Closed);
+    }
+
+    fn record_failure(&self) {
+        if self.state.get() == CircuitState::HalfOpen {
+            // The probe failed; back to waiting out the cool-down.
+            self.opened_at.set(Some(Instant::now()));
+            self.transition(CircuitState::Open);
+            return;
+        }
+        let failures = self.consecutive_failures.get() + 1;
+        self.consecutive_failures.set(failures);
+        if failures >= self.config.failure_threshold {
+            self.opened_at.set(Some(Instant::now()));
+            self.transition(CircuitState::Open);
+        }
+    }
+}
+
+impl<S: WebService> WebService for CircuitBreakerProxy<S> {
+    fn get(&self, path: &str) -> Result<String, ServiceError> {
+        if self.state.get() == CircuitState::Open {
+            let cooled = self
+                .opened_at
+                .get()
+                .is_some_and(|at| at.elapsed() >= self.config.cool_down);
+            if !cooled {
+                return Err(ServiceError::Unavailable("circuit open".to_string()));
+            }
+            self.transition(CircuitState::HalfOpen);
+        }
+        match self.service.get(path) {
+            Ok(body) => {
+                self.record_success();
+                Ok(body)
+            }
+            Err(error @ ServiceError::Unavailable(_)) => {
+                self.record_failure();
+                Err(error)
+            }
+            // Permanent errors are the caller's problem, not the backend's.
+            Err(other) => Err(other),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Retry proxy
+// ---------------------------------------------------------------------------
+
+/// Splits errors into those worth retrying and those that will fail the
+/// same way every time.
+pub trait ErrorClassification {
+    fn is_retryable(&self) -> bool;
+}
+
+impl ErrorClassification for ServiceError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            ServiceError::Unavailable(_) | ServiceError::RateLimited { .. } => true,
+            ServiceError::NotFound(_) | ServiceError::Forbidden(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryConfig {
+    /// Total tries including the first call.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    /// Ceiling for the exponential backoff before jitter.
+    pub max_delay: Duration,
+    /// Fraction of the delay randomized in both directions, `0.0..=1.0`;
+    /// keeps synchronized clients from retrying in lockstep.
+    pub jitter: f64,
+}
+
+/// Retries idempotent calls with exponential backoff and jitter. The wait
+/// function is injected so tests can capture the planned delays instead
+/// of actually sleeping.
+pub struct RetryProxy<S: WebService> {
+    service: S,
+    config: RetryConfig,
+    sleep: Box<dyn Fn(Duration)>,
+    /// xorshift state for the jitter; seedable for deterministic tests.
+    rng_state: Cell<u64>,
+    retries: Cell<u64>,
+}
+
+impl<S: WebService> RetryProxy<S> {
+    pub fn new(service: S, config: RetryConfig) -> Self {
+        RetryProxy::with_sleep(service, config, std::thread::sleep)
+    }
+
+    pub fn with_sleep(
+        service: S,
+        config: RetryConfig,
+        sleep: impl Fn(Duration) + 'static,
+    ) -> Self {
+        assert!(config.max_attempts > 0, "need at least one attempt");
+        assert!(
+            (0.0..=1.0).contains(&config.jitter),
+            "jitter is a fraction of the delay"
+        );
+        RetryProxy {
+            service,
+            config,
+            sleep: Box::new(sleep),
+            rng_state: Cell::new(0x9e37_79b9_7f4a_7c15),
+            retries: Cell::new(0),
+        }
+    }
+
+    pub fn seed_jitter(&self, seed: u64) {
+        self.rng_state.set(seed.max(1));
+    }
+
+    /// Retries performed across all calls (not counting first attempts).
+    pub fn retries(&self) -> u64 {
+        self.retries.get()
+    }
+
+    fn next_random(&self) -> f64 {
+        let mut x = self.rng_state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng_state.set(x);
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Backoff for the given zero-based retry: `base * 2^retry`, capped,
+    /// then spread by the jitter fraction.
+    fn delay_for(&self, retry: u32) -> Duration {
+        let exp = self
+            .config
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.config.max_delay);
+        let spread = 1.0 + self.config.jitter * (2.0 * self.next_random() - 1.0);
+        Duration::from_secs_f64(exp.as_secs_f64() * spread)
+    }
+}
+
+impl<S: WebService> WebService for RetryProxy<S> {
+    fn get(&self, path: &str) -> Result<String, ServiceError> {
+        let mut attempt = 0;
+        loop {
+            match self.service.get(path) {
+                Ok(body) => return Ok(body),
+                Err(error) => {
+                    attempt += 1;
+                    if !error.is_retryable() || attempt >= self.config.max_attempts {
+                        return Err(error);
+                    }
+                    self.retries.set(self.retries.get() + 1);
+                    (self.sleep)(self.delay_for(attempt - 1));
+                }
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Caching proxy
+// ---------------------------------------------------------------------------
+
+/// Cache effectiveness counters from `CachingProxy::stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Hits served while still fresh.
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Hits discarded because the entry had outlived its TTL (strict
+    /// mode blocks and re-fetches).
+    pub expired: u64,
+    /// Entries re-fetched ahead of expiry by refresh-ahead mode.
+    pub refreshes: u64,
+    /// Expired entries served anyway under stale-while-revalidate.
+    pub stale_hits: u64,
+}
+
+/// Per-key access record from `CachingProxy::stats_for_key`. Kept in a
+/// side table, so it survives the entry being evicted — the global
+/// counters in `CacheStats` must never be derived by summing per-entry
+/// state, or eviction would silently erase history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub first_access: SystemTime,
+    pub last_access: SystemTime,
+}
+
+/// What `fetch` does when it finds an expired entry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Block the caller and re-fetch before answering.
+    #[default]
+    Strict,
+    /// Serve the stale value immediately and queue the key for
+    /// revalidation; `revalidate_pending` plays the part of the
+    /// background refresher.
+    StaleWhileRevalidate,
+}
+
+struct CachedEntry {
+    value: String,
+    /// `None` means the entry never expires.
+    expires_at: Option<Instant>,
+    ttl: Option<Duration>,
+}
+
+/// Caches fetch results in a bounded `Cache` whose eviction policy comes
+/// from strategy.rs (LRU, LFU, FIFO, random) instead of a hardcoded
+/// oldest-entry rule. Entries can carry a TTL — a default with per-key
+/// overrides — and refresh-ahead mode re-fetches entries nearing expiry
+/// on access so hot keys rarely pay a cold miss.
+pub struct CachingProxy<S: DataService> {
+    service: S,
+    cache: RefCell<Cache<String, CachedEntry>>,
+    default_ttl: Option<Duration>,
+    ttl_overrides: HashMap<String, Duration>,
+    /// Fraction of the TTL left that triggers an early re-fetch.
+    refresh_ahead: Option<f64>,
+    policy: CachePolicy,
+    /// Keys served stale and awaiting revalidation.
+    pending_revalidation: RefCell<Vec<String>>,
+    hits: Cell<u64>,
+    misses: Cell<u64>,
+    expired: Cell<u64>,
+    refreshes: Cell<u64>,
+    stale_hits: Cell<u64>,
+    key_stats: RefCell<HashMap<String, KeyStats>>,
+}
+
+impl<S: DataService> CachingProxy<S> {
+    pub fn new(service: S, capacity: usize, eviction: Box<dyn EvictionStrategy<String>>) -> Self {
+        CachingProxy {
+            service,
+            cache: RefCell::new(Cache::new(capacity, eviction)),
+            default_ttl: None,
+            ttl_overrides: HashMap::new(),
+            refresh_ahead: None,
+            policy: CachePolicy::default(),
+            pending_revalidation: RefCell::new(Vec::new()),
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+            expired: Cell::new(0),
+            refreshes: Cell::new(0),
+            stale_hits: Cell::new(0),
+            key_stats: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: CachePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Entries expire this long after they were fetched, unless a per-key
+    /// override says otherwise.
+    pub fn with_default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = Some(ttl);
+        self
+    }
+
+    pub fn with_ttl_override(mut self, key: &str, ttl: Duration) -> Self {
+        self.ttl_overrides.insert(key.to_string(), ttl);
+        self
+    }
+
+    /// Re-fetch an entry on access once less than `fraction` of its TTL
+    /// remains, so a steadily-read key is renewed before it can expire.
+    pub fn with_refresh_ahead(mut self, fraction: f64) -> Self {
+        assert!((0.0..1.0).contains(&fraction), "fraction of the TTL");
+        self.refresh_ahead = Some(fraction);
+        self
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.get(),
+            misses: self.misses.get(),
+            evictions: self.cache.borrow().evictions(),
+            expired: self.expired.get(),
+            refreshes: self.refreshes.get(),
+            stale_hits: self.stale_hits.get(),
+        }
+    }
+
+    /// Access history for one key, if it was ever fetched.
+    pub fn stats_for_key(&self, key: &str) -> Option<KeyStats> {
+        self.key_stats.borrow().get(key).copied()
+    }
+
+    fn record_access(&self, key: &str, hit: bool) {
+        let now = SystemTime::now();
+        let mut key_stats = self.key_stats.borrow_mut();
+        let stats = key_stats.entry(key.to_string()).or_insert(KeyStats {
+            hits: 0,
+            misses: 0,
+            first_access: now,
+            last_access: now,
+        });
+        stats.last_access = now;
+        if hit {
+            stats.hits += 1;
+        } else {
+            stats.misses += 1;
+        }
+    }
+
+    /// Re-fetches every key served stale since the last call; the demo
+    /// stand-in for the background refresh task. Returns how many.
+    pub fn revalidate_pending(&self) -> usize {
+        let mut keys: Vec<String> = self.pending_revalidation.borrow_mut().drain(..).collect();
+        keys.sort();
+        keys.dedup();
+        for key in &keys {
+            let value = self.service.fetch(key);
+            let entry = self.entry_for(key, value);
+            self.cache.borrow_mut().insert(key.clone(), entry);
+        }
+        keys.len()
+    }
+
+    /// Writes the cache to disk, one tab-separated line per entry, with
+    /// expiry recorded as wall-clock time so it survives the restart.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let now = Instant::now();
+        let wall = SystemTime::now();
+        let mut out = String::new();
+        for (key, entry) in self.cache.borrow().iter() {
+            let expires_unix_ms = match entry.expires_at {
+                Some(expires_at) if expires_at <= now => continue, // already stale
+                Some(expires_at) => {
+                    let at = wall + (expires_at - now);
+                    at.duration_since(UNIX_EPOCH)
+                        .map_err(|e| e.to_string())?
+                        .as_millis()
+                        .to_string()
+                }
+                None => "-".to_string(),
+            };
+            out.push_str(&format!(
+                "{}\t{}\t{}\n",
+                key,
+                expires_unix_ms,
+                entry.value.replace('\\', "\\\\").replace('\n', "\\n").replace('\t', "\\t")
+            ));
+        }
+        std::fs::write(path, out).map_err(|e| format!("write {}: {}", path.display(), e))
+    }
+
+    /// Re-populates the cache from a `save` file. Entries whose recorded
+    /// expiry has already passed are skipped. Returns how many were
+    /// loaded.
+    pub fn load(&self, path: &Path) -> Result<usize, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+        let now = Instant::now();
+        let wall = SystemTime::now();
+        let mut loaded = 0;
+        for (number, line) in text.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            let mut fields = line.splitn(3, '\t');
+            let (Some(key), Some(raw_expiry), Some(raw_value)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(format!("{}:{}: expected 3 fields", path.display(), number + 1));
+            };
+            let expires_at = match raw_expiry {
+                "-" => None,
+                raw => {
+                    let ms: u64 = raw
+                        .parse()
+                        .map_err(|e| format!("{}:{}: bad expiry: {}", path.display(), number + 1, e))?;
+                    let at = UNIX_EPOCH + Duration::from_millis(ms);
+                    match at.duration_since(wall) {
+                        Ok(remaining) => Some(now + remaining),
+                        Err(_) => continue, // expired while on disk
+                    }
+                }
+            };
+            let value = raw_value
+                .replace("\\t", "\t")
+                .replace("\\n", "\n")
+                .replace("\\\\", "\\");
+            self.cache.borrow_mut().insert(
+                key.to_string(),
+                CachedEntry {
+                    value,
+                    expires_at,
+                    ttl: self.ttl_for(key),
+                },
+            );
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
+    /// Pre-populates the listed keys so the first real readers hit a warm
+    /// cache. Keys already cached are left alone; returns how many were
+    /// actually fetched.
+    pub fn warm_up(&self, keys: &[&str]) -> usize {
+        let mut fetched = 0;
+        for key in keys {
+            if !self.cache.borrow().contains(&key.to_string()) {
+                let value = self.service.fetch(key);
+                let entry = self.entry_for(key, value);
+                self.cache.borrow_mut().insert(key.to_string(), entry);
+                fetched += 1;
+            }
+        }
+        fetched
+    }
+
+    fn ttl_for(&self, key: &str) -> Option<Duration> {
+        self.ttl_overrides.get(key).copied().or(self.default_ttl)
+    }
+
+    fn entry_for(&self, key: &str, value: String) -> CachedEntry {
+        let ttl = self.ttl_for(key);
+        CachedEntry {
+            value,
+            expires_at: ttl.map(|ttl| Instant::now() + ttl),
+            ttl,
+        }
+    }
+}
+
+impl<S: DataService> DataService for CachingProxy<S> {
+    fn fetch(&self, key: &str) -> String {
+        let now = Instant::now();
+        let mut cache = self.cache.borrow_mut();
+        if let Some(entry) = cache.get(&key.to_string()) {
+            match entry.expires_at {
+                Some(expires_at)
+                    if now >= expires_at && self.policy == CachePolicy::StaleWhileRevalidate =>
+                {
+                    // Answer from the stale entry now; freshness is the
+                    // revalidator's problem.
+                    self.stale_hits.set(self.stale_hits.get() + 1);
+                    self.record_access(key, true);
+                    self.pending_revalidation
+                        .borrow_mut()
+                        .push(key.to_string());
+                    return entry.value.clone();
+                }
+                Some(expires_at) if now >= expires_at => {
+                    // Stale in strict mode: fall through to a blocking
+                    // re-fetch below.
+                    self.expired.set(self.expired.get() + 1);
+                    self.record_access(key, false);
+                }
+                expires_at => {
+                    let near_expiry = match (self.refresh_ahead, expires_at, entry.ttl) {
+                        (Some(fraction), Some(expires_at), Some(ttl)) => {
+                            (expires_at - now).as_secs_f64() < ttl.as_secs_f64() * fraction
+                        }
+                        _ => false,
+                    };
+                    if !near_expiry {
+                        self.hits.set(self.hits.get() + 1);
+                        self.record_access(key, true);
+                        return entry.value.clone();
+                    }
+                    self.refreshes.set(self.refreshes.get() + 1);
+                    self.record_access(key, true);
+                    let value = self.service.fetch(key);
+                    cache.insert(key.to_string(), self.entry_for(key, value.clone()));
+                    return value;
+                }
+            }
+        } else {
+            self.misses.set(self.misses.get() + 1);
+            self.record_access(key, false);
+        }
+        let value = self.service.fetch(key);
+        cache.insert(key.to_string(), self.entry_for(key, value.clone()));
+        value
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Flat JSON helpers for the audit log
+// ---------------------------------------------------------------------------
+
+fn json_escape(raw: &str) -> String {
+    raw.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Parses a single-level JSON object with string, number, or boolean
+/// values; all values come back as strings.
+fn parse_flat_json(line: &str) -> Result<HashMap<String, String>, String> {
+    let body = line
+        .trim()
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+        .ok_or("expected a JSON object")?;
+    let mut fields = HashMap::new();
+    let mut chars = body.chars().peekable();
+    loop {
+        while matches!(chars.peek(), Some(c) if c.is_whitespace() || *c == ',') {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            return Ok(fields);
+        }
+        let key = parse_json_string(&mut chars)?;
+        match chars.next() {
+            Some(':') => {}
+            other => return Err(format!("expected ':' after key, got {:?}", other)),
+        }
+        let value = match chars.peek() {
+            Some('"') => parse_json_string(&mut chars)?,
+            Some(_) => {
+                let mut raw = String::new();
+                while matches!(chars.peek(), Some(c) if *c != ',') {
+                    raw.push(chars.next().expect("peeked"));
+                }
+                raw.trim().to_string()
+            }
+            None => return Err("missing value".to_string()),
+        };
+        fields.insert(key, value);
+    }
+}
+
+fn parse_json_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    match chars.next() {
+        Some('"') => {}
+        other => return Err(format!("expected '\"', got {:?}", other)),
+    }
+    let mut out = String::new();
+    loop {
+        match chars.next() {
+            None => return Err("unterminated string".to_string()),
+            Some('"') => return Ok(out),
+            Some('\\') => match chars.next() {
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                other => return Err(format!("bad escape {:?}", other)),
+            },
+            Some(other) => out.push(other),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Protection proxy (role-based access control)
+// ---------------------------------------------------------------------------
+
+/// Roles form a strict hierarchy: each role inherits everything the roles
+/// below it may do, so the derived ordering doubles as the inheritance
+/// check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Guest,
+    User,
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Role::Guest => "guest",
+            Role::User => "user",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+        }
+    }
+
+    fn parse(raw: &str) -> Result<Role, String> {
+        match raw {
+            "guest" => Ok(Role::Guest),
+            "user" => Ok(Role::User),
+            "moderator" => Ok(Role::Moderator),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("unknown role '{}'", other)),
+        }
+    }
+}
+
+/// Paths matching `pattern` require at least `required`. A trailing `/*`
+/// matches the whole subtree; anything else must match exactly.
+#[derive(Debug, Clone)]
+pub struct AccessRule {
+    pub pattern: String,
+    pub required: Role,
+}
+
+impl AccessRule {
+    fn matches(&self, path: &str) -> bool {
+        match self.pattern.strip_suffix("/*") {
+            Some(prefix) => path.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/')),
+            None => self.pattern == path,
+        }
+    }
+}
+
+/// Resource rules plus the fallback role for unmatched paths; built as a
+/// plain config struct so deployments can assemble it from a file.
+#[derive(Debug, Clone)]
+pub struct AccessPolicy {
+    pub rules: Vec<AccessRule>,
+    pub default_required: Role,
+}
+
+impl AccessPolicy {
+    /// The first matching rule wins; order the specific ones first.
+    fn required_for(&self, path: &str) -> (Role, &str) {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(path))
+            .map(|rule| (rule.required, rule.pattern.as_str()))
+            .unwrap_or((self.default_required, "<default>"))
+    }
+}
+
+/// The full answer to "why was this allowed or denied".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessDecision {
+    pub allowed: bool,
+    pub held: Role,
+    pub required: Role,
+    /// The rule pattern that applied, or `<default>`.
+    pub rule: String,
+}
+
+impl fmt::Display for AccessDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.allowed {
+            write!(
+                f,
+                "allowed: {:?} satisfies {:?} (rule {})",
+                self.held, self.required, self.rule
+            )
+        } else {
+            write!(
+                f,
+                "denied: rule {} requires {:?}, caller holds {:?}",
+                self.rule, self.required, self.held
+            )
+        }
+    }
+}
+
+/// One line of the audit trail kept by the protection proxy.
+#[derive(Debug, Clone)]
+pub struct AccessLogEntry {
+    pub at: SystemTime,
+    pub user: String,
+    pub path: String,
+    pub decision: AccessDecision,
+}
+
+impl AccessLogEntry {
+    fn to_json(&self) -> String {
+        let at_unix_ms = self
+            .at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_millis();
+        format!(
+            "{{\"at_unix_ms\":{},\"user\":\"{}\",\"path\":\"{}\",\"allowed\":{},\"held\":\"{}\",\"required\":\"{}\",\"rule\":\"{}\"}}",
+            at_unix_ms,
+            json_escape(&self.user),
+            json_escape(&self.path),
+            self.decision.allowed,
+            self.decision.held.as_str(),
+            self.decision.required.as_str(),
+            json_escape(&self.decision.rule),
+        )
+    }
+
+    fn from_json(line: &str) -> Result<AccessLogEntry, String> {
+        let fields = parse_flat_json(line)?;
+        let take = |key: &str| -> Result<&String, String> {
+            fields.get(key).ok_or_else(|| format!("missing field '{}'", key))
+        };
+        let at_unix_ms: u64 = take("at_unix_ms")?
+            .parse()
+            .map_err(|e| format!("bad at_unix_ms: {}", e))?;
+        Ok(AccessLogEntry {
+            at: UNIX_EPOCH + Duration::from_millis(at_unix_ms),
+            user: take("user")?.clone(),
+            path: take("path")?.clone(),
+            decision: AccessDecision {
+                allowed: take("allowed")?
+                    .parse()
+                    .map_err(|e| format!("bad allowed: {}", e))?,
+                held: Role::parse(take("held")?)?,
+                required: Role::parse(take("required")?)?,
+                rule: take("rule")?.clone(),
+            },
+        })
+    }
+}
+
+/// Checks the caller's role against the policy before letting a request
+/// through, and records every decision.
+pub struct ProtectionProxy<S: WebService> {
+    service: S,
+    policy: AccessPolicy,
+    roles: HashMap<String, Role>,
+    log: RefCell<Vec<AccessLogEntry>>,
+    /// Oldest entries rotate out past this many, if set.
+    log_capacity: Option<usize>,
+    rotated: Cell<u64>,
+}
+
+impl<S: WebService> ProtectionProxy<S> {
+    pub fn new(service: S, policy: AccessPolicy) -> Self {
+        ProtectionProxy {
+            service,
+            policy,
+            roles: HashMap::new(),
+            log: RefCell::new(Vec::new()),
+            log_capacity: None,
+            rotated: Cell::new(0),
+        }
+    }
+
+    /// Caps the in-memory log; the oldest entries rotate out first.
+    pub fn with_log_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        self.log_capacity = Some(capacity);
+        self
+    }
+
+    pub fn assign_role(&mut self, user: &str, role: Role) {
+        self.roles.insert(user.to_string(), role);
+    }
+
+    /// Evaluates the policy without performing the request, so callers
+    /// can ask "why was this denied" after the fact.
+    pub fn explain(&self, user: &str, path: &str) -> AccessDecision {
+        let held = self.roles.get(user).copied().unwrap_or(Role::Guest);
+        let (required, rule) = self.policy.required_for(path);
+        AccessDecision {
+            allowed: held >= required,
+            held,
+            required,
+            rule: rule.to_string(),
+        }
+    }
+
+    pub fn get_as(&self, user: &str, path: &str) -> Result<String, ServiceError> {
+        let decision = self.explain(user, path);
+        self.push_log(AccessLogEntry {
+            at: SystemTime::now(),
+            user: user.to_string(),
+            path: path.to_string(),
+            decision: decision.clone(),
+        });
+        if !decision.allowed {
+            return Err(ServiceError::Forbidden(decision.to_string()));
+        }
+        self.service.get(path)
+    }
+
+    pub fn log_len(&self) -> usize {
+        self.log.borrow().len()
+    }
+
+    /// Entries rotated out of the capped log.
+    pub fn rotated(&self) -> u64 {
+        self.rotated.get()
+    }
+
+    pub fn log_for_user(&self, user: &str) -> Vec<AccessLogEntry> {
+        self.log
+            .borrow()
+            .iter()
+            .filter(|entry| entry.user == user)
+            .cloned()
+            .collect()
+    }
+
+    /// Entries recorded in `from..=to`.
+    pub fn log_between(&self, from: SystemTime, to: SystemTime) -> Vec<AccessLogEntry> {
+        self.log
+            .borrow()
+            .iter()
+            .filter(|entry| entry.at >= from && entry.at <= to)
+            .cloned()
+            .collect()
+    }
+
+    pub fn denied_entries(&self) -> Vec<AccessLogEntry> {
+        self.log
+            .borrow()
+            .iter()
+            .filter(|entry| !entry.decision.allowed)
+            .cloned()
+            .collect()
+    }
+
+    /// Writes the log as JSON Lines for offline audits.
+    pub fn export_log(&self, path: &Path) -> Result<(), String> {
+        let mut out = String::new();
+        for entry in self.log.borrow().iter() {
+            out.push_str(&entry.to_json());
+            out.push('\n');
+        }
+        std::fs::write(path, out).map_err(|e| format!("write {}: {}", path.display(), e))
+    }
+
+    /// Appends entries from a previous export; returns how many.
+    pub fn import_log(&self, path: &Path) -> Result<usize, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+        let mut imported = 0;
+        for (number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = AccessLogEntry::from_json(line)
+                .map_err(|e| format!("{}:{}: {}", path.display(), number + 1, e))?;
+            self.push_log(entry);
+            imported += 1;
+        }
+        Ok(imported)
+    }
+
+    fn push_log(&self, entry: AccessLogEntry) {
+        let mut log = self.log.borrow_mut();
+        log.push(entry);
+        if let Some(capacity) = self.log_capacity {
+            while log.len() > capacity {
+                log.remove(0);
+                self.rotated.set(self.rotated.get() + 1);
+            }
+        }
+    }
+}
+
+/// Calls without an explicit user are treated as an anonymous guest.
+impl<S: WebService> WebService for ProtectionProxy<S> {
+    fn get(&self, path: &str) -> Result<String, ServiceError> {
+        self.get_as("anonymous", path)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Virtual proxy: lazy image loading
+// ---------------------------------------------------------------------------
+
+/// A scaled-down copy of an image's pixel data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    /// RGB, row-major.
+    pub pixels: Vec<u8>,
+}
+
+pub trait Image {
+    fn display(&self) -> String;
+    fn dimensions(&self) -> (u32, u32);
+    fn size_bytes(&self) -> u64;
+    /// A thumbnail whose longer side is at most `max_dim` pixels.
+    fn get_thumbnail(&self, max_dim: u32) -> Result<Thumbnail, String>;
+}
+
+/// The real subject: pixel data read from a binary PPM (`P6`) file. In a
+/// bigger build the parsing would sit behind the `image` crate; the demo
+/// format keeps the example dependency-free.
+pub struct RealImage {
+    path: PathBuf,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl RealImage {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes =
+            std::fs::read(path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+        let (width, height, pixel_offset) = RealImage::parse_header(&bytes)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let expected = (width * height * 3) as usize;
+        let pixels = bytes
+            .get(pixel_offset..pixel_offset + expected)
+            .ok_or_else(|| format!("{}: truncated pixel data", path.display()))?
+            .to_vec();
+        Ok(RealImage {
+            path: path.to_path_buf(),
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Reads just the header, so callers can learn the dimensions without
+    /// paying for the pixel data.
+    pub fn peek_dimensions(path: &Path) -> Result<(u32, u32), String> {
+        let mut header = [0u8; 64];
+        let mut file =
+            File::open(path).map_err(|e| format!("open {}: {}", path.display(), e))?;
+        let read = file
+            .read(&mut header)
+            .map_err(|e| format!("read {}: {}", path.display(), e))?;
+        let (width, height, _) = RealImage::parse_header(&header[..read])
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok((width, height))
+    }
+
+    /// Returns `(width, height, offset of the pixel data)`.
+    fn parse_header(bytes: &[u8]) -> Result<(u32, u32, usize), String> {
+        if !bytes.starts_with(b"P6") {
+            return Err("not a binary PPM (missing P6 magic)".to_string());
+        }
+        // Magic, width, height, maxval, each followed by one whitespace.
+        let mut fields = Vec::new();
+        let mut offset = 2;
+        while fields.len() < 3 {
+            while bytes.get(offset).is_some_and(|b| b.is_ascii_whitespace()) {
+                offset += 1;
+            }
+            let start = offset;
+            while bytes.get(offset).is_some_and(|b| !b.is_ascii_whitespace()) {
+                offset += 1;
+            }
+            if start == offset {
+                return Err("truncated header".to_string());
+            }
+            let field = std::str::from_utf8(&bytes[start..offset])
+                .map_err(|_| "non-ASCII header".to_string())?;
+            fields.push(
+                field
+                    .parse::<u32>()
+                    .map_err(|_| format!("bad header field '{}'", field))?,
+            );
+        }
+        Ok((fields[0], fields[1], offset + 1))
+    }
+}
+
+impl Image for RealImage {
+    fn display(&self) -> String {
+        format!(
+            "{} ({}x{}, {} bytes of pixels)",
+            self.path.display(),
+            self.width,
+            self.height,
+            self.pixels.len()
+        )
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn size_bytes(&self) -> u64 {
+        self.pixels.len() as u64
+    }
+
+    fn get_thumbnail(&self, max_dim: u32) -> Result<Thumbnail, String> {
+        if max_dim == 0 {
+            return Err("max_dim must be positive".to_string());
+        }
+        let scale = (self.width.max(self.height) as f64 / max_dim as f64).max(1.0);
+        let width = ((self.width as f64 / scale) as u32).max(1);
+        let height = ((self.height as f64 / scale) as u32).max(1);
+        // Nearest-neighbor is plenty for a stand-in thumbnailer.
+        let mut pixels = Vec::with_capacity((width * height * 3) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let src_x = (x as f64 * scale) as u32;
+                let src_y = (y as f64 * scale) as u32;
+                let at = ((src_y * self.width + src_x) * 3) as usize;
+                pixels.extend_from_slice(&self.pixels[at..at + 3]);
+            }
+        }
+        Ok(Thumbnail {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+/// Virtual proxy: stands in for a `RealImage` and defers the expensive
+/// pixel load until something actually needs it. Dimension queries are
+/// answered from the file header alone and generated thumbnails are
+/// cached per size.
+pub struct LazyImageProxy {
+    path: PathBuf,
+    real: RefCell<Option<RealImage>>,
+    thumbnails: RefCell<HashMap<u32, Thumbnail>>,
+    thumbnails_generated: Cell<u64>,
+}
+
+impl LazyImageProxy {
+    pub fn new(path: &Path) -> Self {
+        LazyImageProxy {
+            path: path.to_path_buf(),
+            real: RefCell::new(None),
+            thumbnails: RefCell::new(HashMap::new()),
+            thumbnails_generated: Cell::new(0),
+        }
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.real.borrow().is_some()
+    }
+
+    pub fn thumbnails_generated(&self) -> u64 {
+        self.thumbnails_generated.get()
+    }
+
+    fn with_real<T>(&self, use_real: impl FnOnce(&RealImage) -> T) -> Result<T, String> {
+        let mut real = self.real.borrow_mut();
+        if real.is_none() {
+            *real = Some(RealImage::load(&self.path)?);
+        }
+        Ok(use_real(real.as_ref().expect("just loaded")))
+    }
+}
+
+impl Image for LazyImageProxy {
+    fn display(&self) -> String {
+        self.with_real(|image| image.display())
+            .unwrap_or_else(|error| error)
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        if let Some(image) = self.real.borrow().as_ref() {
+            return image.dimensions();
+        }
+        RealImage::peek_dimensions(&self.path).unwrap_or((0, 0))
+    }
+
+    fn size_bytes(&self) -> u64 {
+        if let Some(image) = self.real.borrow().as_ref() {
+            return image.size_bytes();
+        }
+        std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0)
+    }
+
+    fn get_thumbnail(&self, max_dim: u32) -> Result<Thumbnail, String> {
+        if let Some(thumbnail) = self.thumbnails.borrow().get(&max_dim) {
+            return Ok(thumbnail.clone());
+        }
+        let thumbnail = self.with_real(|image| image.get_thumbnail(max_dim))??;
+        self.thumbnails_generated
+            .set(self.thumbnails_generated.get() + 1);
+        self.thumbnails
+            .borrow_mut()
+            .insert(max_dim, thumbnail.clone());
+        Ok(thumbnail)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Copy-on-write proxy
+// ---------------------------------------------------------------------------
+
+/// The expensive-to-copy subject shared by the copy-on-write proxies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
+/// Copy-on-write proxy: cloning the proxy shares one `Document` between
+/// all readers, and the underlying data is duplicated only when a holder
+/// first mutates through its proxy. Until then every copy costs a
+/// reference-count bump instead of the whole document.
+#[derive(Clone)]
+pub struct CowDocumentProxy {
+    inner: Rc<Document>,
+}
+
+impl CowDocumentProxy {
+    pub fn new(document: Document) -> Self {
+        CowDocumentProxy {
+            inner: Rc::new(document),
+        }
+    }
+
+    /// Whether this proxy still shares its document with others.
+    pub fn is_shared(&self) -> bool {
+        Rc::strong_count(&self.inner) > 1
+    }
+
+    pub fn reference_count(&self) -> usize {
+        Rc::strong_count(&self.inner)
+    }
+
+    /// True when both proxies are backed by the same allocation.
+    pub fn shares_with(&self, other: &CowDocumentProxy) -> bool {
+        Rc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    // Readers never trigger a copy.
+
+    pub fn title(&self) -> &str {
+        &self.inner.title
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.inner.lines.len()
+    }
+
+    pub fn line(&self, index: usize) -> Option<&str> {
+        self.inner.lines.get(index).map(String::as_str)
+    }
+
+    // Writers split off a private copy on first use.
+
+    pub fn set_title(&mut self, title: &str) {
+        Rc::make_mut(&mut self.inner).title = title.to_string();
+    }
+
+    pub fn append_line(&mut self, line: &str) {
+        Rc::make_mut(&mut self.inner).lines.push(line.to_string());
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Smart proxy: file system
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsError {
+    NotFound(String),
+    /// The path would escape the backend's sandbox root.
+    OutsideRoot(String),
+    /// The operation ran into a quota or path policy; the payload says
+    /// exactly which one and by how much.
+    Policy(PolicyViolation),
+    Io(String),
+}
+
+impl fmt::Display for FsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FsError::NotFound(path) => write!(f, "no such file: {}", path),
+            FsError::OutsideRoot(path) => write!(f, "path escapes the root: {}", path),
+            FsError::Policy(violation) => write!(f, "policy violation: {}", violation),
+            FsError::Io(message) => write!(f, "io error: {}", message),
+        }
+    }
+}
+
+/// File backend behind the smart proxies. Paths are absolute-style keys
+/// like `/logs/app.txt` regardless of the backend.
+pub trait FileSystem {
+    fn read(&self, path: &str) -> Result<String, FsError>;
+    fn write(&mut self, path: &str, contents: &str) -> Result<(), FsError>;
+    fn delete(&mut self, path: &str) -> Result<(), FsError>;
+    fn exists(&self, path: &str) -> bool;
+}
+
+/// In-memory backend for tests and demos.
+#[derive(Default)]
+pub struct MemFileSystem {
+    files: HashMap<String, String>,
+}
+
+impl MemFileSystem {
+    pub fn new() -> Self {
+        MemFileSystem::default()
+    }
+}
+
+impl FileSystem for MemFileSystem {
+    fn read(&self, path: &str) -> Result<String, FsError> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| FsError::NotFound(path.to_string()))
+    }
+
+    fn write(&mut self, path: &str, contents: &str) -> Result<(), FsError> {
+        self.files.insert(path.to_string(), contents.to_string());
+        Ok(())
+    }
+
+    fn delete(&mut self, path: &str) -> Result<(), FsError> {
+        self.files
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| FsError::NotFound(path.to_string()))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.files.contains_key(path)
+    }
+}
+
+/// Real backend over `std::fs`, sandboxed: every path is resolved under
+/// `root` and parent-directory components are rejected outright.
+pub struct StdFileSystem {
+    root: PathBuf,
+}
+
+impl StdFileSystem {
+    pub fn new(root: &Path) -> Self {
+        StdFileSystem {
+            root: root.to_path_buf(),
+        }
+    }
+
+    fn resolve(&self, path: &str) -> Result<PathBuf, FsError> {
+        let relative = path.trim_start_matches('/');
+        let candidate = Path::new(relative);
+        if candidate
+            .components()
+            .any(|c| !matches!(c, std::path::Component::Normal(_)))
+        {
+            return Err(FsError::OutsideRoot(path.to_string()));
+        }
+        Ok(self.root.join(candidate))
+    }
+}
+
+impl FileSystem for StdFileSystem {
+    fn read(&self, path: &str) -> Result<String, FsError> {
+        let resolved = self.resolve(path)?;
+        match std::fs::read_to_string(&resolved) {
+            Ok(contents) => Ok(contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(FsError::NotFound(path.to_string()))
+            }
+            Err(e) => Err(FsError::Io(e.to_string())),
+        }
+    }
+
+    fn write(&mut self, path: &str, contents: &str) -> Result<(), FsError> {
+        let resolved = self.resolve(path)?;
+        if let Some(parent) = resolved.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| FsError::Io(e.to_string()))?;
+        }
+        std::fs::write(&resolved, contents).map_err(|e| FsError::Io(e.to_string()))
+    }
+
+    fn delete(&mut self, path: &str) -> Result<(), FsError> {
+        let resolved = self.resolve(path)?;
+        match std::fs::remove_file(&resolved) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(FsError::NotFound(path.to_string()))
+            }
+            Err(e) => Err(FsError::Io(e.to_string())),
+        }
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.resolve(path).map(|p| p.exists()).unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyViolation {
+    ByteQuotaExceeded {
+        directory: String,
+        limit: u64,
+        attempted: u64,
+    },
+    FileQuotaExceeded {
+        directory: String,
+        limit: usize,
+    },
+    WriteDenied {
+        path: String,
+        rule: String,
+    },
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyViolation::ByteQuotaExceeded {
+                directory,
+                limit,
+                attempted,
+            } => write!(
+                f,
+                "{} would hold {} bytes, quota is {}",
+                directory, attempted, limit
+            ),
+            PolicyViolation::FileQuotaExceeded { directory, limit } => {
+                write!(f, "{} is capped at {} files", directory, limit)
+            }
+            PolicyViolation::WriteDenied { path, rule } => {
+                write!(f, "writing {} is denied by rule '{}'", path, rule)
+            }
+        }
+    }
+}
+
+/// Per-directory limits enforced by `QuotaFileSystemProxy`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DirQuota {
+    pub max_bytes: Option<u64>,
+    pub max_files: Option<usize>,
+}
+
+/// Enforces byte and file-count quotas per directory subtree plus write
+/// path policies. Usage is tracked from the traffic this proxy sees, so
+/// it must be the only writer to the backend.
+pub struct QuotaFileSystemProxy<F: FileSystem> {
+    inner: F,
+    quotas: Vec<(String, DirQuota)>,
+    /// When non-empty, writes must land under one of these prefixes.
+    allowed_write_prefixes: Vec<String>,
+    denied_write_prefixes: Vec<String>,
+    file_sizes: HashMap<String, u64>,
+}
+
+fn in_directory(path: &str, directory: &str) -> bool {
+    path.strip_prefix(directory)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+impl<F: FileSystem> QuotaFileSystemProxy<F> {
+    pub fn new(inner: F) -> Self {
+        QuotaFileSystemProxy {
+            inner,
+            quotas: Vec::new(),
+            allowed_write_prefixes: Vec::new(),
+            denied_write_prefixes: Vec::new(),
+            file_sizes: HashMap::new(),
+        }
+    }
+
+    pub fn with_quota(mut self, directory: &str, quota: DirQuota) -> Self {
+        self.quotas.push((directory.to_string(), quota));
+        self
+    }
+
+    /// Restricts writes to the given subtree (may be called repeatedly to
+    /// allow several).
+    pub fn allow_writes_under(mut self, directory: &str) -> Self {
+        self.allowed_write_prefixes.push(directory.to_string());
+        self
+    }
+
+    pub fn deny_writes_under(mut self, directory: &str) -> Self {
+        self.denied_write_prefixes.push(directory.to_string());
+        self
+    }
+
+    /// `(bytes, files)` currently accounted to a directory.
+    pub fn usage(&self, directory: &str) -> (u64, usize) {
+        let mut bytes = 0;
+        let mut files = 0;
+        for (path, size) in &self.file_sizes {
+            if in_directory(path, directory) {
+                bytes += size;
+                files += 1;
+            }
+        }
+        (bytes, files)
+    }
+
+    fn check_write(&self, path: &str, new_size: u64) -> Result<(), PolicyViolation> {
+        if let Some(rule) = self
+            .denied_write_prefixes
+            .iter()
+            .find(|prefix| in_directory(path, prefix))
+        {
+            return Err(PolicyViolation::WriteDenied {
+                path: path.to_string(),
+                rule: format!("no writes under {}", rule),
+            });
+        }
+        if !self.allowed_write_prefixes.is_empty()
+            && !self
+                .allowed_write_prefixes
+                .iter()
+                .any(|prefix| in_directory(path, prefix))
+        {
+            return Err(PolicyViolation::WriteDenied {
+                path: path.to_string(),
+                rule: format!("writes restricted to {}", self.allowed_write_prefixes.join(", ")),
+            });
+        }
+        let existing = self.file_sizes.get(path).copied();
+        for (directory, quota) in &self.quotas {
+            if !in_directory(path, directory) {
+                continue;
+            }
+            let (bytes, files) = self.usage(directory);
+            let attempted = bytes - existing.unwrap_or(0) + new_size;
+            if let Some(limit) = quota.max_bytes {
+                if attempted > limit {
+                    return Err(PolicyViolation::ByteQuotaExceeded {
+                        directory: directory.clone(),
+                        limit,
+                        attempted,
+                    });
+                }
+            }
+            if let Some(limit) = quota.max_files {
+                if existing.is_none() && files + 1 > limit {
+                    return Err(PolicyViolation::FileQuotaExceeded {
+                        directory: directory.clone(),
+                        limit,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: FileSystem> FileSystem for QuotaFileSystemProxy<F> {
+    fn read(&self, path: &str) -> Result<String, FsError> {
+        self.inner.read(path)
+    }
+
+    fn write(&mut self, path: &str, contents: &str) -> Result<(), FsError> {
+        let new_size = contents.len() as u64;
+        self.check_write(path, new_size).map_err(FsError::Policy)?;
+        self.inner.write(path, contents)?;
+        self.file_sizes.insert(path.to_string(), new_size);
+        Ok(())
+    }
+
+    fn delete(&mut self, path: &str) -> Result<(), FsError> {
+        self.inner.delete(path)?;
+        self.file_sizes.remove(path);
+        Ok(())
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.inner.exists(path)
+    }
+}
+
+/// Pending writes held back by the buffered mode.
+struct WriteBuffer {
+    /// Entries older than this are flushed by the next operation.
+    window: Duration,
+    pending: Vec<(String, String, Instant)>,
+    suppressed: u64,
+}
+
+/// Smart-reference proxy: works over any `FileSystem` and keeps per-path
+/// access counts alongside the operation totals. An optional buffered
+/// mode coalesces repeated writes to the same path within a window and
+/// only touches the backend on `flush` or when an entry outlives the
+/// window.
+pub struct SmartFileSystemProxy<F: FileSystem> {
+    inner: F,
+    reads: Cell<u64>,
+    writes: Cell<u64>,
+    deletes: Cell<u64>,
+    access_counts: RefCell<HashMap<String, u64>>,
+    buffer: Option<WriteBuffer>,
+}
+
+impl<F: FileSystem> SmartFileSystemProxy<F> {
+    pub fn new(inner: F) -> Self {
+        SmartFileSystemProxy {
+            inner,
+            reads: Cell::new(0),
+            writes: Cell::new(0),
+            deletes: Cell::new(0),
+            access_counts: RefCell::new(HashMap::new()),
+            buffer: None,
+        }
+    }
+
+    /// Coalesce repeated writes to the same path; nothing reaches the
+    /// backend until `flush`, the window elapsing, or a delete.
+    pub fn enable_write_buffering(&mut self, window: Duration) {
+        self.buffer = Some(WriteBuffer {
+            window,
+            pending: Vec::new(),
+            suppressed: 0,
+        });
+    }
+
+    /// Writes absorbed by coalescing (they never reached the backend).
+    pub fn suppressed_writes(&self) -> u64 {
+        self.buffer.as_ref().map_or(0, |b| b.suppressed)
+    }
+
+    pub fn pending_writes(&self) -> usize {
+        self.buffer.as_ref().map_or(0, |b| b.pending.len())
+    }
+
+    /// Pushes every pending write to the backend; returns how many.
+    pub fn flush(&mut self) -> Result<usize, FsError> {
+        let Some(buffer) = &mut self.buffer else {
+            return Ok(0);
+        };
+        let pending: Vec<_> = buffer.pending.drain(..).collect();
+        let flushed = pending.len();
+        for (path, contents, _) in pending {
+            self.inner.write(&path, &contents)?;
+        }
+        Ok(flushed)
+    }
+
+    /// Flushes only the entries that have outlived the window.
+    fn flush_expired(&mut self) -> Result<(), FsError> {
+        let Some(buffer) = &mut self.buffer else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        let mut index = 0;
+        while index < buffer.pending.len() {
+            if now.duration_since(buffer.pending[index].2) > buffer.window {
+                let (path, contents, _) = buffer.pending.remove(index);
+                self.inner.write(&path, &contents)?;
+            } else {
+                index += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// `(reads, writes, deletes)` across all paths.
+    pub fn totals(&self) -> (u64, u64, u64) {
+        (self.reads.get(), self.writes.get(), self.deletes.get())
+    }
+
+    pub fn access_count(&self, path: &str) -> u64 {
+        self.access_counts.borrow().get(path).copied().unwrap_or(0)
+    }
+
+    /// The path touched most often, with its count.
+    pub fn hottest_path(&self) -> Option<(String, u64)> {
+        self.access_counts
+            .borrow()
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(path, count)| (path.clone(), *count))
+    }
+
+    fn touch(&self, path: &str) {
+        *self
+            .access_counts
+            .borrow_mut()
+            .entry(path.to_string())
+            .or_insert(0) += 1;
+    }
+}
+
+impl<F: FileSystem> FileSystem for SmartFileSystemProxy<F> {
+    fn read(&self, path: &str) -> Result<String, FsError> {
+        self.reads.set(self.reads.get() + 1);
+        self.touch(path);
+        // Buffered content is the source of truth until it is flushed.
+        if let Some(buffer) = &self.buffer {
+            if let Some((_, contents, _)) = buffer.pending.iter().find(|(p, _, _)| p == path) {
+                return Ok(contents.clone());
+            }
+        }
+        self.inner.read(path)
+    }
+
+    fn write(&mut self, path: &str, contents: &str) -> Result<(), FsError> {
+        self.writes.set(self.writes.get() + 1);
+        self.touch(path);
+        self.flush_expired()?;
+        let Some(buffer) = &mut self.buffer else {
+            return self.inner.write(path, contents);
+        };
+        if let Some(slot) = buffer.pending.iter_mut().find(|(p, _, _)| p == path) {
+            slot.1 = contents.to_string();
+            buffer.suppressed += 1;
+        } else {
+            buffer
+                .pending
+                .push((path.to_string(), contents.to_string(), Instant::now()));
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, path: &str) -> Result<(), FsError> {
+        self.deletes.set(self.deletes.get() + 1);
+        self.touch(path);
+        // A buffered write to a deleted path must never resurface.
+        if let Some(buffer) = &mut self.buffer {
+            let had_pending = buffer.pending.iter().any(|(p, _, _)| p == path);
+            buffer.pending.retain(|(p, _, _)| p != path);
+            if had_pending && !self.inner.exists(path) {
+                return Ok(());
+            }
+        }
+        self.inner.delete(path)
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        if let Some(buffer) = &self.buffer {
+            if buffer.pending.iter().any(|(p, _, _)| p == path) {
+                return true;
+            }
+        }
+        self.inner.exists(path)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Transparent compression proxy
+// ---------------------------------------------------------------------------
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(text: &str) -> Result<Vec<u8>, String> {
+    if text.len() % 2 != 0 {
+        return Err("odd-length hex".to_string());
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// Compresses file contents on write and decompresses on read, with the
+/// algorithm chosen per file extension from strategy.rs implementations.
+/// Files with an unmapped extension pass through untouched. The
+/// compressed bytes are hex-armored because the `FileSystem` trait
+/// stores strings; the savings counters use the raw compressed size.
+pub struct CompressionFileSystemProxy<F: FileSystem> {
+    inner: F,
+    by_extension: Vec<(String, Box<dyn CompressionStrategy>)>,
+    bytes_original: Cell<u64>,
+    bytes_compressed: Cell<u64>,
+}
+
+impl<F: FileSystem> CompressionFileSystemProxy<F> {
+    pub fn new(inner: F) -> Self {
+        CompressionFileSystemProxy {
+            inner,
+            by_extension: Vec::new(),
+            bytes_original: Cell::new(0),
+            bytes_compressed: Cell::new(0),
+        }
+    }
+
+    pub fn with_strategy(
+        mut self,
+        extension: &str,
+        strategy: Box<dyn CompressionStrategy>,
+    ) -> Self {
+        self.by_extension.push((extension.to_string(), strategy));
+        self
+    }
+
+    /// `(original, compressed)` byte totals over all writes.
+    pub fn savings(&self) -> (u64, u64) {
+        (self.bytes_original.get(), self.bytes_compressed.get())
+    }
+
+    fn strategy_for(&self, path: &str) -> Option<&dyn CompressionStrategy> {
+        let extension = path.rsplit_once('.').map(|(_, ext)| ext)?;
+        self.by_extension
+            .iter()
+            .find(|(ext, _)| ext == extension)
+            .map(|(_, strategy)| strategy.as_ref())
+    }
+}
+
+impl<F: FileSystem> FileSystem for CompressionFileSystemProxy<F> {
+    fn read(&self, path: &str) -> Result<String, FsError> {
+        let stored = self.inner.read(path)?;
+        let Some(strategy) = self.strategy_for(path) else {
+            return Ok(stored);
+        };
+        let compressed = hex_decode(&stored)
+            .map_err(|e| FsError::Io(format!("{}: corrupt armor: {}", path, e)))?;
+        let bytes = strategy
+            .decompress(&compressed)
+            .map_err(|e| FsError::Io(format!("{}: {}", path, e)))?;
+        String::from_utf8(bytes).map_err(|e| FsError::Io(format!("{}: {}", path, e)))
+    }
+
+    fn write(&mut self, path: &str, contents: &str) -> Result<(), FsError> {
+        let Some(strategy) = self.strategy_for(path) else {
+            return self.inner.write(path, contents);
+        };
+        let compressed = strategy.compress(contents.as_bytes());
+        self.bytes_original
+            .set(self.bytes_original.get() + contents.len() as u64);
+        self.bytes_compressed
+            .set(self.bytes_compressed.get() + compressed.len() as u64);
+        let armored = hex_encode(&compressed);
+        self.inner.write(path, &armored)
+    }
+
+    fn delete(&mut self, path: &str) -> Result<(), FsError> {
+        self.inner.delete(path)
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.inner.exists(path)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Metrics-collecting proxy
+// ---------------------------------------------------------------------------
+
+/// Upper bounds of the latency histogram buckets; the last bucket is
+/// unbounded.
+const LATENCY_BUCKETS: [Duration; 4] = [
+    Duration::from_micros(100),
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(100),
+];
+
+#[derive(Debug, Clone, Copy, Default)]
+struct MethodMetrics {
+    calls: u64,
+    errors: u64,
+    total: Duration,
+    histogram: [u64; LATENCY_BUCKETS.len() + 1],
+}
+
+/// Per-method report from `MetricsProxy::snapshot`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodSnapshot {
+    pub method: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub mean_latency: Duration,
+    /// Calls per latency bucket: `<100µs, <1ms, <10ms, <100ms, rest`.
+    pub histogram: [u64; LATENCY_BUCKETS.len() + 1],
+}
+
+/// Decorator that measures every call to the wrapped service: counts,
+/// error rates, and a latency histogram, broken down per method.
+pub struct MetricsProxy<T> {
+    inner: T,
+    metrics: RefCell<HashMap<&'static str, MethodMetrics>>,
+}
+
+impl<T> MetricsProxy<T> {
+    pub fn new(inner: T) -> Self {
+        MetricsProxy {
+            inner,
+            metrics: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The report, sorted by method name for stable output.
+    pub fn snapshot(&self) -> Vec<MethodSnapshot> {
+        let mut report: Vec<MethodSnapshot> = self
+            .metrics
+            .borrow()
+            .iter()
+            .map(|(method, m)| MethodSnapshot {
+                method: method.to_string(),
+                calls: m.calls,
+                errors: m.errors,
+                error_rate: if m.calls == 0 {
+                    0.0
+                } else {
+                    m.errors as f64 / m.calls as f64
+                },
+                mean_latency: if m.calls == 0 {
+                    Duration::ZERO
+                } else {
+                    m.total / m.calls as u32
+                },
+                histogram: m.histogram,
+            })
+            .collect();
+        report.sort_by(|a, b| a.method.cmp(&b.method));
+        report
+    }
+
+    fn measure<V>(
+        &self,
+        method: &'static str,
+        ok: impl Fn(&V) -> bool,
+        call: impl FnOnce(&T) -> V,
+    ) -> V {
+        let started = Instant::now();
+        let result = call(&self.inner);
+        let elapsed = started.elapsed();
+        let mut metrics = self.metrics.borrow_mut();
+        let m = metrics.entry(method).or_default();
+        m.calls += 1;
+        m.total += elapsed;
+        if !ok(&result) {
+            m.errors += 1;
+        }
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| elapsed < *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        m.histogram[bucket] += 1;
+        result
+    }
+}
+
+impl<T: DataService> DataService for MetricsProxy<T> {
+    fn fetch(&self, key: &str) -> String {
+        self.measure("fetch", |_| true, |inner| inner.fetch(key))
+    }
+}
+
+impl<T: WebService> WebService for MetricsProxy<T> {
+    fn get(&self, path: &str) -> Result<String, ServiceError> {
+        self.measure("get", Result::is_ok, |inner| inner.get(path))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Generic logging proxy
+// ---------------------------------------------------------------------------
+
+/// One call as seen by `LoggingProxy`.
+#[derive(Debug, Clone)]
+pub struct CallRecord {
+    pub method: &'static str,
+    /// Human-readable argument summary, not a full dump.
+    pub args: String,
+    pub duration: Duration,
+    pub ok: bool,
+    /// Error text for failed calls, empty otherwise.
+    pub detail: String,
+}
+
+pub trait CallLogger {
+    fn log(&self, record: CallRecord);
+}
+
+/// Prints one line per call.
+pub struct PrintlnCallLogger;
+
+impl CallLogger for PrintlnCallLogger {
+    fn log(&self, record: CallRecord) {
+        println!(
+            "{}({}) -> {} in {:?}{}",
+            record.method,
+            record.args,
+            if record.ok { "ok" } else { "err" },
+            record.duration,
+            if record.detail.is_empty() {
+                String::new()
+            } else {
+                format!(": {}", record.detail)
+            }
+        );
+    }
+}
+
+/// Collects records in memory so tests can assert on them.
+#[derive(Default)]
+pub struct MemoryCallLogger {
+    records: RefCell<Vec<CallRecord>>,
+}
+
+impl MemoryCallLogger {
+    pub fn new() -> Self {
+        MemoryCallLogger::default()
+    }
+
+    pub fn records(&self) -> Vec<CallRecord> {
+        self.records.borrow().clone()
+    }
+}
+
+impl CallLogger for MemoryCallLogger {
+    fn log(&self, record: CallRecord) {
+        self.records.borrow_mut().push(record);
+    }
+}
+
+/// Wraps any of this module's service traits and logs method, argument
+/// summary, duration, and outcome through a pluggable logger, so the
+/// boilerplate is written once instead of per trait.
+pub struct LoggingProxy<T> {
+    inner: T,
+    logger: Rc<dyn CallLogger>,
+}
+
+impl<T> LoggingProxy<T> {
+    pub fn new(inner: T, logger: Rc<dyn CallLogger>) -> Self {
+        LoggingProxy { inner, logger }
+    }
+
+    fn record<V, E: fmt::Display>(
+        &self,
+        method: &'static str,
+        args: String,
+        call: impl FnOnce(&T) -> Result<V, E>,
+    ) -> Result<V, E> {
+        let started = Instant::now();
+        let result = call(&self.inner);
+        self.logger.log(CallRecord {
+            method,
+            args,
+            duration: started.elapsed(),
+            ok: result.is_ok(),
+            detail: result.as_ref().err().map(E::to_string).unwrap_or_default(),
+        });
+        result
+    }
+
+    fn record_mut<V, E: fmt::Display>(
+        &mut self,
+        method: &'static str,
+        args: String,
+        call: impl FnOnce(&mut T) -> Result<V, E>,
+    ) -> Result<V, E> {
+        let started = Instant::now();
+        let result = call(&mut self.inner);
+        self.logger.log(CallRecord {
+            method,
+            args,
+            duration: started.elapsed(),
+            ok: result.is_ok(),
+            detail: result.as_ref().err().map(E::to_string).unwrap_or_default(),
+        });
+        result
+    }
+}
+
+impl<T: DataService> DataService for LoggingProxy<T> {
+    fn fetch(&self, key: &str) -> String {
+        self.record("fetch", key.to_string(), |inner| {
+            Ok::<_, std::convert::Infallible>(inner.fetch(key))
+        })
+        .expect("infallible")
+    }
+}
+
+impl<T: WebService> WebService for LoggingProxy<T> {
+    fn get(&self, path: &str) -> Result<String, ServiceError> {
+        self.record("get", path.to_string(), |inner| inner.get(path))
+    }
+}
+
+impl<T: FileSystem> FileSystem for LoggingProxy<T> {
+    fn read(&self, path: &str) -> Result<String, FsError> {
+        self.record("read", path.to_string(), |inner| inner.read(path))
+    }
+
+    fn write(&mut self, path: &str, contents: &str) -> Result<(), FsError> {
+        let args = format!("{}, {} bytes", path, contents.len());
+        self.record_mut("write", args, |inner| inner.write(path, contents))
+    }
+
+    fn delete(&mut self, path: &str) -> Result<(), FsError> {
+        self.record_mut("delete", path.to_string(), |inner| inner.delete(path))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.inner.exists(path)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Demo
+// ---------------------------------------------------------------------------
+
+fn demo_caching_proxy() {
+    println!("=== Caching proxy with pluggable eviction ===");
+    let policies: Vec<Box<dyn EvictionStrategy<String>>> = vec![
+        Box::new(LruEviction::new()),
+        Box::new(LfuEviction::new()),
+        Box::new(FifoEviction::new()),
+    ];
+    for policy in policies {
+        let label = policy.name().to_string();
+        let proxy = CachingProxy::new(RemoteDataService::new(), 3, policy);
+        // A hot key plus a rotating set that overflows the capacity.
+        for i in 0..30 {
+            proxy.fetch("hot");
+            proxy.fetch(&format!("cold-{}", i % 5));
+        }
+        let stats = proxy.stats();
+        println!(
+            "{:<6} hits {:>2}, misses {:>2}, evictions {:>2}, backend fetches {}",
+            label,
+            stats.hits,
+            stats.misses,
+            stats.evictions,
+            proxy.service.fetch_count()
+        );
+    }
+}
+
+/// In-process stand-in used by the web-service proxy demos.
+struct LocalWebService {
+    calls: Cell<u64>,
+}
+
+impl LocalWebService {
+    fn new() -> Self {
+        LocalWebService {
+            calls: Cell::new(0),
+        }
+    }
+}
+
+impl WebService for LocalWebService {
+    fn get(&self, path: &str) -> Result<String, ServiceError> {
+        self.calls.set(self.calls.get() + 1);
+        Ok(format!("body of {}", path))
+    }
+}
+
+fn demo_ttl_and_refresh() {
+    println!("\n=== TTL and refresh-ahead caching ===");
+    // TTLs: the override expires long before the default.
+    let proxy = CachingProxy::new(RemoteDataService::new(), 8, Box::new(LruEviction::new()))
+        .with_default_ttl(Duration::from_secs(60))
+        .with_ttl_override("volatile", Duration::from_millis(30));
+    proxy.fetch("stable");
+    proxy.fetch("volatile");
+    std::thread::sleep(Duration::from_millis(40));
+    proxy.fetch("stable");
+    proxy.fetch("volatile");
+    let stats = proxy.stats();
+    assert_eq!((stats.hits, stats.misses, stats.expired), (1, 2, 1));
+    assert_eq!(proxy.service.fetch_count(), 3, "only the volatile key re-fetched");
+
+    // Refresh-ahead: an access in the last 50% of the TTL re-fetches, so
+    // the steadily-read key never actually expires.
+    let proxy = CachingProxy::new(RemoteDataService::new(), 8, Box::new(LruEviction::new()))
+        .with_default_ttl(Duration::from_millis(100))
+        .with_refresh_ahead(0.5);
+    proxy.fetch("hot");
+    std::thread::sleep(Duration::from_millis(60));
+    proxy.fetch("hot"); // 40ms left < 50ms window: refreshed
+    std::thread::sleep(Duration::from_millis(60));
+    proxy.fetch("hot"); // renewed above, so again a refresh, not a miss
+    let stats = proxy.stats();
+    assert_eq!(stats.misses, 1);
+    assert_eq!(stats.refreshes, 2);
+    assert_eq!(stats.expired, 0);
+    println!(
+        "volatile key expired once; refresh-ahead kept the hot key warm ({:?})",
+        stats
+    );
+}
+
+fn demo_cache_persistence() {
+    println!("\n=== Cache persistence and warm-up ===");
+    let snapshot = std::env::temp_dir().join("proxy-demo-cache.tsv");
+
+    let proxy = CachingProxy::new(RemoteDataService::new(), 8, Box::new(LruEviction::new()))
+        .with_default_ttl(Duration::from_secs(60))
+        .with_ttl_override("ephemeral", Duration::from_millis(20));
+    proxy.fetch("alpha");
+    proxy.fetch("beta");
+    proxy.fetch("ephemeral");
+    std::thread::sleep(Duration::from_millis(30));
+    proxy.save(&snapshot).unwrap();
+
+    // A fresh proxy after "restart": the durable entries come back, the
+    // expired one does not.
+    let restarted =
+        CachingProxy::new(RemoteDataService::new(), 8, Box::new(LruEviction::new()))
+            .with_default_ttl(Duration::from_secs(60));
+    let loaded = restarted.load(&snapshot).unwrap();
+    assert_eq!(loaded, 2, "ephemeral entry expired on disk");
+    restarted.fetch("alpha");
+    restarted.fetch("beta");
+    assert_eq!(restarted.service.fetch_count(), 0, "served from the snapshot");
+
+    // Warm-up pre-fetches only what is missing.
+    let fetched = restarted.warm_up(&["alpha", "gamma", "delta"]);
+    assert_eq!(fetched, 2);
+    assert_eq!(restarted.service.fetch_count(), 2);
+    let _ = std::fs::remove_file(&snapshot);
+    println!(
+        "restored {} entries, warmed {} more; stats {:?}",
+        loaded,
+        fetched,
+        restarted.stats()
+    );
+}
+
+fn demo_per_key_stats() {
+    println!("\n=== Per-key cache statistics ===");
+    // Capacity 2 guarantees evictions; the counters must not care.
+    let proxy = CachingProxy::new(RemoteDataService::new(), 2, Box::new(FifoEviction::new()));
+    for _ in 0..3 {
+        proxy.fetch("a");
+    }
+    proxy.fetch("b");
+    proxy.fetch("c"); // evicts one of the earlier keys
+    proxy.fetch("a");
+
+    // FIFO evicts "a" when "c" arrives, then "b" when "a" returns; the
+    // cumulative counters are unaffected by either eviction.
+    let stats = proxy.stats();
+    assert_eq!(stats.evictions, 2);
+    assert_eq!((stats.hits, stats.misses), (2, 4));
+    assert_eq!(proxy.service.fetch_count(), 4);
+
+    let a = proxy.stats_for_key("a").unwrap();
+    assert_eq!((a.hits, a.misses), (2, 2));
+    assert!(a.first_access <= a.last_access);
+    assert!(proxy.stats_for_key("nope").is_none());
+    println!(
+        "key 'a': {} hits, {} misses over {:?}",
+        a.hits,
+        a.misses,
+        a.last_access.duration_since(a.first_access).unwrap()
+    );
+}
+
+fn demo_stale_while_revalidate() {
+    println!("\n=== Stale-while-revalidate caching ===");
+    /// Payload changes on every backend fetch, making staleness visible.
+    struct ChangingService {
+        calls: Cell<u64>,
+    }
+    impl DataService for ChangingService {
+        fn fetch(&self, key: &str) -> String {
+            self.calls.set(self.calls.get() + 1);
+            format!("{} v{}", key, self.calls.get())
+        }
+    }
+
+    let proxy = CachingProxy::new(
+        ChangingService {
+            calls: Cell::new(0),
+        },
+        8,
+        Box::new(LruEviction::new()),
+    )
+    .with_default_ttl(Duration::from_millis(30))
+    .with_policy(CachePolicy::StaleWhileRevalidate);
+
+    assert_eq!(proxy.fetch("page"), "page v1"); // miss
+    std::thread::sleep(Duration::from_millis(40));
+    // Expired, but the caller is not made to wait for the backend.
+    assert_eq!(proxy.fetch("page"), "page v1");
+    assert_eq!(proxy.revalidate_pending(), 1);
+    assert_eq!(proxy.fetch("page"), "page v2"); // fresh again
+    let stats = proxy.stats();
+    assert_eq!((stats.hits, stats.stale_hits, stats.misses), (1, 1, 1));
+
+    // Strict mode blocks instead and never serves stale data.
+    let strict = CachingProxy::new(
+        ChangingService {
+            calls: Cell::new(0),
+        },
+        8,
+        Box::new(LruEviction::new()),
+    )
+    .with_default_ttl(Duration::from_millis(30));
+    assert_eq!(strict.fetch("page"), "page v1");
+    std::thread::sleep(Duration::from_millis(40));
+    assert_eq!(strict.fetch("page"), "page v2");
+    assert_eq!(strict.stats().expired, 1);
+    println!("swr stats {:?}", stats);
+}
+
+fn demo_rate_limiting() {
+    println!("\n=== Rate-limiting proxy ===");
+    let proxy = RateLimitingProxy::new(LocalWebService::new(), 5);
+
+    // The burst budget is five; the next two calls are rejected without
+    // touching the backend.
+    let mut denied_delay = Duration::ZERO;
+    for i in 0..7 {
+        match proxy.get_as("alice", "/feed") {
+            Ok(_) => assert!(i < 5, "call {} should have been limited", i),
+            Err(ServiceError::RateLimited { retry_after }) => denied_delay = retry_after,
+            Err(other) => panic!("unexpected error: {}", other),
+        }
+    }
+    assert!(denied_delay > Duration::ZERO);
+    assert_eq!(proxy.counters(), (5, 2));
+    assert_eq!(proxy.service.calls.get(), 5);
+
+    // Budgets are per user: bob is unaffected by alice's burst.
+    assert!(proxy.get_as("bob", "/feed").is_ok());
+
+    // Tokens come back as time passes.
+    std::thread::sleep(Duration::from_millis(250));
+    assert!(proxy.get_as("alice", "/feed").is_ok(), "bucket refilled");
+    let (allowed, denied) = proxy.counters();
+    println!(
+        "allowed {}, denied {}, retry_after on denial {:?}",
+        allowed, denied, denied_delay
+    );
+}
+
+fn demo_protection() {
+    println!("\n=== Protection proxy with role hierarchy ===");
+    let policy = AccessPolicy {
+        rules: vec![
+            AccessRule {
+                pattern: "/admin/*".to_string(),
+                required: Role::Admin,
+            },
+            AccessRule {
+                pattern: "/mod/*".to_string(),
+                required: Role::Moderator,
+            },
+            AccessRule {
+                pattern: "/status".to_string(),
+                required: Role::Guest,
+            },
+        ],
+        default_required: Role::User,
+    };
+    let mut proxy = ProtectionProxy::new(LocalWebService::new(), policy);
+    proxy.assign_role("alice", Role::Admin);
+    proxy.assign_role("bob", Role::User);
+
+    // Inheritance: an admin satisfies every lower requirement.
+    assert!(proxy.get_as("alice", "/admin/users").is_ok());
+    assert!(proxy.get_as("alice", "/mod/reports").is_ok());
+    assert!(proxy.get_as("bob", "/profile").is_ok());
+
+    // A plain user is turned away from the moderator subtree, with the
+    // failing rule in the explanation.
+    let denied = proxy.get_as("bob", "/mod/reports").unwrap_err();
+    assert!(matches!(denied, ServiceError::Forbidden(_)));
+    let why = proxy.explain("bob", "/mod/reports");
+    assert_eq!((why.required, why.held, why.allowed), (Role::Moderator, Role::User, false));
+    assert_eq!(why.rule, "/mod/*");
+
+    // Unknown callers default to guest; unmatched paths to the policy's
+    // default requirement.
+    assert!(proxy.get_as("mallory", "/profile").is_err());
+    assert!(proxy.get_as("mallory", "/status").is_ok());
+    assert_eq!(proxy.log_len(), 6);
+    println!("bob at /mod/reports -> {}", why);
+}
+
+fn demo_access_log() {
+    println!("\n=== Queryable access log ===");
+    let policy = AccessPolicy {
+        rules: vec![AccessRule {
+            pattern: "/admin/*".to_string(),
+            required: Role::Admin,
+        }],
+        default_required: Role::User,
+    };
+    let mut proxy = ProtectionProxy::new(LocalWebService::new(), policy.clone());
+    proxy.assign_role("bob", Role::User);
+
+    let before = SystemTime::now();
+    let _ = proxy.get_as("bob", "/profile");
+    let _ = proxy.get_as("bob", "/admin/flags");
+    let _ = proxy.get_as("eve", "/admin/flags");
+    let after = SystemTime::now();
+
+    assert_eq!(proxy.log_for_user("bob").len(), 2);
+    let denied = proxy.denied_entries();
+    assert_eq!(denied.len(), 2);
+    assert!(denied.iter().all(|e| e.path == "/admin/flags"));
+    assert_eq!(proxy.log_between(before, after).len(), 3);
+
+    // Export, then audit the denials on a freshly started proxy.
+    let audit = std::env::temp_dir().join("proxy-demo-audit.jsonl");
+    proxy.export_log(&audit).unwrap();
+    let auditor = ProtectionProxy::new(LocalWebService::new(), policy.clone());
+    assert_eq!(auditor.import_log(&audit).unwrap(), 3);
+    assert_eq!(auditor.denied_entries().len(), 2);
+    let _ = std::fs::remove_file(&audit);
+
+    // Rotation keeps only the newest entries.
+    let mut capped =
+        ProtectionProxy::new(LocalWebService::new(), policy).with_log_capacity(2);
+    capped.assign_role("bob", Role::User);
+    for path in ["/a", "/b", "/c"] {
+        let _ = capped.get_as("bob", path);
+    }
+    assert_eq!(capped.log_len(), 2);
+    assert_eq!(capped.rotated(), 1);
+    assert_eq!(capped.log_for_user("bob")[0].path, "/b");
+    println!(
+        "denied audit entries: {:?}",
+        auditor
+            .denied_entries()
+            .iter()
+            .map(|e| format!("{} at {}", e.user, e.path))
+            .collect::<Vec<_>>()
+    );
+}
+
+fn demo_virtual_image() {
+    println!("\n=== Virtual image proxy ===");
+    // An 8x6 gradient in binary PPM, written just for the demo.
+    let path = std::env::temp_dir().join("proxy-demo-image.ppm");
+    let (width, height) = (8u32, 6u32);
+    let mut bytes = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for y in 0..height {
+        for x in 0..width {
+            bytes.extend([(x * 32) as u8, (y * 42) as u8, 128]);
+        }
+    }
+    std::fs::write(&path, &bytes).unwrap();
+
+    let proxy = LazyImageProxy::new(&path);
+    // Metadata comes from the header; the pixels stay on disk.
+    assert_eq!(proxy.dimensions(), (8, 6));
+    assert!(!proxy.is_loaded(), "dimensions answered without loading");
+
+    // The first thumbnail forces the load and is cached by size.
+    let thumb = proxy.get_thumbnail(4).unwrap();
+    assert!(proxy.is_loaded());
+    assert_eq!((thumb.width, thumb.height), (4, 3));
+    assert_eq!(thumb.pixels.len(), 4 * 3 * 3);
+    assert_eq!(proxy.get_thumbnail(4).unwrap(), thumb);
+    assert_eq!(proxy.thumbnails_generated(), 1, "second request was cached");
+
+    println!("{}", proxy.display());
+    let _ = std::fs::remove_file(&path);
+
+    // A non-image file is rejected with a useful error.
+    let bogus = std::env::temp_dir().join("proxy-demo-bogus.ppm");
+    std::fs::write(&bogus, b"GIF89a...").unwrap();
+    let error = RealImage::load(&bogus).map(|_| ()).unwrap_err();
+    assert!(error.contains("P6"), "{}", error);
+    let _ = std::fs::remove_file(&bogus);
+}
+
+fn demo_copy_on_write() {
+    println!("\n=== Copy-on-write proxy ===");
+    let original = CowDocumentProxy::new(Document {
+        title: "draft".to_string(),
+        lines: (0..10_000).map(|i| format!("line {}", i)).collect(),
+    });
+
+    // Two more handles: still one document in memory.
+    let reviewer = original.clone();
+    let mut editor = original.clone();
+    assert_eq!(original.reference_count(), 3);
+    assert!(original.is_shared());
+    assert!(reviewer.shares_with(&original));
+
+    // Reading through any handle does not split the sharing.
+    assert_eq!(reviewer.line(9_999), Some("line 9999"));
+    assert!(reviewer.shares_with(&original));
+
+    // The first write through one handle copies for that handle only.
+    editor.set_title("draft v2");
+    editor.append_line("line 10000");
+    assert!(!editor.shares_with(&original));
+    assert_eq!(editor.reference_count(), 1);
+    assert_eq!(original.reference_count(), 2, "original and reviewer still share");
+    assert_eq!(original.title(), "draft");
+    assert_eq!(original.line_count(), 10_000);
+    assert_eq!(editor.line_count(), 10_001);
+    println!(
+        "editor split off at {} lines; {} readers still share the original",
+        editor.line_count(),
+        original.reference_count()
+    );
+}
+
+fn demo_filesystem_proxy() {
+    println!("\n=== Smart file system proxy ===");
+    // The in-memory backend keeps the access-tracking test hermetic.
+    let mut fs = SmartFileSystemProxy::new(MemFileSystem::new());
+    fs.write("/notes/todo.txt", "buy milk").unwrap();
+    assert_eq!(fs.read("/notes/todo.txt").unwrap(), "buy milk");
+    fs.read("/notes/todo.txt").unwrap();
+    assert_eq!(fs.read("/missing").unwrap_err(), FsError::NotFound("/missing".to_string()));
+    fs.delete("/notes/todo.txt").unwrap();
+    assert_eq!(fs.totals(), (3, 1, 1));
+    assert_eq!(fs.hottest_path(), Some(("/notes/todo.txt".to_string(), 4)));
+
+    // The std backend hits the real disk but cannot leave its root.
+    let root = std::env::temp_dir().join("proxy-demo-fsroot");
+    let mut disk = SmartFileSystemProxy::new(StdFileSystem::new(&root));
+    disk.write("/logs/app.log", "started\n").unwrap();
+    assert_eq!(disk.read("/logs/app.log").unwrap(), "started\n");
+    assert!(disk.exists("/logs/app.log"));
+    assert_eq!(
+        disk.read("/../etc/passwd").unwrap_err(),
+        FsError::OutsideRoot("/../etc/passwd".to_string())
+    );
+    let _ = std::fs::remove_dir_all(&root);
+    println!(
+        "mem totals {:?}; disk hottest {:?}",
+        fs.totals(),
+        disk.hottest_path()
+    );
+}
+
+fn demo_quota_policy() {
+    println!("\n=== Quota and path-policy proxy ===");
+    let mut fs = QuotaFileSystemProxy::new(MemFileSystem::new())
+        .allow_writes_under("/sandbox")
+        .with_quota(
+            "/sandbox/logs",
+            DirQuota {
+                max_bytes: Some(40),
+                max_files: Some(2),
+            },
+        );
+
+    // Writes outside the sandbox are rejected with the failing rule.
+    let denied = fs.write("/etc/hosts", "127.0.0.1").unwrap_err();
+    assert!(matches!(
+        denied,
+        FsError::Policy(PolicyViolation::WriteDenied { .. })
+    ));
+
+    fs.write("/sandbox/logs/a.log", "0123456789").unwrap();
+    fs.write("/sandbox/logs/b.log", "0123456789").unwrap();
+    assert_eq!(fs.usage("/sandbox/logs"), (20, 2));
+
+    // Third file trips the count cap; a big overwrite trips the byte cap.
+    assert_eq!(
+        fs.write("/sandbox/logs/c.log", "x").unwrap_err(),
+        FsError::Policy(PolicyViolation::FileQuotaExceeded {
+            directory: "/sandbox/logs".to_string(),
+            limit: 2,
+        })
+    );
+    assert_eq!(
+        fs.write("/sandbox/logs/a.log", &"x".repeat(40)).unwrap_err(),
+        FsError::Policy(PolicyViolation::ByteQuotaExceeded {
+            directory: "/sandbox/logs".to_string(),
+            limit: 40,
+            attempted: 50,
+        })
+    );
+
+    // Overwrites and deletes adjust the accounted usage.
+    fs.write("/sandbox/logs/a.log", "trimmed").unwrap();
+    fs.delete("/sandbox/logs/b.log").unwrap();
+    assert_eq!(fs.usage("/sandbox/logs"), (7, 1));
+    println!("usage after cleanup: {:?}", fs.usage("/sandbox/logs"));
+}
+
+fn demo_write_coalescing() {
+    println!("\n=== Write coalescing ===");
+    let mut fs = SmartFileSystemProxy::new(MemFileSystem::new());
+    fs.enable_write_buffering(Duration::from_millis(30));
+
+    // Three quick saves of the same file collapse into one pending write.
+    fs.write("/doc.txt", "v1").unwrap();
+    fs.write("/doc.txt", "v2").unwrap();
+    fs.write("/doc.txt", "v3").unwrap();
+    assert_eq!(fs.suppressed_writes(), 2);
+    assert_eq!(fs.pending_writes(), 1);
+    assert!(!fs.inner.exists("/doc.txt"), "backend untouched so far");
+    // Readers still see the newest content.
+    assert_eq!(fs.read("/doc.txt").unwrap(), "v3");
+    assert!(fs.exists("/doc.txt"));
+
+    // An entry that outlives the window is flushed by the next write.
+    std::thread::sleep(Duration::from_millis(40));
+    fs.write("/other.txt", "x").unwrap();
+    assert_eq!(fs.inner.read("/doc.txt").unwrap(), "v3");
+
+    assert_eq!(fs.flush().unwrap(), 1);
+    assert_eq!(fs.inner.read("/other.txt").unwrap(), "x");
+    assert_eq!(fs.pending_writes(), 0);
+    println!(
+        "suppressed {} writes; backend saw {} files",
+        fs.suppressed_writes(),
+        2
+    );
+}
+
+fn demo_compression_proxy() {
+    println!("\n=== Transparent compression proxy ===");
+    let mut fs = CompressionFileSystemProxy::new(MemFileSystem::new())
+        .with_strategy("log", Box::new(RleCompression));
+
+    // Repetitive log content shrinks; reading gives the original back.
+    let noisy = "ERROR ".repeat(50) + &"x".repeat(300);
+    fs.write("/app.log", &noisy).unwrap();
+    assert_eq!(fs.read("/app.log").unwrap(), noisy);
+    let stored = fs.inner.read("/app.log").unwrap();
+    assert_ne!(stored, noisy, "backend holds the compressed form");
+    let (original, compressed) = fs.savings();
+    assert!(compressed < original, "{} -> {}", original, compressed);
+
+    // Unmapped extensions pass through verbatim.
+    fs.write("/readme.txt", "plain text").unwrap();
+    assert_eq!(fs.inner.read("/readme.txt").unwrap(), "plain text");
+    println!(
+        "compressed {} bytes down to {} ({}%)",
+        original,
+        compressed,
+        compressed * 100 / original
+    );
+}
+
+fn demo_metrics_proxy() {
+    println!("\n=== Metrics proxy ===");
+    /// 404s on anything under /missing, succeeds elsewhere, and is slow
+    /// on /slow so the histogram has something to show.
+    struct MixedService;
+    impl WebService for MixedService {
+        fn get(&self, path: &str) -> Result<String, ServiceError> {
+            if path.starts_with("/missing") {
+                return Err(ServiceError::NotFound(path.to_string()));
+            }
+            if path == "/slow" {
+                std::thread::sleep(Duration::from_millis(2));
+            }
+            Ok(format!("body of {}", path))
+        }
+    }
+
+    let proxy = MetricsProxy::new(MixedService);
+    for _ in 0..6 {
+        let _ = proxy.get("/fast");
+    }
+    let _ = proxy.get("/slow");
+    let _ = proxy.get("/missing/a");
+    let _ = proxy.get("/missing/b");
+
+    let report = proxy.snapshot();
+    assert_eq!(report.len(), 1);
+    let get = &report[0];
+    assert_eq!((get.method.as_str(), get.calls, get.errors), ("get", 9, 2));
+    assert!((get.error_rate - 2.0 / 9.0).abs() < 1e-9);
+    assert_eq!(get.histogram.iter().sum::<u64>(), 9);
+    // The deliberate 2ms call cannot land in the sub-millisecond buckets.
+    assert!(get.histogram[2..].iter().sum::<u64>() >= 1);
+    println!(
+        "get: {} calls, {:.0}% errors, mean {:?}, histogram {:?}",
+        get.calls,
+        get.error_rate * 100.0,
+        get.mean_latency,
+        get.histogram
+    );
+}
+
+fn demo_logging_proxy() {
+    println!("\n=== Generic logging proxy ===");
+    let logger = Rc::new(MemoryCallLogger::new());
+
+    // The same wrapper serves both a web service and a file system.
+    let web = LoggingProxy::new(LocalWebService::new(), logger.clone());
+    let mut fs = LoggingProxy::new(MemFileSystem::new(), logger.clone());
+
+    web.get("/index").unwrap();
+    fs.write("/a.txt", "hello").unwrap();
+    fs.read("/a.txt").unwrap();
+    let _ = fs.read("/missing");
+
+    let records = logger.records();
+    let summary: Vec<(&str, bool)> = records.iter().map(|r| (r.method, r.ok)).collect();
+    assert_eq!(
+        summary,
+        [("get", true), ("write", true), ("read", true), ("read", false)]
+    );
+    assert!(records.iter().all(|r| r.duration < Duration::from_secs(1)));
+    assert_eq!(records[1].args, "/a.txt, 5 bytes");
+    assert!(records[3].detail.contains("no such file"));
+    for record in &records {
+        PrintlnCallLogger.log(record.clone());
+    }
+}
+
+fn demo_circuit_breaker() {
+    println!("\n=== Circuit breaker proxy ===");
+    /// Fails with `Unavailable` while the switch is on.
+    struct FlakyWebService {
+        failing: Cell<bool>,
+        calls: Cell<u64>,
+    }
+    impl WebService for FlakyWebService {
+        fn get(&self, path: &str) -> Result<String, ServiceError> {
+            self.calls.set(self.calls.get() + 1);
+            if self.failing.get() {
+                Err(ServiceError::Unavailable("backend down".to_string()))
+            } else {
+                Ok(format!("body of {}", path))
+            }
+        }
+    }
+
+    let breaker = CircuitBreakerProxy::new(
+        FlakyWebService {
+            failing: Cell::new(false),
+            calls: Cell::new(0),
+        },
+        CircuitBreakerConfig {
+            failure_threshold: 3,
+            cool_down: Duration::from_millis(50),
+        },
+    );
+    breaker.on_transition(|from, to| println!("breaker: {:?} -> {:?}", from, to));
+
+    assert!(breaker.get("/ok").is_ok());
+
+    // Three consecutive failures trip the breaker...
+    breaker.service.failing.set(true);
+    for _ in 0..3 {
+        assert!(breaker.get("/ok").is_err());
+    }
+    assert_eq!(breaker.state(), CircuitState::Open);
+
+    // ...after which calls fail fast without reaching the backend.
+    let backend_calls = breaker.service.calls.get();
+    assert!(breaker.get("/ok").is_err());
+    assert_eq!(breaker.service.calls.get(), backend_calls);
+
+    // A probe after the cool-down fails and reopens the breaker.
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(breaker.get("/ok").is_err());
+    assert_eq!(breaker.state(), CircuitState::Open);
+
+    // Once the backend recovers, the next probe closes it again.
+    breaker.service.failing.set(false);
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(breaker.get("/ok").is_ok());
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert_eq!(
+        breaker.transitions(),
+        [
+            (CircuitState::Closed, CircuitState::Open),
+            (CircuitState::Open, CircuitState::HalfOpen),
+            (CircuitState::HalfOpen, CircuitState::Open),
+            (CircuitState::Open, CircuitState::HalfOpen),
+            (CircuitState::HalfOpen, CircuitState::Closed),
+        ]
+    );
+}
+
+fn demo_retry() {
+    println!("\n=== Retry proxy with backoff and jitter ===");
+    /// Fails `failures_left` times, then succeeds.
+    struct RecoveringService {
+        failures_left: Cell<u32>,
+        calls: Cell<u64>,
+    }
+    impl WebService for RecoveringService {
+        fn get(&self, path: &str) -> Result<String, ServiceError> {
+            self.calls.set(self.calls.get() + 1);
+            if self.failures_left.get() > 0 {
+                self.failures_left.set(self.failures_left.get() - 1);
+                Err(ServiceError::Unavailable("still booting".to_string()))
+            } else if path == "/missing" {
+                Err(ServiceError::NotFound(path.to_string()))
+            } else {
+                Ok(format!("body of {}", path))
+            }
+        }
+    }
+
+    // Capture the planned waits instead of sleeping, so the demo is
+    // deterministic and instant.
+    let waits = std::rc::Rc::new(RefCell::new(Vec::new()));
+    let recorded = waits.clone();
+    let proxy = RetryProxy::with_sleep(
+        RecoveringService {
+            failures_left: Cell::new(2),
+            calls: Cell::new(0),
+        },
+        RetryConfig {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(2),
+            jitter: 0.2,
+        },
+        move |delay| recorded.borrow_mut().push(delay),
+    );
+    proxy.seed_jitter(42);
+
+    assert_eq!(proxy.get("/report").unwrap(), "body of /report");
+    assert_eq!(proxy.service.calls.get(), 3, "two failures, then success");
+    assert_eq!(proxy.retries(), 2);
+    {
+        let waits = waits.borrow();
+        assert_eq!(waits.len(), 2);
+        // Each backoff stays within the 20% jitter band around 100/200ms
+        // (with a hair of slack for float rounding).
+        for (retry, wait) in waits.iter().enumerate() {
+            let nominal = 0.1 * 2f64.powi(retry as i32);
+            let fraction = wait.as_secs_f64() / nominal;
+            assert!((0.799..=1.201).contains(&fraction), "wait {:?}", wait);
+        }
+        println!("backoff schedule: {:?}", *waits);
+    }
+
+    // Permanent errors are not retried at all.
+    let calls_before = proxy.service.calls.get();
+    assert!(matches!(
+        proxy.get("/missing"),
+        Err(ServiceError::NotFound(_))
+    ));
+    assert_eq!(proxy.service.calls.get(), calls_before + 1);
+    assert_eq!(waits.borrow().len(), 2, "no waits added for NotFound");
+}
+
+#[cfg(feature = "net")]
+fn demo_remote_proxy() {
+    use std::collections::HashMap;
+
+    println!("\n=== Remote proxy over HTTP ===");
+    let mut routes = HashMap::new();
+    routes.insert("/users/1".to_string(), "alice".to_string());
+    let (authority, server) = remote::spawn_test_server(routes);
+
+    let service = remote::RemoteWebServiceProxy::new(&authority);
+    assert_eq!(service.get("/users/1").unwrap(), "alice");
+    assert_eq!(
+        service.get("/users/99").unwrap_err(),
+        ServiceError::NotFound("/users/99".to_string())
+    );
+    let _ = service.get("/shutdown");
+    server.join().expect("server thread");
+    println!("fetched /users/1 remotely; missing path surfaced as NotFound");
+}
+
+fn main() {
+    demo_caching_proxy();
+    demo_ttl_and_refresh();
+    demo_cache_persistence();
+    demo_per_key_stats();
+    demo_stale_while_revalidate();
+    demo_rate_limiting();
+    demo_protection();
+    demo_access_log();
+    demo_virtual_image();
+    demo_copy_on_write();
+    demo_filesystem_proxy();
+    demo_quota_policy();
+    demo_write_coalescing();
+    demo_compression_proxy();
+    demo_metrics_proxy();
+    demo_logging_proxy();
+    demo_circuit_breaker();
+    demo_retry();
+    #[cfg(feature = "net")]
+    demo_remote_proxy();
+}
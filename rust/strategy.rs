@@ -0,0 +1,3532 @@
+#![allow(dead_code)]
+
+/// Strategy Pattern
+///
+/// Defines a family of algorithms, encapsulates each one, and makes them
+/// interchangeable. Strategy lets the algorithm vary independently from the
+/// clients that use it.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+// ---------------------------------------------------------------------------
+// Compression strategies
+// ---------------------------------------------------------------------------
+
+pub trait CompressionStrategy {
+    fn name(&self) -> &str;
+    fn compress(&self, data: &[u8]) -> Vec<u8>;
+    /// Must restore the exact bytes passed to `compress`.
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, String>;
+}
+
+/// Run-length encoding: `(count, byte)` pairs. Simple but a real, lossless
+/// round-trip — good for long runs, poor for high-entropy input.
+pub struct RleCompression;
+
+impl CompressionStrategy for RleCompression {
+    fn name(&self) -> &str {
+        "RLE"
+    }
+
+    fn compress(&self, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut iter = data.iter().peekable();
+        while let Some(&byte) = iter.next() {
+            let mut count: u8 = 1;
+            while count < u8::MAX && iter.peek() == Some(&&byte) {
+                iter.next();
+                count += 1;
+            }
+            out.push(count);
+            out.push(byte);
+        }
+        out
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        if data.len() % 2 != 0 {
+            return Err("RLE stream must be (count, byte) pairs".to_string());
+        }
+        let mut out = Vec::new();
+        for pair in data.chunks(2) {
+            out.extend(std::iter::repeat(pair[1]).take(pair[0] as usize));
+        }
+        Ok(out)
+    }
+}
+
+/// Construction-time tuning shared by the compression strategies: a 1–9
+/// level trading ratio for speed/memory, and an optional preset dictionary
+/// that seeds the coder with domain-typical byte sequences.
+#[derive(Debug, Clone)]
+pub struct CompressionConfig {
+    pub level: u8,
+    pub preset_dictionary: Option<Vec<u8>>,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        CompressionConfig {
+            level: 6,
+            preset_dictionary: None,
+        }
+    }
+}
+
+impl CompressionConfig {
+    pub fn with_level(level: u8) -> Self {
+        CompressionConfig {
+            level: level.clamp(1, 9),
+            ..CompressionConfig::default()
+        }
+    }
+}
+
+/// LZW with a dynamically grown dictionary, emitting 16-bit codes. Lossless
+/// and self-contained; dictionary resets when the code space is exhausted.
+/// The config level caps dictionary size (memory vs ratio); a preset
+/// dictionary pre-learns patterns so short inputs compress better.
+pub struct LzwCompression {
+    config: CompressionConfig,
+}
+
+impl Default for LzwCompression {
+    fn default() -> Self {
+        LzwCompression::new()
+    }
+}
+
+impl LzwCompression {
+    pub fn new() -> Self {
+        LzwCompression {
+            config: CompressionConfig::default(),
+        }
+    }
+
+    pub fn with_config(config: CompressionConfig) -> Self {
+        LzwCompression { config }
+    }
+
+    fn max_codes(&self) -> usize {
+        (256usize << self.config.level).min(u16::MAX as usize)
+    }
+
+    /// Multi-byte entries both sides derive from the preset, in the same
+    /// order the compressor's scan would learn them.
+    fn preset_entries(&self) -> Vec<Vec<u8>> {
+        use std::collections::HashSet;
+        let Some(preset) = &self.config.preset_dictionary else {
+            return Vec::new();
+        };
+        let mut seen: HashSet<Vec<u8>> = HashSet::new();
+        let mut entries = Vec::new();
+        let mut current: Vec<u8> = Vec::new();
+        for &byte in preset {
+            let mut candidate = current.clone();
+            candidate.push(byte);
+            if candidate.len() == 1 || seen.contains(&candidate) {
+                current = candidate;
+            } else {
+                if entries.len() + 256 < self.max_codes() {
+                    seen.insert(candidate.clone());
+                    entries.push(candidate);
+                }
+                current = vec![byte];
+            }
+        }
+        entries
+    }
+}
+
+impl CompressionStrategy for LzwCompression {
+    fn name(&self) -> &str {
+        "LZW"
+    }
+
+    fn compress(&self, data: &[u8]) -> Vec<u8> {
+        use std::collections::HashMap;
+        let max_codes = self.max_codes();
+        let seed = || -> HashMap<Vec<u8>, u16> {
+            let mut d: HashMap<Vec<u8>, u16> = (0..=255u16).map(|i| (vec![i as u8], i)).collect();
+            for entry in self.preset_entries() {
+                let code = d.len() as u16;
+                d.insert(entry, code);
+            }
+            d
+        };
+        let mut dictionary = seed();
+        let mut out = Vec::new();
+        let mut current: Vec<u8> = Vec::new();
+
+        for &byte in data {
+            let mut candidate = current.clone();
+            candidate.push(byte);
+            if dictionary.contains_key(&candidate) {
+                current = candidate;
+            } else {
+                out.extend(dictionary[&current].to_be_bytes());
+                if dictionary.len() < max_codes {
+                    let code = dictionary.len() as u16;
+                    dictionary.insert(candidate, code);
+                } else {
+                    dictionary = seed();
+                }
+                current = vec![byte];
+            }
+        }
+        if !current.is_empty() {
+            out.extend(dictionary[&current].to_be_bytes());
+        }
+        out
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        if data.len() % 2 != 0 {
+            return Err("LZW stream must be 16-bit codes".to_string());
+        }
+        let max_codes = self.max_codes();
+        let seed = || -> Vec<Vec<u8>> {
+            let mut d: Vec<Vec<u8>> = (0..=255u8).map(|i| vec![i]).collect();
+            d.extend(self.preset_entries());
+            d
+        };
+        let codes: Vec<u16> = data
+            .chunks(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        let mut dictionary = seed();
+        let mut out = Vec::new();
+        let mut previous: Option<Vec<u8>> = None;
+
+        for &code in &codes {
+            let entry = if (code as usize) < dictionary.len() {
+                dictionary[code as usize].clone()
+            } else if let Some(prev) = &previous {
+                // The "KwKwK" case: the code being decoded was just created.
+                let mut entry = prev.clone();
+                entry.push(prev[0]);
+                entry
+            } else {
+                return Err(format!("invalid LZW code {}", code));
+            };
+            out.extend_from_slice(&entry);
+            if let Some(prev) = previous.take() {
+                if dictionary.len() < max_codes {
+                    let mut new_entry = prev;
+                    new_entry.push(entry[0]);
+                    dictionary.push(new_entry);
+                } else {
+                    dictionary = seed();
+                }
+            }
+            previous = Some(entry);
+        }
+        Ok(out)
+    }
+}
+
+/// Real DEFLATE/gzip via the `flate2` crate when the feature is enabled.
+#[cfg(feature = "flate2")]
+pub mod flate2_backend {
+    use super::{CompressionConfig, CompressionStrategy};
+    use flate2::read::{GzDecoder, ZlibDecoder};
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+    use std::io::{Read, Write};
+
+    #[derive(Default)]
+    pub struct GzipCompression {
+        pub config: CompressionConfig,
+    }
+
+    impl CompressionStrategy for GzipCompression {
+        fn name(&self) -> &str {
+            "gzip"
+        }
+
+        fn compress(&self, data: &[u8]) -> Vec<u8> {
+            let mut encoder =
+                GzEncoder::new(Vec::new(), Compression::new(self.config.level as u32));
+            encoder.write_all(data).expect("in-memory write");
+            encoder.finish().expect("in-memory finish")
+        }
+
+        fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+            let mut out = Vec::new();
+            GzDecoder::new(data)
+                .read_to_end(&mut out)
+                .map_err(|e| e.to_string())?;
+            Ok(out)
+        }
+    }
+
+    #[derive(Default)]
+    pub struct DeflateCompression {
+        pub config: CompressionConfig,
+    }
+
+    impl CompressionStrategy for DeflateCompression {
+        fn name(&self) -> &str {
+            "deflate"
+        }
+
+        fn compress(&self, data: &[u8]) -> Vec<u8> {
+            let mut encoder =
+                ZlibEncoder::new(Vec::new(), Compression::new(self.config.level as u32));
+            encoder.write_all(data).expect("in-memory write");
+            encoder.finish().expect("in-memory finish")
+        }
+
+        fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+            let mut out = Vec::new();
+            ZlibDecoder::new(data)
+                .read_to_end(&mut out)
+                .map_err(|e| e.to_string())?;
+            Ok(out)
+        }
+    }
+}
+
+/// Zstandard via the `zstd` crate when the feature is enabled.
+#[cfg(feature = "zstd")]
+pub mod zstd_backend {
+    use super::CompressionStrategy;
+
+    pub struct ZstdCompression;
+
+    impl CompressionStrategy for ZstdCompression {
+        fn name(&self) -> &str {
+            "zstd"
+        }
+
+        fn compress(&self, data: &[u8]) -> Vec<u8> {
+            zstd::encode_all(data, 0).expect("in-memory encode")
+        }
+
+        fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+            zstd::decode_all(data).map_err(|e| e.to_string())
+        }
+    }
+}
+
+pub struct Compressor {
+    strategy: Box<dyn CompressionStrategy>,
+}
+
+impl Compressor {
+    pub fn new(strategy: Box<dyn CompressionStrategy>) -> Self {
+        Compressor { strategy }
+    }
+
+    pub fn set_strategy(&mut self, strategy: Box<dyn CompressionStrategy>) {
+        self.strategy = strategy;
+    }
+
+    pub fn compress(&self, data: &[u8]) -> Vec<u8> {
+        self.strategy.compress(data)
+    }
+
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        self.strategy.decompress(data)
+    }
+}
+
+/// Run every strategy over the sample and verify the round-trip restores the
+/// input byte for byte.
+pub fn benchmark_compression(strategies: &[Box<dyn CompressionStrategy>], data: &[u8]) {
+    println!(
+        "{:<10} {:>10} {:>10} {:>8}  round-trip",
+        "strategy", "input", "output", "ratio"
+    );
+    for strategy in strategies {
+        let compressed = strategy.compress(data);
+        let restored = strategy.decompress(&compressed).unwrap_or_else(|e| {
+            panic!("{}: decompress failed: {}", strategy.name(), e);
+        });
+        assert_eq!(
+            restored,
+            data,
+            "{}: round-trip is not byte-for-byte identical",
+            strategy.name()
+        );
+        println!(
+            "{:<10} {:>10} {:>10} {:>7.2}%  ok",
+            strategy.name(),
+            data.len(),
+            compressed.len(),
+            compressed.len() as f64 / data.len() as f64 * 100.0,
+        );
+    }
+}
+
+/// Sweep levels 1–9 for a configurable strategy, tabulating the ratio vs
+/// speed tradeoff.
+pub fn benchmark_compression_levels(
+    make: &dyn Fn(CompressionConfig) -> Box<dyn CompressionStrategy>,
+    data: &[u8],
+) {
+    println!(
+        "{:<6} {:>10} {:>8} {:>12}  round-trip",
+        "level", "output", "ratio", "time"
+    );
+    for level in 1..=9 {
+        let strategy = make(CompressionConfig::with_level(level));
+        let start = std::time::Instant::now();
+        let compressed = strategy.compress(data);
+        let elapsed = start.elapsed();
+        assert_eq!(
+            strategy.decompress(&compressed).as_deref(),
+            Ok(data),
+            "level {}: round-trip is not byte-for-byte identical",
+            level
+        );
+        println!(
+            "{:<6} {:>10} {:>7.2}% {:>12}  ok",
+            level,
+            compressed.len(),
+            compressed.len() as f64 / data.len() as f64 * 100.0,
+            format!("{:?}", elapsed),
+        );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Sorting strategies
+// ---------------------------------------------------------------------------
+
+/// Comparison/swap counters the sort strategies report into, so benchmarks
+/// can compare work done and not just wall time. Counting costs a couple of
+/// `Cell` bumps per operation, negligible next to the comparison itself.
+pub mod instrument {
+    use std::cell::Cell;
+
+    thread_local! {
+        static COMPARISONS: Cell<u64> = const { Cell::new(0) };
+        static SWAPS: Cell<u64> = const { Cell::new(0) };
+    }
+
+    pub fn reset() {
+        COMPARISONS.with(|c| c.set(0));
+        SWAPS.with(|c| c.set(0));
+    }
+
+    /// (comparisons, swaps-or-moves) since the last `reset`.
+    pub fn counts() -> (u64, u64) {
+        (COMPARISONS.with(|c| c.get()), SWAPS.with(|c| c.get()))
+    }
+
+    pub fn le(a: i32, b: i32) -> bool {
+        COMPARISONS.with(|c| c.set(c.get() + 1));
+        a <= b
+    }
+
+    pub fn gt(a: i32, b: i32) -> bool {
+        COMPARISONS.with(|c| c.set(c.get() + 1));
+        a > b
+    }
+
+    pub fn swap(data: &mut [i32], i: usize, j: usize) {
+        SWAPS.with(|c| c.set(c.get() + 1));
+        data.swap(i, j);
+    }
+
+    /// Count an element move (merge sort writes rather than swaps).
+    pub fn moved() {
+        SWAPS.with(|c| c.set(c.get() + 1));
+    }
+}
+
+pub trait SortStrategy {
+    fn name(&self) -> &str;
+    fn sort(&self, data: &mut [i32]);
+
+    /// Whether equal keys keep their relative order. Strategies that cannot
+    /// promise this keep the conservative default.
+    fn is_stable(&self) -> bool {
+        false
+    }
+}
+
+pub struct BubbleSort;
+
+impl SortStrategy for BubbleSort {
+    fn name(&self) -> &str {
+        "BubbleSort"
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn sort(&self, data: &mut [i32]) {
+        let n = data.len();
+        for i in 0..n {
+            let mut swapped = false;
+            for j in 0..n.saturating_sub(i + 1) {
+                if instrument::gt(data[j], data[j + 1]) {
+                    instrument::swap(data, j, j + 1);
+                    swapped = true;
+                }
+            }
+            if !swapped {
+                break;
+            }
+        }
+    }
+}
+
+pub struct QuickSort;
+
+impl QuickSort {
+    fn quicksort(data: &mut [i32]) {
+        if data.len() <= 1 {
+            return;
+        }
+        let pivot_index = Self::partition(data);
+        let (left, right) = data.split_at_mut(pivot_index);
+        Self::quicksort(left);
+        Self::quicksort(&mut right[1..]);
+    }
+
+    fn partition(data: &mut [i32]) -> usize {
+        let pivot_index = data.len() - 1;
+        let mut store = 0;
+        for i in 0..pivot_index {
+            if instrument::le(data[i], data[pivot_index]) {
+                instrument::swap(data, i, store);
+                store += 1;
+            }
+        }
+        instrument::swap(data, store, pivot_index);
+        store
+    }
+}
+
+impl SortStrategy for QuickSort {
+    fn name(&self) -> &str {
+        "QuickSort"
+    }
+
+    fn sort(&self, data: &mut [i32]) {
+        Self::quicksort(data);
+    }
+}
+
+pub struct MergeSort;
+
+impl MergeSort {
+    fn merge_sort(data: &mut [i32]) {
+        let n = data.len();
+        if n <= 1 {
+            return;
+        }
+        let mid = n / 2;
+        Self::merge_sort(&mut data[..mid]);
+        Self::merge_sort(&mut data[mid..]);
+
+        let mut merged = Vec::with_capacity(n);
+        let (left, right) = data.split_at(mid);
+        let (mut i, mut j) = (0, 0);
+        while i < left.len() && j < right.len() {
+            if instrument::le(left[i], right[j]) {
+                merged.push(left[i]);
+                i += 1;
+            } else {
+                merged.push(right[j]);
+                j += 1;
+            }
+            instrument::moved();
+        }
+        merged.extend_from_slice(&left[i..]);
+        merged.extend_from_slice(&right[j..]);
+        data.copy_from_slice(&merged);
+    }
+}
+
+impl SortStrategy for MergeSort {
+    fn name(&self) -> &str {
+        "MergeSort"
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn sort(&self, data: &mut [i32]) {
+        Self::merge_sort(data);
+    }
+}
+
+pub struct InsertionSort;
+
+impl SortStrategy for InsertionSort {
+    fn name(&self) -> &str {
+        "InsertionSort"
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn sort(&self, data: &mut [i32]) {
+        for i in 1..data.len() {
+            let mut j = i;
+            while j > 0 && instrument::gt(data[j - 1], data[j]) {
+                instrument::swap(data, j - 1, j);
+                j -= 1;
+            }
+        }
+    }
+}
+
+pub struct HeapSort;
+
+impl HeapSort {
+    fn sift_down(data: &mut [i32], mut root: usize, end: usize) {
+        loop {
+            let child = 2 * root + 1;
+            if child >= end {
+                return;
+            }
+            let larger = if child + 1 < end && instrument::gt(data[child + 1], data[child]) {
+                child + 1
+            } else {
+                child
+            };
+            if !instrument::gt(data[larger], data[root]) {
+                return;
+            }
+            instrument::swap(data, root, larger);
+            root = larger;
+        }
+    }
+
+    fn heapsort(data: &mut [i32]) {
+        let n = data.len();
+        for root in (0..n / 2).rev() {
+            Self::sift_down(data, root, n);
+        }
+        for end in (1..n).rev() {
+            data.swap(0, end);
+            Self::sift_down(data, 0, end);
+        }
+    }
+}
+
+impl SortStrategy for HeapSort {
+    fn name(&self) -> &str {
+        "HeapSort"
+    }
+
+    fn sort(&self, data: &mut [i32]) {
+        Self::heapsort(data);
+    }
+}
+
+/// Introsort hybrid: quicksort that switches to heapsort when recursion gets
+/// too deep (defeating adversarial pivots) and to insertion sort for small
+/// partitions.
+pub struct IntroSort;
+
+const INTRO_SMALL_CUTOFF: usize = 16;
+
+impl IntroSort {
+    fn introsort(data: &mut [i32], depth_budget: u32) {
+        if data.len() <= INTRO_SMALL_CUTOFF {
+            InsertionSort.sort(data);
+            return;
+        }
+        if depth_budget == 0 {
+            HeapSort::heapsort(data);
+            return;
+        }
+        let pivot_index = QuickSort::partition(data);
+        let (left, right) = data.split_at_mut(pivot_index);
+        Self::introsort(left, depth_budget - 1);
+        Self::introsort(&mut right[1..], depth_budget - 1);
+    }
+}
+
+impl SortStrategy for IntroSort {
+    fn name(&self) -> &str {
+        "IntroSort"
+    }
+
+    fn sort(&self, data: &mut [i32]) {
+        let depth_budget = 2 * (usize::BITS - data.len().leading_zeros());
+        Self::introsort(data, depth_budget);
+    }
+}
+
+/// Merge sort that splits across rayon's thread pool for large slices,
+/// falling back to the sequential `MergeSort` below a threshold where the
+/// join overhead would dominate.
+#[cfg(feature = "rayon")]
+pub mod parallel {
+    use super::{MergeSort, SortStrategy};
+
+    pub struct ParallelMergeSort {
+        /// Slices at or below this length are sorted sequentially.
+        pub sequential_threshold: usize,
+    }
+
+    impl Default for ParallelMergeSort {
+        fn default() -> Self {
+            ParallelMergeSort {
+                sequential_threshold: 8_192,
+            }
+        }
+    }
+
+    impl ParallelMergeSort {
+        fn sort_recursive(&self, data: &mut [i32]) {
+            if data.len() <= self.sequential_threshold {
+                MergeSort.sort(data);
+                return;
+            }
+            let mid = data.len() / 2;
+            let (left, right) = data.split_at_mut(mid);
+            rayon::join(|| self.sort_recursive(left), || self.sort_recursive(right));
+
+            let mut merged = Vec::with_capacity(left.len() + right.len());
+            let (mut i, mut j) = (0, 0);
+            while i < left.len() && j < right.len() {
+                if left[i] <= right[j] {
+                    merged.push(left[i]);
+                    i += 1;
+                } else {
+                    merged.push(right[j]);
+                    j += 1;
+                }
+            }
+            merged.extend_from_slice(&left[i..]);
+            merged.extend_from_slice(&right[j..]);
+            data.copy_from_slice(&merged);
+        }
+    }
+
+    impl SortStrategy for ParallelMergeSort {
+        fn name(&self) -> &str {
+            "ParallelMergeSort"
+        }
+
+        fn sort(&self, data: &mut [i32]) {
+            self.sort_recursive(data);
+        }
+    }
+}
+
+/// Compare `ParallelMergeSort` against the sequential `MergeSort` on a large
+/// random input. Only meaningful with the `rayon` feature enabled.
+#[cfg(feature = "rayon")]
+fn benchmark_parallel_sort() {
+    use std::time::Instant;
+
+    println!("\n=== Parallel vs sequential merge sort (1M elements) ===");
+    let data = pseudo_random_vec(1_000_000, 7);
+
+    let mut sequential = data.clone();
+    let start = Instant::now();
+    MergeSort.sort(&mut sequential);
+    let sequential_time = start.elapsed();
+
+    let mut parallel = data.clone();
+    let start = Instant::now();
+    parallel::ParallelMergeSort::default().sort(&mut parallel);
+    let parallel_time = start.elapsed();
+
+    assert_eq!(sequential, parallel);
+    println!(
+        "sequential {:?}, parallel {:?} ({:.2}x)",
+        sequential_time,
+        parallel_time,
+        sequential_time.as_secs_f64() / parallel_time.as_secs_f64()
+    );
+}
+
+/// Fraction of adjacent pairs already in order: 1.0 for sorted input, about
+/// 0.5 for random input.
+fn presortedness(data: &[i32]) -> f64 {
+    if data.len() < 2 {
+        return 1.0;
+    }
+    let ordered = data.windows(2).filter(|w| w[0] <= w[1]).count();
+    ordered as f64 / (data.len() - 1) as f64
+}
+
+/// Picks a strategy from the input's shape: insertion sort for short or
+/// nearly sorted slices, introsort otherwise.
+pub struct AdaptiveSorter;
+
+impl AdaptiveSorter {
+    pub fn choose(data: &[i32]) -> Box<dyn SortStrategy> {
+        if data.len() <= 32 || presortedness(data) > 0.95 {
+            Box::new(InsertionSort)
+        } else {
+            Box::new(IntroSort)
+        }
+    }
+
+    pub fn sort(data: &mut [i32]) -> &'static str {
+        let strategy = Self::choose(data);
+        let name = if strategy.name() == "InsertionSort" {
+            "InsertionSort"
+        } else {
+            "IntroSort"
+        };
+        strategy.sort(data);
+        name
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Comparator-based sorting for arbitrary element types
+// ---------------------------------------------------------------------------
+
+/// Parallel trait to `SortStrategy` for sorting arbitrary types through a
+/// caller-supplied comparator. The same strategy structs implement both, so
+/// one strategy object can sort `i32` slices and `Product`s alike.
+pub trait SortByStrategy<T> {
+    fn sort_by(&self, data: &mut [T], compare: &mut dyn FnMut(&T, &T) -> Ordering);
+}
+
+/// Sort with a key-extraction closure instead of a full comparator.
+pub fn sort_by_key<T, K, S>(strategy: &S, data: &mut [T], mut key: impl FnMut(&T) -> K)
+where
+    K: Ord,
+    S: SortByStrategy<T> + ?Sized,
+{
+    strategy.sort_by(data, &mut |a, b| key(a).cmp(&key(b)));
+}
+
+impl<T> SortByStrategy<T> for InsertionSort {
+    fn sort_by(&self, data: &mut [T], compare: &mut dyn FnMut(&T, &T) -> Ordering) {
+        for i in 1..data.len() {
+            let mut j = i;
+            while j > 0 && compare(&data[j - 1], &data[j]) == Ordering::Greater {
+                data.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+    }
+}
+
+impl<T> SortByStrategy<T> for QuickSort {
+    fn sort_by(&self, data: &mut [T], compare: &mut dyn FnMut(&T, &T) -> Ordering) {
+        if data.len() <= 1 {
+            return;
+        }
+        let pivot_index = data.len() - 1;
+        let mut store = 0;
+        for i in 0..pivot_index {
+            if compare(&data[i], &data[pivot_index]) != Ordering::Greater {
+                data.swap(i, store);
+                store += 1;
+            }
+        }
+        data.swap(store, pivot_index);
+        let (left, right) = data.split_at_mut(store);
+        self.sort_by(left, compare);
+        self.sort_by(&mut right[1..], compare);
+    }
+}
+
+impl<T: Clone> SortByStrategy<T> for MergeSort {
+    fn sort_by(&self, data: &mut [T], compare: &mut dyn FnMut(&T, &T) -> Ordering) {
+        let n = data.len();
+        if n <= 1 {
+            return;
+        }
+        let mid = n / 2;
+        self.sort_by(&mut data[..mid], compare);
+        self.sort_by(&mut data[mid..], compare);
+
+        let mut merged: Vec<T> = Vec::with_capacity(n);
+        let (left, right) = data.split_at(mid);
+        let (mut i, mut j) = (0, 0);
+        while i < left.len() && j < right.len() {
+            if compare(&left[i], &right[j]) != Ordering::Greater {
+                merged.push(left[i].clone());
+                i += 1;
+            } else {
+                merged.push(right[j].clone());
+                j += 1;
+            }
+        }
+        merged.extend(left[i..].iter().cloned());
+        merged.extend(right[j..].iter().cloned());
+        for (slot, value) in data.iter_mut().zip(merged) {
+            *slot = value;
+        }
+    }
+}
+
+/// The go-to stable strategy: merge-based, so equal keys always keep their
+/// relative order regardless of input shape.
+pub struct StableSort;
+
+impl SortStrategy for StableSort {
+    fn name(&self) -> &str {
+        "StableSort"
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn sort(&self, data: &mut [i32]) {
+        MergeSort.sort(data);
+    }
+}
+
+impl<T: Clone> SortByStrategy<T> for StableSort {
+    fn sort_by(&self, data: &mut [T], compare: &mut dyn FnMut(&T, &T) -> Ordering) {
+        MergeSort.sort_by(data, compare);
+    }
+}
+
+/// Element decorated with its original position, so stability violations are
+/// observable after sorting by key alone.
+#[derive(Debug, Clone)]
+pub struct Decorated {
+    pub key: i32,
+    pub original_index: usize,
+}
+
+/// Sort a duplicate-heavy decorated input by key only and report whether
+/// equal keys kept their original order. Compare the result against the
+/// strategy's `is_stable` claim — the current `QuickSort`, for instance,
+/// silently reorders equal keys.
+pub fn observe_stability(strategy: &dyn SortByStrategy<Decorated>) -> bool {
+    let keys = [3, 1, 3, 2, 1, 3, 2, 1, 2, 3, 1, 2, 3, 1, 2];
+    let mut data: Vec<Decorated> = keys
+        .iter()
+        .enumerate()
+        .map(|(original_index, &key)| Decorated {
+            key,
+            original_index,
+        })
+        .collect();
+    strategy.sort_by(&mut data, &mut |a, b| a.key.cmp(&b.key));
+    data.windows(2)
+        .all(|w| w[0].key != w[1].key || w[0].original_index < w[1].original_index)
+}
+
+/// Picks among *registered* strategies by inspecting the input — length,
+/// presortedness (run detection) — and records which strategy it chose so
+/// the decision is observable in demos and assertions. Unlike
+/// `AdaptiveSorter`, whose choices are fixed, the candidate set is open.
+pub struct AutoSorter {
+    strategies: Vec<Box<dyn SortStrategy>>,
+    last_choice: std::cell::RefCell<Option<String>>,
+}
+
+impl AutoSorter {
+    pub fn new() -> Self {
+        AutoSorter {
+            strategies: Vec::new(),
+            last_choice: std::cell::RefCell::new(None),
+        }
+    }
+
+    pub fn register(&mut self, strategy: Box<dyn SortStrategy>) {
+        self.strategies.push(strategy);
+    }
+
+    fn find(&self, name: &str) -> Option<&dyn SortStrategy> {
+        self.strategies
+            .iter()
+            .find(|s| s.name() == name)
+            .map(|s| s.as_ref())
+    }
+
+    /// Preference order for the given input; the first registered match wins.
+    fn preferences(data: &[i32]) -> &'static [&'static str] {
+        if data.len() <= 32 {
+            &["InsertionSort", "StableSort", "MergeSort"]
+        } else if presortedness(data) > 0.95 {
+            &["InsertionSort", "MergeSort", "IntroSort"]
+        } else {
+            &["IntroSort", "QuickSort", "MergeSort", "HeapSort"]
+        }
+    }
+
+    pub fn sort(&self, data: &mut [i32]) {
+        let chosen = Self::preferences(data)
+            .iter()
+            .find_map(|name| self.find(name))
+            .or(self.strategies.first().map(|s| s.as_ref()))
+            .expect("at least one strategy registered");
+        *self.last_choice.borrow_mut() = Some(chosen.name().to_string());
+        chosen.sort(data);
+    }
+
+    /// Which strategy the last `sort` call delegated to.
+    pub fn last_choice(&self) -> Option<String> {
+        self.last_choice.borrow().clone()
+    }
+}
+
+impl Default for AutoSorter {
+    fn default() -> Self {
+        AutoSorter::new()
+    }
+}
+
+/// One row of `Sorter::benchmark` output.
+#[derive(Debug)]
+pub struct SortComparison {
+    pub name: String,
+    pub wall_time: std::time::Duration,
+    pub comparisons: u64,
+    pub swaps: u64,
+}
+
+pub struct Sorter {
+    strategy: Box<dyn SortStrategy>,
+    registered: Vec<Box<dyn SortStrategy>>,
+}
+
+impl Sorter {
+    pub fn new(strategy: Box<dyn SortStrategy>) -> Self {
+        Sorter {
+            strategy,
+            registered: Vec::new(),
+        }
+    }
+
+    pub fn set_strategy(&mut self, strategy: Box<dyn SortStrategy>) {
+        self.strategy = strategy;
+    }
+
+    pub fn sort(&self, data: &mut [i32]) {
+        self.strategy.sort(data);
+    }
+
+    /// Register a strategy for `benchmark` comparisons.
+    pub fn register(&mut self, strategy: Box<dyn SortStrategy>) {
+        self.registered.push(strategy);
+    }
+
+    /// Run every registered strategy on a clone of `data`, reporting wall
+    /// time plus comparison/swap counts from the instrumentation hooks.
+    pub fn benchmark(&self, data: &[i32]) -> Vec<SortComparison> {
+        let mut results = Vec::new();
+        for strategy in &self.registered {
+            let mut copy = data.to_vec();
+            instrument::reset();
+            let start = std::time::Instant::now();
+            strategy.sort(&mut copy);
+            let wall_time = start.elapsed();
+            let (comparisons, swaps) = instrument::counts();
+            results.push(SortComparison {
+                name: strategy.name().to_string(),
+                wall_time,
+                comparisons,
+                swaps,
+            });
+        }
+        results
+    }
+
+    pub fn format_benchmark_report(results: &[SortComparison]) -> String {
+        let mut out = format!(
+            "{:<16} {:>12} {:>14} {:>12}\n",
+            "strategy", "time", "comparisons", "swaps/moves"
+        );
+        for row in results {
+            out.push_str(&format!(
+                "{:<16} {:>12} {:>14} {:>12}\n",
+                row.name,
+                format!("{:?}", row.wall_time),
+                row.comparisons,
+                row.swaps
+            ));
+        }
+        out
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Payment strategies
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Currency {
+    Usd,
+    Eur,
+    Jpy,
+}
+
+impl Currency {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+            Currency::Jpy => "JPY",
+        }
+    }
+
+    /// Decimal places of the minor unit (JPY has none).
+    pub fn minor_units(&self) -> u32 {
+        match self {
+            Currency::Usd | Currency::Eur => 2,
+            Currency::Jpy => 0,
+        }
+    }
+}
+
+/// Exact money in minor units (cents, yen); avoids float rounding drift.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Money {
+    pub amount_minor: i64,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount_minor: i64, currency: Currency) -> Self {
+        Money {
+            amount_minor,
+            currency,
+        }
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Money::new(0, currency)
+    }
+
+    pub fn as_major(&self) -> f64 {
+        self.amount_minor as f64 / 10f64.powi(self.currency.minor_units() as i32)
+    }
+
+    pub fn checked_add(&self, other: Money) -> Result<Money, String> {
+        if self.currency != other.currency {
+            return Err(format!(
+                "cannot add {} to {}",
+                other.currency.code(),
+                self.currency.code()
+            ));
+        }
+        Ok(Money::new(
+            self.amount_minor + other.amount_minor,
+            self.currency,
+        ))
+    }
+
+    pub fn scale(&self, factor: u32) -> Money {
+        Money::new(self.amount_minor * factor as i64, self.currency)
+    }
+}
+
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let units = self.currency.minor_units();
+        if units == 0 {
+            write!(f, "{} {}", self.amount_minor, self.currency.code())
+        } else {
+            let divisor = 10i64.pow(units);
+            write!(
+                f,
+                "{}.{:0width$} {}",
+                self.amount_minor / divisor,
+                (self.amount_minor % divisor).abs(),
+                self.currency.code(),
+                width = units as usize
+            )
+        }
+    }
+}
+
+/// Source of conversion rates, pluggable so demos can use fixed tables and
+/// real deployments a live feed.
+pub trait ExchangeRateProvider {
+    /// Units of `to` per one unit of `from`.
+    fn rate(&self, from: Currency, to: Currency) -> Option<f64>;
+
+    fn convert(&self, money: Money, to: Currency) -> Option<Money> {
+        if money.currency == to {
+            return Some(money);
+        }
+        let rate = self.rate(money.currency, to)?;
+        let major = money.as_major() * rate;
+        let minor = (major * 10f64.powi(to.minor_units() as i32)).round() as i64;
+        Some(Money::new(minor, to))
+    }
+}
+
+/// Fixed rate table for the demos.
+pub struct FixedRateProvider;
+
+impl ExchangeRateProvider for FixedRateProvider {
+    fn rate(&self, from: Currency, to: Currency) -> Option<f64> {
+        let usd_per = |c: Currency| match c {
+            Currency::Usd => 1.0,
+            Currency::Eur => 1.09,
+            Currency::Jpy => 0.0067,
+        };
+        Some(usd_per(from) / usd_per(to))
+    }
+}
+
+pub trait PaymentStrategy {
+    fn name(&self) -> &str;
+    fn pay(&self, amount: Money) -> Result<String, String>;
+
+    /// Currencies this processor settles in. Strategies reject anything else.
+    fn supported_currencies(&self) -> Vec<Currency> {
+        vec![Currency::Usd]
+    }
+
+    /// Processing fee this method would charge on top of `amount`.
+    fn fee(&self, amount: Money) -> Money {
+        Money::zero(amount.currency)
+    }
+
+    fn ensure_supported(&self, amount: &Money) -> Result<(), String> {
+        if self.supported_currencies().contains(&amount.currency) {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} does not support {}",
+                self.name(),
+                amount.currency.code()
+            ))
+        }
+    }
+}
+
+pub struct CreditCardPayment {
+    card_number: String,
+    holder: String,
+}
+
+impl CreditCardPayment {
+    pub fn new(card_number: &str, holder: &str) -> Self {
+        CreditCardPayment {
+            card_number: card_number.to_string(),
+            holder: holder.to_string(),
+        }
+    }
+
+    fn masked_number(&self) -> String {
+        let digits = self.card_number.len();
+        if digits <= 4 {
+            self.card_number.clone()
+        } else {
+            format!("****{}", &self.card_number[digits - 4..])
+        }
+    }
+}
+
+impl PaymentStrategy for CreditCardPayment {
+    fn name(&self) -> &str {
+        "CreditCard"
+    }
+
+    fn supported_currencies(&self) -> Vec<Currency> {
+        vec![Currency::Usd, Currency::Eur, Currency::Jpy]
+    }
+
+    // Typical card processing: 2.9% + 30 minor units.
+    fn fee(&self, amount: Money) -> Money {
+        Money::new(amount.amount_minor * 29 / 1000 + 30, amount.currency)
+    }
+
+    fn pay(&self, amount: Money) -> Result<String, String> {
+        self.ensure_supported(&amount)?;
+        if self.card_number.len() < 12 {
+            return Err("invalid card number".to_string());
+        }
+        Ok(format!(
+            "Charged {} to card {} ({})",
+            amount,
+            self.masked_number(),
+            self.holder
+        ))
+    }
+}
+
+pub struct PayPalPayment {
+    email: String,
+}
+
+impl PayPalPayment {
+    pub fn new(email: &str) -> Self {
+        PayPalPayment {
+            email: email.to_string(),
+        }
+    }
+}
+
+impl PaymentStrategy for PayPalPayment {
+    fn name(&self) -> &str {
+        "PayPal"
+    }
+
+    fn supported_currencies(&self) -> Vec<Currency> {
+        vec![Currency::Usd, Currency::Eur]
+    }
+
+    // 3.4% + 49 minor units.
+    fn fee(&self, amount: Money) -> Money {
+        Money::new(amount.amount_minor * 34 / 1000 + 49, amount.currency)
+    }
+
+    fn pay(&self, amount: Money) -> Result<String, String> {
+        self.ensure_supported(&amount)?;
+        if !self.email.contains('@') {
+            return Err("invalid PayPal account".to_string());
+        }
+        Ok(format!("Paid {} via PayPal ({})", amount, self.email))
+    }
+}
+
+pub struct BankTransferPayment {
+    iban: String,
+}
+
+impl BankTransferPayment {
+    pub fn new(iban: &str) -> Self {
+        BankTransferPayment {
+            iban: iban.to_string(),
+        }
+    }
+}
+
+impl PaymentStrategy for BankTransferPayment {
+    fn name(&self) -> &str {
+        "BankTransfer"
+    }
+
+    fn supported_currencies(&self) -> Vec<Currency> {
+        vec![Currency::Usd, Currency::Eur]
+    }
+
+    // Flat SEPA-style transfer fee.
+    fn fee(&self, amount: Money) -> Money {
+        Money::new(90, amount.currency)
+    }
+
+    fn pay(&self, amount: Money) -> Result<String, String> {
+        self.ensure_supported(&amount)?;
+        if self.iban.len() < 15 {
+            return Err("invalid IBAN".to_string());
+        }
+        Ok(format!("Transferred {} from {}", amount, self.iban))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CryptoCurrency {
+    Bitcoin,
+    Ethereum,
+    Litecoin,
+}
+
+impl CryptoCurrency {
+    fn address_prefix(&self) -> &'static str {
+        match self {
+            CryptoCurrency::Bitcoin => "bc1",
+            CryptoCurrency::Ethereum => "0x",
+            CryptoCurrency::Litecoin => "ltc1",
+        }
+    }
+
+    /// Flat network fee estimate in USD, standing in for live fee markets.
+    fn network_fee_estimate(&self) -> f64 {
+        match self {
+            CryptoCurrency::Bitcoin => 2.50,
+            CryptoCurrency::Ethereum => 1.20,
+            CryptoCurrency::Litecoin => 0.05,
+        }
+    }
+
+    fn confirmations_required(&self) -> u32 {
+        match self {
+            CryptoCurrency::Bitcoin => 6,
+            CryptoCurrency::Ethereum => 12,
+            CryptoCurrency::Litecoin => 6,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CryptoError {
+    InvalidAddress(String),
+    /// The transaction was broadcast but not yet buried deep enough.
+    InsufficientConfirmations {
+        got: u32,
+        required: u32,
+    },
+}
+
+impl std::fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CryptoError::InvalidAddress(reason) => write!(f, "invalid address: {}", reason),
+            CryptoError::InsufficientConfirmations { got, required } => {
+                write!(f, "only {}/{} confirmations", got, required)
+            }
+        }
+    }
+}
+
+pub struct CryptoPayment {
+    currency: CryptoCurrency,
+    wallet_address: String,
+    /// Confirmations the simulated network has produced for the payment.
+    simulated_confirmations: u32,
+}
+
+impl CryptoPayment {
+    pub fn new(currency: CryptoCurrency, wallet_address: &str) -> Self {
+        CryptoPayment {
+            currency,
+            wallet_address: wallet_address.to_string(),
+            simulated_confirmations: u32::MAX,
+        }
+    }
+
+    /// Simulate a network that has only produced `confirmations` so far.
+    pub fn with_confirmations(mut self, confirmations: u32) -> Self {
+        self.simulated_confirmations = confirmations;
+        self
+    }
+
+    /// Append the checksum character our toy scheme expects: the byte sum of
+    /// the address body encoded as a base-36 digit.
+    pub fn checksum_char(body: &str) -> char {
+        let sum: u32 = body.bytes().map(u32::from).sum();
+        char::from_digit(sum % 36, 36).expect("mod 36 is a valid base-36 digit")
+    }
+
+    fn validate_address(&self) -> Result<(), CryptoError> {
+        let prefix = self.currency.address_prefix();
+        let body = self
+            .wallet_address
+            .strip_prefix(prefix)
+            .ok_or_else(|| CryptoError::InvalidAddress(format!("expected {} prefix", prefix)))?;
+        if body.len() < 8 {
+            return Err(CryptoError::InvalidAddress("address too short".to_string()));
+        }
+        let (payload, checksum) = body.split_at(body.len() - 1);
+        let expected = Self::checksum_char(payload);
+        if checksum.chars().next() != Some(expected) {
+            return Err(CryptoError::InvalidAddress(format!(
+                "checksum mismatch (expected '{}')",
+                expected
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn network_fee(&self) -> f64 {
+        self.currency.network_fee_estimate()
+    }
+
+    fn send(&self, amount: Money) -> Result<String, CryptoError> {
+        self.validate_address()?;
+        let required = self.currency.confirmations_required();
+        let got = self.simulated_confirmations.min(required);
+        if got < required {
+            return Err(CryptoError::InsufficientConfirmations { got, required });
+        }
+        Ok(format!(
+            "Sent {} (+${:.2} network fee) as {:?} to {} after {} confirmations",
+            amount,
+            self.network_fee(),
+            self.currency,
+            self.wallet_address,
+            required
+        ))
+    }
+}
+
+impl PaymentStrategy for CryptoPayment {
+    fn name(&self) -> &str {
+        "Crypto"
+    }
+
+    fn fee(&self, amount: Money) -> Money {
+        // Network fee in minor units of the payment currency.
+        Money::new(
+            (self.network_fee() * 10f64.powi(amount.currency.minor_units() as i32)).round() as i64,
+            amount.currency,
+        )
+    }
+
+    fn pay(&self, amount: Money) -> Result<String, String> {
+        self.ensure_supported(&amount)?;
+        self.send(amount).map_err(|e| e.to_string())
+    }
+}
+
+/// One scheduled installment of a split purchase.
+#[derive(Debug, Clone)]
+pub struct Installment {
+    pub number: u32,
+    pub due_in_days: u32,
+    pub principal: Money,
+    pub fee: Money,
+}
+
+impl Installment {
+    pub fn total(&self) -> Money {
+        self.principal
+            .checked_add(self.fee)
+            .expect("principal and fee share a currency")
+    }
+}
+
+/// Structured plan returned instead of a single receipt string.
+#[derive(Debug, Clone)]
+pub struct PaymentPlan {
+    pub purchase_total: Money,
+    pub installments: Vec<Installment>,
+}
+
+impl PaymentPlan {
+    pub fn total_fees(&self) -> Money {
+        let mut fees = Money::zero(self.purchase_total.currency);
+        for installment in &self.installments {
+            fees = fees.checked_add(installment.fee).expect("same currency");
+        }
+        fees
+    }
+}
+
+/// Splits a purchase into N equal installments (remainder lands on the first
+/// one) with a flat fee per installment.
+pub struct InstallmentPayment {
+    installments: u32,
+    interval_days: u32,
+    fee_per_installment_minor: i64,
+}
+
+impl InstallmentPayment {
+    pub fn new(installments: u32, interval_days: u32, fee_per_installment_minor: i64) -> Self {
+        InstallmentPayment {
+            installments: installments.max(1),
+            interval_days,
+            fee_per_installment_minor,
+        }
+    }
+
+    pub fn plan(&self, amount: Money) -> Result<PaymentPlan, String> {
+        let n = self.installments as i64;
+        let base = amount.amount_minor / n;
+        let remainder = amount.amount_minor % n;
+
+        let installments: Vec<Installment> = (0..self.installments)
+            .map(|i| Installment {
+                number: i + 1,
+                due_in_days: i * self.interval_days,
+                principal: Money::new(
+                    base + if i == 0 { remainder } else { 0 },
+                    amount.currency,
+                ),
+                fee: Money::new(self.fee_per_installment_minor, amount.currency),
+            })
+            .collect();
+
+        // The split must account for every minor unit of the purchase.
+        let principal_sum: i64 = installments.iter().map(|i| i.principal.amount_minor).sum();
+        if principal_sum != amount.amount_minor {
+            return Err(format!(
+                "installments sum to {} but purchase is {}",
+                principal_sum, amount.amount_minor
+            ));
+        }
+        Ok(PaymentPlan {
+            purchase_total: amount,
+            installments,
+        })
+    }
+}
+
+impl PaymentStrategy for InstallmentPayment {
+    fn name(&self) -> &str {
+        "Installments"
+    }
+
+    fn supported_currencies(&self) -> Vec<Currency> {
+        vec![Currency::Usd, Currency::Eur, Currency::Jpy]
+    }
+
+    fn pay(&self, amount: Money) -> Result<String, String> {
+        self.ensure_supported(&amount)?;
+        let plan = self.plan(amount)?;
+        let schedule: Vec<String> = plan
+            .installments
+            .iter()
+            .map(|i| format!("#{} day {}: {}", i.number, i.due_in_days, i.total()))
+            .collect();
+        Ok(format!(
+            "Payment plan for {} ({} fees): {}",
+            plan.purchase_total,
+            plan.total_fees(),
+            schedule.join(", ")
+        ))
+    }
+}
+
+/// One method's fees in a `FeeComparison`.
+#[derive(Debug, Clone)]
+pub struct FeeRow {
+    pub method: String,
+    pub fee: Money,
+    /// Fee as a percentage of the amount.
+    pub effective_rate_percent: f64,
+    pub total_cost: Money,
+}
+
+/// Side-by-side fee report across payment methods.
+#[derive(Debug, Clone)]
+pub struct FeeComparison {
+    pub amount: Money,
+    pub rows: Vec<FeeRow>,
+    pub cheapest: String,
+}
+
+pub fn compare_payment_fees(
+    amount: Money,
+    strategies: &[Box<dyn PaymentStrategy>],
+) -> FeeComparison {
+    let rows: Vec<FeeRow> = strategies
+        .iter()
+        .map(|strategy| {
+            let fee = strategy.fee(amount);
+            FeeRow {
+                method: strategy.name().to_string(),
+                fee,
+                effective_rate_percent: if amount.amount_minor > 0 {
+                    fee.amount_minor as f64 / amount.amount_minor as f64 * 100.0
+                } else {
+                    0.0
+                },
+                total_cost: amount.checked_add(fee).expect("same currency"),
+            }
+        })
+        .collect();
+    let cheapest = rows
+        .iter()
+        .min_by_key(|row| row.fee.amount_minor)
+        .map(|row| row.method.clone())
+        .unwrap_or_default();
+    FeeComparison {
+        amount,
+        rows,
+        cheapest,
+    }
+}
+
+/// Outcome of one attempt inside a `FallbackPayment` chain.
+#[derive(Debug, Clone)]
+pub struct PaymentAttempt {
+    pub strategy: String,
+    pub result: Result<String, String>,
+}
+
+/// Composite strategy that tries each inner strategy in order, falling
+/// through on failure. The attempt trail is kept for the receipt.
+pub struct FallbackPayment {
+    chain: Vec<Box<dyn PaymentStrategy>>,
+    last_attempts: std::cell::RefCell<Vec<PaymentAttempt>>,
+}
+
+impl FallbackPayment {
+    pub fn new(chain: Vec<Box<dyn PaymentStrategy>>) -> Self {
+        FallbackPayment {
+            chain,
+            last_attempts: std::cell::RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The per-attempt trail from the most recent `pay` call.
+    pub fn last_attempts(&self) -> Vec<PaymentAttempt> {
+        self.last_attempts.borrow().clone()
+    }
+}
+
+impl PaymentStrategy for FallbackPayment {
+    fn name(&self) -> &str {
+        "Fallback"
+    }
+
+    fn supported_currencies(&self) -> Vec<Currency> {
+        // The union: any currency some link in the chain can settle.
+        let mut currencies: Vec<Currency> = Vec::new();
+        for strategy in &self.chain {
+            for currency in strategy.supported_currencies() {
+                if !currencies.contains(&currency) {
+                    currencies.push(currency);
+                }
+            }
+        }
+        currencies
+    }
+
+    fn pay(&self, amount: Money) -> Result<String, String> {
+        let mut attempts = self.last_attempts.borrow_mut();
+        attempts.clear();
+        for strategy in &self.chain {
+            let result = strategy.pay(amount);
+            attempts.push(PaymentAttempt {
+                strategy: strategy.name().to_string(),
+                result: result.clone(),
+            });
+            if let Ok(confirmation) = result {
+                let trail: Vec<String> = attempts
+                    .iter()
+                    .map(|a| match &a.result {
+                        Ok(_) => format!("{}: ok", a.strategy),
+                        Err(e) => format!("{}: {}", a.strategy, e),
+                    })
+                    .collect();
+                return Ok(format!("{} [attempts: {}]", confirmation, trail.join("; ")));
+            }
+        }
+        Err(format!(
+            "all {} payment strategies failed",
+            self.chain.len()
+        ))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Discount strategies
+// ---------------------------------------------------------------------------
+
+/// A discount actually taken off the cart, for the itemized breakdown.
+#[derive(Debug, Clone)]
+pub struct AppliedDiscount {
+    pub description: String,
+    pub amount: Money,
+}
+
+pub trait DiscountStrategy {
+    fn name(&self) -> &str;
+    /// Discount for the given items/subtotal, or `None` if it doesn't apply.
+    fn apply(&self, items: &[CartItem], subtotal: Money) -> Option<AppliedDiscount>;
+}
+
+/// Flat percentage off the subtotal, e.g. a "SAVE10" coupon.
+pub struct PercentageCoupon {
+    pub code: String,
+    pub percent: u32,
+}
+
+impl DiscountStrategy for PercentageCoupon {
+    fn name(&self) -> &str {
+        "PercentageCoupon"
+    }
+
+    fn apply(&self, _items: &[CartItem], subtotal: Money) -> Option<AppliedDiscount> {
+        if self.percent == 0 {
+            return None;
+        }
+        let amount = Money::new(
+            subtotal.amount_minor * self.percent as i64 / 100,
+            subtotal.currency,
+        );
+        Some(AppliedDiscount {
+            description: format!("coupon {} (-{}%)", self.code, self.percent),
+            amount,
+        })
+    }
+}
+
+/// Buy one, get one free for a named item: every second unit is free.
+pub struct BuyOneGetOne {
+    pub item_name: String,
+}
+
+impl DiscountStrategy for BuyOneGetOne {
+    fn name(&self) -> &str {
+        "BuyOneGetOne"
+    }
+
+    fn apply(&self, items: &[CartItem], subtotal: Money) -> Option<AppliedDiscount> {
+        let item = items.iter().find(|i| i.name == self.item_name)?;
+        let free_units = item.quantity / 2;
+        if free_units == 0 {
+            return None;
+        }
+        let mut amount = item.price.scale(free_units);
+        if amount.currency != subtotal.currency {
+            // Keep the breakdown in the cart's pricing currency.
+            amount = Money::new(amount.amount_minor, subtotal.currency);
+        }
+        Some(AppliedDiscount {
+            description: format!("BOGO {} ({} free)", self.item_name, free_units),
+            amount,
+        })
+    }
+}
+
+/// Tiered volume discount: the more units in the cart, the larger the cut.
+pub struct TieredVolumeDiscount {
+    /// (minimum total units, percent off) sorted ascending by threshold.
+    pub tiers: Vec<(u32, u32)>,
+}
+
+impl DiscountStrategy for TieredVolumeDiscount {
+    fn name(&self) -> &str {
+        "TieredVolume"
+    }
+
+    fn apply(&self, items: &[CartItem], subtotal: Money) -> Option<AppliedDiscount> {
+        let units: u32 = items.iter().map(|i| i.quantity).sum();
+        let percent = self
+            .tiers
+            .iter()
+            .filter(|(min_units, _)| units >= *min_units)
+            .map(|(_, percent)| *percent)
+            .max()?;
+        Some(AppliedDiscount {
+            description: format!("volume tier ({} units, -{}%)", units, percent),
+            amount: Money::new(
+                subtotal.amount_minor * percent as i64 / 100,
+                subtotal.currency,
+            ),
+        })
+    }
+}
+
+/// How multiple applicable discounts combine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscountStacking {
+    /// Apply every applicable discount, each computed on the gross subtotal.
+    StackAll,
+    /// Apply only the single largest discount.
+    BestOnly,
+}
+
+// ---------------------------------------------------------------------------
+// Tax strategies
+// ---------------------------------------------------------------------------
+
+/// Product category for VAT-style systems with per-category rates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaxCategory {
+    Standard,
+    Food,
+    Books,
+    Medical,
+}
+
+/// One cart line expressed in the cart's pricing currency, handed to tax
+/// strategies so they can apply per-category rates.
+#[derive(Debug, Clone)]
+pub struct TaxLine {
+    pub name: String,
+    pub category: TaxCategory,
+    pub line_total: Money,
+}
+
+pub trait TaxStrategy {
+    fn name(&self) -> &str;
+    /// Tax owed on the given lines. `taxable` is the discounted subtotal;
+    /// strategies taxing per line should scale by `taxable / gross` so
+    /// discounts reduce tax proportionally.
+    fn tax_for(&self, lines: &[TaxLine], taxable: Money, gross: Money) -> Money;
+}
+
+/// Flat state sales tax from a small lookup table.
+pub struct UsStateSalesTax {
+    pub state: &'static str,
+}
+
+impl UsStateSalesTax {
+    fn rate_percent(&self) -> f64 {
+        match self.state {
+            "CA" => 7.25,
+            "NY" => 4.0,
+            "TX" => 6.25,
+            "OR" | "MT" | "NH" | "DE" => 0.0,
+            _ => 5.0,
+        }
+    }
+}
+
+impl TaxStrategy for UsStateSalesTax {
+    fn name(&self) -> &str {
+        "USSalesTax"
+    }
+
+    fn tax_for(&self, _lines: &[TaxLine], taxable: Money, _gross: Money) -> Money {
+        Money::new(
+            (taxable.amount_minor as f64 * self.rate_percent() / 100.0).round() as i64,
+            taxable.currency,
+        )
+    }
+}
+
+/// EU VAT with reduced rates for some categories.
+pub struct EuVat;
+
+impl EuVat {
+    fn rate_percent(category: TaxCategory) -> f64 {
+        match category {
+            TaxCategory::Standard => 21.0,
+            TaxCategory::Food => 9.0,
+            TaxCategory::Books => 6.0,
+            TaxCategory::Medical => 0.0,
+        }
+    }
+}
+
+impl TaxStrategy for EuVat {
+    fn name(&self) -> &str {
+        "EUVAT"
+    }
+
+    fn tax_for(&self, lines: &[TaxLine], taxable: Money, gross: Money) -> Money {
+        let per_line: f64 = lines
+            .iter()
+            .map(|line| line.line_total.amount_minor as f64 * Self::rate_percent(line.category) / 100.0)
+            .sum();
+        // Scale so discounts reduce VAT proportionally.
+        let scale = if gross.amount_minor > 0 {
+            taxable.amount_minor as f64 / gross.amount_minor as f64
+        } else {
+            1.0
+        };
+        Money::new((per_line * scale).round() as i64, taxable.currency)
+    }
+}
+
+/// No tax at all (resellers, exempt organizations).
+pub struct TaxExempt;
+
+impl TaxStrategy for TaxExempt {
+    fn name(&self) -> &str {
+        "TaxExempt"
+    }
+
+    fn tax_for(&self, _lines: &[TaxLine], taxable: Money, _gross: Money) -> Money {
+        Money::zero(taxable.currency)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Shipping strategies
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct ShippingAddress {
+    pub street: String,
+    pub city: String,
+    pub country: String,
+    pub postal_code: String,
+}
+
+pub trait ShippingStrategy {
+    fn name(&self) -> &str;
+    fn cost(&self, items: &[CartItem], subtotal: Money, address: &ShippingAddress) -> Money;
+}
+
+pub struct FlatRateShipping {
+    pub rate_minor: i64,
+}
+
+impl ShippingStrategy for FlatRateShipping {
+    fn name(&self) -> &str {
+        "FlatRate"
+    }
+
+    fn cost(&self, _items: &[CartItem], subtotal: Money, _address: &ShippingAddress) -> Money {
+        Money::new(self.rate_minor, subtotal.currency)
+    }
+}
+
+pub struct WeightBasedShipping {
+    /// Minor units charged per started kilogram.
+    pub rate_per_kg_minor: i64,
+}
+
+impl ShippingStrategy for WeightBasedShipping {
+    fn name(&self) -> &str {
+        "WeightBased"
+    }
+
+    fn cost(&self, items: &[CartItem], subtotal: Money, _address: &ShippingAddress) -> Money {
+        let grams: u32 = items.iter().map(|i| i.weight_grams * i.quantity).sum();
+        let kilos = grams.div_ceil(1000).max(1) as i64;
+        Money::new(kilos * self.rate_per_kg_minor, subtotal.currency)
+    }
+}
+
+/// Flat rate that becomes free once the subtotal crosses a threshold.
+pub struct FreeOverThresholdShipping {
+    pub rate_minor: i64,
+    pub threshold_minor: i64,
+}
+
+impl ShippingStrategy for FreeOverThresholdShipping {
+    fn name(&self) -> &str {
+        "FreeOverThreshold"
+    }
+
+    fn cost(&self, _items: &[CartItem], subtotal: Money, _address: &ShippingAddress) -> Money {
+        if subtotal.amount_minor >= self.threshold_minor {
+            Money::zero(subtotal.currency)
+        } else {
+            Money::new(self.rate_minor, subtotal.currency)
+        }
+    }
+}
+
+/// Express courier: flat premium, doubled for international destinations.
+pub struct ExpressShipping {
+    pub base_rate_minor: i64,
+    pub domestic_country: String,
+}
+
+impl ShippingStrategy for ExpressShipping {
+    fn name(&self) -> &str {
+        "Express"
+    }
+
+    fn cost(&self, _items: &[CartItem], subtotal: Money, address: &ShippingAddress) -> Money {
+        let multiplier = if address.country == self.domestic_country {
+            1
+        } else {
+            2
+        };
+        Money::new(self.base_rate_minor * multiplier, subtotal.currency)
+    }
+}
+
+/// Structured cart totals: discounted pre-tax amount, tax, shipping, and the
+/// grand total.
+#[derive(Debug, Clone)]
+pub struct CartTotals {
+    pub pre_tax: Money,
+    pub tax: Money,
+    pub shipping: Money,
+    pub grand_total: Money,
+}
+
+#[derive(Debug, Clone)]
+pub struct CartItem {
+    pub name: String,
+    pub price: Money,
+    pub quantity: u32,
+    pub category: TaxCategory,
+    pub weight_grams: u32,
+}
+
+/// One line of a `Receipt`.
+#[derive(Debug, Clone)]
+pub struct ReceiptLine {
+    pub name: String,
+    pub quantity: u32,
+    pub line_total: Money,
+}
+
+/// Structured checkout result; callers assert on fields instead of parsing
+/// a formatted string.
+#[derive(Debug, Clone)]
+pub struct Receipt {
+    pub transaction_id: String,
+    pub timestamp_secs: u64,
+    pub line_items: Vec<ReceiptLine>,
+    pub subtotal: Money,
+    pub discounts: Vec<AppliedDiscount>,
+    pub tax: Money,
+    pub shipping: Money,
+    pub total_charged: Money,
+    pub payment_method: String,
+    pub confirmation: String,
+}
+
+impl std::fmt::Display for Receipt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Receipt {} ({})", self.transaction_id, self.payment_method)?;
+        for line in &self.line_items {
+            writeln!(f, "  {} x{}  {}", line.name, line.quantity, line.line_total)?;
+        }
+        writeln!(f, "  subtotal: {}", self.subtotal)?;
+        for discount in &self.discounts {
+            writeln!(f, "  discount: {} -{}", discount.description, discount.amount)?;
+        }
+        writeln!(f, "  tax: {}  shipping: {}", self.tax, self.shipping)?;
+        writeln!(f, "  charged: {}", self.total_charged)?;
+        write!(f, "  {}", self.confirmation)
+    }
+}
+
+pub struct ShoppingCart {
+    items: Vec<CartItem>,
+    payment_strategy: Option<Box<dyn PaymentStrategy>>,
+    /// Currency totals are expressed in; items in other currencies are
+    /// converted through the exchange provider.
+    pricing_currency: Currency,
+    exchange: Option<Box<dyn ExchangeRateProvider>>,
+    discounts: Vec<Box<dyn DiscountStrategy>>,
+    stacking: DiscountStacking,
+    tax_strategy: Option<Box<dyn TaxStrategy>>,
+    shipping_strategy: Option<Box<dyn ShippingStrategy>>,
+    shipping_address: Option<ShippingAddress>,
+}
+
+impl ShoppingCart {
+    pub fn new() -> Self {
+        ShoppingCart::priced_in(Currency::Usd)
+    }
+
+    pub fn priced_in(currency: Currency) -> Self {
+        ShoppingCart {
+            items: Vec::new(),
+            payment_strategy: None,
+            pricing_currency: currency,
+            exchange: None,
+            discounts: Vec::new(),
+            stacking: DiscountStacking::StackAll,
+            tax_strategy: None,
+            shipping_strategy: None,
+            shipping_address: None,
+        }
+    }
+
+    pub fn set_shipping_strategy(&mut self, strategy: Box<dyn ShippingStrategy>) {
+        self.shipping_strategy = Some(strategy);
+    }
+
+    pub fn set_shipping_address(&mut self, address: ShippingAddress) {
+        self.shipping_address = Some(address);
+    }
+
+    pub fn set_tax_strategy(&mut self, strategy: Box<dyn TaxStrategy>) {
+        self.tax_strategy = Some(strategy);
+    }
+
+    pub fn add_discount(&mut self, discount: Box<dyn DiscountStrategy>) {
+        self.discounts.push(discount);
+    }
+
+    pub fn set_discount_stacking(&mut self, stacking: DiscountStacking) {
+        self.stacking = stacking;
+    }
+
+    pub fn set_exchange_provider(&mut self, provider: Box<dyn ExchangeRateProvider>) {
+        self.exchange = Some(provider);
+    }
+
+    pub fn add_item(&mut self, name: &str, price: Money, quantity: u32) {
+        self.add_item_in_category(name, price, quantity, TaxCategory::Standard);
+    }
+
+    pub fn add_item_in_category(
+        &mut self,
+        name: &str,
+        price: Money,
+        quantity: u32,
+        category: TaxCategory,
+    ) {
+        self.items.push(CartItem {
+            name: name.to_string(),
+            price,
+            quantity,
+            category,
+            weight_grams: 0,
+        });
+    }
+
+    pub fn add_weighted_item(&mut self, name: &str, price: Money, quantity: u32, weight_grams: u32) {
+        self.add_item(name, price, quantity);
+        self.items.last_mut().expect("just pushed").weight_grams = weight_grams;
+    }
+
+    pub fn set_payment_strategy(&mut self, strategy: Box<dyn PaymentStrategy>) {
+        self.payment_strategy = Some(strategy);
+    }
+
+    fn in_pricing_currency(&self, money: Money) -> Result<Money, String> {
+        if money.currency == self.pricing_currency {
+            return Ok(money);
+        }
+        self.exchange
+            .as_ref()
+            .and_then(|provider| provider.convert(money, self.pricing_currency))
+            .ok_or_else(|| {
+                format!(
+                    "no exchange rate from {} to {}",
+                    money.currency.code(),
+                    self.pricing_currency.code()
+                )
+            })
+    }
+
+    /// Gross subtotal before discounts and tax.
+    pub fn subtotal(&self) -> Result<Money, String> {
+        let mut total = Money::zero(self.pricing_currency);
+        for item in &self.items {
+            let line = self.in_pricing_currency(item.price.scale(item.quantity))?;
+            total = total.checked_add(line)?;
+        }
+        Ok(total)
+    }
+
+    fn tax_lines(&self) -> Result<Vec<TaxLine>, String> {
+        self.items
+            .iter()
+            .map(|item| {
+                Ok(TaxLine {
+                    name: item.name.clone(),
+                    category: item.category,
+                    line_total: self.in_pricing_currency(item.price.scale(item.quantity))?,
+                })
+            })
+            .collect()
+    }
+
+    /// Pre-tax (after discounts), tax per the selected strategy, and grand
+    /// total.
+    pub fn get_total(&self) -> Result<CartTotals, String> {
+        let gross = self.subtotal()?;
+        let (pre_tax, _) = self.applied_discounts()?;
+        let tax = match &self.tax_strategy {
+            Some(strategy) => strategy.tax_for(&self.tax_lines()?, pre_tax, gross),
+            None => Money::zero(self.pricing_currency),
+        };
+        let shipping = match (&self.shipping_strategy, &self.shipping_address) {
+            (Some(strategy), Some(address)) => strategy.cost(&self.items, pre_tax, address),
+            _ => Money::zero(self.pricing_currency),
+        };
+        Ok(CartTotals {
+            pre_tax,
+            tax,
+            shipping,
+            grand_total: pre_tax.checked_add(tax)?.checked_add(shipping)?,
+        })
+    }
+
+    /// Discounts applied per the stacking rule, plus the discounted subtotal.
+    pub fn applied_discounts(&self) -> Result<(Money, Vec<AppliedDiscount>), String> {
+        let subtotal = self.subtotal()?;
+        let mut applicable: Vec<AppliedDiscount> = self
+            .discounts
+            .iter()
+            .filter_map(|d| d.apply(&self.items, subtotal))
+            .collect();
+        if self.stacking == DiscountStacking::BestOnly {
+            applicable.sort_by_key(|d| std::cmp::Reverse(d.amount.amount_minor));
+            applicable.truncate(1);
+        }
+        let mut total = subtotal;
+        for discount in &applicable {
+            total = Money::new(
+                (total.amount_minor - discount.amount.amount_minor).max(0),
+                total.currency,
+            );
+        }
+        Ok((total, applicable))
+    }
+
+    pub fn checkout(&self) -> Result<Receipt, String> {
+        let strategy = self
+            .payment_strategy
+            .as_ref()
+            .ok_or("no payment strategy selected")?;
+        let (_, discounts) = self.applied_discounts()?;
+        let totals = self.get_total()?;
+        let mut total = totals.grand_total;
+        if strategy.ensure_supported(&total).is_err() {
+            // Settle in the strategy's preferred currency when we can convert.
+            let target = strategy.supported_currencies()[0];
+            total = self
+                .exchange
+                .as_ref()
+                .and_then(|provider| provider.convert(total, target))
+                .ok_or_else(|| {
+                    format!(
+                        "{} does not support {} and no exchange rate is available",
+                        strategy.name(),
+                        total.currency.code()
+                    )
+                })?;
+        }
+        let confirmation = strategy.pay(total)?;
+
+        let timestamp_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let line_items = self
+            .items
+            .iter()
+            .map(|item| {
+                Ok(ReceiptLine {
+                    name: item.name.clone(),
+                    quantity: item.quantity,
+                    line_total: self.in_pricing_currency(item.price.scale(item.quantity))?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Receipt {
+            transaction_id: format!("TXN-{:010x}", timestamp_secs ^ total.amount_minor as u64),
+            timestamp_secs,
+            line_items,
+            subtotal: self.subtotal()?,
+            discounts,
+            tax: totals.tax,
+            shipping: totals.shipping,
+            total_charged: total,
+            payment_method: strategy.name().to_string(),
+            confirmation,
+        })
+    }
+}
+
+impl Default for ShoppingCart {
+    fn default() -> Self {
+        ShoppingCart::new()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Pathfinding strategies
+// ---------------------------------------------------------------------------
+
+pub type Point = (usize, usize);
+
+/// Rectangular grid with blocked cells; the arena the pathfinders search.
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    walls: Vec<bool>,
+}
+
+impl Grid {
+    pub fn open(width: usize, height: usize) -> Self {
+        Grid {
+            width,
+            height,
+            walls: vec![false; width * height],
+        }
+    }
+
+    /// Deterministic pseudo-random maze: roughly `wall_percent`% blocked,
+    /// with start/goal corners kept open.
+    pub fn random_maze(width: usize, height: usize, wall_percent: u32, mut seed: u64) -> Self {
+        let mut grid = Grid::open(width, height);
+        for cell in grid.walls.iter_mut() {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            *cell = seed % 100 < wall_percent as u64;
+        }
+        grid.walls[0] = false;
+        let last = grid.walls.len() - 1;
+        grid.walls[last] = false;
+        grid
+    }
+
+    pub fn set_wall(&mut self, point: Point, blocked: bool) {
+        let index = point.1 * self.width + point.0;
+        self.walls[index] = blocked;
+    }
+
+    pub fn is_open(&self, point: Point) -> bool {
+        point.0 < self.width && point.1 < self.height && !self.walls[point.1 * self.width + point.0]
+    }
+
+    pub fn neighbors(&self, point: Point) -> Vec<Point> {
+        let (x, y) = point;
+        let mut out = Vec::with_capacity(4);
+        if x > 0 && self.is_open((x - 1, y)) {
+            out.push((x - 1, y));
+        }
+        if y > 0 && self.is_open((x, y - 1)) {
+            out.push((x, y - 1));
+        }
+        if self.is_open((x + 1, y)) {
+            out.push((x + 1, y));
+        }
+        if self.is_open((x, y + 1)) {
+            out.push((x, y + 1));
+        }
+        out
+    }
+}
+
+/// A found path plus how much work the search did, so strategies can be
+/// compared on optimality vs effort.
+#[derive(Debug)]
+pub struct PathResult {
+    pub path: Vec<Point>,
+    pub nodes_expanded: usize,
+}
+
+pub trait PathfindingStrategy {
+    fn name(&self) -> &str;
+    fn find_path(&self, grid: &Grid, start: Point, goal: Point) -> Option<PathResult>;
+}
+
+fn reconstruct(came_from: &std::collections::HashMap<Point, Point>, goal: Point) -> Vec<Point> {
+    let mut path = vec![goal];
+    while let Some(&prev) = came_from.get(path.last().expect("non-empty")) {
+        path.push(prev);
+    }
+    path.reverse();
+    path
+}
+
+/// Breadth-first search: optimal on unweighted grids, expands in rings.
+pub struct BfsPathfinding;
+
+impl PathfindingStrategy for BfsPathfinding {
+    fn name(&self) -> &str {
+        "BFS"
+    }
+
+    fn find_path(&self, grid: &Grid, start: Point, goal: Point) -> Option<PathResult> {
+        use std::collections::{HashMap, HashSet, VecDeque};
+        let mut frontier = VecDeque::from([start]);
+        let mut visited: HashSet<Point> = HashSet::from([start]);
+        let mut came_from: HashMap<Point, Point> = HashMap::new();
+        let mut expanded = 0;
+
+        while let Some(current) = frontier.pop_front() {
+            expanded += 1;
+            if current == goal {
+                return Some(PathResult {
+                    path: reconstruct(&came_from, goal),
+                    nodes_expanded: expanded,
+                });
+            }
+            for next in grid.neighbors(current) {
+                if visited.insert(next) {
+                    came_from.insert(next, current);
+                    frontier.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Heuristic for A*: an estimate of remaining distance. Admissible
+/// heuristics keep A* optimal; `ZeroHeuristic` degrades it to Dijkstra.
+pub trait Heuristic {
+    fn name(&self) -> &str;
+    fn estimate(&self, from: Point, goal: Point) -> u64;
+}
+
+pub struct ManhattanHeuristic;
+
+impl Heuristic for ManhattanHeuristic {
+    fn name(&self) -> &str {
+        "Manhattan"
+    }
+
+    fn estimate(&self, from: Point, goal: Point) -> u64 {
+        (from.0.abs_diff(goal.0) + from.1.abs_diff(goal.1)) as u64
+    }
+}
+
+pub struct ZeroHeuristic;
+
+impl Heuristic for ZeroHeuristic {
+    fn name(&self) -> &str {
+        "Zero"
+    }
+
+    fn estimate(&self, _from: Point, _goal: Point) -> u64 {
+        0
+    }
+}
+
+/// A* over uniform-cost grids. With `ZeroHeuristic` this is exactly
+/// Dijkstra, which is how `DijkstraPathfinding` is implemented.
+pub struct AStarPathfinding {
+    pub heuristic: Box<dyn Heuristic>,
+}
+
+impl PathfindingStrategy for AStarPathfinding {
+    fn name(&self) -> &str {
+        "A*"
+    }
+
+    fn find_path(&self, grid: &Grid, start: Point, goal: Point) -> Option<PathResult> {
+        use std::cmp::Reverse;
+        use std::collections::{BinaryHeap, HashMap};
+        let mut frontier: BinaryHeap<(Reverse<u64>, Point)> = BinaryHeap::new();
+        let mut cost_so_far: HashMap<Point, u64> = HashMap::from([(start, 0)]);
+        let mut came_from: HashMap<Point, Point> = HashMap::new();
+        let mut expanded = 0;
+        frontier.push((Reverse(self.heuristic.estimate(start, goal)), start));
+
+        while let Some((_, current)) = frontier.pop() {
+            expanded += 1;
+            if current == goal {
+                return Some(PathResult {
+                    path: reconstruct(&came_from, goal),
+                    nodes_expanded: expanded,
+                });
+            }
+            let current_cost = cost_so_far[&current];
+            for next in grid.neighbors(current) {
+                let new_cost = current_cost + 1;
+                if cost_so_far.get(&next).is_none_or(|&c| new_cost < c) {
+                    cost_so_far.insert(next, new_cost);
+                    came_from.insert(next, current);
+                    frontier.push((
+                        Reverse(new_cost + self.heuristic.estimate(next, goal)),
+                        next,
+                    ));
+                }
+            }
+        }
+        None
+    }
+}
+
+pub struct DijkstraPathfinding;
+
+impl PathfindingStrategy for DijkstraPathfinding {
+    fn name(&self) -> &str {
+        "Dijkstra"
+    }
+
+    fn find_path(&self, grid: &Grid, start: Point, goal: Point) -> Option<PathResult> {
+        AStarPathfinding {
+            heuristic: Box::new(ZeroHeuristic),
+        }
+        .find_path(grid, start, goal)
+    }
+}
+
+/// Context that owns the active pathfinding strategy.
+pub struct Navigator {
+    strategy: Box<dyn PathfindingStrategy>,
+}
+
+impl Navigator {
+    pub fn new(strategy: Box<dyn PathfindingStrategy>) -> Self {
+        Navigator { strategy }
+    }
+
+    pub fn set_strategy(&mut self, strategy: Box<dyn PathfindingStrategy>) {
+        self.strategy = strategy;
+    }
+
+    pub fn navigate(&self, grid: &Grid, start: Point, goal: Point) -> Option<PathResult> {
+        self.strategy.find_path(grid, start, goal)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Cache-eviction strategies
+// ---------------------------------------------------------------------------
+
+/// Bookkeeping half of a cache eviction policy. The cache tells the strategy
+/// about inserts/accesses/removals; the strategy answers "who goes next".
+pub trait EvictionStrategy<K> {
+    fn name(&self) -> &str;
+    fn on_insert(&mut self, key: &K);
+    fn on_access(&mut self, key: &K);
+    fn on_remove(&mut self, key: &K);
+    fn choose_victim(&mut self) -> Option<K>;
+}
+
+/// First in, first out: evicts by insertion order, ignoring accesses.
+pub struct FifoEviction<K> {
+    order: std::collections::VecDeque<K>,
+}
+
+impl<K> FifoEviction<K> {
+    pub fn new() -> Self {
+        FifoEviction {
+            order: std::collections::VecDeque::new(),
+        }
+    }
+}
+
+impl<K> Default for FifoEviction<K> {
+    fn default() -> Self {
+        FifoEviction::new()
+    }
+}
+
+impl<K: Clone + PartialEq> EvictionStrategy<K> for FifoEviction<K> {
+    fn name(&self) -> &str {
+        "FIFO"
+    }
+
+    fn on_insert(&mut self, key: &K) {
+        self.order.push_back(key.clone());
+    }
+
+    fn on_access(&mut self, _key: &K) {}
+
+    fn on_remove(&mut self, key: &K) {
+        self.order.retain(|k| k != key);
+    }
+
+    fn choose_victim(&mut self) -> Option<K> {
+        self.order.front().cloned()
+    }
+}
+
+/// Least recently used: accesses refresh a key's position.
+pub struct LruEviction<K> {
+    order: Vec<K>,
+}
+
+impl<K> LruEviction<K> {
+    pub fn new() -> Self {
+        LruEviction { order: Vec::new() }
+    }
+}
+
+impl<K> Default for LruEviction<K> {
+    fn default() -> Self {
+        LruEviction::new()
+    }
+}
+
+impl<K: Clone + PartialEq> EvictionStrategy<K> for LruEviction<K> {
+    fn name(&self) -> &str {
+        "LRU"
+    }
+
+    fn on_insert(&mut self, key: &K) {
+        self.order.push(key.clone());
+    }
+
+    fn on_access(&mut self, key: &K) {
+        if let Some(position) = self.order.iter().position(|k| k == key) {
+            let key = self.order.remove(position);
+            self.order.push(key);
+        }
+    }
+
+    fn on_remove(&mut self, key: &K) {
+        self.order.retain(|k| k != key);
+    }
+
+    fn choose_victim(&mut self) -> Option<K> {
+        self.order.first().cloned()
+    }
+}
+
+/// Least frequently used: evicts the key with the fewest accesses.
+pub struct LfuEviction<K: std::hash::Hash + Eq> {
+    counts: HashMap<K, u64>,
+}
+
+impl<K: std::hash::Hash + Eq> LfuEviction<K> {
+    pub fn new() -> Self {
+        LfuEviction {
+            counts: HashMap::new(),
+        }
+    }
+}
+
+impl<K: std::hash::Hash + Eq> Default for LfuEviction<K> {
+    fn default() -> Self {
+        LfuEviction::new()
+    }
+}
+
+impl<K: Clone + std::hash::Hash + Eq> EvictionStrategy<K> for LfuEviction<K> {
+    fn name(&self) -> &str {
+        "LFU"
+    }
+
+    fn on_insert(&mut self, key: &K) {
+        self.counts.insert(key.clone(), 0);
+    }
+
+    fn on_access(&mut self, key: &K) {
+        if let Some(count) = self.counts.get_mut(key) {
+            *count += 1;
+        }
+    }
+
+    fn on_remove(&mut self, key: &K) {
+        self.counts.remove(key);
+    }
+
+    fn choose_victim(&mut self) -> Option<K> {
+        self.counts
+            .iter()
+            .min_by_key(|(_, &count)| count)
+            .map(|(key, _)| key.clone())
+    }
+}
+
+/// Random eviction: cheap and surprisingly competitive.
+pub struct RandomEviction<K> {
+    keys: Vec<K>,
+    seed: std::cell::Cell<u64>,
+}
+
+impl<K> RandomEviction<K> {
+    pub fn new(seed: u64) -> Self {
+        RandomEviction {
+            keys: Vec::new(),
+            seed: std::cell::Cell::new(seed.max(1)),
+        }
+    }
+}
+
+impl<K: Clone + PartialEq> EvictionStrategy<K> for RandomEviction<K> {
+    fn name(&self) -> &str {
+        "Random"
+    }
+
+    fn on_insert(&mut self, key: &K) {
+        self.keys.push(key.clone());
+    }
+
+    fn on_access(&mut self, _key: &K) {}
+
+    fn on_remove(&mut self, key: &K) {
+        self.keys.retain(|k| k != key);
+    }
+
+    fn choose_victim(&mut self) -> Option<K> {
+        if self.keys.is_empty() {
+            return None;
+        }
+        let mut s = self.seed.get();
+        s ^= s << 13;
+        s ^= s >> 7;
+        s ^= s << 17;
+        self.seed.set(s);
+        Some(self.keys[(s % self.keys.len() as u64) as usize].clone())
+    }
+}
+
+/// Bounded map whose eviction policy is a pluggable strategy, shared with
+/// `CachingProxy` in proxy.rs.
+pub struct Cache<K: std::hash::Hash + Eq, V> {
+    entries: HashMap<K, V>,
+    capacity: usize,
+    strategy: Box<dyn EvictionStrategy<K>>,
+    evictions: u64,
+}
+
+impl<K: Clone + std::hash::Hash + Eq, V> Cache<K, V> {
+    pub fn new(capacity: usize, strategy: Box<dyn EvictionStrategy<K>>) -> Self {
+        Cache {
+            entries: HashMap::new(),
+            capacity: capacity.max(1),
+            strategy,
+            evictions: 0,
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.entries.contains_key(&key) {
+            self.entries.insert(key.clone(), value);
+            self.strategy.on_access(&key);
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            if let Some(victim) = self.strategy.choose_victim() {
+                self.entries.remove(&victim);
+                self.strategy.on_remove(&victim);
+                self.evictions += 1;
+            }
+        }
+        self.entries.insert(key.clone(), value);
+        self.strategy.on_insert(&key);
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let value = self.entries.get(key);
+        if value.is_some() {
+            self.strategy.on_access(key);
+        }
+        value
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    pub fn strategy_name(&self) -> &str {
+        self.strategy.name()
+    }
+
+    /// Iterates the live entries in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Load-balancing strategies
+// ---------------------------------------------------------------------------
+
+/// Simulated backend server in the pool.
+pub struct Backend {
+    pub name: String,
+    pub weight: u32,
+    active_connections: std::cell::Cell<u32>,
+    handled: std::cell::Cell<u64>,
+}
+
+impl Backend {
+    pub fn new(name: &str, weight: u32) -> Self {
+        Backend {
+            name: name.to_string(),
+            weight: weight.max(1),
+            active_connections: std::cell::Cell::new(0),
+            handled: std::cell::Cell::new(0),
+        }
+    }
+
+    pub fn active_connections(&self) -> u32 {
+        self.active_connections.get()
+    }
+}
+
+pub trait LoadBalancingStrategy {
+    fn name(&self) -> &str;
+    /// Index of the backend that should handle a request with this key.
+    fn pick(&self, backends: &[Backend], request_key: &str) -> usize;
+}
+
+pub struct RoundRobin {
+    next: std::cell::Cell<usize>,
+}
+
+impl RoundRobin {
+    pub fn new() -> Self {
+        RoundRobin {
+            next: std::cell::Cell::new(0),
+        }
+    }
+}
+
+impl Default for RoundRobin {
+    fn default() -> Self {
+        RoundRobin::new()
+    }
+}
+
+impl LoadBalancingStrategy for RoundRobin {
+    fn name(&self) -> &str {
+        "RoundRobin"
+    }
+
+    fn pick(&self, backends: &[Backend], _request_key: &str) -> usize {
+        let index = self.next.get() % backends.len();
+        self.next.set(index + 1);
+        index
+    }
+}
+
+pub struct LeastConnections;
+
+impl LoadBalancingStrategy for LeastConnections {
+    fn name(&self) -> &str {
+        "LeastConnections"
+    }
+
+    fn pick(&self, backends: &[Backend], _request_key: &str) -> usize {
+        backends
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, b)| b.active_connections.get())
+            .map(|(i, _)| i)
+            .expect("pool is non-empty")
+    }
+}
+
+pub struct WeightedRandom {
+    seed: std::cell::Cell<u64>,
+}
+
+impl WeightedRandom {
+    pub fn new(seed: u64) -> Self {
+        WeightedRandom {
+            seed: std::cell::Cell::new(seed.max(1)),
+        }
+    }
+
+    fn next(&self) -> u64 {
+        let mut s = self.seed.get();
+        s ^= s << 13;
+        s ^= s >> 7;
+        s ^= s << 17;
+        self.seed.set(s);
+        s
+    }
+}
+
+impl LoadBalancingStrategy for WeightedRandom {
+    fn name(&self) -> &str {
+        "WeightedRandom"
+    }
+
+    fn pick(&self, backends: &[Backend], _request_key: &str) -> usize {
+        let total: u64 = backends.iter().map(|b| b.weight as u64).sum();
+        let mut ticket = self.next() % total;
+        for (index, backend) in backends.iter().enumerate() {
+            if ticket < backend.weight as u64 {
+                return index;
+            }
+            ticket -= backend.weight as u64;
+        }
+        backends.len() - 1
+    }
+}
+
+/// Rendezvous (highest-random-weight) hashing: the same key always lands on
+/// the same backend, and removing a backend only remaps that backend's keys.
+pub struct ConsistentHashing;
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x1000_0000_01b3);
+    }
+    hash
+}
+
+impl LoadBalancingStrategy for ConsistentHashing {
+    fn name(&self) -> &str {
+        "ConsistentHashing"
+    }
+
+    fn pick(&self, backends: &[Backend], request_key: &str) -> usize {
+        backends
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, b)| fnv1a(format!("{}:{}", b.name, request_key).as_bytes()))
+            .map(|(i, _)| i)
+            .expect("pool is non-empty")
+    }
+}
+
+/// Context dispatching simulated requests across the pool.
+pub struct LoadBalancer {
+    backends: Vec<Backend>,
+    strategy: Box<dyn LoadBalancingStrategy>,
+}
+
+impl LoadBalancer {
+    pub fn new(backends: Vec<Backend>, strategy: Box<dyn LoadBalancingStrategy>) -> Self {
+        LoadBalancer { backends, strategy }
+    }
+
+    pub fn set_strategy(&mut self, strategy: Box<dyn LoadBalancingStrategy>) {
+        self.strategy = strategy;
+    }
+
+    /// Route one request; returns the chosen backend index (the "connection"
+    /// stays open until `finish` is called).
+    pub fn dispatch(&self, request_key: &str) -> usize {
+        let index = self.strategy.pick(&self.backends, request_key);
+        let backend = &self.backends[index];
+        backend.active_connections.set(backend.active_connections.get() + 1);
+        backend.handled.set(backend.handled.get() + 1);
+        index
+    }
+
+    pub fn finish(&self, index: usize) {
+        let backend = &self.backends[index];
+        backend
+            .active_connections
+            .set(backend.active_connections.get().saturating_sub(1));
+    }
+
+    /// Requests handled per backend, for comparing strategies quantitatively.
+    pub fn distribution_stats(&self) -> Vec<(String, u64)> {
+        self.backends
+            .iter()
+            .map(|b| (b.name.clone(), b.handled.get()))
+            .collect()
+    }
+
+    pub fn reset_stats(&self) {
+        for backend in &self.backends {
+            backend.handled.set(0);
+            backend.active_connections.set(0);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Data-driven strategy selection
+// ---------------------------------------------------------------------------
+
+/// Declarative strategy selection, e.g. loaded from a config file at
+/// startup. Serde derives are available behind the `serde` feature; the
+/// std-only `parse` accepts simple `key: value` lines so the demo works
+/// without it.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StrategyConfig {
+    pub payment: Option<String>,
+    pub compression: Option<String>,
+    pub compression_level: Option<u8>,
+    pub sort: Option<String>,
+}
+
+impl StrategyConfig {
+    /// Parse `key: value` lines (a subset of YAML/TOML), ignoring blanks
+    /// and `#` comments.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = StrategyConfig::default();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| format!("line {}: expected 'key: value'", line_number + 1))?;
+            let value = value.trim().to_string();
+            match key.trim() {
+                "payment" => config.payment = Some(value),
+                "compression" => config.compression = Some(value),
+                "compression_level" => {
+                    config.compression_level = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("line {}: bad level", line_number + 1))?,
+                    )
+                }
+                "sort" => config.sort = Some(value),
+                other => return Err(format!("line {}: unknown key '{}'", line_number + 1, other)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Turns config names into live strategy objects.
+pub struct StrategyFactory;
+
+impl StrategyFactory {
+    pub fn payment(name: &str) -> Result<Box<dyn PaymentStrategy>, String> {
+        match name {
+            "creditcard" => Ok(Box::new(CreditCardPayment::new("4111111111111111", "config"))),
+            "paypal" => Ok(Box::new(PayPalPayment::new("config@example.com"))),
+            "banktransfer" => Ok(Box::new(BankTransferPayment::new("DE89370400440532013000"))),
+            other => Err(format!("unknown payment strategy '{}'", other)),
+        }
+    }
+
+    pub fn compression(config: &StrategyConfig) -> Result<Box<dyn CompressionStrategy>, String> {
+        let level = config.compression_level.unwrap_or(6);
+        match config.compression.as_deref() {
+            Some("rle") => Ok(Box::new(RleCompression)),
+            Some("lzw") => Ok(Box::new(LzwCompression::with_config(
+                CompressionConfig::with_level(level),
+            ))),
+            #[cfg(feature = "flate2")]
+            Some("gzip") => Ok(Box::new(flate2_backend::GzipCompression {
+                config: CompressionConfig::with_level(level),
+            })),
+            Some(other) => Err(format!("unknown compression strategy '{}'", other)),
+            None => Err("no compression strategy configured".to_string()),
+        }
+    }
+
+    pub fn sort(name: &str) -> Result<Box<dyn SortStrategy>, String> {
+        match name {
+            "bubble" => Ok(Box::new(BubbleSort)),
+            "insertion" => Ok(Box::new(InsertionSort)),
+            "quick" => Ok(Box::new(QuickSort)),
+            "merge" => Ok(Box::new(MergeSort)),
+            "heap" => Ok(Box::new(HeapSort)),
+            "intro" => Ok(Box::new(IntroSort)),
+            "stable" => Ok(Box::new(StableSort)),
+            other => Err(format!("unknown sort strategy '{}'", other)),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Demo
+// ---------------------------------------------------------------------------
+
+fn demo_compression() {
+    println!("=== Compression strategies ===");
+    let sample: Vec<u8> = b"the quick brown fox jumps over the lazy dog "
+        .iter()
+        .cycle()
+        .take(2_000)
+        .copied()
+        .collect();
+    let strategies: Vec<Box<dyn CompressionStrategy>> =
+        vec![Box::new(RleCompression), Box::new(LzwCompression::new())];
+    benchmark_compression(&strategies, &sample);
+
+    println!("\n--- LZW level sweep ---");
+    benchmark_compression_levels(&|config| Box::new(LzwCompression::with_config(config)), &sample);
+
+    println!("\n--- Preset dictionary on short input ---");
+    let short = b"GET /api/users HTTP/1.1";
+    let preset = CompressionConfig {
+        preset_dictionary: Some(b"GET /api/ HTTP/1.1 POST /api/ HTTP/1.1".to_vec()),
+        ..CompressionConfig::default()
+    };
+    let plain = LzwCompression::new();
+    let seeded = LzwCompression::with_config(preset);
+    assert_eq!(
+        seeded.decompress(&seeded.compress(short)).as_deref(),
+        Ok(short.as_slice())
+    );
+    println!(
+        "plain {} bytes, preset {} bytes (round-trip ok)",
+        plain.compress(short).len(),
+        seeded.compress(short).len(),
+    );
+}
+
+/// Small xorshift generator so demos don't need an external rand crate.
+fn pseudo_random_vec(len: usize, mut seed: u64) -> Vec<i32> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % 10_000) as i32
+        })
+        .collect()
+}
+
+fn demo_sort_correctness() {
+    println!("\n=== Sort correctness vs slice::sort ===");
+    let mut inputs: Vec<(&str, Vec<i32>)> = vec![
+        ("random", pseudo_random_vec(1_000, 42)),
+        ("sorted", (0..1_000).collect()),
+        ("reverse", (0..1_000).rev().collect()),
+        ("all-equal", vec![7; 1_000]),
+    ];
+    // Adversarial for naive quicksort: sorted with a few swaps at the end.
+    let mut nearly = (0..1_000).collect::<Vec<i32>>();
+    nearly.swap(0, 999);
+    inputs.push(("nearly-sorted", nearly));
+
+    let strategies: Vec<Box<dyn SortStrategy>> = vec![
+        Box::new(InsertionSort),
+        Box::new(HeapSort),
+        Box::new(IntroSort),
+    ];
+    for (label, input) in &inputs {
+        let mut expected = input.clone();
+        expected.sort();
+        for strategy in &strategies {
+            let mut copy = input.clone();
+            strategy.sort(&mut copy);
+            assert_eq!(copy, expected, "{} failed on {}", strategy.name(), label);
+        }
+        let mut copy = input.clone();
+        let chosen = AdaptiveSorter::sort(&mut copy);
+        assert_eq!(copy, expected, "AdaptiveSorter failed on {}", label);
+        println!("{:<14} ok (adaptive chose {})", label, chosen);
+    }
+}
+
+fn demo_sorting() {
+    println!("\n=== Sorting strategies ===");
+    let data = vec![5, 2, 9, 1, 7, 3, 8, 6, 4];
+    for strategy in [
+        Box::new(BubbleSort) as Box<dyn SortStrategy>,
+        Box::new(QuickSort),
+        Box::new(MergeSort),
+    ] {
+        let mut copy = data.clone();
+        let sorter = Sorter::new(strategy);
+        sorter.sort(&mut copy);
+        println!("{:?}", copy);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Product {
+    name: String,
+    price_cents: u32,
+}
+
+fn demo_stability() {
+    println!("\n=== Stability claims vs observed behavior ===");
+    let checks: Vec<(&dyn SortStrategy, &dyn SortByStrategy<Decorated>)> = vec![
+        (&InsertionSort, &InsertionSort),
+        (&MergeSort, &MergeSort),
+        (&StableSort, &StableSort),
+        (&QuickSort, &QuickSort),
+    ];
+    for (strategy, by_strategy) in checks {
+        let observed = observe_stability(by_strategy);
+        println!(
+            "{:<14} claims stable={:<5} observed stable={:<5} {}",
+            strategy.name(),
+            strategy.is_stable(),
+            observed,
+            if observed || !strategy.is_stable() {
+                "consistent"
+            } else {
+                "CLAIM VIOLATED"
+            }
+        );
+    }
+}
+
+fn demo_sort_by_key() {
+    println!("\n=== Sorting structs by key/comparator ===");
+    let mut products = vec![
+        Product {
+            name: "Monitor".to_string(),
+            price_cents: 19_999,
+        },
+        Product {
+            name: "Cable".to_string(),
+            price_cents: 499,
+        },
+        Product {
+            name: "Dock".to_string(),
+            price_cents: 8_950,
+        },
+    ];
+
+    sort_by_key(&MergeSort, &mut products, |p| p.price_cents);
+    println!(
+        "by price: {:?}",
+        products.iter().map(|p| &p.name).collect::<Vec<_>>()
+    );
+
+    QuickSort.sort_by(&mut products, &mut |a, b| a.name.cmp(&b.name));
+    println!(
+        "by name:  {:?}",
+        products.iter().map(|p| &p.name).collect::<Vec<_>>()
+    );
+}
+
+fn demo_auto_sorter() {
+    println!("\n=== AutoSorter choices ===");
+    let mut auto = AutoSorter::new();
+    auto.register(Box::new(InsertionSort));
+    auto.register(Box::new(IntroSort));
+    auto.register(Box::new(MergeSort));
+
+    let mut small = pseudo_random_vec(16, 3);
+    auto.sort(&mut small);
+    println!("16 random      -> {:?}", auto.last_choice().unwrap());
+
+    let mut nearly: Vec<i32> = (0..500).collect();
+    nearly.swap(10, 11);
+    auto.sort(&mut nearly);
+    println!("nearly sorted  -> {:?}", auto.last_choice().unwrap());
+
+    let mut random = pseudo_random_vec(500, 4);
+    auto.sort(&mut random);
+    println!("500 random     -> {:?}", auto.last_choice().unwrap());
+}
+
+fn demo_sort_benchmark() {
+    println!("\n=== Sort benchmark (5000 random elements) ===");
+    let data = pseudo_random_vec(5_000, 99);
+    let mut sorter = Sorter::new(Box::new(QuickSort));
+    sorter.register(Box::new(BubbleSort));
+    sorter.register(Box::new(InsertionSort));
+    sorter.register(Box::new(QuickSort));
+    sorter.register(Box::new(MergeSort));
+    sorter.register(Box::new(HeapSort));
+    sorter.register(Box::new(IntroSort));
+    let results = sorter.benchmark(&data);
+    print!("{}", Sorter::format_benchmark_report(&results));
+}
+
+fn demo_pathfinding() {
+    println!("\n=== Pathfinding strategies (60x60 maze) ===");
+    let grid = Grid::random_maze(60, 60, 25, 1234);
+    let start = (0, 0);
+    let goal = (59, 59);
+    let mut navigator = Navigator::new(Box::new(BfsPathfinding));
+
+    let strategies: Vec<Box<dyn PathfindingStrategy>> = vec![
+        Box::new(BfsPathfinding),
+        Box::new(DijkstraPathfinding),
+        Box::new(AStarPathfinding {
+            heuristic: Box::new(ManhattanHeuristic),
+        }),
+    ];
+    for strategy in strategies {
+        let label = strategy.name().to_string();
+        navigator.set_strategy(strategy);
+        let begin = std::time::Instant::now();
+        match navigator.navigate(&grid, start, goal) {
+            Some(result) => println!(
+                "{:<9} path {:>3} steps, {:>5} expanded, {:?}",
+                label,
+                result.path.len(),
+                result.nodes_expanded,
+                begin.elapsed()
+            ),
+            None => println!("{:<9} no path", label),
+        }
+    }
+}
+
+fn demo_load_balancing() {
+    println!("\n=== Load-balancing strategies (300 requests) ===");
+    let pool = || {
+        vec![
+            Backend::new("web-1", 1),
+            Backend::new("web-2", 2),
+            Backend::new("web-3", 3),
+        ]
+    };
+    let strategies: Vec<Box<dyn LoadBalancingStrategy>> = vec![
+        Box::new(RoundRobin::new()),
+        Box::new(LeastConnections),
+        Box::new(WeightedRandom::new(42)),
+        Box::new(ConsistentHashing),
+    ];
+    for strategy in strategies {
+        let label = strategy.name().to_string();
+        let balancer = LoadBalancer::new(pool(), strategy);
+        for request in 0..300 {
+            let key = format!("user-{}", request % 40);
+            let index = balancer.dispatch(&key);
+            // Long-lived connections for every 3rd request, to give
+            // LeastConnections something to react to.
+            if request % 3 != 0 {
+                balancer.finish(index);
+            }
+        }
+        println!("{:<18} {:?}", label, balancer.distribution_stats());
+    }
+}
+
+fn demo_payment() {
+    println!("\n=== Payment strategies ===");
+    let mut cart = ShoppingCart::new();
+    cart.add_item("Keyboard", Money::new(5_999, Currency::Usd), 1);
+    cart.add_item("Mouse", Money::new(2_450, Currency::Usd), 2);
+
+    // Compare fees before committing to a method.
+    let candidates: Vec<Box<dyn PaymentStrategy>> = vec![
+        Box::new(CreditCardPayment::new("4111111111111111", "Alice")),
+        Box::new(PayPalPayment::new("alice@example.com")),
+        Box::new(BankTransferPayment::new("DE89370400440532013000")),
+    ];
+    let comparison = compare_payment_fees(Money::new(10_899, Currency::Usd), &candidates);
+    for row in &comparison.rows {
+        println!(
+            "{:<13} fee {} ({:.2}%), total {}",
+            row.method, row.fee, row.effective_rate_percent, row.total_cost
+        );
+    }
+    println!("cheapest: {}", comparison.cheapest);
+
+    cart.set_payment_strategy(Box::new(CreditCardPayment::new("4111111111111111", "Alice")));
+    println!("{}", cart.checkout().unwrap());
+
+    cart.set_payment_strategy(Box::new(PayPalPayment::new("alice@example.com")));
+    println!("{}", cart.checkout().unwrap());
+
+    let body = "qaddress4242";
+    let address = format!("bc1{}{}", body, CryptoPayment::checksum_char(body));
+    cart.set_payment_strategy(Box::new(CryptoPayment::new(
+        CryptoCurrency::Bitcoin,
+        &address,
+    )));
+    println!("{}", cart.checkout().unwrap());
+
+    let pending = CryptoPayment::new(CryptoCurrency::Bitcoin, &address).with_confirmations(2);
+    println!(
+        "pending tx: {:?}",
+        pending.pay(Money::new(1_000, Currency::Usd))
+    );
+}
+
+fn demo_fallback_payment() {
+    println!("\n=== Fallback payment chain ===");
+    let mut cart = ShoppingCart::new();
+    cart.add_item("SSD", Money::new(9_900, Currency::Usd), 1);
+    cart.set_payment_strategy(Box::new(FallbackPayment::new(vec![
+        Box::new(CreditCardPayment::new("123", "Carol")), // too short, fails
+        Box::new(PayPalPayment::new("carol@example.com")),
+    ])));
+    println!("{}", cart.checkout().unwrap());
+}
+
+fn demo_shipping() {
+    println!("\n=== Shipping strategies ===");
+    let mut cart = ShoppingCart::new();
+    cart.add_weighted_item("Bookshelf", Money::new(14_900, Currency::Usd), 1, 22_000);
+    cart.add_weighted_item("Lamp", Money::new(3_400, Currency::Usd), 2, 1_200);
+    cart.set_shipping_address(ShippingAddress {
+        street: "1 Main St".to_string(),
+        city: "Portland".to_string(),
+        country: "US".to_string(),
+        postal_code: "97201".to_string(),
+    });
+    cart.set_payment_strategy(Box::new(PayPalPayment::new("erin@example.com")));
+
+    for strategy in [
+        Box::new(FlatRateShipping { rate_minor: 799 }) as Box<dyn ShippingStrategy>,
+        Box::new(WeightBasedShipping {
+            rate_per_kg_minor: 120,
+        }),
+        Box::new(FreeOverThresholdShipping {
+            rate_minor: 799,
+            threshold_minor: 10_000,
+        }),
+        Box::new(ExpressShipping {
+            base_rate_minor: 2_500,
+            domestic_country: "US".to_string(),
+        }),
+    ] {
+        cart.set_shipping_strategy(strategy);
+        let totals = cart.get_total().unwrap();
+        println!("shipping {} -> total {}", totals.shipping, totals.grand_total);
+    }
+    println!("{}", cart.checkout().unwrap());
+}
+
+fn demo_tax_strategies() {
+    println!("\n=== Tax strategies ===");
+    let mut cart = ShoppingCart::new();
+    cart.add_item_in_category("Novel", Money::new(1_500, Currency::Usd), 2, TaxCategory::Books);
+    cart.add_item_in_category("Cheese", Money::new(800, Currency::Usd), 1, TaxCategory::Food);
+    cart.add_item("Speaker", Money::new(4_900, Currency::Usd), 1);
+
+    for strategy in [
+        Box::new(UsStateSalesTax { state: "CA" }) as Box<dyn TaxStrategy>,
+        Box::new(EuVat),
+        Box::new(TaxExempt),
+    ] {
+        let label = strategy.name().to_string();
+        cart.set_tax_strategy(strategy);
+        let totals = cart.get_total().unwrap();
+        println!(
+            "{:<11} pre-tax {} + tax {} = {}",
+            label, totals.pre_tax, totals.tax, totals.grand_total
+        );
+    }
+}
+
+fn demo_discounts() {
+    println!("\n=== Discount strategies ===");
+    let mut cart = ShoppingCart::new();
+    cart.add_item("T-shirt", Money::new(2_000, Currency::Usd), 4);
+    cart.add_item("Poster", Money::new(1_000, Currency::Usd), 8);
+    cart.add_discount(Box::new(PercentageCoupon {
+        code: "SAVE10".to_string(),
+        percent: 10,
+    }));
+    cart.add_discount(Box::new(BuyOneGetOne {
+        item_name: "T-shirt".to_string(),
+    }));
+    cart.add_discount(Box::new(TieredVolumeDiscount {
+        tiers: vec![(5, 5), (10, 12)],
+    }));
+    cart.set_payment_strategy(Box::new(PayPalPayment::new("dave@example.com")));
+
+    println!("stacked:   {}", cart.checkout().unwrap());
+    cart.set_discount_stacking(DiscountStacking::BestOnly);
+    println!("best only: {}", cart.checkout().unwrap());
+}
+
+fn demo_installments() {
+    println!("\n=== Installment plan ===");
+    let strategy = InstallmentPayment::new(3, 30, 150);
+    let plan = strategy.plan(Money::new(10_000, Currency::Usd)).unwrap();
+    for installment in &plan.installments {
+        println!(
+            "installment {} due day {}: {} principal + {} fee",
+            installment.number, installment.due_in_days, installment.principal, installment.fee
+        );
+    }
+    println!("{}", strategy.pay(Money::new(10_000, Currency::Usd)).unwrap());
+}
+
+fn demo_config_driven() {
+    println!("\n=== Config-driven strategy selection ===");
+    let config = StrategyConfig::parse(
+        "# checkout defaults\n\
+         payment: paypal\n\
+         compression: lzw\n\
+         compression_level: 6\n\
+         sort: merge\n",
+    )
+    .unwrap();
+
+    let payment = StrategyFactory::payment(config.payment.as_deref().unwrap()).unwrap();
+    let compression = StrategyFactory::compression(&config).unwrap();
+    let sort = StrategyFactory::sort(config.sort.as_deref().unwrap()).unwrap();
+    println!(
+        "wired up: payment={}, compression={}, sort={}",
+        payment.name(),
+        compression.name(),
+        sort.name()
+    );
+    println!(
+        "bad name: {:?}",
+        StrategyFactory::sort("bogo").map(|s| s.name().to_string()).err()
+    );
+}
+
+fn demo_multi_currency() {
+    println!("\n=== Multi-currency checkout ===");
+    let mut cart = ShoppingCart::priced_in(Currency::Eur);
+    cart.set_exchange_provider(Box::new(FixedRateProvider));
+    cart.add_item("Headphones", Money::new(12_900, Currency::Eur), 1);
+    cart.add_item("Adapter", Money::new(1_500, Currency::Usd), 1);
+    println!("total: {}", cart.get_total().unwrap().grand_total);
+
+    cart.set_payment_strategy(Box::new(PayPalPayment::new("bob@example.com")));
+    println!("{}", cart.checkout().unwrap());
+
+    // JPY cart against a EUR/USD-only strategy settles via conversion.
+    let mut jpy_cart = ShoppingCart::priced_in(Currency::Jpy);
+    jpy_cart.set_exchange_provider(Box::new(FixedRateProvider));
+    jpy_cart.add_item("Tea set", Money::new(8_000, Currency::Jpy), 1);
+    jpy_cart.set_payment_strategy(Box::new(PayPalPayment::new("bob@example.com")));
+    println!("{}", jpy_cart.checkout().unwrap());
+
+    // Without an exchange provider the mismatch is a clean error.
+    let mut bare = ShoppingCart::priced_in(Currency::Jpy);
+    bare.add_item("Tea set", Money::new(8_000, Currency::Jpy), 1);
+    bare.set_payment_strategy(Box::new(PayPalPayment::new("bob@example.com")));
+    println!("no provider: {:?}", bare.checkout());
+}
+
+fn main() {
+    demo_compression();
+    demo_sorting();
+    demo_sort_correctness();
+    #[cfg(feature = "rayon")]
+    benchmark_parallel_sort();
+    demo_sort_by_key();
+    demo_stability();
+    demo_auto_sorter();
+    demo_sort_benchmark();
+    demo_pathfinding();
+    demo_load_balancing();
+    demo_payment();
+    demo_multi_currency();
+    demo_fallback_payment();
+    demo_installments();
+    demo_discounts();
+    demo_tax_strategies();
+    demo_shipping();
+    demo_config_driven();
+}